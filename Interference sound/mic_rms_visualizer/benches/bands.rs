@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use mic_rms_visualizer::dsp::bands::{BandFilterBank, THIRD_OCTAVE_BANDS};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+
+// One 2048-sample block of white-ish noise so every band has work to do
+fn block() -> Vec<f32> {
+    let mut seed = 1u32;
+    (0..2048)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+        })
+        .collect()
+}
+
+// Serial or parallel depending on the `rayon` feature; compare
+// `cargo bench --bench bands` against `cargo bench --bench bands --features rayon`
+fn bands(c: &mut Criterion) {
+    let samples = block();
+    let mut bank = BandFilterBank::third_octave(SAMPLE_RATE, 0.49 * SAMPLE_RATE);
+    let mut energy = [0.0f32; THIRD_OCTAVE_BANDS];
+    let name = if cfg!(feature = "rayon") { "rayon" } else { "serial" };
+    c.bench_function(&format!("bands_31x2048_{}", name), |b| {
+        b.iter(|| bank.process(black_box(&samples), black_box(&mut energy)))
+    });
+}
+
+criterion_group!(benches, bands);
+criterion_main!(benches);
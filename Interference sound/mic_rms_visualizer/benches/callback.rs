@@ -0,0 +1,111 @@
+// Time spent in the audio callback while a UI thread keeps taking the shared lock,
+// for a callback that processes its block under the lock and for one that only
+// queues it on the ring. Prints percentiles rather than a criterion report because
+// the tail is what glitches; run in release mode with `cargo bench --bench callback`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use mic_rms_visualizer::audio::{with_samples, AudioData};
+use mic_rms_visualizer::dsp::rms::sum_of_squares;
+use mic_rms_visualizer::ring::block_ring;
+
+const CALLBACKS: usize = 5000;
+const FRAMES: usize = 256;
+const CHANNELS: usize = 2;
+// Faster than a real 256-frame callback so the run stays short
+const CALLBACK_PERIOD: Duration = Duration::from_micros(500);
+const WAVEFORM_LEN: usize = 48_000;
+// Once per 60 Hz frame the UI holds the lock about as long as a busy plot
+const UI_HOLD: Duration = Duration::from_millis(2);
+const UI_FRAME: Duration = Duration::from_millis(16);
+
+// One stereo block of a -20 dBFS sine
+fn block() -> Vec<f32> {
+    (0..FRAMES * CHANNELS).map(|i| 0.1 * ((i / CHANNELS) as f32 * 0.05).sin()).collect()
+}
+
+// Stand-in for the DSP chain: mix down, block RMS and the waveform push
+fn process(data: &mut AudioData, block: &[f32]) {
+    let mono: Vec<f32> = block.chunks(CHANNELS).map(|frame| frame.iter().sum::<f32>() / CHANNELS as f32).collect();
+    data.rms = (sum_of_squares(&mono) / mono.len() as f32).sqrt();
+    data.push_samples(&mono, WAVEFORM_LEN);
+}
+
+fn ui_thread(shared: Arc<Mutex<AudioData>>, stop: Arc<AtomicBool>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            with_samples(&shared, |_| {
+                let started = Instant::now();
+                while started.elapsed() < UI_HOLD {
+                    std::hint::spin_loop();
+                }
+            });
+            thread::sleep(UI_FRAME - UI_HOLD);
+        }
+    })
+}
+
+// Calls `callback` CALLBACKS times at CALLBACK_PERIOD and returns how long each call took
+fn time_callbacks(mut callback: impl FnMut(Instant)) -> Vec<Duration> {
+    (0..CALLBACKS)
+        .map(|_| {
+            let started = Instant::now();
+            callback(started);
+            let elapsed = started.elapsed();
+            thread::sleep(CALLBACK_PERIOD);
+            elapsed
+        })
+        .collect()
+}
+
+fn report(name: &str, mut durations: Vec<Duration>) {
+    durations.sort_unstable();
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * durations.len() as f64).ceil() as usize;
+        durations[rank.saturating_sub(1)].as_secs_f64() * 1e6
+    };
+    println!(
+        "{:<6} p50 {:>9.1} µs   p99 {:>9.1} µs   max {:>9.1} µs",
+        name,
+        percentile(50.0),
+        percentile(99.0),
+        percentile(100.0)
+    );
+}
+
+fn main() {
+    let input = block();
+
+    let shared = Arc::new(Mutex::new(AudioData::default()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let ui = ui_thread(Arc::clone(&shared), Arc::clone(&stop));
+    let durations = time_callbacks(|_| process(&mut shared.lock().unwrap(), &input));
+    stop.store(true, Ordering::Relaxed);
+    ui.join().unwrap();
+    report("mutex", durations);
+
+    let shared = Arc::new(Mutex::new(AudioData::default()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let ui = ui_thread(Arc::clone(&shared), Arc::clone(&stop));
+    let (mut producer, mut consumer) = block_ring(WAVEFORM_LEN * CHANNELS, CHANNELS);
+    let processor = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            while !consumer.is_abandoned() {
+                while let Some((_, block)) = consumer.pop() {
+                    process(&mut shared.lock().unwrap(), block);
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+        })
+    };
+    let durations = time_callbacks(|started| producer.push(&input, started));
+    drop(producer);
+    processor.join().unwrap();
+    stop.store(true, Ordering::Relaxed);
+    ui.join().unwrap();
+    report("ring", durations);
+}
@@ -0,0 +1,19 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use mic_rms_visualizer::dsp::rms::{rms_scalar, rms_simd};
+
+// One 4096-sample block of a -20 dBFS sine
+fn block() -> Vec<f32> {
+    (0..4096).map(|i| 0.1 * (i as f32 * 0.05).sin()).collect()
+}
+
+fn rms(c: &mut Criterion) {
+    let samples = block();
+    let mut group = c.benchmark_group("rms_4096");
+    group.bench_function("scalar", |b| b.iter(|| rms_scalar(black_box(&samples))));
+    group.bench_function("simd", |b| b.iter(|| rms_simd(black_box(&samples))));
+    group.finish();
+}
+
+criterion_group!(benches, rms);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+/// Speed of sound in air in m/s for a temperature in °C and a relative humidity in
+/// percent: c = 331.3 + 0.606·T + 0.0124·H.
+pub fn speed_of_sound(temperature_c: f32, humidity_pct: f32) -> f32 {
+    331.3 + 0.606 * temperature_c + 0.0124 * humidity_pct
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+// RMS colour bands for the header readout
+const RMS_MODERATE: f32 = 0.05;
+const RMS_HIGH: f32 = 0.25;
+
+/// Renders the samples as a `width` x `height` block-character waveform with an
+/// ANSI-coloured RMS/peak header line. Samples are drawn full scale (±1.0).
+pub fn render_ascii_waveform(samples: &VecDeque<f32>, width: usize, height: usize) -> String {
+    let mut out = String::new();
+
+    let rms = if samples.is_empty() {
+        0.0
+    } else {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    };
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    let colour = if rms < RMS_MODERATE {
+        GREEN
+    } else if rms < RMS_HIGH {
+        YELLOW
+    } else {
+        RED
+    };
+    let _ = writeln!(out, "RMS: {colour}{rms:.4}{RESET} | Peak: {peak:.4}\x1b[K");
+
+    if width == 0 || height == 0 {
+        return out;
+    }
+
+    // Each character cell holds two vertical "pixels" (▀ / ▄)
+    let rows = height * 2;
+    let to_row = |s: f32| (((1.0 - s.clamp(-1.0, 1.0)) / 2.0) * (rows - 1) as f32).round() as usize;
+    let mut pixels = vec![vec![false; width]; rows];
+
+    let len = samples.len();
+    if len > 0 {
+        for col in 0..width {
+            let start = (col * len / width).min(len - 1);
+            let end = ((col + 1) * len / width).clamp(start + 1, len);
+            // Include the previous column's last sample so steep edges stay connected
+            let (lo, hi) = samples
+                .range(start.saturating_sub(1)..end)
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+            for row in pixels.iter_mut().take(to_row(lo) + 1).skip(to_row(hi)) {
+                row[col] = true;
+            }
+        }
+    }
+
+    for pair in pixels.chunks(2) {
+        for (&top, &bottom) in pair[0].iter().zip(&pair[1]) {
+            out.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        out.push('\n');
+    }
+
+    out
+}
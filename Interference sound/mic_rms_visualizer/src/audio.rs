@@ -0,0 +1,498 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use cpal::traits::DeviceTrait;
+use cpal::SampleFormat;
+use crossbeam::channel;
+
+use crate::calibration::CalibrationWizard;
+use crate::capture::CaptureSender;
+use crate::dsp::agc::Agc;
+use crate::dsp::aweighting::AWeightingFilter;
+use crate::dsp::biquad::Biquad;
+use crate::dsp::drums::{Drum, DrumPatternRecognizer};
+use crate::dsp::feedback::FeedbackSquealDetector;
+use crate::dsp::filter::AudioFilter;
+use crate::dsp::flatness::RoomResponseMeter;
+use crate::dsp::gain_rider::GainRider;
+use crate::dsp::goertzel::ToneDetectorBank;
+use crate::dsp::heatmap::WaveformHeatmap;
+use crate::dsp::leq::LeqMeter;
+use crate::dsp::lufs::Lufsometer;
+use crate::dsp::onset::OnsetDetector;
+use crate::dsp::sel::SelHistory;
+use crate::dsp::silence::{SilenceDetector, SilenceEvent};
+use crate::dsp::smoother::AudioSmoother;
+use crate::dsp::spectral_gate::FrequencyDomainNoiseGate;
+use crate::dsp::spectral_subtraction::SpectralSubtraction;
+use crate::dsp::stats::RollingStats;
+use crate::dsp::wind::WindNoiseFilter;
+use crate::field_calibration::FieldMeasurement;
+use crate::osc::OscMetrics;
+
+/// Integer sample to -1.0..=1.0; `i16::MIN` lands just below -1.0.
+pub fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / i16::MAX as f32
+}
+
+/// Offset-binary sample to -1.0..=1.0, centred on 32768.
+pub fn u16_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / i16::MAX as f32
+}
+
+/// Builds an input stream in the config's own sample format. `callback` always gets
+/// interleaved f32 samples; i16 and u16 input is converted first. Its `Instant` is when
+/// the driver entered the callback, before any conversion. The stream is not started.
+pub fn build_input_stream_dynamic<D, E>(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    mut callback: D,
+    error_callback: E,
+) -> Result<cpal::Stream>
+where
+    D: FnMut(&[f32], &cpal::InputCallbackInfo, Instant) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    let stream_config = config.config();
+    // Reused by the integer formats so the callback only allocates while the block grows
+    let mut converted: Vec<f32> = Vec::new();
+
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => device.build_input_stream(
+            &stream_config,
+            move |data: &[f32], info: &cpal::InputCallbackInfo| callback(data, info, Instant::now()),
+            error_callback,
+            None,
+        )?,
+        SampleFormat::I16 => device.build_input_stream(
+            &stream_config,
+            move |data: &[i16], info: &cpal::InputCallbackInfo| {
+                let started = Instant::now();
+                converted.clear();
+                converted.extend(data.iter().map(|&s| i16_to_f32(s)));
+                callback(&converted, info, started);
+            },
+            error_callback,
+            None,
+        )?,
+        SampleFormat::U16 => device.build_input_stream(
+            &stream_config,
+            move |data: &[u16], info: &cpal::InputCallbackInfo| {
+                let started = Instant::now();
+                converted.clear();
+                converted.extend(data.iter().map(|&s| u16_to_f32(s)));
+                callback(&converted, info, started);
+            },
+            error_callback,
+            None,
+        )?,
+        other => return Err(anyhow!("Unsupported input sample format {}", other)),
+    };
+    Ok(stream)
+}
+
+/// Mini-block sizes of the waveform envelope (max |sample| per block).
+pub const ENVELOPE_BLOCKS: [usize; 4] = [4, 8, 16, 32];
+pub const DEFAULT_ENVELOPE_BLOCK: usize = 8;
+
+/// Single-shot capture, filled by the audio callback: waits for a rising edge through
+/// `level`, then records one buffer including `pre_trigger` samples before the edge.
+#[derive(Default)]
+pub enum SingleShot {
+    #[default]
+    Off,
+    Armed {
+        level: f32,
+        pre_trigger: usize,
+        // Newest samples, at least one for the edge test
+        history: VecDeque<f32>,
+    },
+    Capturing(Vec<f32>),
+    Captured(Vec<f32>),
+}
+
+impl SingleShot {
+    /// Feeds one processed sample; `capture_len` is the length of a complete capture.
+    pub fn push(&mut self, s: f32, capture_len: usize) {
+        match self {
+            SingleShot::Armed {
+                level,
+                pre_trigger,
+                history,
+            } => {
+                if history.back().is_some_and(|&prev| prev < *level) && s >= *level {
+                    let mut capture = Vec::with_capacity(capture_len);
+                    capture.extend(history.iter().skip(history.len().saturating_sub(*pre_trigger)));
+                    capture.push(s);
+                    *self = SingleShot::Capturing(capture);
+                    return;
+                }
+                history.push_back(s);
+                if history.len() > (*pre_trigger).max(1) {
+                    history.pop_front();
+                }
+            }
+            SingleShot::Capturing(capture) => {
+                capture.push(s);
+                if capture.len() >= capture_len {
+                    *self = SingleShot::Captured(std::mem::take(capture));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Callbacks with more frames than this are counted as oversized.
+pub const OVERSIZED_CALLBACK_FRAMES: usize = 4096;
+/// Newest callback durations kept for `CallbackStats::duration_percentile`.
+pub const CALLBACK_DURATION_WINDOW: usize = 1000;
+
+/// Callback buffer lengths in samples (all channels), since the stream was opened.
+#[derive(Default)]
+pub struct CallbackStats {
+    pub callbacks: u64,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub last_len: usize,
+    pub oversized: u64,
+    /// Times the length differed from the previous callback, and the latest change
+    /// as (when, old length, new length).
+    pub length_changes: u64,
+    pub last_change: Option<(Instant, usize, usize)>,
+    /// Time spent in the newest callbacks, oldest first.
+    pub durations: VecDeque<Duration>,
+    /// Input samples lost because the processing thread fell behind the callback.
+    pub dropped_samples: u64,
+}
+
+impl CallbackStats {
+    /// Counts one callback of `len` samples.
+    pub fn record(&mut self, len: usize, channels: usize) {
+        if self.callbacks == 0 {
+            self.min_len = len;
+            self.max_len = len;
+        } else {
+            self.min_len = self.min_len.min(len);
+            self.max_len = self.max_len.max(len);
+            if len != self.last_len {
+                self.length_changes += 1;
+                self.last_change = Some((Instant::now(), self.last_len, len));
+            }
+        }
+        if len > OVERSIZED_CALLBACK_FRAMES * channels {
+            self.oversized += 1;
+        }
+        self.last_len = len;
+        self.callbacks += 1;
+    }
+
+    /// Keeps the duration of one callback for the percentile readout.
+    pub fn record_duration(&mut self, duration: Duration) {
+        self.durations.push_back(duration);
+        if self.durations.len() > CALLBACK_DURATION_WINDOW {
+            self.durations.pop_front();
+        }
+    }
+
+    /// The `p`-th percentile (0..=100) of the kept callback durations.
+    pub fn duration_percentile(&self, p: f32) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.durations.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f32).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+/// Tap test capture: once armed, the first sample at or above `threshold` starts a
+/// ring-down recording.
+#[derive(Default)]
+pub enum TapState {
+    #[default]
+    Idle,
+    Armed { threshold: f32 },
+    Capturing(Vec<f32>),
+    Done(Vec<f32>),
+}
+
+impl TapState {
+    /// Feeds one processed sample; `capture_len` is the length of the ring-down.
+    pub fn push(&mut self, s: f32, capture_len: usize) {
+        match self {
+            TapState::Armed { threshold } if s.abs() >= *threshold => {
+                let mut capture = Vec::with_capacity(capture_len);
+                capture.push(s);
+                *self = TapState::Capturing(capture);
+            }
+            TapState::Capturing(capture) => {
+                capture.push(s);
+                if capture.len() >= capture_len {
+                    *self = TapState::Done(std::mem::take(capture));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Everything the audio callback shares with the UI, behind one `Mutex`. Readers that
+/// only need the waveform can use `with_samples` and `latest_n_samples`.
+#[derive(Default)]
+pub struct AudioData {
+    pub device_name: String,
+    /// Mean of all channels, after processing.
+    pub samples: VecDeque<f32>,
+    /// The same samples binned by amplitude over a longer history, for the heatmap view.
+    pub heatmap: WaveformHeatmap,
+    /// Max |sample| of each complete `envelope_block` of `samples`, oldest first, and
+    /// (max, count) of the newer samples that do not fill a block yet.
+    pub envelope: VecDeque<f32>,
+    pub envelope_block: usize,
+    pub envelope_pending: (f32, usize),
+    /// Raw per-channel waveforms, same length as `samples`.
+    pub channel_samples: Vec<VecDeque<f32>>,
+    pub channel_rms: Vec<f32>,
+    /// Channels muted or left out of a solo. They are zeroed before anything reads
+    /// the block and left out of the mix-down.
+    pub channel_silenced: Vec<bool>,
+    /// The current block with the silenced channels zeroed.
+    pub silenced_block: Vec<f32>,
+    /// Newest (L, R) input frames; empty for mono devices.
+    pub stereo: VecDeque<[f32; 2]>,
+    pub rms: f32,
+    /// Newest block RMS values, served over HTTP.
+    pub rms_history: VecDeque<f32>,
+    /// Processed samples of the current callback block, for the RMS sum.
+    pub block: Vec<f32>,
+    /// Weights the RMS only; designed for the stream's rate on first use.
+    pub a_weighted: bool,
+    pub a_weighting: Option<AWeightingFilter>,
+    /// Processed samples of the current block before A-weighting, for the waveform and
+    /// the loudness meter.
+    pub unweighted_block: Vec<f32>,
+    pub lufs: Lufsometer,
+    /// Exponential average of `rms`.
+    pub rms_smoother: AudioSmoother,
+    pub rms_stats: RollingStats,
+    /// Zero-crossing rate of the last processed block, per sample.
+    pub zcr: f32,
+    pub zcr_stats: RollingStats,
+    /// (samples_written at the block end, ZCR) for the blocks still in `samples`.
+    pub zcr_history: VecDeque<(u64, f32)>,
+    pub amplitude: f32,
+    /// Highest block amplitude, raised by the callback and decayed by the UI.
+    pub peak_hold: f32,
+    /// When `peak_hold` was last raised or decayed.
+    pub peak_hold_age: Option<Instant>,
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub tap: TapState,
+    pub single_shot: SingleShot,
+    pub gain_rider: GainRider,
+    pub gain_rider_enabled: bool,
+    pub digital_gain_db: f32,
+    /// Block RMS of the input before any gain, and when the gain last pushed a sample
+    /// past full scale.
+    pub pre_gain_rms: f32,
+    pub last_gain_clip: Option<Instant>,
+    /// Scales only the plotted waveform towards a steady level; the meters never see it.
+    pub agc: Agc,
+    pub agc_enabled: bool,
+    pub feedback: FeedbackSquealDetector,
+    pub feedback_enabled: bool,
+    pub pressure_gradient: bool,
+    pub noise_gate: FrequencyDomainNoiseGate,
+    pub noise_gate_enabled: bool,
+    /// Learns its noise spectrum during the noise floor calibration.
+    pub spectral_subtraction: SpectralSubtraction,
+    pub spectral_subtraction_enabled: bool,
+    pub bin_floor: Vec<f32>,
+    pub tones: ToneDetectorBank,
+    pub leq: LeqMeter,
+    pub sel: SelHistory,
+    /// Arms a `sel` measurement on every onset, so claps are measured hands-free.
+    pub sel_on_onset: bool,
+    /// Runs on the smoothed RMS; finished quiet periods wait in `silence_events` for the UI.
+    pub silence: SilenceDetector,
+    pub silence_enabled: bool,
+    pub silence_events: Vec<SilenceEvent>,
+    pub wind: WindNoiseFilter,
+    pub wind_enabled: bool,
+    pub peq: Vec<Biquad>,
+    /// Samples of the current block right after the parametric EQ.
+    pub eq_block: Vec<f32>,
+    /// Band levels of the input after the EQ while a room sweep is measured.
+    pub room_response: Option<RoomResponseMeter>,
+    /// Mic input of the current block before gain, filters and EQ.
+    pub field_block: Vec<f32>,
+    /// Fed with `field_block` while the field calibrator measures.
+    pub field_measurement: Option<FieldMeasurement>,
+    pub filter: AudioFilter,
+    /// Blocks with any raw input sample at or beyond full scale.
+    pub clip_count: u64,
+    pub last_clip: Option<Instant>,
+    /// Samples pushed to `samples` so far, and the absolute indices of clipped ones.
+    pub samples_written: u64,
+    pub clip_positions: VecDeque<u64>,
+    /// Newest processed samples for the pitch detector.
+    pub pitch_frame: VecDeque<f32>,
+    pub onset: OnsetDetector,
+    /// Set by the callback on an onset, cleared by the UI when it starts the flash.
+    pub onset_detected: bool,
+    /// `samples_written` index at the start of the latest onset block, the beat grid anchor.
+    pub last_onset_position: Option<u64>,
+    pub drums: DrumPatternRecognizer,
+    /// Latest classified drum hit, cleared by the UI when it flashes the grid.
+    pub drum_hit: Option<Drum>,
+    /// (sum of squares, frames) while the noise floor is being calibrated.
+    pub noise_calibration: Option<(f32, usize)>,
+    pub calibration: CalibrationWizard,
+    /// Raw interleaved input while recording.
+    pub recording: Option<Vec<f32>>,
+    /// A recording cut short because the stream was reopened in another format, with
+    /// its channel count and sample rate. The UI offers to save it.
+    pub interrupted_recording: Option<(Vec<f32>, u16, u32)>,
+    /// Raw interleaved input while capturing to a file.
+    pub capture: Option<CaptureSender>,
+    /// Format of the open stream, and the frames in its latest callback.
+    pub stream_config: Option<cpal::SupportedStreamConfig>,
+    pub block_frames: usize,
+    /// Feeds the OSC sender thread while OSC output is enabled.
+    pub osc: Option<channel::Sender<OscMetrics>>,
+    pub callback_stats: CallbackStats,
+}
+
+impl AudioData {
+    /// Appends to the waveform and drops the oldest samples beyond `max_len`. For
+    /// callers that already hold the lock; see also the free `push_samples`.
+    pub fn push_samples(&mut self, input: &[f32], max_len: usize) {
+        self.samples.extend(input);
+        let excess = self.samples.len().saturating_sub(max_len);
+        self.samples.drain(..excess);
+
+        let block = self.envelope_block.max(1);
+        for &s in input {
+            let (max, count) = &mut self.envelope_pending;
+            *max = max.max(s.abs());
+            *count += 1;
+            if *count == block {
+                let (max, _) = std::mem::take(&mut self.envelope_pending);
+                self.envelope.push_back(max);
+            }
+        }
+        // Blocks that reach past the oldest sample go with it
+        let whole_blocks = self.samples.len().saturating_sub(self.envelope_pending.1) / block;
+        let excess = self.envelope.len().saturating_sub(whole_blocks);
+        self.envelope.drain(..excess);
+    }
+
+    /// Rebuilds the envelope from the current waveform for a new block size, dropping
+    /// the oldest samples that do not fill a block. Unknown sizes fall back to the default.
+    pub fn set_envelope_block(&mut self, block: usize) {
+        self.envelope_block = if ENVELOPE_BLOCKS.contains(&block) {
+            block
+        } else {
+            DEFAULT_ENVELOPE_BLOCK
+        };
+        let skip = self.samples.len() % self.envelope_block;
+        let samples = self.samples.make_contiguous();
+        self.envelope = samples[skip..]
+            .chunks_exact(self.envelope_block)
+            .map(|chunk| chunk.iter().fold(0.0f32, |m, s| m.max(s.abs())))
+            .collect();
+        self.envelope_pending = (0.0, 0);
+    }
+}
+
+/// Locks `data` and appends `input` to the waveform, keeping at most `max_len` samples.
+pub fn push_samples(data: &Mutex<AudioData>, input: &[f32], max_len: usize) {
+    data.lock().unwrap().push_samples(input, max_len);
+}
+
+/// Runs `f` on the processed waveform, oldest sample first, while holding the lock.
+/// Keep `f` short: the audio callback waits for it.
+pub fn with_samples<F, R>(data: &Mutex<AudioData>, f: F) -> R
+where
+    F: FnOnce(&[f32]) -> R,
+{
+    let mut data = data.lock().unwrap();
+    f(data.samples.make_contiguous())
+}
+
+/// Copies at most the newest `n` processed samples, so the work on them can run
+/// without the lock.
+pub fn latest_n_samples(data: &Mutex<AudioData>, n: usize) -> Vec<f32> {
+    with_samples(data, |samples| samples[samples.len().saturating_sub(n)..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_samples_keeps_the_newest_max_len() {
+        let data = Mutex::new(AudioData::default());
+        push_samples(&data, &[1.0, 2.0, 3.0], 4);
+        push_samples(&data, &[4.0, 5.0], 4);
+        assert_eq!(with_samples(&data, |samples| samples.to_vec()), [2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(latest_n_samples(&data, 2), [4.0, 5.0]);
+        assert_eq!(latest_n_samples(&data, 10).len(), 4);
+    }
+
+    #[test]
+    fn envelope_holds_the_block_maxima_of_the_kept_samples() {
+        let mut data = AudioData::default();
+        data.set_envelope_block(4);
+        data.push_samples(&[0.1, -0.5, 0.2, 0.0, 0.3, 0.3, -0.9, 0.1, 0.4], 100);
+        assert_eq!(data.envelope, [0.5, 0.9]);
+        assert_eq!(data.envelope_pending, (0.4, 1));
+
+        // Trimming to 5 samples leaves one whole block before the pending sample
+        data.push_samples(&[], 5);
+        assert_eq!(data.envelope, [0.9]);
+    }
+
+    #[test]
+    fn unknown_envelope_block_falls_back_to_the_default() {
+        let mut data = AudioData::default();
+        data.set_envelope_block(5);
+        assert_eq!(data.envelope_block, DEFAULT_ENVELOPE_BLOCK);
+    }
+
+    #[test]
+    fn duration_percentile_uses_the_nearest_rank() {
+        let mut stats = CallbackStats::default();
+        assert_eq!(stats.duration_percentile(99.0), None);
+        for us in (1..=100).rev() {
+            stats.record_duration(Duration::from_micros(us));
+        }
+        assert_eq!(stats.duration_percentile(99.0), Some(Duration::from_micros(99)));
+        assert_eq!(stats.duration_percentile(50.0), Some(Duration::from_micros(50)));
+        assert_eq!(stats.duration_percentile(100.0), Some(Duration::from_micros(100)));
+    }
+
+    #[test]
+    fn integer_samples_convert_to_unit_range() {
+        assert_eq!(i16_to_f32(i16::MAX), 1.0);
+        assert_eq!(i16_to_f32(0), 0.0);
+        assert_eq!(u16_to_f32(32768), 0.0);
+        assert_eq!(u16_to_f32(u16::MAX), 1.0);
+    }
+
+    #[test]
+    fn integer_minimum_lands_just_below_minus_one() {
+        let expected = -32768.0 / 32767.0;
+        assert_eq!(i16_to_f32(i16::MIN), expected);
+        assert_eq!(u16_to_f32(0), expected);
+    }
+
+    #[test]
+    fn u16_is_i16_offset_by_half_the_range() {
+        for s in (i16::MIN..=i16::MAX).step_by(97) {
+            let offset = (s as i32 + 32768) as u16;
+            assert_eq!(u16_to_f32(offset), i16_to_f32(s), "{}", s);
+        }
+    }
+}
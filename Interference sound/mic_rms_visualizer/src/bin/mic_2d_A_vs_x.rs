@@ -1,20 +1,89 @@
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::Instant;
 
-use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use cpal::traits::{DeviceTrait, StreamTrait};
 use crossbeam::channel;
 use eframe::egui::{self, Slider};
-use egui_plot::{Line, Plot, PlotPoints};
+use egui_plot::{Line, LineStyle, Plot, PlotPoints, Points};
+
+use mic_rms_visualizer::audio::{build_input_stream_dynamic, latest_n_samples, push_samples, AudioData};
+use mic_rms_visualizer::device::{find_input_device, input_config};
+use mic_rms_visualizer::dsp::rms::rms_simd;
+use mic_rms_visualizer::dsp::spectrum::spectral_peaks;
+use mic_rms_visualizer::report::{write_pdf, SessionReport};
+use mic_rms_visualizer::ring::spawn_block_processor;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+use mic_rms_visualizer::session::{spectrum_points, Session, SESSION_EXTENSION};
+use mic_rms_visualizer::stream_guard::{AudioStreamGuard, StreamErrorFlag, StreamStatus};
+
+// Samples kept for the report's spectrum (channel 0)
+const SPECTRUM_LEN: usize = 8192;
+
+const CSV_HEADER: &str = "x_position,rms_amplitude,temperature_c,humidity_pct";
+// Files exported before the air columns were added
+const CSV_HEADER_WITHOUT_AIR: &str = "x_position,rms_amplitude";
+
+// Heatmap strip under the plot: one cell per HEATMAP_CELL_PX of plot width
+const HEATMAP_CELL_PX: f32 = 4.0;
+const HEATMAP_HEIGHT: f32 = 24.0;
+
+// Polar mode: X is an angle in degrees, 0° at the top and increasing clockwise
+const POLAR_MAX_DEG: f32 = 360.0;
+const POLAR_RING_STEP: f64 = 0.05;
+// Smallest outer ring, matching the Cartesian plot's default Y range
+const POLAR_MIN_RADIUS: f64 = 0.2;
+const POLAR_SPOKE_DEG: usize = 30;
+// Measured points are joined in 1° steps so the line follows the circle
+const POLAR_CURVE_STEP_DEG: f64 = 1.0;
+
+#[derive(Default)]
+struct SessionInfo {
+    device_name: String,
+    sample_rate: u32,
+}
+
+#[derive(Parser)]
+#[command(about = "Microphone RMS amplitude against a manually set X position")]
+struct Args {
+    /// Input device, matched as a case-insensitive substring of its name
+    #[arg(long)]
+    device: Option<String>,
+    /// Sample rate in Hz; the device default is used if omitted
+    #[arg(long)]
+    sample_rate: Option<u32>,
+    /// Upper end of the X position slider
+    #[arg(long, default_value_t = 100.0)]
+    x_max: f32,
+}
 
 fn main() {
+    let args = Args::parse();
+    let host = cpal::default_host();
+    let (device, config) = find_input_device(&host, args.device.as_deref())
+        .and_then(|device| {
+            let config = input_config(&device, args.sample_rate)?;
+            Ok((device, config))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        });
+
     let (sender, receiver) = channel::bounded::<(f32, f32)>(1024);
     let x_position = Arc::new(Mutex::new(0.0));
     let x_clone = Arc::clone(&x_position);
+    let session = Arc::new(Mutex::new(SessionInfo::default()));
+    let session_clone = Arc::clone(&session);
+    let recent_samples = Arc::new(Mutex::new(AudioData::default()));
+    let recent_clone = Arc::clone(&recent_samples);
+    let stream_status = Arc::new(Mutex::new(StreamStatus::Running));
+    let status_clone = Arc::clone(&stream_status);
 
     thread::spawn(move || {
-        if let Err(e) = capture_audio(sender, x_clone) {
+        if let Err(e) = capture_audio(device, config, sender, x_clone, session_clone, recent_clone, status_clone) {
             eprintln!("Audio thread error: {:?}", e);
         }
     });
@@ -24,6 +93,26 @@ fn main() {
         values: Vec::new(),
         x_position,
         mic_locked: true, // Default locked
+        x_max: args.x_max,
+        x_text: format!("{:.2}", 0.0),
+        x_text_invalid: false,
+        x_text_editing: false,
+        show_heatmap: false,
+        polar: false,
+        interpolate: false,
+        interpolation_step: 0.5,
+        session,
+        recent_samples,
+        stream_status,
+        organization: String::new(),
+        started: Instant::now(),
+        started_at: chrono::Local::now(),
+        report_status: None,
+        append_on_import: false,
+        csv_status: None,
+        temperature_c: 20.0,
+        humidity_pct: 50.0,
+        screenshots: ScreenshotExporter::default(),
     };
 
     let native_options = eframe::NativeOptions::default();
@@ -36,62 +125,377 @@ fn main() {
 }
 
 fn capture_audio(
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
     sender: channel::Sender<(f32, f32)>,
     x_position: Arc<Mutex<f32>>,
+    session: Arc<Mutex<SessionInfo>>,
+    recent_samples: Arc<Mutex<AudioData>>,
+    status: Arc<Mutex<StreamStatus>>,
 ) -> Result<()> {
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .expect("No input device available");
-    let config = device.default_input_config()?;
-
-    let stream = device.build_input_stream(
-        &config.into(),
-        move |data: &[f32], _| {
-            if data.is_empty() {
+    let channels = config.channels() as usize;
+    *session.lock().unwrap() = SessionInfo {
+        device_name: device.name().unwrap_or_else(|_| "Unknown device".to_owned()),
+        sample_rate: config.sample_rate().0,
+    };
+
+    let build = move |errors: &StreamErrorFlag| -> Result<cpal::Stream> {
+        let sender = sender.clone();
+        let x_position = Arc::clone(&x_position);
+        let recent_samples = Arc::clone(&recent_samples);
+        // The callback only queues its block; the locks are taken on the processing thread
+        // First channel of each block, reused across blocks
+        let mut mono = Vec::new();
+        let capacity = config.sample_rate().0 as usize * channels;
+        let mut producer = spawn_block_processor(capacity, channels, move |_, block| {
+            if block.is_empty() {
                 return;
             }
-            let rms = (data.iter().map(|&s| s * s).sum::<f32>() / data.len() as f32).sqrt();
+            mono.clear();
+            mono.extend(block.chunks(channels).map(|frame| frame[0]));
+            push_samples(&recent_samples, &mono, SPECTRUM_LEN);
+            let rms = rms_simd(block);
             if rms > 0.01 {
                 let x = *x_position.lock().unwrap();
                 let _ = sender.send((x, rms));
             }
-        },
-        move |err| {
-            eprintln!("Stream error: {:?}", err);
-        },
-        None,
-    )?;
+        });
+        let stream = build_input_stream_dynamic(
+            &device,
+            &config,
+            move |data: &[f32], _, started| producer.push(data, started),
+            errors.callback(),
+        )?;
+        stream.play()?;
+        Ok(stream)
+    };
 
-    stream.play()?;
-    loop {
-        std::thread::sleep(Duration::from_secs(1));
-    }
+    AudioStreamGuard::new(status, build)?.run()
 }
 
 struct AudioPlotApp {
     receiver: channel::Receiver<(f32, f32)>,
-    values: Vec<(f32, f32)>,
+    // (x, (sum of amplitudes, readings)) per rounded position
+    values: Vec<(f32, (f32, u32))>,
     x_position: Arc<Mutex<f32>>,
     mic_locked: bool,
+    x_max: f32,
+    // Typed X position; shown red and not applied while it does not parse into range
+    x_text: String,
+    x_text_invalid: bool,
+    x_text_editing: bool,
+    show_heatmap: bool,
+    // Slider in degrees and a polar diagram instead of the plot; `values` are shared
+    polar: bool,
+    // Draw straight-line fill-in points every `interpolation_step` between measured positions
+    interpolate: bool,
+    interpolation_step: f32,
+    session: Arc<Mutex<SessionInfo>>,
+    recent_samples: Arc<Mutex<AudioData>>,
+    stream_status: Arc<Mutex<StreamStatus>>,
+    organization: String,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Local>,
+    report_status: Option<String>,
+    append_on_import: bool,
+    csv_status: Option<String>,
+    // Air at the measurement, written next to every exported point
+    temperature_c: f32,
+    humidity_pct: f32,
+    screenshots: ScreenshotExporter,
+}
+
+impl AudioPlotApp {
+    // Upper end of the position slider: degrees in polar mode, `--x-max` otherwise
+    fn slider_max(&self) -> f32 {
+        if self.polar {
+            POLAR_MAX_DEG
+        } else {
+            self.x_max
+        }
+    }
+
+    fn record(&mut self, x: f32, a: f32) {
+        let x_rounded = (x * 100.0).round() / 100.0;
+        if let Some((_, (sum, count))) = self.values.iter_mut().find(|(ex, _)| *ex == x_rounded) {
+            *sum += a;
+            *count += 1;
+        } else {
+            self.values.push((x_rounded, (a, 1)));
+        }
+    }
+
+    /// Mean amplitude at each position.
+    fn averages(&self) -> Vec<(f32, f32)> {
+        self.values
+            .iter()
+            .map(|&(x, (sum, count))| (x, sum / count as f32))
+            .collect()
+    }
+
+    fn generate_report(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .set_file_name("measurement_report.pdf")
+            .save_file()
+        else {
+            return;
+        };
+
+        let samples = latest_n_samples(&self.recent_samples, SPECTRUM_LEN);
+        let values = self.averages();
+        let session = self.session.lock().unwrap();
+        let peaks = spectral_peaks(&samples, session.sample_rate, 5);
+        let report = SessionReport {
+            organization: &self.organization,
+            device_name: &session.device_name,
+            sample_rate: session.sample_rate,
+            date: self.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            duration: self.started.elapsed(),
+            values: &values,
+            peaks: &peaks,
+        };
+
+        self.report_status = Some(match write_pdf(&path, &report) {
+            Ok(()) => format!("Report saved to {}", path.display()),
+            Err(e) => format!("Failed to write report: {}", e),
+        });
+    }
+
+    fn export_csv(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("amplitude_vs_x.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        let written = write_values_csv(&path, &self.averages(), self.temperature_c, self.humidity_pct);
+        self.csv_status = Some(match written {
+            Ok(()) => format!("Exported {} points to {}", self.values.len(), path.display()),
+            Err(e) => format!("Failed to export CSV: {}", e),
+        });
+    }
+
+    // The CSV rows plus the current spectrum, for comparison in `mic_session_compare`
+    fn save_session(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Session", &[SESSION_EXTENSION])
+            .set_file_name(format!("session.{}", SESSION_EXTENSION))
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut values = self.averages();
+        values.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let samples = latest_n_samples(&self.recent_samples, SPECTRUM_LEN);
+        let info = self.session.lock().unwrap();
+        let session = Session {
+            device_name: info.device_name.clone(),
+            sample_rate: info.sample_rate,
+            date: self.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            temperature_c: self.temperature_c,
+            humidity_pct: self.humidity_pct,
+            spectrum: spectrum_points(&samples, info.sample_rate),
+            values,
+            ..Session::default()
+        };
+        drop(info);
+
+        self.csv_status = Some(match session.save(&path) {
+            Ok(()) => format!("Saved session with {} points to {}", session.values.len(), path.display()),
+            Err(e) => format!("Failed to save session: {:#}", e),
+        });
+    }
+
+    fn import_csv(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
+            return;
+        };
+
+        self.csv_status = Some(match read_values_csv(&path) {
+            Ok(values) => {
+                let count = values.len();
+                if !self.append_on_import {
+                    self.values.clear();
+                }
+                for (x, a) in values {
+                    self.record(x, a);
+                }
+                format!("Imported {} points from {}", count, path.display())
+            }
+            Err(e) => format!("Failed to import CSV: {:#}", e),
+        });
+    }
+}
+
+fn write_values_csv(
+    path: &std::path::Path,
+    values: &[(f32, f32)],
+    temperature_c: f32,
+    humidity_pct: f32,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "{}", CSV_HEADER)?;
+    for (x, rms) in values {
+        writeln!(file, "{:.6},{:.6},{:.1},{:.1}", x, rms, temperature_c, humidity_pct)?;
+    }
+    file.flush()
+}
+
+fn read_values_csv(path: &std::path::Path) -> Result<Vec<(f32, f32)>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut lines = text.lines();
+    let header = lines.next().map(str::trim);
+    if header != Some(CSV_HEADER) && header != Some(CSV_HEADER_WITHOUT_AIR) {
+        return Err(anyhow!("Expected header \"{}\"", CSV_HEADER));
+    }
+
+    lines
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let parse = || -> Option<(f32, f32)> {
+                // The air columns are informational and not read back
+                let mut fields = line.trim().split(',');
+                let (x, rms) = (fields.next()?, fields.next()?);
+                Some((x.trim().parse().ok()?, rms.trim().parse().ok()?))
+            };
+            parse().with_context(|| format!("Invalid row {}: \"{}\"", i + 2, line))
+        })
+        .collect()
+}
+
+// Amplitude along X as a colour strip under the plot, using the plot's X transform so
+// the two pan and zoom together. Cells without a measured position are gray.
+fn heatmap_strip(ui: &mut egui::Ui, transform: &egui_plot::PlotTransform, points: &[[f64; 2]]) {
+    let frame = transform.frame();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), HEATMAP_HEIGHT), egui::Sense::hover());
+    let rect = egui::Rect::from_x_y_ranges(frame.x_range(), rect.y_range());
+    let painter = ui.painter_at(rect);
+    let max = points.iter().fold(0.0f64, |m, p| m.max(p[1]));
+
+    let cells = (rect.width() / HEATMAP_CELL_PX).ceil() as usize;
+    for cell in 0..cells {
+        let left = rect.left() + cell as f32 * HEATMAP_CELL_PX;
+        let right = (left + HEATMAP_CELL_PX).min(rect.right());
+        let x_range = transform.value_from_position(egui::pos2(left, rect.top())).x
+            ..transform.value_from_position(egui::pos2(right, rect.top())).x;
+        let (sum, count) = points
+            .iter()
+            .filter(|p| x_range.contains(&p[0]))
+            .fold((0.0, 0), |(sum, count), p| (sum + p[1], count + 1));
+        let color = if count == 0 || max <= 0.0 {
+            egui::Color32::GRAY
+        } else {
+            // Blue at zero, red at the loudest position
+            let t = (sum / count as f64 / max) as f32;
+            egui::Color32::from_rgb((255.0 * t) as u8, 0, (255.0 * (1.0 - t)) as u8)
+        };
+        painter.rect(
+            egui::Rect::from_x_y_ranges(left..=right, rect.y_range()),
+            0.0,
+            color,
+            egui::Stroke::NONE,
+        );
+    }
+}
+
+// Measured points in X order with linearly interpolated points every `step` between
+// neighbours; for drawing only
+fn interpolate(points: &[[f64; 2]], step: f64) -> Vec<[f64; 2]> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]));
+
+    let mut filled = Vec::with_capacity(sorted.len());
+    for pair in sorted.windows(2) {
+        let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+        filled.push(pair[0]);
+        let mut x = x0 + step;
+        while x < x1 {
+            filled.push([x, y0 + (y1 - y0) * (x - x0) / (x1 - x0)]);
+            x += step;
+        }
+    }
+    filled.extend(sorted.last());
+    filled
+}
+
+// Radius = amplitude, angle = X in degrees, with rings every POLAR_RING_STEP and
+// spokes every POLAR_SPOKE_DEG. `curve` is drawn dashed for interpolated data.
+fn polar_diagram(ui: &mut egui::Ui, points: &[[f64; 2]], curve: &[[f64; 2]], dashed: bool) {
+    let size = ui.available_width().min(ui.available_height()).max(200.0);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let center = rect.center();
+    // Room for the angle labels outside the outer ring
+    let radius_px = size / 2.0 - 24.0;
+
+    let max = points.iter().fold(POLAR_MIN_RADIUS, |m, p| m.max(p[1]));
+    let rings = (max / POLAR_RING_STEP).ceil().max(1.0) as usize;
+    let outer = rings as f64 * POLAR_RING_STEP;
+    let to_screen = |deg: f64, amplitude: f64| {
+        let r = (amplitude / outer) as f32 * radius_px;
+        let theta = deg.to_radians() as f32;
+        center + egui::vec2(r * theta.sin(), -r * theta.cos())
+    };
+
+    let guide = egui::Stroke::new(1.0, egui::Color32::from_gray(70));
+    let text_color = egui::Color32::from_gray(160);
+    let font = egui::FontId::proportional(11.0);
+    for ring in 1..=rings {
+        let amplitude = ring as f64 * POLAR_RING_STEP;
+        painter.circle_stroke(center, (amplitude / outer) as f32 * radius_px, guide);
+        painter.text(
+            to_screen(0.0, amplitude),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.2}", amplitude),
+            font.clone(),
+            text_color,
+        );
+    }
+    for deg in (0..360).step_by(POLAR_SPOKE_DEG) {
+        painter.line_segment([center, to_screen(deg as f64, outer)], guide);
+        painter.text(
+            to_screen(deg as f64, outer * 1.08),
+            egui::Align2::CENTER_CENTER,
+            format!("{}°", deg),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let path: Vec<egui::Pos2> = curve.iter().map(|p| to_screen(p[0], p[1])).collect();
+    if dashed {
+        let stroke = egui::Stroke::new(1.5, egui::Color32::from_gray(160));
+        painter.extend(egui::Shape::dashed_line(&path, stroke, 6.0, 4.0));
+    } else {
+        painter.add(egui::Shape::line(path, egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE)));
+    }
+    for p in points {
+        painter.circle_filled(to_screen(p[0], p[1]), 3.0, egui::Color32::LIGHT_BLUE);
+    }
+}
+
+// A typed X position within the slider range
+fn parse_x(text: &str, x_max: f32) -> Option<f32> {
+    let x: f32 = text.trim().parse().ok()?;
+    (0.0..=x_max).contains(&x).then_some(x)
 }
 
 impl eframe::App for AudioPlotApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
         // Only update sound if unlocked
         if !self.mic_locked {
             while let Ok((x, a)) = self.receiver.try_recv() {
                 if a > 0.01 {
-                    let x_rounded = (x * 100.0).round() / 100.0;
-
-                    // Always update amplitude at that position
-                    if let Some(existing) = self.values.iter_mut().find(|(ex, _)| {
-                        (*ex * 100.0).round() / 100.0 == x_rounded
-                    }) {
-                        existing.1 = a;
-                    } else {
-                        self.values.push((x_rounded, a));
-                    }
+                    // Average every reading at that position
+                    self.record(x, a);
                 }
             }
         } else {
@@ -104,12 +508,71 @@ impl eframe::App for AudioPlotApp {
             .sort_by(|(x1, _), (x2, _)| x1.partial_cmp(x2).unwrap_or(std::cmp::Ordering::Equal));
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.label("Adjust X position manually:");
-            let mut x = *self.x_position.lock().unwrap();
-            if ui.add(Slider::new(&mut x, 0.0..=100.0).text("X Position")).changed() {
-                *self.x_position.lock().unwrap() = x;
+            // Yellow while the stream is being rebuilt, red once recovery has given up
+            let status = self.stream_status.lock().unwrap().clone();
+            if let Some(message) = status.message() {
+                let color = match status {
+                    StreamStatus::Failed(_) => egui::Color32::RED,
+                    _ => egui::Color32::YELLOW,
+                };
+                ui.label(egui::RichText::new(message).strong().color(egui::Color32::BLACK).background_color(color));
             }
 
+            ui.horizontal(|ui| {
+                let cartesian = ui.selectable_value(&mut self.polar, false, "Cartesian").changed();
+                let polar = ui.selectable_value(&mut self.polar, true, "Polar").changed();
+                // Keep the current position inside the new slider range
+                if cartesian || polar {
+                    let mut x = self.x_position.lock().unwrap();
+                    *x = x.clamp(0.0, self.slider_max());
+                    self.x_text_invalid = false;
+                }
+            });
+
+            let x_max = self.slider_max();
+            ui.label(if self.polar {
+                "Adjust source angle manually:"
+            } else {
+                "Adjust X position manually:"
+            });
+            ui.horizontal(|ui| {
+                let mut x = *self.x_position.lock().unwrap();
+                let slider = if self.polar {
+                    Slider::new(&mut x, 0.0..=x_max).suffix("°").text("Angle")
+                } else {
+                    Slider::new(&mut x, 0.0..=x_max).text("X Position")
+                };
+                if ui.add(slider).changed() {
+                    *self.x_position.lock().unwrap() = x;
+                    self.x_text_invalid = false;
+                }
+                if !self.x_text_editing && !self.x_text_invalid {
+                    self.x_text = format!("{:.2}", x);
+                }
+
+                let response = ui
+                    .scope(|ui| {
+                        if self.x_text_invalid {
+                            ui.visuals_mut().extreme_bg_color = egui::Color32::from_rgb(120, 20, 20);
+                        }
+                        ui.add(egui::TextEdit::singleline(&mut self.x_text).desired_width(70.0))
+                    })
+                    .inner
+                    .on_hover_text(format!("Exact position, 0 to {}", x_max));
+                self.x_text_editing = response.has_focus();
+                // Enter also ends editing of a single-line field
+                if response.lost_focus() {
+                    match parse_x(&self.x_text, x_max) {
+                        Some(x) => {
+                            *self.x_position.lock().unwrap() = x;
+                            self.x_text = format!("{:.2}", x);
+                            self.x_text_invalid = false;
+                        }
+                        None => self.x_text_invalid = true,
+                    }
+                }
+            });
+
             ui.separator();
 
             // Lock toggle
@@ -123,21 +586,169 @@ impl eframe::App for AudioPlotApp {
                 });
             });
 
-            let plot_points: PlotPoints = self
-                .values
+            ui.horizontal(|ui| {
+                ui.label("Organization / project:");
+                ui.text_edit_singleline(&mut self.organization);
+                if ui.button("Generate Report").clicked() {
+                    self.generate_report();
+                }
+            });
+            if let Some(status) = &self.report_status {
+                ui.label(status);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Export CSV").clicked() {
+                    self.export_csv();
+                }
+                if ui.button("Import CSV").clicked() {
+                    self.import_csv();
+                }
+                if ui.button("Save session…").clicked() {
+                    self.save_session();
+                }
+                ui.checkbox(&mut self.append_on_import, "Append on import");
+                ui.label("Air:");
+                ui.add(egui::DragValue::new(&mut self.temperature_c).clamp_range(-30.0..=50.0).speed(0.1).suffix(" °C"));
+                ui.add(egui::DragValue::new(&mut self.humidity_pct).clamp_range(0.0..=100.0).speed(0.5).suffix(" %"));
+                ui.add_enabled(!self.polar, egui::Checkbox::new(&mut self.show_heatmap, "Heatmap"));
+                if ui.button("Reset averages").clicked() {
+                    self.values.clear();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.interpolate, "Interpolate");
+                ui.add_enabled(
+                    self.interpolate,
+                    egui::DragValue::new(&mut self.interpolation_step)
+                        .speed(0.01)
+                        .clamp_range(0.01..=x_max.max(0.01))
+                        .prefix("step "),
+                );
+            });
+            if let Some(status) = &self.csv_status {
+                ui.label(status);
+            }
+
+            let points: Vec<[f64; 2]> = self
+                .averages()
                 .iter()
                 .map(|(x, y)| [*x as f64, *y as f64])
                 .collect();
+            let counts: Vec<(f64, u32)> = self.values.iter().map(|&(x, (_, count))| (x as f64, count)).collect();
+
+            if self.polar {
+                let step = if self.interpolate {
+                    self.interpolation_step as f64
+                } else {
+                    POLAR_CURVE_STEP_DEG
+                };
+                polar_diagram(ui, &points, &interpolate(&points, step), self.interpolate);
+                return;
+            }
 
-            Plot::new("amplitude_vs_x")
+            let heatmap_points = self.show_heatmap.then(|| points.clone());
+            let interpolated = self
+                .interpolate
+                .then(|| interpolate(&points, self.interpolation_step as f64));
+            let plot = Plot::new("amplitude_vs_x")
                 .view_aspect(2.0)
                 .include_y(0.0)
                 .include_y(0.2)
+                // Hovering shows how many readings the nearest position is averaged over
+                .label_formatter(move |_, value| {
+                    let nearest = counts
+                        .iter()
+                        .min_by(|a, b| (a.0 - value.x).abs().total_cmp(&(b.0 - value.x).abs()));
+                    match nearest {
+                        Some(&(x, count)) => format!("x = {:.2}\nRMS = {:.4}\n{} readings", x, value.y, count),
+                        None => String::new(),
+                    }
+                })
                 .show(ui, |plot_ui| {
-                    plot_ui.line(Line::new(plot_points).name("RMS Amplitude"));
+                    match interpolated {
+                        Some(interpolated) => plot_ui.line(
+                            Line::new(PlotPoints::from(interpolated))
+                                .color(egui::Color32::from_gray(160))
+                                .style(LineStyle::dashed_dense())
+                                .name("Interpolated"),
+                        ),
+                        None => plot_ui.line(Line::new(PlotPoints::from(points.clone())).name("RMS Amplitude")),
+                    }
+                    plot_ui.points(Points::new(points).radius(3.0).name("Positions"));
                 });
+            if let Some(points) = heatmap_points {
+                heatmap_strip(ui, &plot.transform, &points);
+            }
         });
 
         ctx.request_repaint();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn args_parse_device_sample_rate_and_x_max() {
+        Args::command().debug_assert();
+        let args = Args::try_parse_from(["mic_2d_A_vs_x", "--device", "usb", "--sample-rate", "96000"]).unwrap();
+        assert_eq!(args.device.as_deref(), Some("usb"));
+        assert_eq!(args.sample_rate, Some(96_000));
+        assert_eq!(args.x_max, 100.0);
+    }
+
+    fn temp_csv(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.csv", name, std::process::id()))
+    }
+
+    #[test]
+    fn csv_rows_round_trip() {
+        let path = temp_csv("values-round-trip");
+        let values = [(0.0, 0.125), (0.5, 0.25), (1.25, 0.0625)];
+        write_values_csv(&path, &values, 21.5, 40.0).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let read = read_values_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                CSV_HEADER,
+                "0.000000,0.125000,21.5,40.0",
+                "0.500000,0.250000,21.5,40.0",
+                "1.250000,0.062500,21.5,40.0"
+            ]
+        );
+        assert_eq!(read, values);
+    }
+
+    #[test]
+    fn csv_import_reads_files_without_air_columns() {
+        let path = temp_csv("values-without-air");
+        std::fs::write(&path, format!("{}\n0.5,0.25\n", CSV_HEADER_WITHOUT_AIR)).unwrap();
+        let read = read_values_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(read, [(0.5, 0.25)]);
+    }
+
+    #[test]
+    fn csv_import_reports_the_bad_row() {
+        let path = temp_csv("values-bad-row");
+        std::fs::write(&path, format!("{}\n0.1,0.2\n\n0.3,abc\n", CSV_HEADER)).unwrap();
+        let error = read_values_csv(&path).unwrap_err();
+        let _ = std::fs::remove_file(&path);
+        assert!(error.to_string().contains("row 4"), "{}", error);
+    }
+
+    #[test]
+    fn csv_import_needs_the_header() {
+        let path = temp_csv("values-no-header");
+        std::fs::write(&path, "0.1,0.2\n").unwrap();
+        let result = read_values_csv(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+}
@@ -1,48 +1,191 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::path::Path;
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use kiss3d::camera::{FirstPerson};
-use kiss3d::event::{Action, Key, WindowEvent};
+use anyhow::{Context, Result};
+use clap::Parser;
+use cpal::traits::StreamTrait;
+use kiss3d::camera::{Camera, FirstPerson};
+use kiss3d::context::Context as GlContext;
+use kiss3d::event::{Action, Key, Modifiers, MouseButton, WindowEvent};
 use kiss3d::light::Light;
-use kiss3d::nalgebra::{Point2, Point3, Translation3, Vector3};
-use kiss3d::resource::Mesh;
-use kiss3d::scene::SceneNode;
+use kiss3d::nalgebra::{Isometry3, Matrix3, Matrix4, Point2, Point3, Translation3, Vector2, Vector3};
+use kiss3d::resource::{Effect, Material, Mesh, ShaderAttribute, ShaderUniform};
+use kiss3d::scene::{ObjectData, SceneNode};
+use kiss3d::text::Font;
 use kiss3d::window::Window;
 
+use mic_rms_visualizer::audio::{build_input_stream_dynamic, latest_n_samples, push_samples, AudioData};
+use mic_rms_visualizer::device::{find_input_device, input_config};
+use mic_rms_visualizer::dsp::spectrum::{dominant_band, FREQUENCY_BANDS};
+use mic_rms_visualizer::ring::spawn_block_processor;
+use mic_rms_visualizer::room::RoomBox;
+use mic_rms_visualizer::screenshot::screenshot_path;
+use mic_rms_visualizer::stream_guard::{AudioStreamGuard, StreamErrorFlag, StreamStatus};
+use serde::{Deserialize, Serialize};
+
+// Samples kept for the band analysis taken when a point is placed
+const SNAPSHOT_LEN: usize = 4096;
+
+// Point colours for FREQUENCY_BANDS (toggled with B): blue, green, yellow, orange, red
+const BAND_COLORS: [(f32, f32, f32); 5] = [
+    (0.0, 0.3, 1.0),
+    (0.0, 0.8, 0.0),
+    (0.9, 0.9, 0.0),
+    (1.0, 0.55, 0.0),
+    (1.0, 0.0, 0.0),
+];
+
+// Room used by the image-source simulation (toggled with M)
+const ROOM: RoomBox = RoomBox {
+    min: [-2.0, -1.5, -1.0],
+    max: [2.5, 3.0, 1.5],
+};
+
+// Measured surface: inverse-distance-weighted grid over the points' bounding box. kiss3d
+// meshes use u16 indices, which caps the grid at 255 × 255 vertices.
+const DEFAULT_IDW_GRID: usize = 20;
+const MAX_IDW_GRID: usize = 255;
+const IDW_POWER: f32 = 2.0;
+
+// Ctrl+E writes the surface mesh here, in the working directory
+const OBJ_PATH: &str = "room_acoustic.obj";
+
+// Point placements that Ctrl+Z can take back
+const MAX_UNDO: usize = 100;
+
+// The simulated map covers the drawn grid, one vertex per grid line
+const SIM_GRID_STEPS: usize = 20;
+const SIM_GRID_EXTENT: f32 = 1.0;
+// Opacity of the simulated surface, so the measured dots show through it
+const SIM_ALPHA: f32 = 0.4;
+// A click this close to the source sphere (in scene units) grabs it
+const SOURCE_PICK_RADIUS: f32 = 0.06;
+
+// Polar pattern (toggled with P): drawn on the XY plane around the origin, the loudest
+// point on the outer ring and POLAR_DB_RANGE below it at the centre
+const POLAR_MIN_POINTS: usize = 8;
+const POLAR_RADIUS: f32 = 1.0;
+const POLAR_DB_RANGE: f32 = 40.0;
+const POLAR_RING_DB: f32 = 10.0;
+const POLAR_LABEL_DEG: usize = 30;
+const POLAR_CIRCLE_SEGMENTS: usize = 72;
+
+// Interpolated grid, n × n vertices in row-major order
+struct Surface {
+    n: usize,
+    vertices: Vec<Point3<f32>>,
+    z_range: (f32, f32),
+}
+
 struct SamplePoint {
     position: Point2<f32>,
     amplitude: f32,
+    dominant_band: usize,
+}
+
+// On-disk form of a SamplePoint (Ctrl+S / Ctrl+O)
+#[derive(Serialize, Deserialize)]
+struct SavedPoint {
+    x: f32,
+    y: f32,
+    amplitude: f32,
+    #[serde(default)]
+    dominant_band: usize,
+}
+
+impl From<&SamplePoint> for SavedPoint {
+    fn from(sample: &SamplePoint) -> Self {
+        Self {
+            x: sample.position.x,
+            y: sample.position.y,
+            amplitude: sample.amplitude,
+            dominant_band: sample.dominant_band,
+        }
+    }
+}
+
+impl From<SavedPoint> for SamplePoint {
+    fn from(saved: SavedPoint) -> Self {
+        Self {
+            position: Point2::new(saved.x, saved.y),
+            amplitude: saved.amplitude,
+            dominant_band: saved.dominant_band.min(BAND_COLORS.len() - 1),
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(about = "3D map of microphone amplitude over position")]
+struct Args {
+    /// Input device, matched as a case-insensitive substring of its name
+    #[arg(long)]
+    device: Option<String>,
+    /// Sample rate in Hz; the device default is used if omitted
+    #[arg(long)]
+    sample_rate: Option<u32>,
+    /// Vertices per side of the interpolated surface
+    #[arg(long, default_value_t = DEFAULT_IDW_GRID)]
+    grid: usize,
 }
 
 fn main() {
+    let args = Args::parse();
+    let host = cpal::default_host();
+    let (device, config) = find_input_device(&host, args.device.as_deref())
+        .and_then(|device| {
+            let config = input_config(&device, args.sample_rate)?;
+            Ok((device, config))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        });
+
+    let grid = args.grid.clamp(2, MAX_IDW_GRID);
     let (tx, rx) = mpsc::channel::<f32>();
+    let snapshot = Arc::new(Mutex::new(AudioData::default()));
 
     // Spawn audio capture thread
+    let audio_snapshot = Arc::clone(&snapshot);
+    let stream_status = Arc::new(Mutex::new(StreamStatus::Running));
+    let audio_status = Arc::clone(&stream_status);
     thread::spawn(move || {
-        let host = cpal::default_host();
-        let device = host.default_input_device().expect("No input device available");
-        let config = device.default_input_config().unwrap();
         let channels = config.channels() as usize;
+        audio_snapshot.lock().unwrap().sample_rate = config.sample_rate().0;
 
-        let stream = device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _| {
-                let max = data.chunks(channels)
+        let build = move |errors: &StreamErrorFlag| -> Result<cpal::Stream> {
+            let tx = tx.clone();
+            let audio_snapshot = Arc::clone(&audio_snapshot);
+            // The callback only queues its block; the lock is taken on the processing thread
+            // First channel of each block, reused across blocks
+            let mut mono = Vec::new();
+            let capacity = config.sample_rate().0 as usize * channels;
+            let mut producer = spawn_block_processor(capacity, channels, move |_, block| {
+                let max = block.chunks(channels)
                     .map(|frame| frame[0].abs())
                     .fold(0.0, f32::max);
                 let _ = tx.send(max);
-            },
-            move |err| eprintln!("Stream error: {}", err),
-            None,
-        ).unwrap();
 
-        stream.play().unwrap();
-        loop {
-            thread::sleep(Duration::from_millis(10));
+                mono.clear();
+                mono.extend(block.chunks(channels).map(|frame| frame[0]));
+                push_samples(&audio_snapshot, &mono, SNAPSHOT_LEN);
+            });
+            let stream = build_input_stream_dynamic(
+                &device,
+                &config,
+                move |data: &[f32], _, started| producer.push(data, started),
+                errors.callback(),
+            )?;
+            stream.play()?;
+            Ok(stream)
+        };
+
+        match AudioStreamGuard::new(audio_status, build) {
+            Ok(guard) => guard.run(),
+            Err(e) => eprintln!("Failed to open input device: {:#}", e),
         }
     });
 
@@ -62,12 +205,141 @@ fn main() {
     // Storage
     let mut samples: Vec<SamplePoint> = Vec::new();
     let mut surface_node: Option<SceneNode> = None;
+    let mut surface: Option<Surface> = None;
+    // Interpolation runs off the render loop; results from before the latest change
+    // are dropped by generation
+    let (surface_tx, surface_rx) = mpsc::channel::<(u64, Surface)>();
+    let mut surface_generation = 0u64;
     let mut camera_shift = Vector3::new(0.0, 0.0, 0.0);
+    let mut sample_nodes: Vec<SceneNode> = Vec::new();
+    let mut color_by_band = false;
+    let mut show_polar = false;
+    let mut file_status: Option<String> = None;
+    // Ctrl+P result, shown for 2 s
+    let mut screenshot_toast: Option<(String, std::time::Instant)> = None;
+    // The last `undo_depth` entries of `samples` can be undone; undone points wait in `redo`
+    let mut undo_depth = 0usize;
+    let mut redo: Vec<SamplePoint> = Vec::new();
+    let mut title = String::new();
+    // Peak of the newest audio block, which Space records
+    let mut latest_amp: Option<f32> = None;
+
+    // Simulation
+    let mut show_simulation = false;
+    let mut sim_dirty = false;
+    let mut source_position = Point3::new(0.5, 0.5, 0.3);
+    let mut source_node = window.add_sphere(0.03);
+    source_node.set_color(1.0, 0.4, 0.0);
+    source_node.set_visible(false);
+    let mut sim_node: Option<SceneNode> = None;
+    let sim_material: Rc<RefCell<Box<dyn Material + 'static>>> =
+        Rc::new(RefCell::new(Box::new(TranslucentMaterial::new(SIM_ALPHA))));
+    // Last cursor position, and whether the left button is dragging the source
+    let mut cursor = Point2::new(0.0f32, 0.0);
+    let mut dragging_source = false;
+    let font = Font::default();
 
     while window.render_with_camera(&mut camera) {
-        for event in window.events().iter() {
-            if let WindowEvent::Key(key, Action::Press, _) = event.value {
+        // Drained every frame so the queue stays short and Space gets the current level
+        while let Ok(amp) = rx.try_recv() {
+            latest_amp = Some(amp);
+        }
+
+        for mut event in window.events().iter() {
+            // Dragging the source is kept from the camera, which would rotate instead
+            let size = Vector2::new(window.width() as f32, window.height() as f32);
+            match event.value {
+                WindowEvent::CursorPos(x, y, _) => {
+                    cursor = Point2::new(x as f32, y as f32);
+                    if dragging_source {
+                        let (origin, direction) = camera.unproject(&cursor, &size);
+                        if let Some(hit) = ray_at_height(origin, direction, source_position.z) {
+                            source_position.x = hit.x.clamp(ROOM.min[0], ROOM.max[0]);
+                            source_position.y = hit.y.clamp(ROOM.min[1], ROOM.max[1]);
+                            sim_dirty = true;
+                        }
+                        event.inhibited = true;
+                    }
+                }
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) if show_simulation => {
+                    let (origin, direction) = camera.unproject(&cursor, &size);
+                    if ray_passes_near(origin, direction, source_position, SOURCE_PICK_RADIUS) {
+                        dragging_source = true;
+                        event.inhibited = true;
+                    }
+                }
+                WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => dragging_source = false,
+                _ => {}
+            }
+            if let WindowEvent::Key(key, Action::Press, modifiers) = event.value {
+                let ctrl = modifiers.contains(Modifiers::Control);
                 match key {
+                    Key::S if ctrl => {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .set_file_name("mic_3d_points.json")
+                            .save_file()
+                        {
+                            file_status = Some(match save_points(&path, &samples) {
+                                Ok(()) => format!("Saved {} points to {}", samples.len(), path.display()),
+                                Err(e) => format!("Save failed: {:#}", e),
+                            });
+                        }
+                    }
+                    Key::O if ctrl => {
+                        if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                            match load_points(&path) {
+                                Ok(loaded) => {
+                                    for mut node in sample_nodes.drain(..) {
+                                        window.remove_node(&mut node);
+                                    }
+                                    sample_nodes = loaded
+                                        .iter()
+                                        .map(|sample| add_sample_node(&mut window, sample, color_by_band))
+                                        .collect();
+                                    file_status = Some(format!("Loaded {} points from {}", loaded.len(), path.display()));
+                                    samples = loaded;
+                                    undo_depth = 0;
+                                    redo.clear();
+                                    surface_generation += 1;
+                                    request_surface(&samples, grid, surface_generation, &surface_tx);
+                                }
+                                Err(e) => file_status = Some(format!("Load failed: {:#}", e)),
+                            }
+                        }
+                    }
+                    Key::E if ctrl => {
+                        file_status = Some(match &surface {
+                            Some(surface) => match write_obj(Path::new(OBJ_PATH), surface, &samples) {
+                                Ok(()) => format!("Exported mesh to {}", OBJ_PATH),
+                                Err(e) => format!("Export failed: {:#}", e),
+                            },
+                            None => "No surface to export yet (place at least 3 points)".to_owned(),
+                        });
+                    }
+                    Key::P if ctrl => {
+                        let path = screenshot_path();
+                        let message = match window.snap_image().save(&path) {
+                            Ok(()) => format!("Saved: {}", path.display()),
+                            Err(e) => format!("Screenshot failed: {}", e),
+                        };
+                        screenshot_toast = Some((message, std::time::Instant::now()));
+                    }
+                    Key::P => show_polar = !show_polar,
+                    Key::Z if ctrl && undo_depth > 0 => {
+                        if let (Some(sample), Some(mut node)) = (samples.pop(), sample_nodes.pop()) {
+                            window.remove_node(&mut node);
+                            redo.push(sample);
+                            undo_depth -= 1;
+                        }
+                    }
+                    Key::Y if ctrl => {
+                        if let Some(sample) = redo.pop() {
+                            sample_nodes.push(add_sample_node(&mut window, &sample, color_by_band));
+                            samples.push(sample);
+                            undo_depth = (undo_depth + 1).min(MAX_UNDO);
+                        }
+                    }
                     Key::W => mic_position.y += 0.05,
                     Key::S => mic_position.y -= 0.05,
                     Key::A => mic_position.x -= 0.05,
@@ -76,25 +348,72 @@ fn main() {
                     Key::Down => camera_shift.y -= 0.05,
                     Key::Left => camera_shift.x -= 0.05,
                     Key::Right => camera_shift.x += 0.05,
+                    Key::I => source_position.y += 0.05,
+                    Key::K => source_position.y -= 0.05,
+                    Key::J => source_position.x -= 0.05,
+                    Key::L => source_position.x += 0.05,
+                    Key::O => source_position.z += 0.05,
+                    Key::U => source_position.z -= 0.05,
+                    Key::M => show_simulation = !show_simulation,
+                    Key::B => {
+                        color_by_band = !color_by_band;
+                        for (node, sample) in sample_nodes.iter_mut().zip(&samples) {
+                            color_sample_node(node, sample, color_by_band);
+                        }
+                    }
                     Key::Space => {
-                        if let Ok(amp) = rx.try_recv() {
-                            samples.push(SamplePoint {
+                        if let Some(amp) = latest_amp {
+                            let dominant_band = {
+                                let sample_rate = snapshot.lock().unwrap().sample_rate;
+                                let samples = latest_n_samples(&snapshot, SNAPSHOT_LEN);
+                                dominant_band(&samples, sample_rate).unwrap_or(0)
+                            };
+                            let sample = SamplePoint {
                                 position: mic_position,
                                 amplitude: amp,
-                            });
+                                dominant_band,
+                            };
+                            sample_nodes.push(add_sample_node(&mut window, &sample, color_by_band));
+                            samples.push(sample);
+                            undo_depth = (undo_depth + 1).min(MAX_UNDO);
+                            redo.clear();
                         }
                     }
                     Key::R => {
                         samples.clear();
+                        undo_depth = 0;
+                        redo.clear();
+                        for mut node in sample_nodes.drain(..) {
+                            window.remove_node(&mut node);
+                        }
                         if let Some(mut node) = surface_node.take() {
                             window.remove_node(&mut node);
                         }
+                        surface = None;
+                        surface_generation += 1;
                     }
                     _ => {}
                 }
+                sim_dirty = true;
+                if matches!(key, Key::Space | Key::Z | Key::Y) {
+                    surface_generation += 1;
+                    if samples.len() < 3 {
+                        if let Some(mut node) = surface_node.take() {
+                            window.remove_node(&mut node);
+                        }
+                        surface = None;
+                    }
+                    request_surface(&samples, grid, surface_generation, &surface_tx);
+                }
             }
         }
 
+        let new_title = format!("Mic 3D Visualizer  [Undo: {}]  [Redo: {}]", undo_depth, redo.len());
+        if new_title != title {
+            window.set_title(&new_title);
+            title = new_title;
+        }
+
         // Update mic dot and camera
         mic_node.set_local_translation(Translation3::new(mic_position.x, mic_position.y, 0.0));
         camera.translate(&Translation3::from(camera_shift));
@@ -130,21 +449,550 @@ fn main() {
             }
         }
 
-        // Surface mesh
-        if points.len() >= 3 {
+        // Surface mesh, replaced when the background interpolation finishes
+        while let Ok((generation, new_surface)) = surface_rx.try_recv() {
+            if generation != surface_generation {
+                continue;
+            }
             if let Some(mut node) = surface_node.take() {
                 window.remove_node(&mut node);
             }
-
-            let vertices = points.clone();
-            let indices = (0..vertices.len() - 2)
-                .map(|i| Point3::new(i as u16, (i + 1) as u16, (i + 2) as u16))
-                .collect();
-
-            let mesh = Mesh::new(vertices, indices, None, None, false);
+            let mesh = Mesh::new(new_surface.vertices.clone(), grid_indices(new_surface.n), None, None, false);
             let mut node = window.add_mesh(Rc::new(RefCell::new(mesh)), Vector3::new(1.0, 1.0, 1.0));
             node.set_color(0.7, 0.7, 0.7);
             surface_node = Some(node);
+            surface = Some(new_surface);
+        }
+        if let Some(surface) = &surface {
+            draw_height_lines(&mut window, surface);
         }
+
+        // Image-source simulation, drawn translucent so the measurements show through
+        source_node.set_visible(show_simulation);
+        if sim_dirty {
+            sim_dirty = false;
+            if let Some(mut node) = sim_node.take() {
+                window.remove_node(&mut node);
+            }
+            if show_simulation {
+                source_node.set_local_translation(Translation3::from(source_position.coords));
+                let measured_peak = samples.iter().map(|s| s.amplitude).fold(0.0, f32::max);
+                let scale = if measured_peak > 0.0 { measured_peak } else { 1.0 };
+                let mut node = add_simulated_surface(&mut window, source_position, scale);
+                node.set_material(Rc::clone(&sim_material));
+                sim_node = Some(node);
+            }
+        }
+        if show_polar {
+            if samples.len() >= POLAR_MIN_POINTS {
+                draw_polar_pattern(&mut window, &camera, &samples, &font);
+            } else {
+                window.draw_text(
+                    &format!("Polar pattern needs at least {} points  [P hide]", POLAR_MIN_POINTS),
+                    &Point2::new(10.0, 360.0),
+                    36.0,
+                    &font,
+                    &Point3::new(0.0, 0.0, 0.0),
+                );
+            }
+        }
+        if color_by_band {
+            for (i, ((name, lo, hi), (r, g, b))) in FREQUENCY_BANDS.iter().zip(BAND_COLORS).enumerate() {
+                window.draw_text(
+                    &format!("■ {} ({:.0}-{:.0} Hz)", name, lo, hi),
+                    &Point2::new(10.0, 60.0 + i as f32 * 36.0),
+                    36.0,
+                    &font,
+                    &Point3::new(r, g, b),
+                );
+            }
+        }
+        if show_simulation {
+            window.draw_text(
+                &format!(
+                    "Simulation - source ({:.2}, {:.2}, {:.2})  [drag or IJKL/UO move, M hide]",
+                    source_position.x, source_position.y, source_position.z
+                ),
+                &Point2::new(10.0, 10.0),
+                40.0,
+                &font,
+                &Point3::new(0.0, 0.0, 0.0),
+            );
+        }
+        if let Some(status) = &file_status {
+            window.draw_text(
+                &format!("{}  [Ctrl+S save, Ctrl+O load, Ctrl+E export OBJ]", status),
+                &Point2::new(10.0, 260.0),
+                36.0,
+                &font,
+                &Point3::new(0.0, 0.0, 0.0),
+            );
+        }
+        if let Some((message, shown)) = &screenshot_toast {
+            if shown.elapsed().as_secs_f32() < 2.0 {
+                let x = window.width() as f32 * 2.0 - 20.0 * message.len() as f32;
+                window.draw_text(
+                    message,
+                    &Point2::new(x.max(10.0), 10.0),
+                    40.0,
+                    &font,
+                    &Point3::new(0.0, 0.5, 0.0),
+                );
+            } else {
+                screenshot_toast = None;
+            }
+        }
+        // Yellow while the stream is being rebuilt, red once recovery has given up
+        let status = stream_status.lock().unwrap().clone();
+        if let Some(message) = status.message() {
+            let color = match status {
+                StreamStatus::Failed(_) => Point3::new(0.9, 0.0, 0.0),
+                _ => Point3::new(0.8, 0.6, 0.0),
+            };
+            window.draw_text(&message, &Point2::new(10.0, 310.0), 40.0, &font, &color);
+        }
+    }
+}
+
+// Angle around the origin against level in dB relative to the loudest point. Points are
+// already on the XY plane (Z is only used to show amplitude), so the angle is atan2(y, x).
+fn draw_polar_pattern(window: &mut Window, camera: &FirstPerson, samples: &[SamplePoint], font: &Rc<Font>) {
+    let grid_color = Point3::new(0.6, 0.6, 0.6);
+    let on_plane = |angle: f32, radius: f32| Point3::new(radius * angle.cos(), radius * angle.sin(), 0.0);
+
+    // Rings at 0, -10, -20 and -30 dB
+    let rings = (POLAR_DB_RANGE / POLAR_RING_DB) as usize;
+    for ring in 0..rings {
+        let radius = POLAR_RADIUS * (1.0 - ring as f32 * POLAR_RING_DB / POLAR_DB_RANGE);
+        for segment in 0..POLAR_CIRCLE_SEGMENTS {
+            let a0 = segment as f32 / POLAR_CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            let a1 = (segment + 1) as f32 / POLAR_CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            window.draw_line(&on_plane(a0, radius), &on_plane(a1, radius), &grid_color);
+        }
+    }
+
+    // Spokes and angle labels, placed where the spoke end lands on screen
+    let size = Vector2::new(window.width() as f32, window.height() as f32);
+    let scale = window.scale_factor() as f32;
+    for deg in (0..360).step_by(POLAR_LABEL_DEG) {
+        let end = on_plane((deg as f32).to_radians(), POLAR_RADIUS * 1.1);
+        window.draw_line(&Point3::origin(), &end, &grid_color);
+        let screen = camera.project(&end, &size);
+        window.draw_text(
+            &format!("{}°", deg),
+            &Point2::new(screen.x * scale, (size.y - screen.y) * scale),
+            30.0,
+            font,
+            &Point3::new(0.3, 0.3, 0.3),
+        );
+    }
+
+    let peak = samples.iter().map(|s| s.amplitude).fold(0.0, f32::max);
+    if peak <= 0.0 {
+        return;
+    }
+    let mut pattern: Vec<(f32, f32)> = samples
+        .iter()
+        .map(|s| {
+            let db = 20.0 * (s.amplitude / peak).max(1e-10).log10();
+            let radius = POLAR_RADIUS * (1.0 + db / POLAR_DB_RANGE).max(0.0);
+            (s.position.y.atan2(s.position.x), radius)
+        })
+        .collect();
+    pattern.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Closed curve through the points in angle order
+    let curve_color = Point3::new(0.8, 0.0, 0.6);
+    for (i, &(angle, radius)) in pattern.iter().enumerate() {
+        let (next_angle, next_radius) = pattern[(i + 1) % pattern.len()];
+        window.draw_line(&on_plane(angle, radius), &on_plane(next_angle, next_radius), &curve_color);
+        window.draw_point(&on_plane(angle, radius), &curve_color);
+    }
+}
+
+fn request_surface(samples: &[SamplePoint], n: usize, generation: u64, tx: &mpsc::Sender<(u64, Surface)>) {
+    if samples.len() < 3 {
+        return;
+    }
+    let points: Vec<[f32; 3]> = samples
+        .iter()
+        .map(|s| [s.position.x, s.position.y, s.amplitude])
+        .collect();
+    let tx = tx.clone();
+    thread::spawn(move || {
+        let _ = tx.send((generation, interpolate_surface(&points, n)));
+    });
+}
+
+// z = Σ(a_i / d_i^p) / Σ(1 / d_i^p) at every vertex of an n × n grid over the points'
+// bounding box; a vertex on top of a point takes its amplitude
+fn interpolate_surface(points: &[[f32; 3]], n: usize) -> Surface {
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for p in points {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(p[axis]);
+            max[axis] = max[axis].max(p[axis]);
+        }
+    }
+    // Points on a line still get a strip of surface
+    for axis in 0..2 {
+        if max[axis] - min[axis] < 0.1 {
+            let mid = (max[axis] + min[axis]) / 2.0;
+            (min[axis], max[axis]) = (mid - 0.05, mid + 0.05);
+        }
+    }
+
+    let step = |axis: usize| (max[axis] - min[axis]) / (n - 1) as f32;
+    let vertices: Vec<Point3<f32>> = (0..n * n)
+        .map(|i| {
+            let x = min[0] + (i % n) as f32 * step(0);
+            let y = min[1] + (i / n) as f32 * step(1);
+            let (mut num, mut den) = (0.0, 0.0);
+            for p in points {
+                let d2 = (p[0] - x).powi(2) + (p[1] - y).powi(2);
+                if d2 < 1e-12 {
+                    return Point3::new(x, y, p[2]);
+                }
+                let w = 1.0 / d2.powf(IDW_POWER / 2.0);
+                num += w * p[2];
+                den += w;
+            }
+            Point3::new(x, y, num / den)
+        })
+        .collect();
+
+    let z_range = vertices
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| (lo.min(v.z), hi.max(v.z)));
+    Surface { n, vertices, z_range }
+}
+
+// Two triangles per cell of an n × n vertex grid
+fn grid_indices(n: usize) -> Vec<Point3<u16>> {
+    let mut indices = Vec::with_capacity((n - 1) * (n - 1) * 2);
+    for row in 0..n - 1 {
+        for col in 0..n - 1 {
+            let i = (row * n + col) as u16;
+            let n = n as u16;
+            indices.push(Point3::new(i, i + 1, i + n));
+            indices.push(Point3::new(i + 1, i + n + 1, i + n));
+        }
+    }
+    indices
+}
+
+// Cool-to-warm diverging map (blue, light grey, red), `t` in 0..=1
+fn height_color(t: f32) -> Point3<f32> {
+    let (cool, mid, warm) = ([0.23, 0.30, 0.75], [0.87, 0.87, 0.87], [0.71, 0.02, 0.15]);
+    let t = t.clamp(0.0, 1.0) * 2.0;
+    let (a, b, f) = if t < 1.0 { (cool, mid, t) } else { (mid, warm, t - 1.0) };
+    Point3::new(a[0] + (b[0] - a[0]) * f, a[1] + (b[1] - a[1]) * f, a[2] + (b[2] - a[2]) * f)
+}
+
+// Grid lines over the surface coloured by height, since the mesh itself has one colour
+fn draw_height_lines(window: &mut Window, surface: &Surface) {
+    let (lo, hi) = surface.z_range;
+    let span = (hi - lo).max(f32::EPSILON);
+    let n = surface.n;
+    let v = &surface.vertices;
+    for row in 0..n {
+        for col in 0..n {
+            let i = row * n + col;
+            for j in [(col + 1 < n).then_some(i + 1), (row + 1 < n).then_some(i + n)].into_iter().flatten() {
+                let t = ((v[i].z + v[j].z) / 2.0 - lo) / span;
+                window.draw_line(&v[i], &v[j], &height_color(t));
+            }
+        }
+    }
+}
+
+fn add_sample_node(window: &mut Window, sample: &SamplePoint, by_band: bool) -> SceneNode {
+    let mut node = window.add_sphere(0.01);
+    color_sample_node(&mut node, sample, by_band);
+    node.set_local_translation(Translation3::new(sample.position.x, sample.position.y, sample.amplitude));
+    node
+}
+
+fn save_points(path: &Path, samples: &[SamplePoint]) -> Result<()> {
+    let saved: Vec<SavedPoint> = samples.iter().map(SavedPoint::from).collect();
+    let json = serde_json::to_string_pretty(&saved)?;
+    std::fs::write(path, json).with_context(|| format!("Cannot write {}", path.display()))
+}
+
+fn load_points(path: &Path) -> Result<Vec<SamplePoint>> {
+    let json = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let saved: Vec<SavedPoint> = serde_json::from_str(&json).context("Malformed point file")?;
+    Ok(saved.into_iter().map(SamplePoint::from).collect())
+}
+
+// Wavefront OBJ of the surface: `v x y z` per vertex and `f a b c` per triangle
+// (1-based), preceded by the measured points as comments. The grid is capped at
+// MAX_IDW_GRID² vertices, so the u16 mesh indices always fit and it is written as one object.
+fn write_obj(path: &Path, surface: &Surface, samples: &[SamplePoint]) -> Result<()> {
+    use std::io::Write;
+
+    let file = std::fs::File::create(path).with_context(|| format!("Cannot create {}", path.display()))?;
+    let mut obj = std::io::BufWriter::new(file);
+    writeln!(obj, "# mic_3d surface, {} x {} vertices", surface.n, surface.n)?;
+    writeln!(obj, "# sample x y amplitude dominant_band")?;
+    for sample in samples {
+        writeln!(
+            obj,
+            "# sample {} {} {} {}",
+            sample.position.x, sample.position.y, sample.amplitude, FREQUENCY_BANDS[sample.dominant_band].0
+        )?;
+    }
+    writeln!(obj, "o surface")?;
+    for v in &surface.vertices {
+        writeln!(obj, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for face in grid_indices(surface.n) {
+        writeln!(obj, "f {} {} {}", face.x as usize + 1, face.y as usize + 1, face.z as usize + 1)?;
+    }
+    obj.flush().with_context(|| format!("Cannot write {}", path.display()))
+}
+
+fn color_sample_node(node: &mut SceneNode, sample: &SamplePoint, by_band: bool) {
+    let (r, g, b) = if by_band {
+        BAND_COLORS[sample.dominant_band]
+    } else {
+        (0.0, 0.0, 0.0)
+    };
+    node.set_color(r, g, b);
+}
+
+// Grid of simulated amplitudes over the measurement plane (z = 0), normalised so its
+// peak matches `scale`
+fn add_simulated_surface(window: &mut Window, source: Point3<f32>, scale: f32) -> SceneNode {
+    let n = SIM_GRID_STEPS + 1;
+    let step = 2.0 * SIM_GRID_EXTENT / SIM_GRID_STEPS as f32;
+    let source = [source.x, source.y, source.z];
+
+    let mut vertices: Vec<Point3<f32>> = (0..n * n)
+        .map(|i| {
+            let x = -SIM_GRID_EXTENT + (i % n) as f32 * step;
+            let y = -SIM_GRID_EXTENT + (i / n) as f32 * step;
+            Point3::new(x, y, ROOM.amplitude_at(source, [x, y, 0.0]))
+        })
+        .collect();
+    let peak = vertices.iter().map(|v| v.z).fold(0.0, f32::max).max(f32::EPSILON);
+    for v in vertices.iter_mut() {
+        v.z *= scale / peak;
+    }
+
+    let mesh = Mesh::new(vertices, grid_indices(n), None, None, false);
+    let mut node = window.add_mesh(Rc::new(RefCell::new(mesh)), Vector3::new(1.0, 1.0, 1.0));
+    node.set_color(0.2, 0.4, 1.0);
+    node
+}
+
+// Point where the ray from `origin` along `direction` crosses the plane at height `z`,
+// if it does so in front of the camera
+fn ray_at_height(origin: Point3<f32>, direction: Vector3<f32>, z: f32) -> Option<Point3<f32>> {
+    let t = (z - origin.z) / direction.z;
+    (t.is_finite() && t > 0.0).then(|| origin + direction * t)
+}
+
+// True if the ray from `origin` along `direction` passes within `radius` of `point`
+fn ray_passes_near(origin: Point3<f32>, direction: Vector3<f32>, point: Point3<f32>, radius: f32) -> bool {
+    let direction = direction.normalize();
+    let to_point = point - origin;
+    let along = to_point.dot(&direction);
+    along > 0.0 && (to_point - direction * along).norm() <= radius
+}
+
+// kiss3d's default material is opaque; this one draws the node's colour at a fixed
+// alpha, shaded by how much the surface faces up, with both sides visible
+struct TranslucentMaterial {
+    shader: Effect,
+    position: ShaderAttribute<Point3<f32>>,
+    normal: ShaderAttribute<Vector3<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    color: ShaderUniform<Point3<f32>>,
+    alpha_uniform: ShaderUniform<f32>,
+    alpha: f32,
+}
+
+impl TranslucentMaterial {
+    fn new(alpha: f32) -> Self {
+        let mut shader = Effect::new_from_str(TRANSLUCENT_VERTEX_SRC, TRANSLUCENT_FRAGMENT_SRC);
+        shader.use_program();
+        Self {
+            position: shader.get_attrib("position").expect("position attribute"),
+            normal: shader.get_attrib("normal").expect("normal attribute"),
+            view: shader.get_uniform("view").expect("view uniform"),
+            proj: shader.get_uniform("proj").expect("proj uniform"),
+            transform: shader.get_uniform("transform").expect("transform uniform"),
+            scale: shader.get_uniform("scale").expect("scale uniform"),
+            color: shader.get_uniform("color").expect("color uniform"),
+            alpha_uniform: shader.get_uniform("alpha").expect("alpha uniform"),
+            alpha,
+            shader,
+        }
+    }
+}
+
+impl Material for TranslucentMaterial {
+    fn render(
+        &mut self,
+        pass: usize,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        camera: &mut dyn Camera,
+        _: &Light,
+        data: &ObjectData,
+        mesh: &mut Mesh,
+    ) {
+        let ctxt = GlContext::get();
+        self.shader.use_program();
+        self.position.enable();
+        self.normal.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+        self.transform.upload(&transform.to_homogeneous());
+        self.scale.upload(&Matrix3::from_diagonal(scale));
+        self.color.upload(data.color());
+        self.alpha_uniform.upload(&self.alpha);
+
+        mesh.bind_coords(&mut self.position);
+        mesh.bind_normals(&mut self.normal);
+        mesh.bind_faces();
+
+        ctxt.disable(GlContext::CULL_FACE);
+        ctxt.enable(GlContext::BLEND);
+        ctxt.blend_func_separate(
+            GlContext::SRC_ALPHA,
+            GlContext::ONE_MINUS_SRC_ALPHA,
+            GlContext::ONE,
+            GlContext::ONE_MINUS_SRC_ALPHA,
+        );
+        ctxt.draw_elements(GlContext::TRIANGLES, mesh.num_pts() as i32, GlContext::UNSIGNED_SHORT, 0);
+        ctxt.disable(GlContext::BLEND);
+
+        mesh.unbind();
+        self.position.disable();
+        self.normal.disable();
+    }
+}
+
+const TRANSLUCENT_VERTEX_SRC: &str = "#version 100
+attribute vec3 position;
+attribute vec3 normal;
+uniform mat4 view;
+uniform mat4 proj;
+uniform mat4 transform;
+uniform mat3 scale;
+varying vec3 ls_normal;
+
+void main() {
+    ls_normal = normal;
+    gl_Position = proj * view * transform * mat4(scale) * vec4(position, 1.0);
+}
+";
+
+const TRANSLUCENT_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+uniform vec3 color;
+uniform float alpha;
+varying vec3 ls_normal;
+
+void main() {
+    float shade = 0.6 + 0.4 * abs(normalize(ls_normal).z);
+    gl_FragColor = vec4(color * shade, alpha);
+}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn args_parse_device_sample_rate_and_grid() {
+        Args::command().debug_assert();
+        let args = Args::try_parse_from(["mic_3d", "--device", "usb", "--sample-rate", "44100", "--grid", "40"]).unwrap();
+        assert_eq!(args.device.as_deref(), Some("usb"));
+        assert_eq!(args.sample_rate, Some(44_100));
+        assert_eq!(args.grid, 40);
+        assert_eq!(Args::try_parse_from(["mic_3d"]).unwrap().grid, DEFAULT_IDW_GRID);
+    }
+
+    #[test]
+    fn grid_indices_split_each_cell_into_two_triangles() {
+        assert_eq!(grid_indices(2), [Point3::new(0, 1, 2), Point3::new(1, 3, 2)]);
+    }
+
+    #[test]
+    fn obj_lists_points_vertices_and_one_based_faces() {
+        let surface = Surface {
+            n: 2,
+            vertices: vec![
+                Point3::new(0.0, 0.0, 0.5),
+                Point3::new(1.0, 0.0, 0.25),
+                Point3::new(0.0, 1.0, 0.125),
+                Point3::new(1.0, 1.0, 1.0),
+            ],
+            z_range: (0.125, 1.0),
+        };
+        let samples = [SamplePoint {
+            position: Point2::new(0.5, 0.5),
+            amplitude: 0.75,
+            dominant_band: 1,
+        }];
+        let path = std::env::temp_dir().join(format!("mic_3d-test-{}.obj", std::process::id()));
+        write_obj(&path, &surface, &samples).unwrap();
+        let obj = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            obj.lines().collect::<Vec<_>>(),
+            [
+                "# mic_3d surface, 2 x 2 vertices",
+                "# sample x y amplitude dominant_band",
+                "# sample 0.5 0.5 0.75 bass",
+                "o surface",
+                "v 0 0 0.5",
+                "v 1 0 0.25",
+                "v 0 1 0.125",
+                "v 1 1 1",
+                "f 1 2 3",
+                "f 2 4 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn rays_meet_the_drag_plane_in_front_only() {
+        let origin = Point3::new(0.0, -2.0, 1.0);
+        let hit = ray_at_height(origin, Vector3::new(0.0, 1.0, -0.5), 0.5).unwrap();
+        assert!((hit - Point3::new(0.0, -1.0, 0.5)).norm() < 1e-6);
+        assert!(ray_at_height(origin, Vector3::new(0.0, 1.0, 0.5), 0.5).is_none());
+        assert!(ray_at_height(origin, Vector3::new(0.0, 1.0, 0.0), 0.5).is_none());
+    }
+
+    #[test]
+    fn clicks_pick_the_source_only_near_it() {
+        let origin = Point3::new(0.0, -2.0, 0.0);
+        let source = Point3::new(0.0, 0.0, 0.0);
+        assert!(ray_passes_near(origin, Vector3::new(0.01, 1.0, 0.0), source, SOURCE_PICK_RADIUS));
+        assert!(!ray_passes_near(origin, Vector3::new(0.2, 1.0, 0.0), source, SOURCE_PICK_RADIUS));
+        // Behind the camera
+        assert!(!ray_passes_near(origin, Vector3::new(0.0, -1.0, 0.0), source, SOURCE_PICK_RADIUS));
+    }
+
+    #[test]
+    fn largest_grid_fits_u16_indices() {
+        let n = MAX_IDW_GRID;
+        let indices = grid_indices(n);
+        assert_eq!(indices.len(), 2 * (n - 1) * (n - 1));
+        let max = indices.iter().flat_map(|t| [t.x, t.y, t.z]).max().unwrap();
+        assert_eq!(max as usize, n * n - 1);
     }
 }
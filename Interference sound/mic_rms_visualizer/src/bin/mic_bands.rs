@@ -0,0 +1,212 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Plot};
+
+use mic_rms_visualizer::dsp::bands::{band_label, BandFilterBank, THIRD_OCTAVE_BANDS};
+use mic_rms_visualizer::dsp::smoother::AudioSmoother;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+// The bars refresh at ~15 Hz and are smoothed with this time constant
+const REFRESH: Duration = Duration::from_millis(66);
+const SMOOTHING_TAU_MS: f32 = 100.0;
+
+// Bottom of the bars, and the level reported for silent or unavailable bands
+const MIN_DBFS: f32 = -100.0;
+
+// Bands whose upper edge is past this fraction of the sample rate are left out
+const MAX_EDGE_FRACTION: f32 = 0.49;
+
+#[derive(Default)]
+struct BandsData {
+    filters: BandFilterBank,
+    // First channel of the current callback block
+    block: Vec<f32>,
+    // Sum of squares per band since the UI last read it
+    energy: [f32; THIRD_OCTAVE_BANDS],
+    frames: usize,
+    sample_rate: u32,
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let data = Arc::new(Mutex::new(BandsData::default()));
+    start_audio_thread(Arc::clone(&data));
+
+    let app = BandsApp {
+        data,
+        levels: [AudioSmoother::new(SMOOTHING_TAU_MS); THIRD_OCTAVE_BANDS],
+        loudest: None,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "🎧 Mic 1/3-Octave Bands",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+}
+
+fn start_audio_thread(shared: Arc<Mutex<BandsData>>) {
+    thread::spawn(move || {
+        let host = cpal::default_host();
+        let device = host.default_input_device().expect("No input device found");
+        let config = device.default_input_config().unwrap();
+        let channels = config.channels() as usize;
+        let sample_rate = config.sample_rate().0;
+        {
+            let mut data = shared.lock().unwrap();
+            data.sample_rate = sample_rate;
+            data.filters = BandFilterBank::third_octave(sample_rate as f32, MAX_EDGE_FRACTION * sample_rate as f32);
+        }
+
+        let sample_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let mut buffer = shared.lock().unwrap();
+            let buffer = &mut *buffer;
+            buffer.block.clear();
+            buffer.block.extend(data.chunks(channels).map(|frame| frame[0]));
+            buffer.filters.process(&buffer.block, &mut buffer.energy);
+            buffer.frames += data.len() / channels;
+        };
+
+        let err_fn = |err| eprintln!("Stream error: {}", err);
+        let stream = device
+            .build_input_stream(&config.into(), sample_fn, err_fn, None)
+            .unwrap();
+
+        stream.play().unwrap();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+fn to_dbfs(rms: f32) -> f32 {
+    (20.0 * rms.max(1e-10).log10()).max(MIN_DBFS)
+}
+
+struct BandsApp {
+    data: Arc<Mutex<BandsData>>,
+    // Smoothed RMS per band
+    levels: [AudioSmoother; THIRD_OCTAVE_BANDS],
+    // Band with the highest power in the latest refresh
+    loudest: Option<usize>,
+    screenshots: ScreenshotExporter,
+}
+
+impl BandsApp {
+    // Folds the energy gathered since the last refresh into the smoothed levels
+    fn refresh(&mut self) {
+        let mut data = self.data.lock().unwrap();
+        if data.frames == 0 {
+            return;
+        }
+        let (frames, sample_rate) = (data.frames, data.sample_rate);
+        let energy = std::mem::take(&mut data.energy);
+        data.frames = 0;
+        drop(data);
+
+        for (level, &sum) in self.levels.iter_mut().zip(&energy) {
+            level.update((sum / frames as f32).sqrt(), frames, sample_rate);
+        }
+        self.loudest = energy
+            .iter()
+            .enumerate()
+            .filter(|&(_, &sum)| sum > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+    }
+
+    fn table_text(&self) -> String {
+        let mut text = String::from("band_hz\tlevel_dbfs\n");
+        for (i, level) in self.levels.iter().enumerate() {
+            text.push_str(&format!("{}\t{:.1}\n", band_label(i), to_dbfs(level.value())));
+        }
+        text
+    }
+}
+
+impl eframe::App for BandsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        self.refresh();
+
+        egui::SidePanel::right("band_table").show(ctx, |ui| {
+            ui.heading("Levels");
+            if ui.button("📋 Copy table").clicked() {
+                let text = self.table_text();
+                ui.output_mut(|o| o.copied_text = text);
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("band_levels").striped(true).show(ui, |ui| {
+                    ui.strong("Band (Hz)");
+                    ui.strong("dBFS");
+                    ui.end_row();
+                    for (i, level) in self.levels.iter().enumerate() {
+                        let text = format!("{:.1}", to_dbfs(level.value()));
+                        ui.label(band_label(i));
+                        if self.loudest == Some(i) {
+                            ui.colored_label(egui::Color32::from_rgb(255, 140, 0), text);
+                        } else {
+                            ui.label(text);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("📊 1/3-Octave Band Levels");
+            ui.label(match self.loudest {
+                Some(i) => format!("Loudest band: {} Hz", band_label(i)),
+                None => "Loudest band: —".to_owned(),
+            });
+
+            // Bars rise from MIN_DBFS; the loudest band is highlighted
+            let bars: Vec<Bar> = self
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(i, level)| {
+                    let db = to_dbfs(level.value()) as f64;
+                    let color = if self.loudest == Some(i) {
+                        egui::Color32::from_rgb(255, 140, 0)
+                    } else {
+                        egui::Color32::LIGHT_BLUE
+                    };
+                    Bar::new(i as f64, db - MIN_DBFS as f64)
+                        .base_offset(MIN_DBFS as f64)
+                        .width(0.8)
+                        .fill(color)
+                        .name(format!("{} Hz", band_label(i)))
+                })
+                .collect();
+
+            Plot::new("bands_plot")
+                .view_aspect(2.0)
+                .allow_scroll(false)
+                .allow_zoom(false)
+                .include_y(MIN_DBFS as f64)
+                .include_y(0.0)
+                .y_axis_label("dBFS")
+                .x_axis_formatter(|mark, _, _| {
+                    let i = mark.value.round();
+                    if (mark.value - i).abs() < 1e-6 && (0.0..THIRD_OCTAVE_BANDS as f64).contains(&i) {
+                        band_label(i as usize).to_owned()
+                    } else {
+                        String::new()
+                    }
+                })
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(BarChart::new(bars));
+                });
+        });
+
+        ctx.request_repaint_after(REFRESH);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use eframe::egui::{self, Slider};
+use egui_plot::{Line, Plot, PlotPoints, VLine};
+
+use mic_rms_visualizer::dsp::spectrum::magnitude_spectrum_dbfs;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+// Long FFT so a beat of a few Hz still resolves into its own bins
+const SPECTRUM_LEN: usize = 32768;
+
+struct BeatState {
+    base_hz: f32,
+    beat_hz: f32,
+    amplitude: f32,
+    playing: bool,
+    sample_rate: u32,
+    mic: VecDeque<f32>,
+}
+
+fn main() {
+    let state = Arc::new(Mutex::new(BeatState {
+        base_hz: 200.0,
+        beat_hz: 10.0,
+        amplitude: 0.2,
+        playing: false,
+        sample_rate: 0,
+        mic: VecDeque::from(vec![0.0; SPECTRUM_LEN]),
+    }));
+    let error = Arc::new(Mutex::new(None));
+
+    let state_clone = Arc::clone(&state);
+    let error_clone = Arc::clone(&error);
+    thread::spawn(move || {
+        if let Err(e) = run_streams(state_clone) {
+            eprintln!("Audio thread error: {:?}", e);
+            *error_clone.lock().unwrap() = Some(e.to_string());
+        }
+    });
+
+    let app = BinauralApp {
+        state,
+        error,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "Binaural Beat Generator",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+    .expect("Failed to launch GUI");
+}
+
+fn run_streams(state: Arc<Mutex<BeatState>>) -> Result<()> {
+    let host = cpal::default_host();
+    let input = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+    let output = host
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No output device available"))?;
+
+    let input_config = input.default_input_config()?;
+    let output_config = output.default_output_config()?;
+    let output_channels = output_config.channels() as usize;
+    if output_channels < 2 {
+        return Err(anyhow!("Binaural beats need a stereo output device"));
+    }
+    let channels = input_config.channels() as usize;
+    let output_rate = output_config.sample_rate().0 as f32;
+    state.lock().unwrap().sample_rate = input_config.sample_rate().0;
+
+    let input_state = Arc::clone(&state);
+    let input_stream = input.build_input_stream(
+        &input_config.into(),
+        move |data: &[f32], _| {
+            let mut state = input_state.lock().unwrap();
+            state.mic.extend(data.chunks(channels).map(|frame| frame[0]));
+            let excess = state.mic.len().saturating_sub(SPECTRUM_LEN);
+            state.mic.drain(..excess);
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    let (mut left_phase, mut right_phase) = (0.0f32, 0.0f32);
+    let output_stream = output.build_output_stream(
+        &output_config.into(),
+        move |data: &mut [f32], _| {
+            let state = state.lock().unwrap();
+            let amplitude = if state.playing { state.amplitude } else { 0.0 };
+            let left_step = TAU * state.base_hz / output_rate;
+            let right_step = TAU * (state.base_hz + state.beat_hz) / output_rate;
+
+            for frame in data.chunks_mut(output_channels) {
+                frame.fill(0.0);
+                frame[0] = amplitude * left_phase.sin();
+                frame[1] = amplitude * right_phase.sin();
+                left_phase = (left_phase + left_step) % TAU;
+                right_phase = (right_phase + right_step) % TAU;
+            }
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    input_stream.play()?;
+    output_stream.play()?;
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+struct BinauralApp {
+    state: Arc<Mutex<BeatState>>,
+    error: Arc<Mutex<Option<String>>>,
+    screenshots: ScreenshotExporter,
+}
+
+impl eframe::App for BinauralApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Binaural Beat Generator");
+
+            if let Some(error) = self.error.lock().unwrap().as_ref() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            let mut state = self.state.lock().unwrap();
+            ui.add(Slider::new(&mut state.base_hz, 100.0..=500.0).text("Base frequency f (Hz)"));
+            ui.add(Slider::new(&mut state.beat_hz, 0.5..=40.0).text("Beat frequency Δf (Hz)"));
+            ui.add(Slider::new(&mut state.amplitude, 0.0..=1.0).text("Amplitude"));
+            let label = if state.playing { "⏹ Stop" } else { "▶ Play" };
+            if ui.button(label).clicked() {
+                state.playing = !state.playing;
+            }
+
+            let (base_hz, beat_hz) = (state.base_hz, state.beat_hz);
+            ui.label(format!(
+                "Left: {:.1} Hz | Right: {:.1} Hz | Binaural beat: {:.1} Hz",
+                base_hz,
+                base_hz + beat_hz,
+                beat_hz
+            ));
+
+            let mic: Vec<f32> = state.mic.iter().copied().collect();
+            let sample_rate = state.sample_rate.max(1);
+            drop(state);
+
+            let spectrum = magnitude_spectrum_dbfs(&mic);
+            let bin_hz = sample_rate as f32 / mic.len().max(1) as f32;
+            let level_at = |hz: f32| {
+                spectrum
+                    .get((hz / bin_hz).round() as usize)
+                    .copied()
+                    .unwrap_or(f32::NEG_INFINITY)
+            };
+
+            // The beat exists only in the listener's head, so Δf should stay at the noise floor
+            ui.label(format!(
+                "Mic level at f: {:.1} dBFS | at f+Δf: {:.1} dBFS | at Δf: {:.1} dBFS",
+                level_at(base_hz),
+                level_at(base_hz + beat_hz),
+                level_at(beat_hz)
+            ));
+
+            let max_hz = (base_hz + beat_hz) * 2.0;
+            let points: PlotPoints = spectrum
+                .iter()
+                .enumerate()
+                .map(|(i, &db)| (i as f32 * bin_hz, db))
+                .take_while(|&(hz, _)| hz <= max_hz)
+                .map(|(hz, db)| [hz as f64, db as f64])
+                .collect();
+
+            Plot::new("mic_spectrum")
+                .view_aspect(2.0)
+                .include_y(-120.0)
+                .include_y(0.0)
+                .x_axis_label("Hz")
+                .y_axis_label("dBFS")
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(points).name("Mic spectrum"));
+                    plot_ui.vline(VLine::new(base_hz).color(egui::Color32::BLUE).name("f"));
+                    plot_ui.vline(VLine::new(base_hz + beat_hz).color(egui::Color32::BLUE).name("f + Δf"));
+                    plot_ui.vline(VLine::new(beat_hz).color(egui::Color32::RED).name("Δf"));
+                });
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use eframe::egui;
+use egui_plot::{AxisHints, HPlacement, Legend, Line, Plot, PlotPoints};
+
+use mic_rms_visualizer::device::{find_input_device, input_config};
+use mic_rms_visualizer::dsp::resample::SampleRateConverter;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+// One RMS value per 20 ms, 10 s of history
+const RMS_WINDOW_SECS: f32 = 0.02;
+const HISTORY_LEN: usize = 500;
+
+// Window of the Pearson coherence in the heading
+const COHERENCE_SECS: f32 = 2.0;
+
+// Below this the reference mic is treated as silent and no ratio is drawn
+const RATIO_FLOOR: f32 = 1e-4;
+
+#[derive(Parser)]
+#[command(about = "Compare the levels of two microphones in one plot")]
+struct Args {
+    /// Measurement mic, matched as a case-insensitive substring of its name
+    #[arg(long)]
+    mic_a: String,
+    /// Reference mic, matched the same way
+    #[arg(long)]
+    mic_b: String,
+}
+
+// Shared between one input stream and the UI; both run at the common rate
+#[derive(Clone, Default)]
+struct MicBuffers {
+    rms: Arc<Mutex<VecDeque<f32>>>,
+    // Last COHERENCE_SECS of samples
+    recent: Arc<Mutex<VecDeque<f32>>>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let host = cpal::default_host();
+    let open = |query: &str| {
+        find_input_device(&host, Some(query)).and_then(|device| {
+            let config = input_config(&device, None)?;
+            Ok((device, config))
+        })
+    };
+    let (a, b) = match (open(&args.mic_a), open(&args.mic_b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // The lower-rate stream is interpolated up to the higher rate so both line up
+    let rate = a.1.sample_rate().0.max(b.1.sample_rate().0);
+    let names = [a.0.name().unwrap_or_default(), b.0.name().unwrap_or_default()];
+    let buffers = [MicBuffers::default(), MicBuffers::default()];
+
+    let stream_buffers = buffers.clone();
+    thread::spawn(move || {
+        let [buffers_a, buffers_b] = stream_buffers;
+        let streams = start_stream(a.0, a.1, rate, buffers_a)
+            .and_then(|stream_a| Ok((stream_a, start_stream(b.0, b.1, rate, buffers_b)?)));
+        if let Err(e) = streams {
+            eprintln!("Audio thread error: {:?}", e);
+            return;
+        }
+        loop {
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+
+    let app = CompareApp {
+        names,
+        buffers,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "🎧 Mic Comparison",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+    .expect("Failed to launch GUI");
+}
+
+fn start_stream(
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+    rate: u32,
+    buffers: MicBuffers,
+) -> Result<cpal::Stream> {
+    let channels = config.channels() as usize;
+    let window_len = ((RMS_WINDOW_SECS * rate as f32) as usize).max(1);
+    let recent_len = (COHERENCE_SECS * rate as f32) as usize;
+    let mut converter = SampleRateConverter::new(config.sample_rate().0, rate);
+    let mut mono = Vec::new();
+    let mut resampled = Vec::new();
+    // (sum of squares, samples) of the RMS window being filled
+    let mut window = (0.0f32, 0usize);
+
+    let stream = device.build_input_stream(
+        &config.into(),
+        move |data: &[f32], _| {
+            mono.clear();
+            mono.extend(data.chunks(channels).map(|frame| frame[0]));
+            resampled.clear();
+            converter.process(&mono, &mut resampled);
+
+            let mut rms = buffers.rms.lock().unwrap();
+            for &s in &resampled {
+                window.0 += s * s;
+                window.1 += 1;
+                if window.1 == window_len {
+                    rms.push_back((window.0 / window_len as f32).sqrt());
+                    window = (0.0, 0);
+                }
+            }
+            let excess = rms.len().saturating_sub(HISTORY_LEN);
+            rms.drain(..excess);
+            drop(rms);
+
+            let mut recent = buffers.recent.lock().unwrap();
+            recent.extend(resampled.iter().copied());
+            let excess = recent.len().saturating_sub(recent_len);
+            recent.drain(..excess);
+        },
+        |err| eprintln!("Stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    Ok(stream)
+}
+
+// Pearson correlation of the newest common samples; None for silence or too few samples
+fn pearson(a: &VecDeque<f32>, b: &VecDeque<f32>) -> Option<f32> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let pairs = a.iter().skip(a.len() - n).zip(b.iter().skip(b.len() - n));
+    let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0f64, 0.0, 0.0, 0.0, 0.0);
+    for (&x, &y) in pairs {
+        let (x, y) = (x as f64, y as f64);
+        sa += x;
+        sb += y;
+        saa += x * x;
+        sbb += y * y;
+        sab += x * y;
+    }
+    let n = n as f64;
+    let cov = sab - sa * sb / n;
+    let var = (saa - sa * sa / n) * (sbb - sb * sb / n);
+    (var > f64::EPSILON).then(|| (cov / var.sqrt()).clamp(-1.0, 1.0) as f32)
+}
+
+struct CompareApp {
+    names: [String; 2],
+    buffers: [MicBuffers; 2],
+    screenshots: ScreenshotExporter,
+}
+
+impl eframe::App for CompareApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        let [a, b] = &self.buffers;
+        let coherence = pearson(&a.recent.lock().unwrap(), &b.recent.lock().unwrap());
+        let rms_a: Vec<f32> = a.rms.lock().unwrap().iter().copied().collect();
+        let rms_b: Vec<f32> = b.rms.lock().unwrap().iter().copied().collect();
+        // Newest values line up; time runs up to 0 = now
+        let n = rms_a.len().min(rms_b.len());
+        let (rms_a, rms_b) = (&rms_a[rms_a.len() - n..], &rms_b[rms_b.len() - n..]);
+        let time = |i: usize| (i as f64 - n as f64) * RMS_WINDOW_SECS as f64;
+
+        // The ratio uses the right-hand axis: ratio 1 is drawn at the loudest level shown
+        let scale = rms_a.iter().chain(rms_b).fold(0.0f32, |m, &x| m.max(x)).max(RATIO_FLOOR) as f64;
+        let ratio: Vec<[f64; 2]> = rms_a
+            .iter()
+            .zip(rms_b)
+            .enumerate()
+            .filter(|(_, (_, &b))| b > RATIO_FLOOR)
+            .map(|(i, (&a, &b))| [time(i), (a / b) as f64 * scale])
+            .collect();
+        let line = |values: &[f32]| -> PlotPoints { values.iter().enumerate().map(|(i, &x)| [time(i), x as f64]).collect() };
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(match coherence {
+                Some(r) => format!("🎙 Mic comparison | coherence r = {:.3} over {:.0} s", r, COHERENCE_SECS),
+                None => "🎙 Mic comparison | coherence —".to_owned(),
+            });
+            ui.label(format!("A: {}   B (reference): {}", self.names[0], self.names[1]));
+
+            Plot::new("compare_plot")
+                .view_aspect(2.0)
+                .legend(Legend::default())
+                .x_axis_label("Time (s)")
+                .custom_y_axes(vec![
+                    AxisHints::new_y().label("RMS"),
+                    AxisHints::new_y()
+                        .label("A / B")
+                        .placement(HPlacement::Right)
+                        .formatter(move |mark, _, _| format!("{:.2}", mark.value / scale)),
+                ])
+                .include_y(0.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(line(rms_a)).name("Mic A RMS"));
+                    plot_ui.line(Line::new(line(rms_b)).name("Mic B RMS"));
+                    plot_ui.line(Line::new(PlotPoints::from(ratio)).name("A / B (right axis)"));
+                });
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
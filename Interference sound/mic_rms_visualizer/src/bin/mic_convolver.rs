@@ -0,0 +1,347 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crossbeam::channel;
+use eframe::egui::{self, Slider};
+
+use mic_rms_visualizer::dsp::convolver::{Convolver, OverlapAdd};
+use mic_rms_visualizer::dsp::resample::resample_linear;
+#[cfg(feature = "auralization")]
+use mic_rms_visualizer::dsp::stereo_width::{CorrelationMeter, StereoWidth};
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+// Output queue limit; beyond this the oldest samples are dropped to keep latency bounded
+const MAX_QUEUED_SAMPLES: usize = 8192;
+
+// Block size used to plan the FFT before the first callback has reported one
+const FALLBACK_BLOCK_SIZE: usize = 512;
+
+#[derive(Default)]
+struct StreamInfo {
+    sample_rate: AtomicU32,
+    block_size: AtomicUsize,
+}
+
+// Width set by the UI and the resulting L/R correlation reported by the audio thread
+#[cfg(feature = "auralization")]
+struct StereoState {
+    width: f32,
+    correlation: f32,
+}
+
+struct ImpulseResponse {
+    name: String,
+    samples: Vec<f32>,
+    source_rate: u32,
+}
+
+fn main() {
+    let (engine_sender, engine_receiver) = channel::bounded::<OverlapAdd>(4);
+    let info = Arc::new(StreamInfo::default());
+    let gain = Arc::new(Mutex::new(1.0));
+    #[cfg(feature = "auralization")]
+    let stereo = Arc::new(Mutex::new(StereoState {
+        width: 1.0,
+        correlation: 1.0,
+    }));
+
+    let info_clone = Arc::clone(&info);
+    let gain_clone = Arc::clone(&gain);
+    #[cfg(feature = "auralization")]
+    let stereo_clone = Arc::clone(&stereo);
+    thread::spawn(move || {
+        if let Err(e) = run_convolver(
+            engine_receiver,
+            info_clone,
+            gain_clone,
+            #[cfg(feature = "auralization")]
+            stereo_clone,
+        ) {
+            eprintln!("Audio thread error: {:?}", e);
+        }
+    });
+
+    let app = ConvolverApp {
+        engine_sender,
+        info,
+        gain,
+        gain_db: 0.0,
+        #[cfg(feature = "auralization")]
+        stereo,
+        ir: None,
+        status: None,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "Real-Time Convolver",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+    .expect("Failed to launch GUI");
+}
+
+fn run_convolver(
+    engines: channel::Receiver<OverlapAdd>,
+    info: Arc<StreamInfo>,
+    gain: Arc<Mutex<f32>>,
+    #[cfg(feature = "auralization")] stereo: Arc<Mutex<StereoState>>,
+) -> Result<()> {
+    let host = cpal::default_host();
+    let input = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+    let output = host
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No output device available"))?;
+
+    let input_config = input.default_input_config()?;
+    let channels = input_config.channels() as usize;
+    let sample_rate = input_config.sample_rate();
+    info.sample_rate.store(sample_rate.0, Ordering::Relaxed);
+
+    // Run the output at the input rate so no resampling is needed in between
+    let output_channels = output.default_output_config()?.channels();
+    let output_config = cpal::StreamConfig {
+        channels: output_channels,
+        sample_rate,
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    // Stereo frames; without auralization both sides carry the same mono signal
+    let queue = Arc::new(Mutex::new(VecDeque::<[f32; 2]>::with_capacity(MAX_QUEUED_SAMPLES)));
+    let input_queue = Arc::clone(&queue);
+    let mut convolver = Convolver::new();
+    let mut dry = Vec::new();
+    let mut wet = Vec::new();
+    #[cfg(feature = "auralization")]
+    let (mut widener, mut meter) = (StereoWidth::new(sample_rate.0, 1.0), CorrelationMeter::new(sample_rate.0));
+
+    let input_stream = input.build_input_stream(
+        &input_config.into(),
+        move |data: &[f32], _| {
+            info.block_size.store(data.len() / channels, Ordering::Relaxed);
+            while let Ok(engine) = engines.try_recv() {
+                convolver.replace(engine);
+            }
+            if !convolver.is_loaded() {
+                return;
+            }
+
+            dry.clear();
+            dry.extend(data.chunks(channels).map(|frame| frame[0]));
+            wet.resize(dry.len(), 0.0);
+            convolver.process(&dry, &mut wet);
+
+            let gain = *gain.lock().unwrap();
+            let mut queue = input_queue.lock().unwrap();
+
+            #[cfg(feature = "auralization")]
+            {
+                let mut stereo = stereo.lock().unwrap();
+                widener.width = stereo.width;
+                for &y in &wet {
+                    let (l, r) = widener.process(y * gain);
+                    meter.push(l, r);
+                    queue.push_back([l, r]);
+                }
+                stereo.correlation = meter.correlation();
+            }
+            #[cfg(not(feature = "auralization"))]
+            queue.extend(wet.iter().map(|y| [y * gain; 2]));
+
+            let excess = queue.len().saturating_sub(MAX_QUEUED_SAMPLES);
+            queue.drain(..excess);
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    let output_channels = output_channels as usize;
+    let output_stream = output.build_output_stream(
+        &output_config,
+        move |data: &mut [f32], _| {
+            let mut queue = queue.lock().unwrap();
+            for frame in data.chunks_mut(output_channels) {
+                let [l, r] = queue.pop_front().unwrap_or([0.0; 2]);
+                match frame {
+                    [mono] => *mono = (l + r) / 2.0,
+                    [left, right, rest @ ..] => {
+                        *left = l;
+                        *right = r;
+                        rest.fill(0.0);
+                    }
+                    [] => {}
+                }
+            }
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    input_stream.play()?;
+    output_stream.play()?;
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn load_impulse_response(path: &Path, target_rate: u32) -> Result<ImpulseResponse> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    // Only the first channel of a multi-channel IR is used
+    let mut samples: Vec<f32> = interleaved
+        .chunks(spec.channels as usize)
+        .map(|frame| frame[0])
+        .collect();
+    if samples.is_empty() {
+        return Err(anyhow!("Impulse response contains no samples"));
+    }
+    if spec.sample_rate != target_rate {
+        samples = resample_linear(&samples, spec.sample_rate, target_rate);
+    }
+
+    // Normalise to unit energy so long, reverberant IRs do not blow up the output level
+    let energy = samples.iter().map(|s| s * s).sum::<f32>().sqrt();
+    if energy > 0.0 {
+        samples.iter_mut().for_each(|s| *s /= energy);
+    }
+
+    Ok(ImpulseResponse {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        samples,
+        source_rate: spec.sample_rate,
+    })
+}
+
+struct ConvolverApp {
+    engine_sender: channel::Sender<OverlapAdd>,
+    info: Arc<StreamInfo>,
+    gain: Arc<Mutex<f32>>,
+    gain_db: f32,
+    #[cfg(feature = "auralization")]
+    stereo: Arc<Mutex<StereoState>>,
+    ir: Option<ImpulseResponse>,
+    status: Option<String>,
+    screenshots: ScreenshotExporter,
+}
+
+impl ConvolverApp {
+    fn load_ir(&mut self, path: &Path) {
+        let sample_rate = self.info.sample_rate.load(Ordering::Relaxed);
+        if sample_rate == 0 {
+            self.status = Some("Input stream is not running yet".to_owned());
+            return;
+        }
+
+        match load_impulse_response(path, sample_rate) {
+            Ok(ir) => {
+                let block_size = match self.info.block_size.load(Ordering::Relaxed) {
+                    0 => FALLBACK_BLOCK_SIZE,
+                    n => n,
+                };
+                // Plan the FFTs here so the audio callback only swaps the engine in
+                let engine = OverlapAdd::new(&ir.samples, block_size);
+                if self.engine_sender.send(engine).is_err() {
+                    self.status = Some("Audio thread is not running".to_owned());
+                    return;
+                }
+                self.status = None;
+                self.ir = Some(ir);
+            }
+            Err(e) => self.status = Some(format!("Failed to load IR: {}", e)),
+        }
+    }
+}
+
+impl eframe::App for ConvolverApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Real-Time Convolver");
+
+            let sample_rate = self.info.sample_rate.load(Ordering::Relaxed);
+            let block_size = self.info.block_size.load(Ordering::Relaxed);
+            ui.label(format!(
+                "Stream: {} Hz | Callback block: {} samples",
+                sample_rate, block_size
+            ));
+
+            if ui.button("Load IR…").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("WAV", &["wav"])
+                    .pick_file()
+                {
+                    self.load_ir(&path);
+                }
+            }
+
+            match &self.ir {
+                Some(ir) => {
+                    ui.label(format!(
+                        "IR: {} ({:.1} ms, recorded at {} Hz)",
+                        ir.name,
+                        ir.samples.len() as f32 / sample_rate.max(1) as f32 * 1000.0,
+                        ir.source_rate
+                    ));
+                }
+                None => {
+                    ui.label("No impulse response loaded - output is silent");
+                }
+            }
+
+            if let Some(status) = &self.status {
+                ui.colored_label(egui::Color32::RED, status);
+            }
+
+            ui.separator();
+
+            if ui
+                .add(Slider::new(&mut self.gain_db, -40.0..=12.0).text("Output gain (dB)"))
+                .changed()
+            {
+                *self.gain.lock().unwrap() = 10f32.powf(self.gain_db / 20.0);
+            }
+
+            #[cfg(feature = "auralization")]
+            {
+                let mut stereo = self.stereo.lock().unwrap();
+                ui.add(Slider::new(&mut stereo.width, 0.0..=2.0).text("Stereo width (0 = mono, 1 = normal, 2 = wide)"));
+                let correlation = stereo.correlation;
+                ui.add(
+                    egui::ProgressBar::new((correlation + 1.0) / 2.0)
+                        .text(format!("L/R correlation: {:+.2}", correlation)),
+                );
+            }
+
+            ui.label("Use headphones: playing the convolved signal through speakers will feed back into the mic.");
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Line, Plot, PlotPoints};
+
+use mic_rms_visualizer::audio::{latest_n_samples, push_samples, AudioData};
+use mic_rms_visualizer::dsp::analyzer::SpectrumAnalyzer;
+use mic_rms_visualizer::ring::spawn_block_processor;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+const DEFAULT_FFT_SIZE: usize = 2048;
+const FFT_SIZES: [usize; 6] = [512, 1024, 2048, 4096, 8192, 16384];
+
+// Lowest frequency on the log axis
+const MIN_FREQ_HZ: f64 = 20.0;
+
+// Bottom of the plot, where the peak-hold bars start
+const MIN_DBFS: f64 = -120.0;
+
+// Peak-hold decay in dB/s; 0 holds forever and the maximum disables the hold
+const PEAK_DECAY_RANGE: std::ops::RangeInclusive<f32> = 0.0..=120.0;
+const DEFAULT_PEAK_DECAY: f32 = 10.0;
+
+fn main() -> Result<(), eframe::Error> {
+    let data = Arc::new(Mutex::new(AudioData::default()));
+    let fft_size = Arc::new(AtomicUsize::new(DEFAULT_FFT_SIZE));
+    start_audio_thread(Arc::clone(&data), Arc::clone(&fft_size));
+
+    let app = FftApp {
+        data,
+        fft_size,
+        analyzer: SpectrumAnalyzer::new(DEFAULT_FFT_SIZE),
+        peak_hold: Vec::new(),
+        peak_decay_db_per_sec: DEFAULT_PEAK_DECAY,
+        last_frame: None,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "🎧 Mic FFT Spectrum",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+}
+
+fn start_audio_thread(shared: Arc<Mutex<AudioData>>, fft_size: Arc<AtomicUsize>) {
+    thread::spawn(move || {
+        let host = cpal::default_host();
+        let device = host.default_input_device().expect("No input device found");
+        let config = device.default_input_config().unwrap();
+        let channels = config.channels() as usize;
+        let sample_rate = config.sample_rate().0;
+        shared.lock().unwrap().sample_rate = sample_rate;
+
+        // The callback only queues its block; the lock is taken on the processing thread
+        // First channel of each block, reused across blocks
+        let mut mono = Vec::new();
+        let mut producer = spawn_block_processor(sample_rate as usize * channels, channels, move |_, block| {
+            mono.clear();
+            mono.extend(block.chunks(channels).map(|frame| frame[0]));
+            push_samples(&shared, &mono, fft_size.load(Ordering::Relaxed));
+        });
+        let sample_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            producer.push(data, Instant::now());
+        };
+
+        let err_fn = |err| eprintln!("Stream error: {}", err);
+        let stream = device
+            .build_input_stream(&config.into(), sample_fn, err_fn, None)
+            .unwrap();
+
+        stream.play().unwrap();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+struct FftApp {
+    data: Arc<Mutex<AudioData>>,
+    // Samples kept by the callback and analyzed per frame
+    fft_size: Arc<AtomicUsize>,
+    analyzer: SpectrumAnalyzer,
+    // Highest dBFS per bin, decayed at `peak_decay_db_per_sec`
+    peak_hold: Vec<f32>,
+    peak_decay_db_per_sec: f32,
+    last_frame: Option<Instant>,
+    screenshots: ScreenshotExporter,
+}
+
+impl FftApp {
+    fn update_peak_hold(&mut self, magnitudes: &[f64]) {
+        let now = Instant::now();
+        let elapsed = self.last_frame.replace(now).map_or(0.0, |t| now.duration_since(t).as_secs_f32());
+        let hold = self.peak_decay_db_per_sec < *PEAK_DECAY_RANGE.end();
+        if !hold || self.peak_hold.len() != magnitudes.len() {
+            self.peak_hold = magnitudes.iter().map(|&db| db as f32).collect();
+            return;
+        }
+        let decay = self.peak_decay_db_per_sec * elapsed;
+        for (peak, &db) in self.peak_hold.iter_mut().zip(magnitudes) {
+            *peak = (*peak - decay).max(db as f32);
+        }
+    }
+
+    // Bin with the highest held level, skipping DC
+    fn loudest_peak(&self) -> Option<(usize, f32)> {
+        self.peak_hold
+            .iter()
+            .copied()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+impl eframe::App for FftApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        // Ctrl+P is the screenshot
+        if ctx.input(|i| !i.modifiers.command && i.key_pressed(egui::Key::P)) {
+            self.peak_hold.clear();
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let sample_rate = self.data.lock().unwrap().sample_rate;
+            let mut fft_size = self.fft_size.load(Ordering::Relaxed);
+            let bin_hz = sample_rate.max(1) as f64 / fft_size as f64;
+            ui.horizontal(|ui| {
+                ui.heading("📈 Live FFT Spectrum");
+                // Held levels from the previous frame
+                if let Some((bin, db)) = self.loudest_peak() {
+                    ui.label(format!("Peak: {:.0} Hz at {:.1} dBFS", bin as f64 * bin_hz, db));
+                }
+            });
+
+            egui::ComboBox::from_label("FFT size")
+                .selected_text(fft_size.to_string())
+                .show_ui(ui, |ui| {
+                    for size in FFT_SIZES {
+                        ui.selectable_value(&mut fft_size, size, size.to_string());
+                    }
+                });
+            if fft_size != self.fft_size.load(Ordering::Relaxed) {
+                self.fft_size.store(fft_size, Ordering::Relaxed);
+                self.analyzer = SpectrumAnalyzer::new(fft_size);
+            }
+
+            let bin_hz = sample_rate.max(1) as f64 / fft_size as f64;
+            ui.label(format!(
+                "{} Hz | {:.1} Hz per bin | {:.0} ms frame",
+                sample_rate,
+                bin_hz,
+                fft_size as f64 / sample_rate.max(1) as f64 * 1000.0
+            ));
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.peak_decay_db_per_sec, PEAK_DECAY_RANGE)
+                        .suffix(" dB/s")
+                        .text("Peak decay"),
+                )
+                .on_hover_text("0 holds peaks until reset; the maximum turns the hold off");
+                if ui.button("Reset peaks").on_hover_text("P").clicked() {
+                    self.peak_hold.clear();
+                }
+            });
+
+            // Zero-filled at the front until the callback has delivered a whole frame, so
+            // the plot shows immediately
+            let mut frame = latest_n_samples(&self.data, fft_size);
+            frame.splice(0..0, std::iter::repeat_n(0.0, fft_size - frame.len()));
+            let magnitudes = self.analyzer.analyze(&frame).to_vec();
+            self.update_peak_hold(&magnitudes);
+
+            // Log frequency axis: plot against log10(f), skipping DC and bins below MIN_FREQ_HZ
+            let points: PlotPoints = magnitudes
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &db)| (i as f64 * bin_hz, db))
+                .filter(|&(f, _)| f >= MIN_FREQ_HZ)
+                .map(|(f, db)| [f.log10(), db])
+                .collect();
+
+            // One bar per bin from the plot floor up to the held level, as wide as the bin
+            let half_bin = bin_hz / 2.0;
+            let peak_bars: Vec<Bar> = self
+                .peak_hold
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, &db)| (i as f64 * bin_hz, db as f64))
+                .filter(|&(f, _)| f >= MIN_FREQ_HZ)
+                .map(|(f, db)| {
+                    let width = (f + half_bin).log10() - (f - half_bin).log10();
+                    Bar::new(f.log10(), (db - MIN_DBFS).max(0.0))
+                        .base_offset(MIN_DBFS)
+                        .width(width)
+                        .stroke(egui::Stroke::NONE)
+                })
+                .collect();
+
+            Plot::new("fft_plot")
+                .view_aspect(2.0)
+                .include_y(MIN_DBFS)
+                .include_y(0.0)
+                .x_axis_label("Frequency (Hz)")
+                .y_axis_label("dBFS")
+                .x_axis_formatter(|mark, _, _| format!("{:.0}", 10f64.powf(mark.value)))
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(
+                        BarChart::new(peak_bars)
+                            .color(egui::Color32::from_gray(110))
+                            .name("Peak hold"),
+                    );
+                    plot_ui.line(Line::new(points).name("Magnitude"));
+                });
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use eframe::egui::{self, DragValue, Slider};
+use egui_plot::{Line, Plot, PlotPoints};
+
+use mic_rms_visualizer::dsp::biquad::{Biquad, BUTTERWORTH_4TH_Q};
+use mic_rms_visualizer::dsp::resample::SampleRateConverter;
+use mic_rms_visualizer::dsp::spectrum::magnitude_spectrum_dbfs;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+// Highest input rate requested from the device
+const MAX_INPUT_RATE: u32 = 192_000;
+
+// The mixed-down signal is band-limited to the audible range
+const AUDIBLE_CUTOFF_HZ: f32 = 20_000.0;
+
+const SPECTRUM_LEN: usize = 4096;
+const MAX_QUEUED_SAMPLES: usize = 16384;
+
+struct MixerState {
+    lo_hz: f32,
+    input_rate: u32,
+    output_rate: u32,
+    original: VecDeque<f32>,
+    mixed: VecDeque<f32>,
+}
+
+fn main() {
+    let state = Arc::new(Mutex::new(MixerState {
+        lo_hz: 40_000.0,
+        input_rate: 0,
+        output_rate: 0,
+        original: VecDeque::from(vec![0.0; SPECTRUM_LEN]),
+        mixed: VecDeque::from(vec![0.0; SPECTRUM_LEN]),
+    }));
+
+    let state_clone = Arc::clone(&state);
+    thread::spawn(move || {
+        if let Err(e) = run_mixer(state_clone) {
+            eprintln!("Audio thread error: {:?}", e);
+        }
+    });
+
+    let app = HeterodyneApp {
+        state,
+        coarse_khz: 40.0,
+        fine_hz: 0.0,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "Heterodyne Mixer",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+    .expect("Failed to launch GUI");
+}
+
+fn run_mixer(state: Arc<Mutex<MixerState>>) -> Result<()> {
+    let host = cpal::default_host();
+    let input = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+    let output = host
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No output device available"))?;
+
+    // Ultrasound needs the highest rate the interface offers
+    let input_config = input
+        .supported_input_configs()?
+        .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
+        .max_by_key(|c| c.max_sample_rate().0.min(MAX_INPUT_RATE))
+        .map(|c| {
+            let rate = c.max_sample_rate().0.min(MAX_INPUT_RATE).max(c.min_sample_rate().0);
+            c.with_sample_rate(cpal::SampleRate(rate))
+        })
+        .ok_or_else(|| anyhow!("Input device has no f32 configuration"))?;
+    let output_config = output.default_output_config()?;
+
+    let channels = input_config.channels() as usize;
+    let input_rate = input_config.sample_rate().0;
+    let output_rate = output_config.sample_rate().0;
+    let output_channels = output_config.channels() as usize;
+    {
+        let mut state = state.lock().unwrap();
+        state.input_rate = input_rate;
+        state.output_rate = output_rate;
+    }
+
+    let queue = Arc::new(Mutex::new(VecDeque::<f32>::with_capacity(MAX_QUEUED_SAMPLES)));
+    let input_queue = Arc::clone(&queue);
+    let cutoff = AUDIBLE_CUTOFF_HZ.min(output_rate as f32 * 0.45);
+    let mut low_pass = BUTTERWORTH_4TH_Q.map(|q| Biquad::low_pass(input_rate as f32, cutoff, q));
+    let mut converter = SampleRateConverter::new(input_rate, output_rate);
+    let mut phase = 0.0f32;
+    let mut mixed_block = Vec::new();
+    let mut resampled = Vec::new();
+
+    let input_stream = input.build_input_stream(
+        &input_config.into(),
+        move |data: &[f32], _| {
+            let mut state = state.lock().unwrap();
+            let phase_step = TAU * state.lo_hz / input_rate as f32;
+
+            mixed_block.clear();
+            for frame in data.chunks(channels) {
+                let x = frame[0];
+                let y = low_pass
+                    .iter_mut()
+                    .fold(x * phase.cos(), |acc, section| section.process(acc));
+                phase = (phase + phase_step) % TAU;
+
+                state.original.push_back(x);
+                state.mixed.push_back(y);
+                mixed_block.push(y);
+            }
+            let excess = state.original.len().saturating_sub(SPECTRUM_LEN);
+            state.original.drain(..excess);
+            state.mixed.drain(..excess);
+            drop(state);
+
+            resampled.clear();
+            converter.process(&mixed_block, &mut resampled);
+            let mut queue = input_queue.lock().unwrap();
+            queue.extend(resampled.iter().copied());
+            let excess = queue.len().saturating_sub(MAX_QUEUED_SAMPLES);
+            queue.drain(..excess);
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    let output_stream = output.build_output_stream(
+        &output_config.into(),
+        move |data: &mut [f32], _| {
+            let mut queue = queue.lock().unwrap();
+            for frame in data.chunks_mut(output_channels) {
+                frame.fill(queue.pop_front().unwrap_or(0.0));
+            }
+        },
+        move |err| {
+            eprintln!("Stream error: {:?}", err);
+        },
+        None,
+    )?;
+
+    input_stream.play()?;
+    output_stream.play()?;
+    loop {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn spectrum_points(samples: &VecDeque<f32>, sample_rate: u32) -> PlotPoints {
+    let samples: Vec<f32> = samples.iter().copied().collect();
+    let bin_hz = sample_rate as f64 / samples.len().max(1) as f64;
+    magnitude_spectrum_dbfs(&samples)
+        .iter()
+        .enumerate()
+        .map(|(i, &db)| [i as f64 * bin_hz / 1000.0, db as f64])
+        .collect()
+}
+
+struct HeterodyneApp {
+    state: Arc<Mutex<MixerState>>,
+    coarse_khz: f32,
+    fine_hz: f32,
+    screenshots: ScreenshotExporter,
+}
+
+impl eframe::App for HeterodyneApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Heterodyne Mixer");
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    Slider::new(&mut self.coarse_khz, 20.0..=100.0)
+                        .step_by(1.0)
+                        .text("LO coarse (kHz)"),
+                );
+                ui.label("Fine (Hz):");
+                ui.add(DragValue::new(&mut self.fine_hz).speed(10.0).clamp_range(-500.0..=500.0));
+            });
+            self.fine_hz = (self.fine_hz / 10.0).round() * 10.0;
+            let lo_hz = self.coarse_khz * 1000.0 + self.fine_hz;
+
+            let (original, mixed, input_rate, output_rate) = {
+                let mut state = self.state.lock().unwrap();
+                state.lo_hz = lo_hz;
+                (
+                    spectrum_points(&state.original, state.input_rate),
+                    spectrum_points(&state.mixed, state.input_rate),
+                    state.input_rate,
+                    state.output_rate,
+                )
+            };
+
+            ui.label(format!(
+                "LO: {:.2} kHz | Input: {} Hz | Output: {} Hz",
+                lo_hz / 1000.0,
+                input_rate,
+                output_rate
+            ));
+            if input_rate > 0 && lo_hz >= input_rate as f32 / 2.0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    "LO is above the input Nyquist frequency - the device cannot capture this band",
+                );
+            }
+
+            ui.columns(2, |columns| {
+                columns[0].label("Original (ultrasonic)");
+                Plot::new("original_spectrum")
+                    .view_aspect(1.5)
+                    .include_y(-120.0)
+                    .include_y(0.0)
+                    .x_axis_label("kHz")
+                    .y_axis_label("dBFS")
+                    .show(&mut columns[0], |plot_ui| {
+                        plot_ui.line(Line::new(original));
+                    });
+
+                columns[1].label("Mixed down (audible)");
+                Plot::new("mixed_spectrum")
+                    .view_aspect(1.5)
+                    .include_y(-120.0)
+                    .include_y(0.0)
+                    .include_x(0.0)
+                    .include_x(AUDIBLE_CUTOFF_HZ as f64 / 1000.0)
+                    .x_axis_label("kHz")
+                    .y_axis_label("dBFS")
+                    .show(&mut columns[1], |plot_ui| {
+                        plot_ui.line(Line::new(mixed));
+                    });
+            });
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
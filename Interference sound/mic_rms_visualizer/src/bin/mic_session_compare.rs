@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use eframe::egui::{self, Color32};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use mic_rms_visualizer::report::{write_comparison_pdf, ComparisonReport};
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+use mic_rms_visualizer::session::{Session, SessionComparison, SESSION_EXTENSION};
+
+// Lowest frequency drawn on the log axis
+const MIN_FREQ_HZ: f32 = 20.0;
+
+const COLOR_A: Color32 = Color32::from_rgb(60, 120, 220);
+const COLOR_B: Color32 = Color32::from_rgb(230, 130, 30);
+const COLOR_DIFFERENCE: Color32 = Color32::from_rgb(200, 40, 40);
+
+#[derive(Parser)]
+#[command(about = "Compare two amplitude-vs-X sessions saved by mic_2d_A_vs_x")]
+struct Args {
+    /// Session A (.mrviz)
+    a: Option<PathBuf>,
+    /// Session B (.mrviz), compared against A
+    b: Option<PathBuf>,
+}
+
+// A loaded session and the file name it is shown under
+struct LoadedSession {
+    name: String,
+    session: Session,
+}
+
+#[derive(Default)]
+struct SessionComparisonTool {
+    a: Option<LoadedSession>,
+    b: Option<LoadedSession>,
+    // Recomputed whenever A or B changes
+    comparison: Option<SessionComparison>,
+    status: Option<String>,
+    screenshots: ScreenshotExporter,
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut app = SessionComparisonTool::default();
+    if let Some(path) = &args.a {
+        app.load(path, false);
+    }
+    if let Some(path) = &args.b {
+        app.load(path, true);
+    }
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "📊 Session Comparison",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+    .expect("Failed to launch GUI");
+}
+
+impl SessionComparisonTool {
+    fn load(&mut self, path: &Path, into_b: bool) {
+        let session = match Session::load(path) {
+            Ok(session) => session,
+            Err(e) => {
+                self.status = Some(format!("Failed to load session: {:#}", e));
+                return;
+            }
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        let slot = if into_b { &mut self.b } else { &mut self.a };
+        *slot = Some(LoadedSession { name, session });
+        self.status = None;
+        self.comparison = match (&self.a, &self.b) {
+            (Some(a), Some(b)) => Some(SessionComparison::new(&a.session, &b.session)),
+            _ => None,
+        };
+    }
+
+    fn pick_and_load(&mut self, into_b: bool) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Session", &[SESSION_EXTENSION])
+            .pick_file()
+        {
+            self.load(&path, into_b);
+        }
+    }
+
+    fn export_pdf(&mut self) {
+        let (Some(a), Some(b), Some(comparison)) = (&self.a, &self.b, &self.comparison) else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .set_file_name("session_comparison.pdf")
+            .save_file()
+        else {
+            return;
+        };
+
+        let report = ComparisonReport {
+            a_name: &a.name,
+            b_name: &b.name,
+            a: &a.session,
+            b: &b.session,
+            comparison,
+        };
+        self.status = Some(match write_comparison_pdf(&path, &report) {
+            Ok(()) => format!("Report saved to {}", path.display()),
+            Err(e) => format!("Failed to write report: {}", e),
+        });
+    }
+}
+
+fn points(values: &[(f32, f32)]) -> PlotPoints {
+    values.iter().map(|&(x, y)| [x as f64, y as f64]).collect()
+}
+
+// Spectrum against log10(f), leaving out everything below MIN_FREQ_HZ
+fn log_points(values: &[(f32, f32)]) -> PlotPoints {
+    values
+        .iter()
+        .filter(|&&(f, _)| f >= MIN_FREQ_HZ)
+        .map(|&(f, db)| [(f as f64).log10(), db as f64])
+        .collect()
+}
+
+impl eframe::App for SessionComparisonTool {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("📊 Session comparison");
+            ui.horizontal(|ui| {
+                if ui.button("Load A…").clicked() {
+                    self.pick_and_load(false);
+                }
+                if ui.button("Load B…").clicked() {
+                    self.pick_and_load(true);
+                }
+                if ui
+                    .add_enabled(self.comparison.is_some(), egui::Button::new("Export report PDF…"))
+                    .clicked()
+                {
+                    self.export_pdf();
+                }
+            });
+            for (label, loaded, color) in [("A", &self.a, COLOR_A), ("B", &self.b, COLOR_B)] {
+                let text = match loaded {
+                    Some(LoadedSession { name, session }) => format!(
+                        "{}: {} | {} @ {} Hz | {} | {:.1} °C, {:.0} % | {} points",
+                        label,
+                        name,
+                        session.device_name,
+                        session.sample_rate,
+                        session.date,
+                        session.temperature_c,
+                        session.humidity_pct,
+                        session.values.len(),
+                    ),
+                    None => format!("{}: not loaded", label),
+                };
+                ui.colored_label(color, text);
+            }
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+
+            if let Some(comparison) = &self.comparison {
+                let similarity = match comparison.similarity {
+                    Some(r) => format!("Similarity: {:.1} %", r * 100.0),
+                    None => "Similarity: — (no common X range)".to_owned(),
+                };
+                let stats = |stats: Option<(f32, f32)>, unit: &str| match stats {
+                    Some((mean, max)) => format!("mean {:+.4}{}, largest {:.4}{}", mean, unit, max, unit),
+                    None => "—".to_owned(),
+                };
+                ui.label(format!(
+                    "{}   |   B − A amplitude: {}   |   B − A spectrum: {}",
+                    similarity,
+                    stats(comparison.amplitude_difference_stats(), ""),
+                    stats(comparison.spectrum_difference_stats(), " dB"),
+                ));
+            }
+
+            let plot_height = (ui.available_height() / 2.0 - 10.0).max(100.0);
+            ui.columns(2, |columns| {
+                Plot::new("session_amplitude")
+                    .height(plot_height)
+                    .legend(Legend::default())
+                    .x_axis_label("X position")
+                    .y_axis_label("RMS")
+                    .include_y(0.0)
+                    .show(&mut columns[0], |plot_ui| {
+                        for (loaded, color) in [(&self.a, COLOR_A), (&self.b, COLOR_B)] {
+                            if let Some(loaded) = loaded {
+                                plot_ui.line(Line::new(points(&loaded.session.values)).color(color).name(&loaded.name));
+                            }
+                        }
+                    });
+                Plot::new("session_spectrum")
+                    .height(plot_height)
+                    .legend(Legend::default())
+                    .x_axis_label("Frequency (Hz)")
+                    .y_axis_label("dBFS")
+                    .x_axis_formatter(|mark, _, _| format!("{:.0}", 10f64.powf(mark.value)))
+                    .show(&mut columns[1], |plot_ui| {
+                        for (loaded, color) in [(&self.a, COLOR_A), (&self.b, COLOR_B)] {
+                            if let Some(loaded) = loaded {
+                                plot_ui.line(Line::new(log_points(&loaded.session.spectrum)).color(color).name(&loaded.name));
+                            }
+                        }
+                    });
+            });
+
+            let Some(comparison) = &self.comparison else {
+                return;
+            };
+            ui.columns(2, |columns| {
+                Plot::new("session_amplitude_difference")
+                    .height(plot_height)
+                    .x_axis_label("X position")
+                    .y_axis_label("B − A")
+                    .include_y(0.0)
+                    .show(&mut columns[0], |plot_ui| {
+                        plot_ui.line(Line::new(points(&comparison.amplitude_difference)).color(COLOR_DIFFERENCE));
+                    });
+                Plot::new("session_spectrum_difference")
+                    .height(plot_height)
+                    .x_axis_label("Frequency (Hz)")
+                    .y_axis_label("B − A (dB)")
+                    .x_axis_formatter(|mark, _, _| format!("{:.0}", 10f64.powf(mark.value)))
+                    .include_y(0.0)
+                    .show(&mut columns[1], |plot_ui| {
+                        plot_ui.line(Line::new(log_points(&comparison.spectrum_difference)).color(COLOR_DIFFERENCE));
+                    });
+            });
+        });
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use eframe::egui;
+
+use mic_rms_visualizer::dsp::analyzer::SpectrumAnalyzer;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+
+const DEFAULT_FFT_SIZE: usize = 512;
+const FFT_SIZES: [usize; 4] = [256, 512, 1024, 2048];
+const OVERLAPS_PCT: [usize; 3] = [0, 50, 75];
+
+// Time steps kept on screen
+const HISTORY_COLUMNS: usize = 200;
+
+// Unprocessed samples are capped so a stalled UI does not grow the queue forever
+const MAX_PENDING: usize = 1 << 16;
+
+// Levels mapped onto the colour map
+const MIN_DBFS: f64 = -120.0;
+const MAX_DBFS: f64 = 0.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColorMap {
+    Viridis,
+    Inferno,
+    Grayscale,
+}
+
+impl ColorMap {
+    const ALL: [ColorMap; 3] = [ColorMap::Viridis, ColorMap::Inferno, ColorMap::Grayscale];
+
+    fn name(self) -> &'static str {
+        match self {
+            ColorMap::Viridis => "Viridis",
+            ColorMap::Inferno => "Inferno",
+            ColorMap::Grayscale => "Grayscale",
+        }
+    }
+
+    // `t` in 0..=1, cold to hot
+    fn color(self, t: f32) -> egui::Color32 {
+        // Five evenly spaced control points of the matplotlib maps
+        const VIRIDIS: [[f32; 3]; 5] = [
+            [68.0, 1.0, 84.0],
+            [59.0, 82.0, 139.0],
+            [33.0, 145.0, 140.0],
+            [94.0, 201.0, 98.0],
+            [253.0, 231.0, 37.0],
+        ];
+        const INFERNO: [[f32; 3]; 5] = [
+            [0.0, 0.0, 4.0],
+            [87.0, 16.0, 110.0],
+            [188.0, 55.0, 84.0],
+            [249.0, 142.0, 9.0],
+            [252.0, 255.0, 164.0],
+        ];
+
+        let t = t.clamp(0.0, 1.0);
+        let stops = match self {
+            ColorMap::Viridis => &VIRIDIS,
+            ColorMap::Inferno => &INFERNO,
+            ColorMap::Grayscale => {
+                let v = (t * 255.0) as u8;
+                return egui::Color32::from_gray(v);
+            }
+        };
+        let pos = t * (stops.len() - 1) as f32;
+        let i = (pos as usize).min(stops.len() - 2);
+        let frac = pos - i as f32;
+        let channel = |c: usize| (stops[i][c] + (stops[i + 1][c] - stops[i][c]) * frac) as u8;
+        egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+    }
+}
+
+#[derive(Default)]
+struct SpectrogramData {
+    // Samples not yet turned into columns
+    pending: VecDeque<f32>,
+    sample_rate: u32,
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let data = Arc::new(Mutex::new(SpectrogramData::default()));
+    start_audio_thread(Arc::clone(&data));
+
+    let app = SpectrogramApp {
+        data,
+        analyzer: SpectrumAnalyzer::new(DEFAULT_FFT_SIZE),
+        overlap_pct: 50,
+        color_map: ColorMap::Viridis,
+        columns: vec![MIN_DBFS as f32; HISTORY_COLUMNS * DEFAULT_FFT_SIZE / 2],
+        next_column: 0,
+        texture: None,
+        screenshots: ScreenshotExporter::default(),
+    };
+
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "🎧 Mic Spectrogram",
+        native_options,
+        Box::new(|_cc| Box::new(app)),
+    )
+}
+
+fn start_audio_thread(shared: Arc<Mutex<SpectrogramData>>) {
+    thread::spawn(move || {
+        let host = cpal::default_host();
+        let device = host.default_input_device().expect("No input device found");
+        let config = device.default_input_config().unwrap();
+        let channels = config.channels() as usize;
+        shared.lock().unwrap().sample_rate = config.sample_rate().0;
+
+        let sample_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let mut buffer = shared.lock().unwrap();
+            buffer.pending.extend(data.chunks(channels).map(|frame| frame[0]));
+            let excess = buffer.pending.len().saturating_sub(MAX_PENDING);
+            buffer.pending.drain(..excess);
+        };
+
+        let err_fn = |err| eprintln!("Stream error: {}", err);
+        let stream = device
+            .build_input_stream(&config.into(), sample_fn, err_fn, None)
+            .unwrap();
+
+        stream.play().unwrap();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+struct SpectrogramApp {
+    data: Arc<Mutex<SpectrogramData>>,
+    analyzer: SpectrumAnalyzer,
+    overlap_pct: usize,
+    color_map: ColorMap,
+    // HISTORY_COLUMNS columns of fft_size / 2 dBFS bins, written circularly
+    columns: Vec<f32>,
+    next_column: usize,
+    // Reused every frame so only the pixels are uploaded
+    texture: Option<egui::TextureHandle>,
+    screenshots: ScreenshotExporter,
+}
+
+impl SpectrogramApp {
+    fn bins(&self) -> usize {
+        self.analyzer.frame_len() / 2
+    }
+
+    fn hop(&self) -> usize {
+        (self.analyzer.frame_len() * (100 - self.overlap_pct) / 100).max(1)
+    }
+
+    fn set_fft_size(&mut self, fft_size: usize) {
+        self.analyzer = SpectrumAnalyzer::new(fft_size);
+        self.columns = vec![MIN_DBFS as f32; HISTORY_COLUMNS * fft_size / 2];
+        self.next_column = 0;
+    }
+
+    // Turns every complete frame of pending samples into a column
+    fn consume_pending(&mut self) {
+        let (fft_size, hop, bins) = (self.analyzer.frame_len(), self.hop(), self.bins());
+        let mut data = self.data.lock().unwrap();
+        while data.pending.len() >= fft_size {
+            let magnitudes = self.analyzer.analyze(data.pending.iter().take(fft_size));
+            let column = &mut self.columns[self.next_column * bins..(self.next_column + 1) * bins];
+            for (dst, &db) in column.iter_mut().zip(magnitudes) {
+                *dst = db as f32;
+            }
+            self.next_column = (self.next_column + 1) % HISTORY_COLUMNS;
+            data.pending.drain(..hop);
+        }
+    }
+
+    // Oldest column on the left, lowest frequency at the bottom
+    fn image(&self) -> egui::ColorImage {
+        let bins = self.bins();
+        let mut image = egui::ColorImage::new([HISTORY_COLUMNS, bins], egui::Color32::BLACK);
+        for x in 0..HISTORY_COLUMNS {
+            let column = (self.next_column + x) % HISTORY_COLUMNS;
+            for bin in 0..bins {
+                let db = self.columns[column * bins + bin] as f64;
+                let t = ((db - MIN_DBFS) / (MAX_DBFS - MIN_DBFS)) as f32;
+                image[(x, bins - 1 - bin)] = self.color_map.color(t);
+            }
+        }
+        image
+    }
+}
+
+impl eframe::App for SpectrogramApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("🌈 Live Spectrogram");
+
+            ui.horizontal(|ui| {
+                let mut fft_size = self.analyzer.frame_len();
+                egui::ComboBox::from_label("FFT size")
+                    .selected_text(fft_size.to_string())
+                    .show_ui(ui, |ui| {
+                        for size in FFT_SIZES {
+                            ui.selectable_value(&mut fft_size, size, size.to_string());
+                        }
+                    });
+                if fft_size != self.analyzer.frame_len() {
+                    self.set_fft_size(fft_size);
+                }
+
+                egui::ComboBox::from_label("Overlap")
+                    .selected_text(format!("{} %", self.overlap_pct))
+                    .show_ui(ui, |ui| {
+                        for overlap in OVERLAPS_PCT {
+                            ui.selectable_value(&mut self.overlap_pct, overlap, format!("{} %", overlap));
+                        }
+                    });
+
+                egui::ComboBox::from_label("Colour map")
+                    .selected_text(self.color_map.name())
+                    .show_ui(ui, |ui| {
+                        for map in ColorMap::ALL {
+                            ui.selectable_value(&mut self.color_map, map, map.name());
+                        }
+                    });
+            });
+
+            self.consume_pending();
+
+            let sample_rate = self.data.lock().unwrap().sample_rate.max(1) as f32;
+            let fft_size = self.analyzer.frame_len() as f32;
+            ui.label(format!(
+                "0 – {:.0} Hz | {:.1} Hz per bin | {:.1} s shown",
+                sample_rate / 2.0,
+                sample_rate / fft_size,
+                HISTORY_COLUMNS as f32 * self.hop() as f32 / sample_rate
+            ));
+
+            let image = self.image();
+            let texture = match &mut self.texture {
+                Some(texture) => {
+                    texture.set(image, egui::TextureOptions::LINEAR);
+                    texture.clone()
+                }
+                None => {
+                    let texture = ctx.load_texture("spectrogram", image, egui::TextureOptions::LINEAR);
+                    self.texture.insert(texture).clone()
+                }
+            };
+            let size = ui.available_size();
+            ui.image(egui::load::SizedTexture::new(texture.id(), size));
+        });
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,118 @@
+//! Microphone sensitivity calibration with an acoustic calibrator.
+//!
+//! The calibrator plays a 1 kHz tone at a known 94 dBSPL (1 Pa). The input RMS
+//! it produces gives the offset from dBFS to dBSPL for this mic and gain setting.
+//! The level readouts, Leq and SEL all report through `calibrated_level`.
+
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+
+/// Level of a standard class 1 calibrator, 1 Pa RMS.
+pub const CALIBRATOR_DBSPL: f32 = 94.0;
+pub const CALIBRATOR_HZ: f32 = 1000.0;
+/// Length of the calibrator recording.
+pub const CALIBRATION_SECS: f32 = 3.0;
+
+/// Level of `rms` (full scale = 1.0) in dBSPL, given the correction found by
+/// calibration; with a correction of 0 this is the level in dBFS.
+pub fn rms_to_dbspl(rms: f32, sensitivity_correction_db: f32) -> f32 {
+    20.0 * rms.max(1e-10).log10() + sensitivity_correction_db
+}
+
+/// A level measured in dBFS as it is reported: in dBSPL once calibrated, in
+/// dBFS before. Returns the level and its unit.
+pub fn calibrated_level(dbfs: f32, sensitivity_correction_db: Option<f32>) -> (f32, &'static str) {
+    match sensitivity_correction_db {
+        Some(correction) => (dbfs + correction, "dBSPL"),
+        None => (dbfs, "dBFS"),
+    }
+}
+
+/// The two steps of the wizard. The UI moves it from `Prompt` to `Recording`
+/// and the audio callback feeds the recording.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CalibrationWizard {
+    #[default]
+    Idle,
+    /// Waiting for the calibrator to be fitted and switched on.
+    Prompt,
+    Recording {
+        started: Instant,
+        sum_squares: f64,
+        frames: usize,
+    },
+}
+
+impl CalibrationWizard {
+    pub fn prompt(&mut self) {
+        *self = Self::Prompt;
+    }
+
+    pub fn start_recording(&mut self) {
+        *self = Self::Recording {
+            started: Instant::now(),
+            sum_squares: 0.0,
+            frames: 0,
+        };
+    }
+
+    pub fn cancel(&mut self) {
+        *self = Self::Idle;
+    }
+
+    /// Adds a block's sum of squares over `frames` frames while recording.
+    pub fn push(&mut self, sum_squares: f32, frames: usize) {
+        if let Self::Recording {
+            sum_squares: total,
+            frames: count,
+            ..
+        } = self
+        {
+            *total += sum_squares as f64;
+            *count += frames;
+        }
+    }
+
+    /// Seconds of recording left, or `None` when not recording.
+    pub fn remaining_secs(&self) -> Option<f32> {
+        match self {
+            Self::Recording { started, .. } => Some((CALIBRATION_SECS - started.elapsed().as_secs_f32()).max(0.0)),
+            _ => None,
+        }
+    }
+
+    /// `sensitivity_correction_db` once CALIBRATION_SECS have been recorded, after
+    /// which the wizard is back to `Idle`; `None` until then. Silence is an error.
+    pub fn finish(&mut self) -> Option<Result<f32>> {
+        let Self::Recording {
+            started,
+            sum_squares,
+            frames,
+        } = *self
+        else {
+            return None;
+        };
+        if started.elapsed().as_secs_f32() < CALIBRATION_SECS {
+            return None;
+        }
+        *self = Self::Idle;
+        let measured_rms = (sum_squares / frames.max(1) as f64).sqrt() as f32;
+        Some(if measured_rms > 0.0 {
+            Ok(CALIBRATOR_DBSPL - rms_to_dbspl(measured_rms, 0.0))
+        } else {
+            Err(anyhow!("No signal from the calibrator"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibrated_levels_are_in_dbspl() {
+        assert_eq!(calibrated_level(-30.0, Some(120.0)), (90.0, "dBSPL"));
+        assert_eq!(calibrated_level(-30.0, None), (-30.0, "dBFS"));
+    }
+}
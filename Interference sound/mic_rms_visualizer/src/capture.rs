@@ -0,0 +1,250 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use cpal::SupportedStreamConfig;
+use crossbeam::channel;
+
+use crate::recording::{backup_path, check_same_size, Redundancy};
+
+// Callback blocks queued for the writer; past this, blocks are dropped rather than blocking audio
+const QUEUE_BLOCKS: usize = 256;
+// Buffers handed to the callback up front, and the samples each can hold before it grows
+const POOL_BLOCKS: usize = 32;
+const POOL_BLOCK_LEN: usize = 4096;
+// How often the writer thread checks for a stop while no audio arrives
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Controls a capture started by `capture_to_file`. Stopping, or dropping the
+/// handle, makes the writer write out what is queued and finalize the file.
+pub struct CaptureHandle {
+    sender: CaptureSender,
+    stopped: Arc<AtomicBool>,
+    backup_failed: Arc<AtomicBool>,
+}
+
+impl CaptureHandle {
+    /// Sender for interleaved callback blocks.
+    pub fn sender(&self) -> CaptureSender {
+        self.sender.clone()
+    }
+
+    /// True once the backup copy has failed; the primary file is still being written.
+    pub fn backup_failed(&self) -> bool {
+        self.backup_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(self) {
+        // Drop does the work
+    }
+}
+
+impl Drop for CaptureHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Audio-thread end of a capture. Blocks are copied into buffers the writer hands
+/// back once written, so sending only allocates if the writer falls behind the pool.
+#[derive(Clone)]
+pub struct CaptureSender {
+    blocks: channel::Sender<Vec<f32>>,
+    free: channel::Receiver<Vec<f32>>,
+}
+
+impl CaptureSender {
+    /// Queues a copy of `block` without waiting. Returns false if the queue was full
+    /// and the block was dropped.
+    pub fn try_send(&self, block: &[f32]) -> bool {
+        let mut buffer = self.free.try_recv().unwrap_or_default();
+        buffer.clear();
+        buffer.extend_from_slice(block);
+        self.blocks.try_send(buffer).is_ok()
+    }
+}
+
+/// Streams interleaved samples to a 32-bit float WAV file at `path` in the format of
+/// `config`, from a background thread. With a `backup_dir`, the same samples also go
+/// to a file of the same name in it; a failure there is logged and the primary file
+/// carries on. Join the returned thread after stopping to learn whether the primary
+/// file was written completely and how the backup fared.
+pub fn capture_to_file(
+    path: &Path,
+    backup_dir: Option<&Path>,
+    config: &SupportedStreamConfig,
+) -> (CaptureHandle, JoinHandle<Result<Redundancy>>) {
+    let spec = hound::WavSpec {
+        channels: config.channels(),
+        sample_rate: config.sample_rate().0,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let (blocks, receiver) = channel::bounded::<Vec<f32>>(QUEUE_BLOCKS);
+    let (recycle, free) = channel::bounded::<Vec<f32>>(QUEUE_BLOCKS);
+    for _ in 0..POOL_BLOCKS {
+        let _ = recycle.try_send(Vec::with_capacity(POOL_BLOCK_LEN));
+    }
+    let stopped = Arc::new(AtomicBool::new(false));
+    let writer_stopped = Arc::clone(&stopped);
+    let backup_failed = Arc::new(AtomicBool::new(false));
+    let writer_backup_failed = Arc::clone(&backup_failed);
+    let backup = backup_dir.map(|dir| backup_path(path, dir));
+    let path = path.to_owned();
+
+    let writer_thread = thread::spawn(move || -> Result<Redundancy> {
+        let mut writer =
+            hound::WavWriter::create(&path, spec).with_context(|| format!("Cannot create {}", path.display()))?;
+        // Logs the first backup failure; the caller stops writing the backup
+        let fail_backup = |redundancy: &mut Redundancy, message: String| {
+            if !matches!(redundancy, Redundancy::Failed(_)) {
+                eprintln!("Redundant capture failed: {}", message);
+                *redundancy = Redundancy::Failed(message);
+            }
+            writer_backup_failed.store(true, Ordering::Relaxed);
+        };
+        let mut redundancy = Redundancy::Off;
+        let mut backup_writer = None;
+        if let Some(backup) = &backup {
+            redundancy = Redundancy::Ok;
+            match hound::WavWriter::create(backup, spec) {
+                Ok(created) => backup_writer = Some(created),
+                Err(e) => fail_backup(&mut redundancy, format!("Cannot create {}: {}", backup.display(), e)),
+            }
+        }
+
+        let mut write_block = |block: Vec<f32>, redundancy: &mut Redundancy| -> Result<()> {
+            for &s in &block {
+                writer.write_sample(s)?;
+            }
+            if let Some(backup) = &mut backup_writer {
+                let written = block.iter().try_for_each(|&s| backup.write_sample(s));
+                // Both writers buffer, so compare what each has taken rather than the files
+                match written {
+                    Ok(()) if backup.len() == writer.len() => {}
+                    Ok(()) => {
+                        let message = format!("backup has {} samples, primary {}", backup.len(), writer.len());
+                        fail_backup(redundancy, message);
+                        backup_writer = None;
+                    }
+                    Err(e) => {
+                        fail_backup(redundancy, e.to_string());
+                        backup_writer = None;
+                    }
+                }
+            }
+            // Back to the callback; a full pool just frees it
+            let _ = recycle.try_send(block);
+            Ok(())
+        };
+
+        while !writer_stopped.load(Ordering::Relaxed) {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(block) => write_block(block, &mut redundancy)?,
+                Err(channel::RecvTimeoutError::Timeout) => {}
+                Err(channel::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        // Blocks queued before the stop
+        for block in receiver.try_iter() {
+            write_block(block, &mut redundancy)?;
+        }
+        writer.finalize().with_context(|| format!("Cannot finalize {}", path.display()))?;
+
+        if let (Some(backup_writer), Some(backup)) = (backup_writer, &backup) {
+            let finalized = backup_writer.finalize().map_err(anyhow::Error::from);
+            if let Err(e) = finalized.and_then(|()| check_same_size(&path, backup)) {
+                fail_backup(&mut redundancy, format!("{:#}", e));
+            }
+        }
+        if redundancy == Redundancy::Ok {
+            if let Some(backup) = &backup {
+                eprintln!("Redundant capture written to {}", backup.display());
+            }
+        }
+        Ok(redundancy)
+    });
+
+    let sender = CaptureSender { blocks, free };
+    let handle = CaptureHandle {
+        sender,
+        stopped,
+        backup_failed,
+    };
+    (handle, writer_thread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::{SampleFormat, SampleRate, SupportedBufferSize};
+
+    #[test]
+    fn queued_blocks_reach_the_file_in_order() {
+        let path = std::env::temp_dir().join(format!("capture-test-{}.wav", std::process::id()));
+        let config = SupportedStreamConfig::new(2, SampleRate(48_000), SupportedBufferSize::Unknown, SampleFormat::F32);
+        let (handle, writer) = capture_to_file(&path, None, &config);
+        let sender = handle.sender();
+        let blocks: Vec<Vec<f32>> = (0..POOL_BLOCKS * 2)
+            .map(|b| (0..64).map(|i| (b * 64 + i) as f32 / 10_000.0).collect())
+            .collect();
+        for block in &blocks {
+            while !sender.try_send(block) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        handle.stop();
+        assert_eq!(writer.join().unwrap().unwrap(), Redundancy::Off);
+
+        let written: Vec<f32> = hound::WavReader::open(&path)
+            .unwrap()
+            .into_samples::<f32>()
+            .map(Result::unwrap)
+            .collect();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written, blocks.concat());
+    }
+
+    #[test]
+    fn backup_capture_matches_the_primary() {
+        let dir = std::env::temp_dir().join(format!("capture-backup-test-{}", std::process::id()));
+        let backup_dir = dir.join("backup");
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        let path = dir.join("capture.wav");
+        let config = SupportedStreamConfig::new(1, SampleRate(48_000), SupportedBufferSize::Unknown, SampleFormat::F32);
+        let (handle, writer) = capture_to_file(&path, Some(&backup_dir), &config);
+        let sender = handle.sender();
+        for b in 0..8 {
+            let block: Vec<f32> = (0..256).map(|i| (b * 256 + i) as f32 / 4096.0).collect();
+            while !sender.try_send(&block) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        assert!(!handle.backup_failed());
+        handle.stop();
+        let redundancy = writer.join().unwrap().unwrap();
+        let primary = std::fs::read(&path).unwrap();
+        let backup = std::fs::read(backup_dir.join("capture.wav")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(redundancy, Redundancy::Ok);
+        assert_eq!(primary, backup);
+    }
+
+    #[test]
+    fn missing_backup_dir_fails_only_the_backup() {
+        let path = std::env::temp_dir().join(format!("capture-lone-test-{}.wav", std::process::id()));
+        let missing = std::env::temp_dir().join(format!("capture-no-such-dir-{}", std::process::id()));
+        let config = SupportedStreamConfig::new(1, SampleRate(48_000), SupportedBufferSize::Unknown, SampleFormat::F32);
+        let (handle, writer) = capture_to_file(&path, Some(&missing), &config);
+        assert!(handle.sender().try_send(&[0.25; 64]));
+        handle.stop();
+        let redundancy = writer.join().unwrap().unwrap();
+        let samples = hound::WavReader::open(&path).unwrap().len();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(redundancy, Redundancy::Failed(_)));
+        assert_eq!(samples, 64);
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::window::WindowFunction;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Linear,
+    Dbfs,
+}
+
+/// Settings of the mic_2d visualizer kept between sessions. Missing fields take
+/// their defaults, so older files keep loading as fields are added.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Input device name; `None` uses the system default.
+    pub device: Option<String>,
+    pub buffer_size: usize,
+    pub tau_ms: f32,
+    pub display_mode: DisplayMode,
+    pub trigger_enabled: bool,
+    pub window: WindowFunction,
+    /// Digital gain applied to the input, in dB.
+    pub gain_db: f32,
+    /// Samples per point of the waveform's amplitude envelope.
+    pub envelope_block: usize,
+    /// dBFS to dBSPL offset from the calibration wizard; `None` when uncalibrated.
+    pub sensitivity_correction_db: Option<f32>,
+    /// Stream levels over OSC to `osc_host:osc_port`.
+    pub osc_enabled: bool,
+    pub osc_host: String,
+    pub osc_port: u16,
+    /// Directory that receives a second copy of every recording and capture.
+    pub redundant_path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            device: None,
+            buffer_size: 500,
+            tau_ms: 100.0,
+            display_mode: DisplayMode::Linear,
+            trigger_enabled: false,
+            window: WindowFunction::Rectangular,
+            gain_db: 0.0,
+            envelope_block: 8,
+            sensitivity_correction_db: None,
+            osc_enabled: false,
+            osc_host: "127.0.0.1".to_owned(),
+            osc_port: 9000,
+            redundant_path: None,
+        }
+    }
+}
+
+impl Config {
+    /// `<config dir>/mic_visualizer/config.toml`, e.g. `$XDG_CONFIG_HOME` on Linux.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("mic_visualizer").join("config.toml"))
+    }
+
+    /// Loads the saved config; a missing file gives the defaults.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+        let text = std::fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("No config directory on this platform")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?).with_context(|| format!("Cannot write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trips_through_toml() {
+        let config = Config {
+            device: Some("USB Mic".to_owned()),
+            buffer_size: 1200,
+            tau_ms: 35.0,
+            display_mode: DisplayMode::Dbfs,
+            trigger_enabled: true,
+            window: WindowFunction::Blackman,
+            gain_db: 6.5,
+            envelope_block: 16,
+            sensitivity_correction_db: Some(121.3),
+            osc_enabled: true,
+            osc_host: "192.168.1.20".to_owned(),
+            osc_port: 7000,
+            redundant_path: Some(PathBuf::from("/mnt/backup/recordings")),
+        };
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(toml::from_str::<Config>(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn default_config_round_trips_through_toml() {
+        let text = toml::to_string_pretty(&Config::default()).unwrap();
+        assert_eq!(toml::from_str::<Config>(&text).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let config: Config = toml::from_str("buffer_size = 800\ndisplay_mode = \"Dbfs\"\n").unwrap();
+        assert_eq!(config.buffer_size, 800);
+        assert_eq!(config.display_mode, DisplayMode::Dbfs);
+        assert_eq!(config.tau_ms, Config::default().tau_ms);
+        assert_eq!(config.device, None);
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait};
+
+/// Case-insensitive substring match used by `--device`.
+pub fn device_name_matches(name: &str, query: &str) -> bool {
+    name.to_lowercase().contains(&query.to_lowercase())
+}
+
+/// The first input device whose name contains `query`, or the default input device.
+pub fn find_input_device(host: &cpal::Host, query: Option<&str>) -> Result<cpal::Device> {
+    let Some(query) = query else {
+        return host.default_input_device().ok_or_else(|| anyhow!("No input device found"));
+    };
+
+    let devices: Vec<cpal::Device> = host.input_devices().context("Cannot list input devices")?.collect();
+    let names: Vec<String> = devices.iter().map(|d| d.name().unwrap_or_default()).collect();
+    devices
+        .into_iter()
+        .zip(&names)
+        .find(|(_, name)| device_name_matches(name, query))
+        .map(|(device, _)| device)
+        .ok_or_else(|| {
+            anyhow!(
+                "No input device matches \"{}\". Available: {}",
+                query,
+                if names.is_empty() { "none".to_owned() } else { names.join(", ") }
+            )
+        })
+}
+
+/// The device's default input config, moved to `sample_rate` if one is given.
+pub fn input_config(device: &cpal::Device, sample_rate: Option<u32>) -> Result<cpal::SupportedStreamConfig> {
+    let default = device.default_input_config().context("Cannot read the default input config")?;
+    let Some(rate) = sample_rate else {
+        return Ok(default);
+    };
+
+    device
+        .supported_input_configs()
+        .context("Cannot list supported input configs")?
+        .filter(|range| range.channels() == default.channels() && range.sample_format() == default.sample_format())
+        .find(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate))
+        .map(|range| range.with_sample_rate(cpal::SampleRate(rate)))
+        .ok_or_else(|| {
+            anyhow!(
+                "Sample rate {} Hz is not supported by {}",
+                rate,
+                device.name().unwrap_or_else(|_| "the input device".to_owned())
+            )
+        })
+}
+
+/// What an input device reports it can do, deduplicated and sorted.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceCapabilities {
+    /// Inclusive (min, max) sample rate ranges in Hz.
+    pub sample_rates: Vec<(u32, u32)>,
+    pub channels: Vec<u16>,
+    pub sample_formats: Vec<cpal::SampleFormat>,
+}
+
+/// Capabilities of the input device named exactly `name`.
+pub fn input_capabilities(host: &cpal::Host, name: &str) -> Result<DeviceCapabilities> {
+    let device = host
+        .input_devices()
+        .context("Cannot list input devices")?
+        .find(|d| d.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| anyhow!("Input device \"{}\" is gone", name))?;
+
+    let mut capabilities = DeviceCapabilities::default();
+    for range in device.supported_input_configs().context("Cannot list supported input configs")? {
+        capabilities.sample_rates.push((range.min_sample_rate().0, range.max_sample_rate().0));
+        capabilities.channels.push(range.channels());
+        capabilities.sample_formats.push(range.sample_format());
+    }
+    capabilities.sample_rates.sort_unstable();
+    capabilities.sample_rates.dedup();
+    capabilities.channels.sort_unstable();
+    capabilities.channels.dedup();
+    capabilities.sample_formats.sort_by_key(|f| f.to_string());
+    capabilities.sample_formats.dedup();
+    Ok(capabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_query_is_a_case_insensitive_substring() {
+        let name = "Scarlett 2i2 USB";
+        assert!(device_name_matches(name, "scarlett"));
+        assert!(device_name_matches(name, "2I2 usb"));
+        assert!(!device_name_matches(name, "Focusrite"));
+    }
+}
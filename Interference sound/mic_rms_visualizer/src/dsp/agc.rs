@@ -0,0 +1,97 @@
+// Limits of the gain factor, so silence or a burst cannot send it running away
+const MIN_GAIN: f32 = 0.01;
+const MAX_GAIN: f32 = 100.0;
+
+/// Automatic gain control that moves a gain factor towards `target_rms / rms`:
+/// quickly down when the signal gets louder (attack), slowly back up when it
+/// gets quieter (release).
+pub struct Agc {
+    pub target_rms: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    gain_factor: f32,
+}
+
+impl Agc {
+    pub fn new(target_rms: f32, attack_ms: f32, release_ms: f32) -> Self {
+        Self {
+            target_rms,
+            attack_ms,
+            release_ms,
+            gain_factor: 1.0,
+        }
+    }
+
+    pub fn gain_factor(&self) -> f32 {
+        self.gain_factor
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        20.0 * self.gain_factor.log10()
+    }
+
+    pub fn reset(&mut self) {
+        self.gain_factor = 1.0;
+    }
+
+    /// Feeds the RMS of a block lasting `block_secs`, measured before this gain.
+    pub fn update(&mut self, rms: f32, block_secs: f32) {
+        let wanted = (self.target_rms / rms.max(1e-10)).clamp(MIN_GAIN, MAX_GAIN);
+        let tau_ms = if wanted < self.gain_factor {
+            self.attack_ms
+        } else {
+            self.release_ms
+        };
+        // One-pole smoothing in dB, so rises and falls of the same size take equally long
+        let k = 1.0 - (-block_secs * 1000.0 / tau_ms.max(0.1)).exp();
+        let db = self.gain_db() + k * 20.0 * (wanted / self.gain_factor).log10();
+        self.gain_factor = 10f32.powf(db / 20.0).clamp(MIN_GAIN, MAX_GAIN);
+    }
+}
+
+impl Default for Agc {
+    /// -20 dBFS target, 10 ms attack, 300 ms release.
+    fn default() -> Self {
+        Self::new(0.1, 10.0, 300.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_SECS: f32 = 0.01;
+
+    fn dbfs(rms: f32) -> f32 {
+        20.0 * rms.log10()
+    }
+
+    #[test]
+    fn step_from_minus_40_to_minus_10_dbfs_settles_within_500_ms() {
+        let mut agc = Agc::default();
+        let (quiet, loud) = (10f32.powf(-40.0 / 20.0), 10f32.powf(-10.0 / 20.0));
+        for _ in 0..500 {
+            agc.update(quiet, BLOCK_SECS);
+        }
+        assert!((dbfs(quiet * agc.gain_factor()) - dbfs(agc.target_rms)).abs() < 0.5);
+
+        for _ in 0..(0.5 / BLOCK_SECS) as usize {
+            agc.update(loud, BLOCK_SECS);
+        }
+        let error_db = dbfs(loud * agc.gain_factor()) - dbfs(agc.target_rms);
+        assert!(error_db.abs() < 0.5, "output {:.2} dB off target", error_db);
+    }
+
+    #[test]
+    fn gain_stays_within_limits() {
+        let mut agc = Agc::default();
+        for _ in 0..1000 {
+            agc.update(0.0, BLOCK_SECS);
+        }
+        assert!(agc.gain_factor() <= MAX_GAIN && agc.gain_factor() > 0.99 * MAX_GAIN);
+        for _ in 0..1000 {
+            agc.update(1000.0, BLOCK_SECS);
+        }
+        assert!(agc.gain_factor() >= MIN_GAIN && agc.gain_factor() < 1.01 * MIN_GAIN);
+    }
+}
@@ -0,0 +1,53 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+/// Reusable Hann-windowed FFT of a fixed frame size. All buffers are allocated
+/// once in `new`, so calling `analyze` every repaint does not allocate.
+pub struct SpectrumAnalyzer {
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    magnitudes_dbfs: Vec<f64>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(frame_len: usize) -> Self {
+        let frame_len = frame_len.max(2);
+        let fft = FftPlanner::new().plan_fft_forward(frame_len);
+        let scratch_len = fft.get_inplace_scratch_len();
+
+        Self {
+            fft,
+            window: (0..frame_len)
+                .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (frame_len - 1) as f32).cos())
+                .collect(),
+            buffer: vec![Complex::new(0.0, 0.0); frame_len],
+            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
+            magnitudes_dbfs: vec![f64::NEG_INFINITY; frame_len / 2],
+        }
+    }
+
+    pub fn frame_len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Magnitude spectrum in dBFS (a full-scale sine reads 0 dBFS) of the first
+    /// `frame_len` samples; missing samples are treated as zeros.
+    pub fn analyze<'a>(&mut self, samples: impl IntoIterator<Item = &'a f32>) -> &[f64] {
+        let mut samples = samples.into_iter();
+        for (bin, &w) in self.buffer.iter_mut().zip(&self.window) {
+            *bin = Complex::new(samples.next().copied().unwrap_or(0.0) * w, 0.0);
+        }
+        self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+
+        // Hann coherent gain is 0.5, and a real sine splits its energy over ± frequencies
+        let scale = 4.0 / self.frame_len() as f32;
+        for (db, c) in self.magnitudes_dbfs.iter_mut().zip(&self.buffer) {
+            *db = 20.0 * ((c.norm() * scale).max(1e-10) as f64).log10();
+        }
+        &self.magnitudes_dbfs
+    }
+}
@@ -0,0 +1,98 @@
+use std::f32::consts::PI;
+
+use super::biquad::Biquad;
+
+// IEC 61672 pole frequencies of the A-weighting curve, in Hz
+const F1: f32 = 20.598_997;
+const F2: f32 = 107.652_65;
+const F3: f32 = 737.862_2;
+const F4: f32 = 12_194.217;
+
+// The curve is normalised to 0 dB here
+const REFERENCE_HZ: f32 = 1000.0;
+
+/// A-weighting as three biquads from the bilinear transform of the analog
+/// IEC 61672 curve, designed for the stream's sample rate.
+#[derive(Clone, Copy, Debug)]
+pub struct AWeightingFilter {
+    sections: [Biquad; 3],
+    gain: f32,
+}
+
+impl AWeightingFilter {
+    pub fn new(sample_rate: f32) -> Self {
+        // Pole frequencies are pre-warped so they land in the right place after the transform
+        let w = |f: f32| 2.0 * sample_rate * (PI * f.min(0.49 * sample_rate) / sample_rate).tan();
+        let (w1, w2, w3, w4) = (w(F1), w(F2), w(F3), w(F4));
+        let k = 2.0 * sample_rate;
+
+        // Analog sections: s² / (s + w1)², s² / ((s + w2)(s + w3)), w4² / (s + w4)²
+        let sections = [
+            bilinear(k, [1.0, 0.0, 0.0], [1.0, 2.0 * w1, w1 * w1]),
+            bilinear(k, [1.0, 0.0, 0.0], [1.0, w2 + w3, w2 * w3]),
+            bilinear(k, [0.0, 0.0, w4 * w4], [1.0, 2.0 * w4, w4 * w4]),
+        ];
+        let reference_db: f32 = sections.iter().map(|s| s.response_db(sample_rate, REFERENCE_HZ)).sum();
+        Self {
+            sections,
+            gain: 10f32.powf(-reference_db / 20.0),
+        }
+    }
+
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        self.gain * self.sections.iter_mut().fold(x, |s, section| section.process(s))
+    }
+}
+
+// Bilinear transform (s = k (1 - z⁻¹) / (1 + z⁻¹)) of (b[0] s² + b[1] s + b[2]) / (a[0] s² + a[1] s + a[2])
+fn bilinear(k: f32, b: [f32; 3], a: [f32; 3]) -> Biquad {
+    let digital = |c: [f32; 3]| {
+        let k2 = k * k;
+        [
+            c[0] * k2 + c[1] * k + c[2],
+            2.0 * (c[2] - c[0] * k2),
+            c[0] * k2 - c[1] * k + c[2],
+        ]
+    };
+    let (b, a) = (digital(b), digital(a));
+    Biquad::from_coefficients(b[0], b[1], b[2], a[0], a[1], a[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Gain in dB of the filter for a sine at `freq`, measured after it has settled
+    fn measured_gain_db(sample_rate: f32, freq: f32) -> f32 {
+        let mut filter = AWeightingFilter::new(sample_rate);
+        let len = 2 * sample_rate as usize;
+        let (mut input_sq, mut output_sq) = (0.0f64, 0.0f64);
+        for i in 0..len {
+            let x = 0.5 * (2.0 * PI * freq * i as f32 / sample_rate).sin();
+            let y = filter.process_sample(x);
+            if i >= len / 2 {
+                input_sq += (x * x) as f64;
+                output_sq += (y * y) as f64;
+            }
+        }
+        (10.0 * (output_sq / input_sq).log10()) as f32
+    }
+
+    #[test]
+    fn response_is_within_class_1_tolerance() {
+        // (frequency, IEC 61672 A-weighting, class 1 tolerance) in Hz and dB
+        let table = [
+            (31.5, -39.4, 1.5),
+            (100.0, -19.1, 1.0),
+            (1000.0, 0.0, 0.05),
+            (4000.0, 1.0, 1.0),
+            (10_000.0, -2.5, 2.0),
+        ];
+        for sample_rate in [44_100.0, 48_000.0] {
+            for (freq, expected, tolerance) in table {
+                let gain = measured_gain_db(sample_rate, freq);
+                assert!((gain - expected).abs() <= tolerance, "{} Hz at {} Hz: {:.2} dB", freq, sample_rate, gain);
+            }
+        }
+    }
+}
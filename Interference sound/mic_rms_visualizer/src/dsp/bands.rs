@@ -0,0 +1,107 @@
+use std::f32::consts::PI;
+
+/// IEC 61260 base-ten one-third-octave bands from 20 Hz to 20 kHz.
+pub const THIRD_OCTAVE_BANDS: usize = 31;
+
+// Band i has the exact centre 1 kHz * 10^((i - 17) / 10)
+const FIRST_BAND_EXPONENT: i32 = -17;
+
+const NOMINAL_CENTERS: [&str; THIRD_OCTAVE_BANDS] = [
+    "20", "25", "31.5", "40", "50", "63", "80", "100", "125", "160", "200", "250", "315", "400", "500", "630",
+    "800", "1k", "1.25k", "1.6k", "2k", "2.5k", "3.15k", "4k", "5k", "6.3k", "8k", "10k", "12.5k", "16k", "20k",
+];
+
+/// Exact centre frequency of band `i` (0 = 20 Hz).
+pub fn band_center(i: usize) -> f32 {
+    1000.0 * 10f32.powf((i as i32 + FIRST_BAND_EXPONENT) as f32 / 10.0)
+}
+
+/// Lower and upper edge of band `i`, a sixth of an octave either side of the centre.
+pub fn band_edges(i: usize) -> (f32, f32) {
+    let center = band_center(i);
+    let k = 10f32.powf(1.0 / 20.0);
+    (center / k, center * k)
+}
+
+/// Nominal centre as printed on meters, e.g. "31.5" or "1.25k".
+pub fn band_label(i: usize) -> &'static str {
+    NOMINAL_CENTERS[i]
+}
+
+/// Second-order Butterworth band-pass (bilinear transform, unity gain at the
+/// centre), transposed direct form II.
+#[derive(Clone, Copy, Debug)]
+pub struct BandFilter {
+    b: [f32; 3],
+    // a[0] is normalised to 1
+    a: [f32; 3],
+    z: [f32; 2],
+}
+
+impl BandFilter {
+    pub fn new(sample_rate: f32, center: f32, bandwidth: f32) -> Self {
+        let w0 = 2.0 * PI * (center / sample_rate).clamp(1e-6, 0.499);
+        let alpha = w0.sin() * bandwidth / (2.0 * center);
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [alpha / a0, 0.0, -alpha / a0],
+            a: [1.0, -2.0 * w0.cos() / a0, (1.0 - alpha) / a0],
+            z: [0.0; 2],
+        }
+    }
+
+    /// Filter for one-third-octave band `i`.
+    pub fn third_octave(sample_rate: f32, i: usize) -> Self {
+        let (lower, upper) = band_edges(i);
+        Self::new(sample_rate, band_center(i), upper - lower)
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[1] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[2] * y;
+        y
+    }
+}
+
+/// The 31 one-third-octave filters of one stream. Bands whose upper edge is at
+/// or past `max_edge_hz` are left out and collect no energy.
+#[derive(Clone, Debug, Default)]
+pub struct BandFilterBank {
+    filters: Vec<Option<BandFilter>>,
+}
+
+impl BandFilterBank {
+    pub fn third_octave(sample_rate: f32, max_edge_hz: f32) -> Self {
+        Self {
+            filters: (0..THIRD_OCTAVE_BANDS)
+                .map(|i| (band_edges(i).1 < max_edge_hz).then(|| BandFilter::third_octave(sample_rate, i)))
+                .collect(),
+        }
+    }
+
+    /// Runs `block` through every band and adds each band's sum of squares to
+    /// `energy[band]`. With the `rayon` feature the bands run in parallel; each
+    /// filter owns its state, so the work splits into disjoint borrows.
+    pub fn process(&mut self, block: &[f32], energy: &mut [f32]) {
+        let band = |(filter, energy): (&mut Option<BandFilter>, &mut f32)| {
+            if let Some(filter) = filter {
+                *energy += block
+                    .iter()
+                    .map(|&x| {
+                        let y = filter.process(x);
+                        y * y
+                    })
+                    .sum::<f32>();
+            }
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+            (&mut self.filters).into_par_iter().zip(energy.into_par_iter()).for_each(band);
+        }
+        #[cfg(not(feature = "rayon"))]
+        self.filters.iter_mut().zip(energy.iter_mut()).for_each(band);
+    }
+}
@@ -0,0 +1,140 @@
+use std::f32::consts::PI;
+
+/// Second-order IIR section (transposed direct form II) with RBJ cookbook designs.
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    pub(crate) fn from_coefficients(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    // Digital frequency and alpha shared by all designs
+    fn omega(sample_rate: f32, freq: f32, q: f32) -> (f32, f32) {
+        let w0 = 2.0 * PI * (freq / sample_rate).clamp(1e-6, 0.499);
+        (w0, w0.sin() / (2.0 * q))
+    }
+
+    pub fn low_pass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, cutoff, q);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn high_pass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, cutoff, q);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn notch(sample_rate: f32, center: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, center, q);
+        let cos = w0.cos();
+        Self::from_coefficients(1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+    }
+
+    pub fn peaking(sample_rate: f32, center: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, center, q);
+        let a = 10f32.powf(gain_db / 40.0);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            1.0 + alpha * a,
+            -2.0 * cos,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos,
+            1.0 - alpha / a,
+        )
+    }
+
+    pub fn low_shelf(sample_rate: f32, corner: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, corner, q);
+        let a = 10f32.powf(gain_db / 40.0);
+        let (cos, k) = (w0.cos(), 2.0 * a.sqrt() * alpha);
+        Self::from_coefficients(
+            a * ((a + 1.0) - (a - 1.0) * cos + k),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - k),
+            (a + 1.0) + (a - 1.0) * cos + k,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - k,
+        )
+    }
+
+    pub fn high_shelf(sample_rate: f32, corner: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::omega(sample_rate, corner, q);
+        let a = 10f32.powf(gain_db / 40.0);
+        let (cos, k) = (w0.cos(), 2.0 * a.sqrt() * alpha);
+        Self::from_coefficients(
+            a * ((a + 1.0) + (a - 1.0) * cos + k),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - k),
+            (a + 1.0) - (a - 1.0) * cos + k,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - k,
+        )
+    }
+
+    /// Magnitude response at `freq`, in dB.
+    pub fn response_db(&self, sample_rate: f32, freq: f32) -> f32 {
+        let w = 2.0 * PI * freq / sample_rate;
+        let (c1, s1, c2, s2) = (w.cos(), w.sin(), (2.0 * w).cos(), (2.0 * w).sin());
+        let num = (self.b0 + self.b1 * c1 + self.b2 * c2).hypot(self.b1 * s1 + self.b2 * s2);
+        let den = (1.0 + self.a1 * c1 + self.a2 * c2).hypot(self.a1 * s1 + self.a2 * s2);
+        20.0 * (num / den.max(f32::MIN_POSITIVE)).max(1e-10).log10()
+    }
+
+    /// Takes the coefficients of `design` and keeps the current delay state.
+    pub fn set_coefficients(&mut self, design: &Biquad) {
+        self.b0 = design.b0;
+        self.b1 = design.b1;
+        self.b2 = design.b2;
+        self.a1 = design.a1;
+        self.a2 = design.a2;
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// Q of each section of a 4th-order Butterworth built from two biquads.
+pub const BUTTERWORTH_4TH_Q: [f32; 2] = [0.541_196_1, 1.306_563];
@@ -0,0 +1,62 @@
+use std::f32::consts::PI;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+#[derive(Clone, Copy, Debug)]
+pub struct EchoPeak {
+    pub quefrency_bin: usize,
+    pub delay_ms: f32,
+    pub magnitude: f32,
+}
+
+/// Real cepstrum `IFFT(log|FFT(x)|)` of a Hann-windowed frame. Returns the first
+/// half, where bin `i` corresponds to a quefrency of `i / sample_rate` seconds.
+pub fn real_cepstrum(samples: &[f32]) -> Vec<f32> {
+    let n = samples.len();
+    if n < 4 {
+        return Vec::new();
+    }
+
+    let mut buf: Vec<Complex<f32>> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let w = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+            Complex::new(s * w, 0.0)
+        })
+        .collect();
+
+    let mut planner = FftPlanner::new();
+    planner.plan_fft_forward(n).process(&mut buf);
+    for c in buf.iter_mut() {
+        *c = Complex::new(c.norm().max(1e-10).ln(), 0.0);
+    }
+    planner.plan_fft_inverse(n).process(&mut buf);
+
+    buf[..n / 2].iter().map(|c| c.re / n as f32).collect()
+}
+
+/// Local maxima of the cepstrum above `lifter_bins` that stand out from the rest
+/// (more than three standard deviations above the mean), strongest first.
+pub fn find_echo_peaks(cepstrum: &[f32], sample_rate: u32, lifter_bins: usize) -> Vec<EchoPeak> {
+    let start = lifter_bins.max(1);
+    if cepstrum.len() < start + 3 {
+        return Vec::new();
+    }
+
+    let region = &cepstrum[start..];
+    let mean = region.iter().sum::<f32>() / region.len() as f32;
+    let std_dev = (region.iter().map(|c| (c - mean).powi(2)).sum::<f32>() / region.len() as f32).sqrt();
+    let threshold = mean + 3.0 * std_dev;
+
+    let mut peaks: Vec<EchoPeak> = (start + 1..cepstrum.len() - 1)
+        .filter(|&i| cepstrum[i] > threshold && cepstrum[i] > cepstrum[i - 1] && cepstrum[i] >= cepstrum[i + 1])
+        .map(|i| EchoPeak {
+            quefrency_bin: i,
+            delay_ms: i as f32 / sample_rate as f32 * 1000.0,
+            magnitude: cepstrum[i],
+        })
+        .collect();
+    peaks.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+    peaks
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+// Length of the cross-fade when a new impulse response replaces the old one
+pub const CROSSFADE_SAMPLES: usize = 512;
+
+/// FFT overlap-add convolution of a block stream with a fixed impulse response.
+pub struct OverlapAdd {
+    block_size: usize,
+    ir_spectrum: Vec<Complex<f32>>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    scratch: Vec<Complex<f32>>,
+    overlap_add: Vec<f32>,
+}
+
+impl OverlapAdd {
+    /// Plans the FFTs for blocks of at most `block_size` samples. All buffers are
+    /// allocated here so `process` never allocates.
+    pub fn new(ir: &[f32], block_size: usize) -> Self {
+        let block_size = block_size.max(1);
+        let fft_size = (block_size + ir.len().max(1) - 1).next_power_of_two();
+
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(fft_size);
+        let inverse = planner.plan_fft_inverse(fft_size);
+
+        // Fold the 1/N inverse FFT scaling into the IR spectrum
+        let scale = 1.0 / fft_size as f32;
+        let mut ir_spectrum = vec![Complex::new(0.0, 0.0); fft_size];
+        for (bin, &h) in ir_spectrum.iter_mut().zip(ir) {
+            bin.re = h * scale;
+        }
+        forward.process(&mut ir_spectrum);
+
+        Self {
+            block_size,
+            ir_spectrum,
+            forward,
+            inverse,
+            scratch: vec![Complex::new(0.0, 0.0); fft_size],
+            overlap_add: vec![0.0; fft_size],
+        }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Convolves `input` into `output` (same length). Inputs longer than the
+    /// planned block size are processed in several blocks.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        for (input, output) in input.chunks(self.block_size).zip(output.chunks_mut(self.block_size)) {
+            self.process_block(input, output);
+        }
+    }
+
+    fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        let len = input.len();
+
+        for (bin, &x) in self.scratch.iter_mut().zip(input.iter().chain(std::iter::repeat(&0.0))) {
+            *bin = Complex::new(x, 0.0);
+        }
+        self.forward.process(&mut self.scratch);
+        for (bin, h) in self.scratch.iter_mut().zip(&self.ir_spectrum) {
+            *bin *= h;
+        }
+        self.inverse.process(&mut self.scratch);
+
+        for (acc, y) in self.overlap_add.iter_mut().zip(&self.scratch) {
+            *acc += y.re;
+        }
+        output.copy_from_slice(&self.overlap_add[..len]);
+
+        // Shift the tail forward by one block
+        self.overlap_add.copy_within(len.., 0);
+        let tail = self.overlap_add.len() - len;
+        self.overlap_add[tail..].fill(0.0);
+    }
+}
+
+/// Live convolver that cross-fades to a newly loaded impulse response instead of
+/// cutting over, so swapping IRs mid-stream does not click.
+pub struct Convolver {
+    current: Option<OverlapAdd>,
+    fading_out: Option<(OverlapAdd, usize)>,
+    fade_buffer: Vec<f32>,
+}
+
+impl Convolver {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            fading_out: None,
+            fade_buffer: Vec::new(),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Swaps in a new engine; the previous one keeps running for
+    /// `CROSSFADE_SAMPLES` while its output is faded out.
+    pub fn replace(&mut self, engine: OverlapAdd) {
+        self.fade_buffer.resize(engine.block_size(), 0.0);
+        self.fading_out = self.current.replace(engine).map(|old| (old, 0));
+    }
+
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let Some(current) = self.current.as_mut() else {
+            output.fill(0.0);
+            return;
+        };
+        current.process(input, output);
+
+        if let Some((old, faded)) = self.fading_out.as_mut() {
+            if self.fade_buffer.len() < input.len() {
+                self.fade_buffer.resize(input.len(), 0.0);
+            }
+            let old_out = &mut self.fade_buffer[..input.len()];
+            old.process(input, old_out);
+
+            for (y, &y_old) in output.iter_mut().zip(old_out.iter()) {
+                let g = (*faded as f32 / CROSSFADE_SAMPLES as f32).min(1.0);
+                *y = g * *y + (1.0 - g) * y_old;
+                *faded += 1;
+            }
+
+            if *faded >= CROSSFADE_SAMPLES {
+                self.fading_out = None;
+            }
+        }
+    }
+}
+
+impl Default for Convolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::VecDeque;
+
+use super::analyzer::SpectrumAnalyzer;
+
+// Spectrum of the samples leading up to an onset; ~43 ms at 48 kHz, one onset block
+const FRAME_LEN: usize = 2048;
+
+// Kick energy sits below KICK_MAX_HZ, snare energy between it and SNARE_MAX_HZ
+const KICK_MAX_HZ: f32 = 200.0;
+const SNARE_MAX_HZ: f32 = 5000.0;
+// Wiener entropy of the snare band above which a hit counts as noisy (a snare)
+const SNARE_MIN_FLATNESS: f32 = 0.2;
+
+pub const BEATS_PER_BAR: usize = 4;
+// Bars kept for the drum roll and "Record Pattern"
+pub const BARS: usize = 4;
+
+// A tempo change larger than this starts a new grid
+const TEMPO_TOLERANCE: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drum {
+    Kick,
+    Snare,
+}
+
+impl Drum {
+    pub const ALL: [Drum; 2] = [Drum::Kick, Drum::Snare];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Drum::Kick => "Kick",
+            Drum::Snare => "Snare",
+        }
+    }
+
+    fn row(self) -> usize {
+        self as usize
+    }
+}
+
+/// Hits of one bar: `bar[drum.row()][beat]`.
+pub type Bar = [[bool; BEATS_PER_BAR]; 2];
+
+/// Classifies onsets as kick or snare by where their energy sits in the spectrum
+/// and lays them out on a 4/4 beat grid. The grid starts on the first kick once a
+/// tempo is known. The FFT is planned up front, since it runs on the processing thread.
+pub struct DrumPatternRecognizer {
+    frame: VecDeque<f32>,
+    analyzer: SpectrumAnalyzer,
+    // Samples seen so far
+    position: u64,
+    // Position of beat 1 of bar 0, and samples per beat
+    anchor: Option<u64>,
+    period: f32,
+    bpm: f32,
+    // Newest bar last; `first_bar` is the bar number of the front one
+    bars: VecDeque<Bar>,
+    first_bar: u64,
+}
+
+impl Default for DrumPatternRecognizer {
+    fn default() -> Self {
+        Self {
+            frame: VecDeque::with_capacity(FRAME_LEN),
+            analyzer: SpectrumAnalyzer::new(FRAME_LEN),
+            position: 0,
+            anchor: None,
+            period: 0.0,
+            bpm: 0.0,
+            bars: VecDeque::with_capacity(BARS + 1),
+            first_bar: 0,
+        }
+    }
+}
+
+impl DrumPatternRecognizer {
+    /// Clears the grid; the next kick starts a new one.
+    pub fn reset(&mut self) {
+        self.anchor = None;
+        self.bars.clear();
+        self.first_bar = 0;
+    }
+
+    pub fn push(&mut self, x: f32) {
+        if self.frame.len() == FRAME_LEN {
+            self.frame.pop_front();
+        }
+        self.frame.push_back(x);
+        self.position += 1;
+    }
+
+    /// Classifies the onset that began `onset_len` samples ago and, with a tempo
+    /// known, puts it on the grid. Returns the drum, or `None` for other sounds.
+    pub fn onset(&mut self, onset_len: usize, bpm: Option<f32>, sample_rate: u32) -> Option<Drum> {
+        let drum = self.classify(sample_rate)?;
+        let Some(bpm) = bpm.filter(|&bpm| bpm > 0.0) else {
+            return Some(drum);
+        };
+        let start = self.position.saturating_sub(onset_len as u64);
+
+        if self.anchor.is_some() && (bpm - self.bpm).abs() > self.bpm * TEMPO_TOLERANCE {
+            self.reset();
+        }
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            // Bars start on a kick
+            None if drum == Drum::Kick => {
+                self.anchor = Some(start);
+                self.period = sample_rate as f32 * 60.0 / bpm;
+                self.bpm = bpm;
+                start
+            }
+            None => return Some(drum),
+        };
+
+        let beat = (start.saturating_sub(anchor) as f32 / self.period).round() as u64;
+        let bar = beat / BEATS_PER_BAR as u64;
+        let last_bar = self.first_bar + self.bars.len() as u64;
+        if bar >= last_bar + BARS as u64 {
+            // Nothing of the old bars would stay on screen
+            self.bars.clear();
+            self.first_bar = bar;
+        }
+        while self.first_bar + (self.bars.len() as u64) <= bar {
+            self.bars.push_back(Bar::default());
+            if self.bars.len() > BARS {
+                self.bars.pop_front();
+                self.first_bar += 1;
+            }
+        }
+        if let Some(hits) = bar.checked_sub(self.first_bar).and_then(|i| self.bars.get_mut(i as usize)) {
+            hits[drum.row()][(beat % BEATS_PER_BAR as u64) as usize] = true;
+        }
+        Some(drum)
+    }
+
+    /// Tempo of the grid, once it has started.
+    pub fn bpm(&self) -> Option<f32> {
+        self.anchor.map(|_| self.bpm)
+    }
+
+    /// Beat of the bar (0-3) the newest sample falls on.
+    pub fn current_beat(&self) -> Option<usize> {
+        let anchor = self.anchor?;
+        let beats = self.position.saturating_sub(anchor) as f32 / self.period;
+        Some(beats as usize % BEATS_PER_BAR)
+    }
+
+    /// The last `BARS` bars, oldest first; the last one is being filled.
+    pub fn bars(&self) -> &VecDeque<Bar> {
+        &self.bars
+    }
+
+    /// The bars as text, one row per drum, "x" for a hit and "." for a rest.
+    pub fn pattern_text(&self) -> String {
+        let mut text = format!("# {:.0} BPM, 4/4\n", self.bpm);
+        for drum in Drum::ALL {
+            text += &format!("{:<6}", drum.name());
+            for bar in &self.bars {
+                text += "|";
+                for &hit in &bar[drum.row()] {
+                    text += if hit { " x" } else { " ." };
+                }
+                text += " ";
+            }
+            text += "|\n";
+        }
+        text
+    }
+
+    fn classify(&mut self, sample_rate: u32) -> Option<Drum> {
+        let bin_hz = sample_rate as f32 / FRAME_LEN as f32;
+        let kick_bins = 1..((KICK_MAX_HZ / bin_hz).ceil() as usize);
+        let snare_bins = kick_bins.end..((SNARE_MAX_HZ / bin_hz).ceil() as usize).min(FRAME_LEN / 2);
+        if snare_bins.is_empty() {
+            return None;
+        }
+
+        let spectrum = self.analyzer.analyze(&self.frame);
+        let power = |bin: usize| 10f64.powf(spectrum[bin] / 10.0);
+        let kick_energy: f64 = kick_bins.map(power).sum();
+        let snare_energy: f64 = snare_bins.clone().map(power).sum();
+        if kick_energy >= snare_energy {
+            return Some(Drum::Kick);
+        }
+        // Wiener entropy: geometric over arithmetic mean power; noise is near 1, tones near 0
+        let n = snare_bins.len() as f64;
+        let log_mean = snare_bins.map(|bin| power(bin).ln()).sum::<f64>() / n;
+        let flatness = (log_mean.exp() / (snare_energy / n)) as f32;
+        (flatness >= SNARE_MIN_FLATNESS).then_some(Drum::Snare)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    // Deterministic white noise from a xorshift generator
+    fn noise(len: usize) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect()
+    }
+
+    fn sine(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn hit(recognizer: &mut DrumPatternRecognizer, sound: &[f32], bpm: Option<f32>) -> Option<Drum> {
+        for &s in sound {
+            recognizer.push(s);
+        }
+        recognizer.onset(FRAME_LEN, bpm, SAMPLE_RATE)
+    }
+
+    // A hit of `sound`, then silence up to one beat at 120 BPM
+    fn beat(recognizer: &mut DrumPatternRecognizer, sound: &[f32]) -> Option<Drum> {
+        let drum = hit(recognizer, sound, Some(120.0));
+        for _ in sound.len()..SAMPLE_RATE as usize / 2 {
+            recognizer.push(0.0);
+        }
+        drum
+    }
+
+    #[test]
+    fn low_thumps_are_kicks() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        assert_eq!(hit(&mut recognizer, &sine(60.0, FRAME_LEN), None), Some(Drum::Kick));
+    }
+
+    #[test]
+    fn noise_bursts_are_snares() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        assert_eq!(hit(&mut recognizer, &noise(FRAME_LEN), None), Some(Drum::Snare));
+    }
+
+    #[test]
+    fn mid_range_tones_are_neither() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        assert_eq!(hit(&mut recognizer, &sine(1000.0, FRAME_LEN), None), None);
+    }
+
+    #[test]
+    fn a_backbeat_fills_the_grid() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        let (kick, snare) = (sine(60.0, FRAME_LEN), noise(FRAME_LEN));
+        for _ in 0..2 {
+            beat(&mut recognizer, &kick);
+            beat(&mut recognizer, &snare);
+            beat(&mut recognizer, &kick);
+            beat(&mut recognizer, &snare);
+        }
+
+        let expected: Bar = [[true, false, true, false], [false, true, false, true]];
+        assert_eq!(recognizer.bars().iter().copied().collect::<Vec<_>>(), [expected, expected]);
+        assert_eq!(recognizer.bpm(), Some(120.0));
+        assert_eq!(recognizer.current_beat(), Some(0));
+        assert_eq!(
+            recognizer.pattern_text(),
+            "# 120 BPM, 4/4\nKick  | x . x . | x . x . |\nSnare | . x . x | . x . x |\n"
+        );
+    }
+
+    #[test]
+    fn only_the_last_bars_are_kept() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        let kick = sine(60.0, FRAME_LEN);
+        for _ in 0..(BARS + 2) * BEATS_PER_BAR {
+            beat(&mut recognizer, &kick);
+        }
+        assert_eq!(recognizer.bars().len(), BARS);
+    }
+
+    #[test]
+    fn the_grid_waits_for_a_kick() {
+        let mut recognizer = DrumPatternRecognizer::default();
+        beat(&mut recognizer, &noise(FRAME_LEN));
+        assert!(recognizer.bars().is_empty());
+        assert_eq!(recognizer.current_beat(), None);
+    }
+}
@@ -0,0 +1,129 @@
+use super::analyzer::SpectrumAnalyzer;
+use super::biquad::Biquad;
+
+// Analysis frame; ~85 ms at 48 kHz, so a 200 ms tone spans a few frames
+const FRAME_LEN: usize = 4096;
+
+// A bin is a feedback candidate when it stands this far above its neighbourhood
+const PROMINENCE_DB: f32 = 15.0;
+const NEIGHBOURHOOD_BINS: usize = 16;
+// Bins right next to the peak belong to the tone itself (Hann main lobe)
+const MAIN_LOBE_BINS: usize = 2;
+// Ignore "tones" in the noise floor
+const MIN_LEVEL_DBFS: f32 = -60.0;
+
+const PERSIST_SECS: f32 = 0.2;
+const NOTCH_Q: f32 = 30.0;
+pub const MAX_NOTCHES: usize = 8;
+
+pub struct Notch {
+    pub frequency_hz: f32,
+    filter: Biquad,
+}
+
+/// Watches the signal for narrow, sustained spectral spikes and notches them out.
+/// Runs on the audio thread, so the FFT is planned and the buffers are allocated
+/// up front.
+pub struct FeedbackSquealDetector {
+    frame: Vec<f32>,
+    analyzer: SpectrumAnalyzer,
+    spectrum: Vec<f32>,
+    // (bin, seconds the bin has stayed prominent)
+    candidates: Vec<(usize, f32)>,
+    // Reused by `analyse` to build the next `candidates`
+    next_candidates: Vec<(usize, f32)>,
+    notches: Vec<Notch>,
+}
+
+impl Default for FeedbackSquealDetector {
+    fn default() -> Self {
+        Self {
+            frame: Vec::with_capacity(FRAME_LEN),
+            analyzer: SpectrumAnalyzer::new(FRAME_LEN),
+            spectrum: Vec::with_capacity(FRAME_LEN / 2),
+            candidates: Vec::new(),
+            next_candidates: Vec::new(),
+            notches: Vec::with_capacity(MAX_NOTCHES),
+        }
+    }
+}
+
+impl FeedbackSquealDetector {
+    pub fn notches(&self) -> &[Notch] {
+        &self.notches
+    }
+
+    pub fn remove_notch(&mut self, index: usize) {
+        if index < self.notches.len() {
+            self.notches.remove(index);
+        }
+    }
+
+    /// Last analysed spectrum in dBFS and its bin width, after the notches.
+    pub fn spectrum(&self, sample_rate: u32) -> (&[f32], f32) {
+        (&self.spectrum, sample_rate as f32 / FRAME_LEN as f32)
+    }
+
+    /// Runs `x` through the active notches and feeds the result to the detector.
+    pub fn process(&mut self, x: f32, sample_rate: u32) -> f32 {
+        let y = self.notches.iter_mut().fold(x, |s, notch| notch.filter.process(s));
+
+        self.frame.push(y);
+        if self.frame.len() >= FRAME_LEN {
+            self.analyse(sample_rate);
+            self.frame.clear();
+        }
+        y
+    }
+
+    fn analyse(&mut self, sample_rate: u32) {
+        self.spectrum.clear();
+        self.spectrum
+            .extend(self.analyzer.analyze(&self.frame).iter().map(|&db| db as f32));
+        let frame_secs = FRAME_LEN as f32 / sample_rate as f32;
+        let bin_hz = sample_rate as f32 / FRAME_LEN as f32;
+
+        // Keep candidates that are still prominent (allowing a bin of drift), drop the rest
+        self.next_candidates.clear();
+        for bin in 1..self.spectrum.len().saturating_sub(1) {
+            if !self.is_prominent(bin) {
+                continue;
+            }
+            let held = self
+                .candidates
+                .iter()
+                .find(|(b, _)| b.abs_diff(bin) <= 1)
+                .map_or(0.0, |&(_, secs)| secs);
+            self.next_candidates.push((bin, held + frame_secs));
+        }
+        std::mem::swap(&mut self.candidates, &mut self.next_candidates);
+
+        for &(bin, secs) in &self.candidates {
+            if secs <= PERSIST_SECS || self.notches.len() >= MAX_NOTCHES {
+                continue;
+            }
+            let frequency_hz = bin as f32 * bin_hz;
+            if self.notches.iter().any(|n| (n.frequency_hz - frequency_hz).abs() < 2.0 * bin_hz) {
+                continue;
+            }
+            self.notches.push(Notch {
+                frequency_hz,
+                filter: Biquad::notch(sample_rate as f32, frequency_hz, NOTCH_Q),
+            });
+        }
+    }
+
+    fn is_prominent(&self, i: usize) -> bool {
+        let s = &self.spectrum;
+        if s[i] < MIN_LEVEL_DBFS || s[i] < s[i - 1] || s[i] < s[i + 1] {
+            return false;
+        }
+
+        let lo = i.saturating_sub(NEIGHBOURHOOD_BINS);
+        let hi = (i + NEIGHBOURHOOD_BINS).min(s.len() - 1);
+        let (sum, count) = (lo..=hi)
+            .filter(|&j| j.abs_diff(i) > MAIN_LOBE_BINS)
+            .fold((0.0, 0), |(sum, count), j| (sum + s[j], count + 1));
+        count > 0 && s[i] - sum / count as f32 > PROMINENCE_DB
+    }
+}
@@ -0,0 +1,79 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use super::biquad::Biquad;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FilterKind {
+    #[default]
+    Off,
+    HighPass,
+    LowPass,
+}
+
+/// Second-order Butterworth high- or low-pass (bilinear transform) for the input chain.
+pub struct AudioFilter {
+    kind: FilterKind,
+    cutoff_hz: f32,
+    // Built on the first sample so the stream's sample rate is known
+    biquad: Option<(Biquad, u32)>,
+}
+
+impl AudioFilter {
+    pub fn new(kind: FilterKind, cutoff_hz: f32) -> Self {
+        Self {
+            kind,
+            cutoff_hz,
+            biquad: None,
+        }
+    }
+
+    pub fn kind(&self) -> FilterKind {
+        self.kind
+    }
+
+    pub fn cutoff_hz(&self) -> f32 {
+        self.cutoff_hz
+    }
+
+    /// Switching type starts from a cleared state so the old response cannot pop.
+    pub fn set_kind(&mut self, kind: FilterKind) {
+        if kind != self.kind {
+            self.kind = kind;
+            self.biquad = None;
+        }
+    }
+
+    /// Recomputes the coefficients but keeps the delay elements, so a slider drag stays smooth.
+    pub fn set_cutoff(&mut self, cutoff_hz: f32) {
+        self.cutoff_hz = cutoff_hz;
+        if let Some((biquad, sample_rate)) = &mut self.biquad {
+            if let Some(design) = Self::design(self.kind, *sample_rate, cutoff_hz) {
+                biquad.set_coefficients(&design);
+            }
+        }
+    }
+
+    fn design(kind: FilterKind, sample_rate: u32, cutoff_hz: f32) -> Option<Biquad> {
+        match kind {
+            FilterKind::Off => None,
+            FilterKind::HighPass => Some(Biquad::high_pass(sample_rate as f32, cutoff_hz, FRAC_1_SQRT_2)),
+            FilterKind::LowPass => Some(Biquad::low_pass(sample_rate as f32, cutoff_hz, FRAC_1_SQRT_2)),
+        }
+    }
+
+    pub fn process(&mut self, x: f32, sample_rate: u32) -> f32 {
+        if self.biquad.is_none_or(|(_, rate)| rate != sample_rate) {
+            self.biquad = Self::design(self.kind, sample_rate, self.cutoff_hz).map(|b| (b, sample_rate));
+        }
+        match &mut self.biquad {
+            Some((biquad, _)) => biquad.process(x),
+            None => x,
+        }
+    }
+}
+
+impl Default for AudioFilter {
+    fn default() -> Self {
+        Self::new(FilterKind::Off, 20.0)
+    }
+}
@@ -0,0 +1,222 @@
+use std::ops::RangeInclusive;
+
+use super::bands::{band_center, band_edges, band_label, BandFilterBank, THIRD_OCTAVE_BANDS};
+use super::peq::{PeqFilter, PeqKind};
+
+/// Most peak filters `FrequencyResponseFlatness::suggested_eq` returns.
+pub const MAX_SUGGESTED_FILTERS: usize = 10;
+
+// Deviations smaller than this are left alone; larger ones are corrected at most this much
+const MIN_CORRECTION_DB: f32 = 1.0;
+const MAX_CORRECTION_DB: f32 = 12.0;
+
+// Q of a peak filter one third of an octave wide
+const THIRD_OCTAVE_Q: f32 = 4.32;
+
+// Bands whose upper edge is past this fraction of the sample rate are left out
+const MAX_EDGE_FRACTION: f32 = 0.49;
+
+/// Response the room is corrected towards.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TargetCurve {
+    #[default]
+    Flat,
+    /// The Harman in-room target, approximated by a +6 dB shelf below 105 Hz and a
+    /// tilt of -1 dB per octave above 1 kHz.
+    Harman,
+}
+
+impl TargetCurve {
+    pub const ALL: [TargetCurve; 2] = [TargetCurve::Flat, TargetCurve::Harman];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetCurve::Flat => "Flat",
+            TargetCurve::Harman => "Harman",
+        }
+    }
+
+    /// Target level at `frequency_hz`, relative to the curve's midrange.
+    pub fn level_db(self, frequency_hz: f32) -> f32 {
+        match self {
+            TargetCurve::Flat => 0.0,
+            TargetCurve::Harman => {
+                let bass = 6.0 / (1.0 + (frequency_hz / 105.0).powi(2));
+                let tilt = -(frequency_hz / 1000.0).log2().max(0.0);
+                bass + tilt
+            }
+        }
+    }
+}
+
+/// Energy of the input per one-third-octave band while a sweep plays. A logarithmic
+/// sweep spends the same time in every band, so a flat system leaves every band
+/// with the same energy.
+#[derive(Clone, Debug)]
+pub struct RoomResponseMeter {
+    filters: BandFilterBank,
+    energy: [f32; THIRD_OCTAVE_BANDS],
+}
+
+impl RoomResponseMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        Self {
+            filters: BandFilterBank::third_octave(sample_rate, MAX_EDGE_FRACTION * sample_rate),
+            energy: [0.0; THIRD_OCTAVE_BANDS],
+        }
+    }
+
+    pub fn process(&mut self, block: &[f32]) {
+        self.filters.process(block, &mut self.energy);
+    }
+
+    /// Level of each band in dB, or `None` for bands that are not entirely inside
+    /// `swept` (the sweep's frequency range) or collected no energy.
+    pub fn band_levels_db(&self, swept: RangeInclusive<f32>) -> [Option<f32>; THIRD_OCTAVE_BANDS] {
+        std::array::from_fn(|i| {
+            let (lower, upper) = band_edges(i);
+            let energy = self.energy[i];
+            (swept.contains(&lower) && swept.contains(&upper) && energy > 0.0).then(|| 10.0 * energy.log10())
+        })
+    }
+}
+
+/// How far a measured room response is from a target curve, per one-third-octave
+/// band, with the overall level taken out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequencyResponseFlatness {
+    pub target: TargetCurve,
+    /// Measured minus target level in dB; positive bands are too loud.
+    pub deviations: [Option<f32>; THIRD_OCTAVE_BANDS],
+}
+
+impl FrequencyResponseFlatness {
+    pub fn new(levels_db: &[Option<f32>; THIRD_OCTAVE_BANDS], target: TargetCurve) -> Self {
+        let raw: [Option<f32>; THIRD_OCTAVE_BANDS] =
+            std::array::from_fn(|i| levels_db[i].map(|level| level - target.level_db(band_center(i))));
+        let measured: Vec<f32> = raw.iter().flatten().copied().collect();
+        let mean = measured.iter().sum::<f32>() / measured.len().max(1) as f32;
+        Self {
+            target,
+            deviations: raw.map(|deviation| deviation.map(|d| d - mean)),
+        }
+    }
+
+    /// RMS of the deviations in dB; 0 for a response that follows the target exactly.
+    pub fn rms_deviation_db(&self) -> f32 {
+        let measured: Vec<f32> = self.deviations.iter().flatten().copied().collect();
+        (measured.iter().map(|d| d * d).sum::<f32>() / measured.len().max(1) as f32).sqrt()
+    }
+
+    /// One peak filter per band that deviates by more than 1 dB, the largest
+    /// deviations first and at most MAX_SUGGESTED_FILTERS of them, sorted by frequency.
+    /// Each cancels its band's deviation, limited to 12 dB either way.
+    pub fn suggested_eq(&self) -> Vec<PeqFilter> {
+        let mut worst: Vec<(usize, f32)> = self
+            .deviations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.map(|d| (i, d)))
+            .filter(|(_, d)| d.abs() > MIN_CORRECTION_DB)
+            .collect();
+        worst.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        worst.truncate(MAX_SUGGESTED_FILTERS);
+        worst.sort_by_key(|&(i, _)| i);
+        worst
+            .into_iter()
+            .map(|(i, d)| PeqFilter {
+                kind: PeqKind::Peak,
+                frequency_hz: band_center(i),
+                gain_db: (-d).clamp(-MAX_CORRECTION_DB, MAX_CORRECTION_DB),
+                q: THIRD_OCTAVE_Q,
+            })
+            .collect()
+    }
+
+    /// One line per band worth correcting, e.g. "Band 200 Hz: +3.5 dB, suggested cut".
+    pub fn suggestions(&self) -> Vec<String> {
+        self.deviations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.filter(|d| d.abs() > MIN_CORRECTION_DB).map(|d| (i, d)))
+            .map(|(i, d)| {
+                let action = if d > 0.0 { "cut" } else { "boost" };
+                format!("Band {} Hz: {:+.1} dB, suggested {}", band_label(i), d, action)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::test_tone::{SweepParams, TestToneGenerator};
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn levels(db: impl Fn(usize) -> f32) -> [Option<f32>; THIRD_OCTAVE_BANDS] {
+        std::array::from_fn(|i| Some(db(i)))
+    }
+
+    #[test]
+    fn a_flat_response_needs_no_eq() {
+        let flatness = FrequencyResponseFlatness::new(&levels(|_| -20.0), TargetCurve::Flat);
+        assert!(flatness.deviations.iter().all(|d| d.unwrap().abs() < 1e-5));
+        assert!(flatness.rms_deviation_db() < 1e-5);
+        assert!(flatness.suggested_eq().is_empty());
+    }
+
+    #[test]
+    fn a_resonance_gets_a_cut_at_its_band() {
+        // 200 Hz is band 10
+        let flatness = FrequencyResponseFlatness::new(&levels(|i| if i == 10 { 3.1 } else { 0.0 }), TargetCurve::Flat);
+        let eq = flatness.suggested_eq();
+        assert_eq!(eq.len(), 1);
+        assert_eq!(eq[0].kind, PeqKind::Peak);
+        assert!((eq[0].frequency_hz - band_center(10)).abs() < 1e-3);
+        assert!((eq[0].gain_db + 3.0).abs() < 1e-3, "{}", eq[0].gain_db);
+        assert_eq!(flatness.suggestions(), ["Band 200 Hz: +3.0 dB, suggested cut"]);
+    }
+
+    #[test]
+    fn at_most_ten_filters_are_suggested() {
+        let flatness = FrequencyResponseFlatness::new(
+            &levels(|i| if i % 2 == 0 { i as f32 } else { -(i as f32) }),
+            TargetCurve::Flat,
+        );
+        let eq = flatness.suggested_eq();
+        assert_eq!(eq.len(), MAX_SUGGESTED_FILTERS);
+        // Sorted by frequency, and every correction within the limit
+        assert!(eq.windows(2).all(|pair| pair[0].frequency_hz < pair[1].frequency_hz));
+        assert!(eq.iter().all(|f| f.gain_db.abs() <= MAX_CORRECTION_DB));
+    }
+
+    #[test]
+    fn a_response_on_the_target_has_no_deviation() {
+        let target = TargetCurve::Harman;
+        let flatness = FrequencyResponseFlatness::new(&levels(|i| target.level_db(band_center(i))), target);
+        assert!(flatness.rms_deviation_db() < 1e-4);
+    }
+
+    #[test]
+    fn a_log_sweep_fills_the_swept_bands_evenly() {
+        let params = SweepParams {
+            f_start_hz: 100.0,
+            f_end_hz: 5000.0,
+            duration_secs: 5.0,
+            amplitude: 0.5,
+        };
+        let mut sweep = TestToneGenerator::sine_sweep(params, SAMPLE_RATE);
+        let block: Vec<f32> = std::iter::from_fn(|| sweep.next_sample()).collect();
+        let mut meter = RoomResponseMeter::new(SAMPLE_RATE);
+        meter.process(&block);
+
+        let levels = meter.band_levels_db(params.f_start_hz..=params.f_end_hz);
+        // 125 Hz to 4 kHz lie entirely inside the sweep
+        assert_eq!(levels.iter().flatten().count(), 16);
+        assert!(levels[7].is_none() && levels[8].is_some());
+        let flatness = FrequencyResponseFlatness::new(&levels, TargetCurve::Flat);
+        assert!(flatness.rms_deviation_db() < 1.0, "{:?}", flatness.deviations);
+    }
+}
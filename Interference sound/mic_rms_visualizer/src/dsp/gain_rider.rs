@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+// The gain is re-evaluated once per second against the average of the last three
+const ADJUST_INTERVAL_SECS: f32 = 1.0;
+const AVERAGE_INTERVALS: usize = 3;
+
+/// Slowly rides a software gain so the average RMS stays near `target_rms`.
+pub struct GainRider {
+    pub target_rms: f32,
+    /// Half-width of the band around the target, in dB, inside which nothing is changed.
+    pub deadband: f32,
+    /// The gain is clamped to ±`max_gain_db`.
+    pub max_gain_db: f32,
+    /// Largest gain change per adjustment, in dB.
+    pub speed: f32,
+    gain_db: f32,
+    current: (f32, usize),
+    intervals: VecDeque<(f32, usize)>,
+    elapsed_samples: u64,
+    history: Vec<[f64; 2]>,
+}
+
+impl GainRider {
+    pub fn new(target_rms: f32, deadband: f32, max_gain_db: f32, speed: f32) -> Self {
+        Self {
+            target_rms,
+            deadband,
+            max_gain_db,
+            speed,
+            gain_db: 0.0,
+            current: (0.0, 0),
+            intervals: VecDeque::with_capacity(AVERAGE_INTERVALS),
+            elapsed_samples: 0,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    pub fn gain(&self) -> f32 {
+        10f32.powf(self.gain_db / 20.0)
+    }
+
+    /// `(seconds, gain_db)` pairs, one per adjustment interval.
+    pub fn history(&self) -> &[[f64; 2]] {
+        &self.history
+    }
+
+    pub fn reset(&mut self) {
+        self.gain_db = 0.0;
+        self.current = (0.0, 0);
+        self.intervals.clear();
+        self.elapsed_samples = 0;
+        self.history.clear();
+    }
+
+    /// Feeds the sum of squares of `n` pre-gain samples.
+    pub fn update(&mut self, sum_sq: f32, n: usize, sample_rate: u32) {
+        self.current.0 += sum_sq;
+        self.current.1 += n;
+        self.elapsed_samples += n as u64;
+
+        if (self.current.1 as f32) < ADJUST_INTERVAL_SECS * sample_rate as f32 {
+            return;
+        }
+        if self.intervals.len() == AVERAGE_INTERVALS {
+            self.intervals.pop_front();
+        }
+        self.intervals.push_back(std::mem::take(&mut self.current));
+
+        let (sum_sq, n) = self
+            .intervals
+            .iter()
+            .fold((0.0, 0), |(s, c), &(is, ic)| (s + is, c + ic));
+        let pre_gain_rms = (sum_sq / n.max(1) as f32).sqrt();
+
+        if pre_gain_rms > 0.0 && self.target_rms > 0.0 {
+            let error_db = 20.0 * (self.target_rms / (pre_gain_rms * self.gain())).log10();
+            if error_db.abs() > self.deadband {
+                self.gain_db = (self.gain_db + error_db.clamp(-self.speed, self.speed))
+                    .clamp(-self.max_gain_db, self.max_gain_db);
+            }
+        }
+
+        let secs = self.elapsed_samples as f64 / sample_rate as f64;
+        self.history.push([secs, self.gain_db as f64]);
+    }
+}
+
+impl Default for GainRider {
+    fn default() -> Self {
+        Self::new(0.05, 3.0, 20.0, 1.0)
+    }
+}
@@ -0,0 +1,168 @@
+use std::f32::consts::PI;
+
+pub const MAX_DETECTORS: usize = 16;
+
+// Analysis block; 20 ms resolves the 73 Hz DTMF row spacing and still catches 40 ms tones
+const BLOCK_SECS: f32 = 0.02;
+
+const DTMF_ROWS: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+const DTMF_COLUMNS: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+const DTMF_KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+// A detector counts as a DTMF tone if it is within this fraction of the nominal frequency
+const DTMF_TOLERANCE: f32 = 0.015;
+
+/// Amplitude (peak, full scale = 1.0) of the `frequency_hz` component of `samples`.
+pub fn goertzel_amplitude(samples: &[f32], frequency_hz: f32, sample_rate: u32) -> f32 {
+    if samples.is_empty() || sample_rate == 0 {
+        return 0.0;
+    }
+    let coeff = 2.0 * (2.0 * PI * frequency_hz / sample_rate as f32).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &x in samples {
+        let s0 = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    let power = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0);
+    2.0 * power.sqrt() / samples.len() as f32
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ToneDetector {
+    pub frequency_hz: f32,
+    pub threshold_dbfs: f32,
+    level_dbfs: f32,
+    // (start time, loudest level) while the tone is present
+    active: Option<(f64, f32)>,
+}
+
+impl ToneDetector {
+    pub fn new(frequency_hz: f32, threshold_dbfs: f32) -> Self {
+        Self {
+            frequency_hz,
+            threshold_dbfs,
+            level_dbfs: f32::NEG_INFINITY,
+            active: None,
+        }
+    }
+
+    pub fn level_dbfs(&self) -> f32 {
+        self.level_dbfs
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.active.is_some()
+    }
+}
+
+/// One tone from onset to release.
+#[derive(Clone, Copy, Debug)]
+pub struct ToneEvent {
+    pub frequency_hz: f32,
+    pub start_secs: f64,
+    pub duration_secs: f64,
+    pub peak_dbfs: f32,
+}
+
+/// Goertzel detectors for a handful of fixed frequencies, evaluated once per block.
+#[derive(Default)]
+pub struct ToneDetectorBank {
+    pub detectors: Vec<ToneDetector>,
+    block: Vec<f32>,
+    elapsed_samples: u64,
+    events: Vec<ToneEvent>,
+    last_key: Option<char>,
+    decoded: String,
+}
+
+impl ToneDetectorBank {
+    pub fn add(&mut self, detector: ToneDetector) -> bool {
+        if self.detectors.len() >= MAX_DETECTORS {
+            return false;
+        }
+        self.detectors.push(detector);
+        true
+    }
+
+    /// Replaces the detectors with the eight DTMF frequencies.
+    pub fn load_dtmf_preset(&mut self, threshold_dbfs: f32) {
+        self.detectors = DTMF_ROWS
+            .iter()
+            .chain(&DTMF_COLUMNS)
+            .map(|&f| ToneDetector::new(f, threshold_dbfs))
+            .collect();
+        self.last_key = None;
+    }
+
+    pub fn events(&self) -> &[ToneEvent] {
+        &self.events
+    }
+
+    /// Keys decoded so far, one per key press.
+    pub fn decoded(&self) -> &str {
+        &self.decoded
+    }
+
+    pub fn clear_log(&mut self) {
+        self.events.clear();
+        self.decoded.clear();
+    }
+
+    pub fn process(&mut self, x: f32, sample_rate: u32) {
+        self.block.push(x);
+        self.elapsed_samples += 1;
+        if (self.block.len() as f32) < BLOCK_SECS * sample_rate as f32 {
+            return;
+        }
+
+        let now = self.elapsed_samples as f64 / sample_rate as f64;
+        for detector in self.detectors.iter_mut() {
+            let amplitude = goertzel_amplitude(&self.block, detector.frequency_hz, sample_rate);
+            detector.level_dbfs = 20.0 * amplitude.max(1e-10).log10();
+            let present = detector.level_dbfs >= detector.threshold_dbfs;
+
+            match (detector.active, present) {
+                (None, true) => detector.active = Some((now - BLOCK_SECS as f64, detector.level_dbfs)),
+                (Some((start, peak)), true) => detector.active = Some((start, peak.max(detector.level_dbfs))),
+                (Some((start, peak)), false) => {
+                    self.events.push(ToneEvent {
+                        frequency_hz: detector.frequency_hz,
+                        start_secs: start,
+                        duration_secs: now - BLOCK_SECS as f64 - start,
+                        peak_dbfs: peak,
+                    });
+                    detector.active = None;
+                }
+                (None, false) => {}
+            }
+        }
+        self.block.clear();
+
+        let key = self.dtmf_key();
+        if let Some(key) = key.filter(|_| key != self.last_key) {
+            self.decoded.push(key);
+        }
+        self.last_key = key;
+    }
+
+    /// The DTMF key currently held, if exactly one row and one column tone are present.
+    pub fn dtmf_key(&self) -> Option<char> {
+        let present = |nominal: &[f32; 4]| -> Option<usize> {
+            let mut hits = nominal.iter().enumerate().filter(|&(_, &f)| {
+                self.detectors
+                    .iter()
+                    .any(|d| d.is_present() && (d.frequency_hz - f).abs() <= f * DTMF_TOLERANCE)
+            });
+            match (hits.next(), hits.next()) {
+                (Some((i, _)), None) => Some(i),
+                _ => None,
+            }
+        };
+        Some(DTMF_KEYS[present(&DTMF_ROWS)?][present(&DTMF_COLUMNS)?])
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+/// Amplitude bins of a column, over [-1, 1].
+pub const ROWS: usize = 100;
+/// Time slices kept.
+pub const COLUMNS: usize = 100;
+/// Time the columns span; long enough for speech and the pauses between it.
+pub const HISTORY_SECS: f32 = 10.0;
+
+/// One time slice: samples per amplitude bin, the +1 row first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub counts: Vec<u32>,
+    sum: f32,
+    len: usize,
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; ROWS],
+            sum: 0.0,
+            len: 0,
+        }
+    }
+}
+
+impl Column {
+    pub fn mean(&self) -> f32 {
+        self.sum / self.len.max(1) as f32
+    }
+}
+
+/// Rolling 2D histogram of (time, amplitude) over the last `HISTORY_SECS`. Samples
+/// are binned as they arrive, so drawing it costs the same however long the history.
+#[derive(Default)]
+pub struct WaveformHeatmap {
+    // Complete columns, oldest first
+    columns: VecDeque<Column>,
+    current: Column,
+    column_len: usize,
+    sample_rate: u32,
+}
+
+impl WaveformHeatmap {
+    pub fn push(&mut self, x: f32, sample_rate: u32) {
+        if sample_rate != self.sample_rate {
+            *self = Self {
+                column_len: ((sample_rate as f32 * HISTORY_SECS / COLUMNS as f32) as usize).max(1),
+                sample_rate,
+                ..Self::default()
+            };
+        }
+
+        let row = ((1.0 - x.clamp(-1.0, 1.0)) / 2.0 * (ROWS - 1) as f32).round() as usize;
+        self.current.counts[row] += 1;
+        self.current.sum += x;
+        self.current.len += 1;
+        if self.current.len < self.column_len {
+            return;
+        }
+
+        if self.columns.len() == COLUMNS {
+            self.columns.pop_front();
+        }
+        self.columns.push_back(std::mem::take(&mut self.current));
+    }
+
+    /// Complete columns, oldest first; fewer than `COLUMNS` until the history has filled.
+    pub fn columns(&self) -> &VecDeque<Column> {
+        &self.columns
+    }
+
+    /// Length of one column in seconds, or 0 before the first sample.
+    pub fn column_secs(&self) -> f32 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.column_len as f32 / self.sample_rate as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    #[test]
+    fn columns_cover_the_history() {
+        let mut heatmap = WaveformHeatmap::default();
+        for _ in 0..(2.0 * HISTORY_SECS * SAMPLE_RATE as f32) as usize {
+            heatmap.push(0.0, SAMPLE_RATE);
+        }
+        assert_eq!(heatmap.columns().len(), COLUMNS);
+        assert!((heatmap.column_secs() * COLUMNS as f32 - HISTORY_SECS).abs() < 1e-4);
+        // 100 samples per column, all of them in the middle row
+        let column = &heatmap.columns()[0];
+        assert_eq!(column.counts[ROWS / 2], 100);
+        assert_eq!(column.counts.iter().sum::<u32>(), 100);
+    }
+
+    #[test]
+    fn a_square_wave_fills_two_rows() {
+        let mut heatmap = WaveformHeatmap::default();
+        for i in 0..(HISTORY_SECS * SAMPLE_RATE as f32) as usize {
+            heatmap.push(if i % 2 == 0 { 0.5 } else { -0.5 }, SAMPLE_RATE);
+        }
+        let column = heatmap.columns().back().unwrap();
+        let half = heatmap.column_secs() * SAMPLE_RATE as f32 / 2.0;
+        assert_eq!(column.counts[25] as f32, half);
+        assert_eq!(column.counts[74] as f32, half);
+        assert_eq!(column.mean(), 0.0);
+    }
+
+    #[test]
+    fn a_new_sample_rate_starts_over() {
+        let mut heatmap = WaveformHeatmap::default();
+        for _ in 0..SAMPLE_RATE {
+            heatmap.push(0.1, SAMPLE_RATE);
+        }
+        assert!(!heatmap.columns().is_empty());
+        heatmap.push(0.1, 2 * SAMPLE_RATE);
+        assert!(heatmap.columns().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+// Energy is accumulated as one mean-square value per "fast" 125 ms interval
+pub const INTERVAL_SECS: f32 = 0.125;
+
+/// Timed equivalent continuous level: Leq = 10·log10(mean of the interval mean squares),
+/// in dBFS.
+#[derive(Default)]
+pub struct LeqMeter {
+    current: (f32, usize),
+    sum_sq: f64,
+    intervals: u64,
+    target_intervals: u64,
+    result: Option<f32>,
+}
+
+impl LeqMeter {
+    /// Starts a new period of `duration_secs`, discarding any unfinished one.
+    pub fn start(&mut self, duration_secs: f32) {
+        *self = Self {
+            target_intervals: (duration_secs / INTERVAL_SECS).ceil().max(1.0) as u64,
+            ..Self::default()
+        };
+    }
+
+    pub fn cancel(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.intervals < self.target_intervals
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        (self.target_intervals - self.intervals.min(self.target_intervals)) as f32 * INTERVAL_SECS
+    }
+
+    /// Leq of the period that just completed; returned only once.
+    pub fn take_result(&mut self) -> Option<f32> {
+        self.result.take()
+    }
+
+    pub fn push(&mut self, x: f32, sample_rate: u32) {
+        if !self.is_running() {
+            return;
+        }
+        self.current.0 += x * x;
+        self.current.1 += 1;
+        if (self.current.1 as f32) < INTERVAL_SECS * sample_rate as f32 {
+            return;
+        }
+
+        let (sum, n) = std::mem::take(&mut self.current);
+        self.sum_sq += (sum / n as f32) as f64;
+        self.intervals += 1;
+        if !self.is_running() {
+            let mean_sq = self.sum_sq / self.intervals as f64;
+            self.result = Some((10.0 * mean_sq.max(1e-20).log10()) as f32);
+        }
+    }
+}
+
+/// Energy average of several Leq periods, weighted by their durations.
+pub fn combined_leq(periods: impl IntoIterator<Item = (f32, f32)>) -> Option<f32> {
+    let (energy, time) = periods
+        .into_iter()
+        .fold((0.0f64, 0.0f64), |(e, t), (leq, secs)| {
+            (e + secs as f64 * 10f64.powf(leq as f64 / 10.0), t + secs as f64)
+        });
+    (time > 0.0).then(|| (10.0 * (energy / time).log10()) as f32)
+}
@@ -0,0 +1,266 @@
+/// Mean of the channels of one interleaved frame, so identical channels give the
+/// same value as a mono input.
+pub fn mix_down(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    frame.iter().sum::<f32>() / frame.len() as f32
+}
+
+/// Mean of the channels of `frame` that are not `silenced`, so a soloed channel reads
+/// at its own level; 0 when every channel is silenced.
+pub fn mix_down_unsilenced(frame: &[f32], silenced: &[bool]) -> f32 {
+    let (sum, count) = frame
+        .iter()
+        .enumerate()
+        .filter(|&(channel, _)| !silenced.get(channel).copied().unwrap_or(false))
+        .fold((0.0, 0), |(sum, count), (_, &s)| (sum + s, count + 1));
+    if count == 0 {
+        return 0.0;
+    }
+    sum / count as f32
+}
+
+/// Copies interleaved `data` with `channels` channels to `out` with the `silenced`
+/// channels zeroed. The allocation of `out` is reused.
+pub fn silence_channels(data: &[f32], channels: usize, silenced: &[bool], out: &mut Vec<f32>) {
+    let channels = channels.max(1);
+    out.clear();
+    out.extend(data.iter().enumerate().map(|(i, &s)| {
+        if silenced.get(i % channels).copied().unwrap_or(false) {
+            0.0
+        } else {
+            s
+        }
+    }));
+}
+
+/// RMS of each channel of an interleaved buffer with `channels` channels, written
+/// to `rms`. Its allocation is reused, so this can run on the audio thread.
+pub fn channel_rms(data: &[f32], channels: usize, rms: &mut Vec<f32>) {
+    let channels = channels.max(1);
+    rms.clear();
+    rms.resize(channels, 0.0);
+    for frame in data.chunks_exact(channels) {
+        for (sum, &s) in rms.iter_mut().zip(frame) {
+            *sum += s * s;
+        }
+    }
+    let frames = (data.len() / channels).max(1) as f32;
+    for sum in rms.iter_mut() {
+        *sum = (*sum / frames).sqrt();
+    }
+}
+
+/// Sign changes between neighbouring samples divided by the block length, so a sine
+/// of frequency f gives about 2f / sample rate.
+pub fn zero_crossing_rate(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    crossings as f32 / samples.len() as f32
+}
+
+/// Per-channel mute and solo buttons with DAW semantics: while any channel is
+/// soloed, every channel that is not soloed is silent as well.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChannelSoloMute {
+    pub muted: Vec<bool>,
+    pub soloed: Vec<bool>,
+}
+
+impl ChannelSoloMute {
+    /// Resizes to `channels` channels, clearing every button if the count changed.
+    pub fn set_channels(&mut self, channels: usize) {
+        if self.muted.len() != channels {
+            self.muted = vec![false; channels];
+            self.soloed = vec![false; channels];
+        }
+    }
+
+    pub fn toggle_mute(&mut self, channel: usize) {
+        if let Some(muted) = self.muted.get_mut(channel) {
+            *muted = !*muted;
+        }
+    }
+
+    /// Toggles the solo of `channel`. An exclusive solo also clears every other solo;
+    /// an additive one (Ctrl held) leaves them alone.
+    pub fn toggle_solo(&mut self, channel: usize, additive: bool) {
+        let Some(&was_soloed) = self.soloed.get(channel) else {
+            return;
+        };
+        if !additive {
+            self.soloed.iter_mut().for_each(|soloed| *soloed = false);
+        }
+        self.soloed[channel] = !was_soloed;
+    }
+
+    /// True if `channel` is muted, or another channel is soloed and this one is not.
+    pub fn is_silenced(&self, channel: usize) -> bool {
+        let any_solo = self.soloed.iter().any(|&soloed| soloed);
+        self.muted.get(channel).copied().unwrap_or(false)
+            || (any_solo && !self.soloed.get(channel).copied().unwrap_or(false))
+    }
+
+    /// `is_silenced` of every channel, written to `silenced`.
+    pub fn silenced(&self, silenced: &mut Vec<bool>) {
+        silenced.clear();
+        silenced.extend((0..self.muted.len()).map(|channel| self.is_silenced(channel)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize) -> Vec<f32> {
+        (0..len).map(|i| 0.5 * (i as f32 * 0.1).sin()).collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn identical_stereo_channels_mix_down_to_the_mono_signal() {
+        let mono = sine(1024);
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s]).collect();
+        let mixed: Vec<f32> = stereo.chunks(2).map(mix_down).collect();
+        assert_eq!(mixed, mono);
+        assert_eq!(rms(&mixed), rms(&mono));
+    }
+
+    #[test]
+    fn channel_rms_of_identical_channels_matches_mono() {
+        let mono = sine(1024);
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s]).collect();
+        let mut per_channel = Vec::new();
+        channel_rms(&stereo, 2, &mut per_channel);
+        assert_eq!(per_channel.len(), 2);
+        for value in per_channel {
+            assert!((value - rms(&mono)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn channel_rms_keeps_channels_apart() {
+        let data = [1.0, 0.0, -1.0, 0.0, 1.0, 0.5, -1.0, -0.5];
+        let mut per_channel = vec![9.0; 5];
+        channel_rms(&data, 2, &mut per_channel);
+        assert_eq!(per_channel.len(), 2);
+        assert!((per_channel[0] - 1.0).abs() < 1e-6);
+        assert!((per_channel[1] - 0.125f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mix_down_of_an_empty_frame_is_silence() {
+        assert_eq!(mix_down(&[]), 0.0);
+    }
+
+    #[test]
+    fn mix_down_leaves_out_silenced_channels() {
+        assert_eq!(mix_down_unsilenced(&[0.8, 0.2], &[false, true]), 0.8);
+        assert_eq!(mix_down_unsilenced(&[0.8, 0.2], &[false, false]), 0.5);
+        assert_eq!(mix_down_unsilenced(&[0.8, 0.2], &[true, true]), 0.0);
+        // Channels past the end of `silenced` are live
+        assert_eq!(mix_down_unsilenced(&[0.8, 0.2], &[]), 0.5);
+    }
+
+    #[test]
+    fn silence_channels_zeroes_only_the_silenced_ones() {
+        let mut out = vec![9.0; 10];
+        silence_channels(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3, &[false, true, false], &mut out);
+        assert_eq!(out, [1.0, 0.0, 3.0, 4.0, 0.0, 6.0]);
+    }
+
+    #[test]
+    fn zcr_of_a_sine_is_twice_its_frequency() {
+        let sample_rate = 48_000.0;
+        for frequency in [100.0, 1000.0, 5000.0] {
+            // Phase offset so no sample lands exactly on zero
+            let samples: Vec<f32> = (0..48_000)
+                .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate + 0.1).sin())
+                .collect();
+            let expected = 2.0 * frequency / sample_rate;
+            let zcr = zero_crossing_rate(&samples);
+            assert!((zcr - expected).abs() < expected * 0.01, "{} Hz: {}", frequency, zcr);
+        }
+    }
+
+    #[test]
+    fn zcr_of_alternating_signs_is_almost_one() {
+        let samples: Vec<f32> = (0..100).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        assert_eq!(zero_crossing_rate(&samples), 0.99);
+    }
+
+    #[test]
+    fn mute_silences_only_its_channel() {
+        let mut buttons = ChannelSoloMute::default();
+        buttons.set_channels(3);
+        buttons.toggle_mute(1);
+        assert_eq!((0..3).map(|ch| buttons.is_silenced(ch)).collect::<Vec<_>>(), [false, true, false]);
+        buttons.toggle_mute(1);
+        assert!(!buttons.is_silenced(1));
+    }
+
+    #[test]
+    fn exclusive_solo_silences_all_other_channels() {
+        let mut buttons = ChannelSoloMute::default();
+        buttons.set_channels(4);
+        buttons.toggle_solo(0, false);
+        buttons.toggle_solo(2, false);
+        let mut silenced = Vec::new();
+        buttons.silenced(&mut silenced);
+        assert_eq!(silenced, [true, true, false, true]);
+
+        // Un-soloing the last soloed channel brings everything back
+        buttons.toggle_solo(2, false);
+        buttons.silenced(&mut silenced);
+        assert_eq!(silenced, [false; 4]);
+    }
+
+    #[test]
+    fn additive_solo_keeps_earlier_solos() {
+        let mut buttons = ChannelSoloMute::default();
+        buttons.set_channels(4);
+        buttons.toggle_solo(0, false);
+        buttons.toggle_solo(2, true);
+        let mut silenced = Vec::new();
+        buttons.silenced(&mut silenced);
+        assert_eq!(silenced, [false, true, false, true]);
+    }
+
+    #[test]
+    fn a_muted_channel_stays_silent_while_soloed() {
+        let mut buttons = ChannelSoloMute::default();
+        buttons.set_channels(2);
+        buttons.toggle_mute(0);
+        buttons.toggle_solo(0, false);
+        assert!(buttons.is_silenced(0));
+        assert!(buttons.is_silenced(1));
+    }
+
+    #[test]
+    fn changing_the_channel_count_clears_the_buttons() {
+        let mut buttons = ChannelSoloMute::default();
+        buttons.set_channels(2);
+        buttons.toggle_mute(1);
+        buttons.set_channels(2);
+        assert!(buttons.is_silenced(1));
+        buttons.set_channels(4);
+        assert_eq!(buttons.muted, [false; 4]);
+        assert!(!buttons.is_silenced(7));
+    }
+
+    #[test]
+    fn zcr_of_dc_and_silence_is_zero() {
+        assert_eq!(zero_crossing_rate(&[0.3; 64]), 0.0);
+        assert_eq!(zero_crossing_rate(&[0.0; 64]), 0.0);
+        assert_eq!(zero_crossing_rate(&[]), 0.0);
+    }
+}
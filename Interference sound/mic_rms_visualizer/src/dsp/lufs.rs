@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use super::biquad::Biquad;
+
+// Gating blocks are 400 ms with 75 % overlap, so a new one completes every 100 ms
+const STEP_SECS: f64 = 0.1;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+// Analog prototypes of the BS.1770 K-weighting stages; the 48 kHz coefficients
+// in the standard come out of these, and other rates are designed the same way
+const SHELF_HZ: f64 = 1_681.974_450_955_533;
+const SHELF_GAIN_DB: f64 = 3.999_843_853_973_347;
+const SHELF_Q: f64 = 0.707_175_236_955_419_6;
+const RLB_HZ: f64 = 38.135_470_876_024_44;
+const RLB_Q: f64 = 0.500_327_037_323_877_3;
+
+/// ITU-R BS.1770 loudness: K-weighting, 400 ms gating blocks and the two-stage
+/// gate for integrated loudness. All values are in LUFS and `f32::NEG_INFINITY`
+/// until there is enough signal.
+#[derive(Default)]
+pub struct Lufsometer {
+    // Pre-filter and RLB high-pass of each channel, designed for `sample_rate` on first use
+    k_weighting: Vec<[Biquad; 2]>,
+    sample_rate: u32,
+    channels: usize,
+    // (sum of squares, samples) of the 100 ms step being filled
+    current: (f64, usize),
+    // Mean squares of the last SHORT_TERM_STEPS steps
+    steps: VecDeque<f64>,
+    // Mean square of every gating block since the last reset
+    blocks: Vec<f64>,
+}
+
+impl Lufsometer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Feeds a block of a mono signal.
+    pub fn process_block(&mut self, samples: &[f32], sample_rate: u32) {
+        self.process_interleaved(samples, 1, sample_rate);
+    }
+
+    /// Feeds a block of interleaved front channels, whose powers add up with equal
+    /// weights as in BS.1770. Changing the rate or channel count starts over.
+    pub fn process_interleaved(&mut self, data: &[f32], channels: usize, sample_rate: u32) {
+        if sample_rate == 0 || channels == 0 {
+            return;
+        }
+        if sample_rate != self.sample_rate || channels != self.channels {
+            self.reset();
+            self.sample_rate = sample_rate;
+            self.channels = channels;
+        }
+        let step_len = (STEP_SECS * sample_rate as f64).round() as usize;
+        if self.k_weighting.is_empty() {
+            self.k_weighting = vec![k_weighting(sample_rate as f64); channels];
+        }
+
+        for frame in data.chunks_exact(channels) {
+            for (&x, filters) in frame.iter().zip(self.k_weighting.iter_mut()) {
+                let y = filters.iter_mut().fold(x, |s, section| section.process(s)) as f64;
+                self.current.0 += y * y;
+            }
+            self.current.1 += 1;
+            if self.current.1 < step_len {
+                continue;
+            }
+
+            let (sum, n) = std::mem::take(&mut self.current);
+            self.steps.push_back(sum / n as f64);
+            if self.steps.len() > SHORT_TERM_STEPS {
+                self.steps.pop_front();
+            }
+            if self.steps.len() >= MOMENTARY_STEPS {
+                self.blocks.push(mean_of_last(&self.steps, MOMENTARY_STEPS));
+            }
+        }
+    }
+
+    /// Loudness of the last 400 ms.
+    pub fn momentary_lufs(&self) -> f32 {
+        if self.steps.len() < MOMENTARY_STEPS {
+            return f32::NEG_INFINITY;
+        }
+        loudness(mean_of_last(&self.steps, MOMENTARY_STEPS)) as f32
+    }
+
+    /// Loudness of the last 3 s.
+    pub fn short_term_lufs(&self) -> f32 {
+        if self.steps.len() < SHORT_TERM_STEPS {
+            return f32::NEG_INFINITY;
+        }
+        loudness(mean_of_last(&self.steps, SHORT_TERM_STEPS)) as f32
+    }
+
+    /// Gated loudness since the last reset.
+    pub fn integrated_lufs(&self) -> f32 {
+        let gated_mean = |threshold: f64| {
+            let (sum, n) = self
+                .blocks
+                .iter()
+                .filter(|&&ms| loudness(ms) > threshold)
+                .fold((0.0, 0usize), |(sum, n), &ms| (sum + ms, n + 1));
+            (n > 0).then(|| sum / n as f64)
+        };
+        let Some(absolute) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+            return f32::NEG_INFINITY;
+        };
+        gated_mean(loudness(absolute) + RELATIVE_GATE_LU)
+            .map_or(f32::NEG_INFINITY, |ms| loudness(ms) as f32)
+    }
+
+    /// Length of signal covered by the integrated loudness.
+    pub fn integrated_secs(&self) -> f32 {
+        (self.blocks.len() as f64 * STEP_SECS) as f32
+    }
+}
+
+fn mean_of_last(steps: &VecDeque<f64>, n: usize) -> f64 {
+    steps.iter().rev().take(n).sum::<f64>() / n as f64
+}
+
+fn loudness(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(1e-20).log10()
+}
+
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let k = (PI * SHELF_HZ / sample_rate).tan();
+    let vh = 10f64.powf(SHELF_GAIN_DB / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let shelf = [
+        vh + vb * k / SHELF_Q + k * k,
+        2.0 * (k * k - vh),
+        vh - vb * k / SHELF_Q + k * k,
+        1.0 + k / SHELF_Q + k * k,
+        2.0 * (k * k - 1.0),
+        1.0 - k / SHELF_Q + k * k,
+    ];
+
+    // The standard leaves the high-pass numerator at [1, -2, 1] after normalising
+    let k = (PI * RLB_HZ / sample_rate).tan();
+    let a0 = 1.0 + k / RLB_Q + k * k;
+    let high_pass = [
+        a0,
+        -2.0 * a0,
+        a0,
+        a0,
+        2.0 * (k * k - 1.0),
+        1.0 - k / RLB_Q + k * k,
+    ];
+
+    [shelf, high_pass].map(|c| {
+        let c = c.map(|v| v as f32);
+        Biquad::from_coefficients(c[0], c[1], c[2], c[3], c[4], c[5])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    // EBU Tech 3341 uses stereo sines; one channel 3 dB hotter reads the same on a mono meter
+    fn feed_tone(meter: &mut Lufsometer, stereo_dbfs: f64, secs: f64) {
+        let amplitude = 10f64.powf((stereo_dbfs + 3.0103) / 20.0);
+        let len = (secs * SAMPLE_RATE as f64) as usize;
+        let samples: Vec<f32> = (0..len)
+            .map(|i| (amplitude * (2.0 * PI * 1000.0 * i as f64 / SAMPLE_RATE as f64).sin()) as f32)
+            .collect();
+        // Callback-sized blocks, as in the app
+        for block in samples.chunks(512) {
+            meter.process_block(block, SAMPLE_RATE);
+        }
+    }
+
+    fn assert_lufs(value: f32, expected: f32) {
+        assert!((value - expected).abs() <= 0.1, "{} LUFS, expected {}", value, expected);
+    }
+
+    #[test]
+    fn ebu_3341_case_1_and_2_steady_tone() {
+        for level in [-23.0, -33.0] {
+            let mut meter = Lufsometer::new();
+            feed_tone(&mut meter, level, 20.0);
+            assert_lufs(meter.momentary_lufs(), level as f32);
+            assert_lufs(meter.short_term_lufs(), level as f32);
+            assert_lufs(meter.integrated_lufs(), level as f32);
+        }
+    }
+
+    #[test]
+    fn ebu_3341_case_3_relative_gate() {
+        let mut meter = Lufsometer::new();
+        feed_tone(&mut meter, -36.0, 10.0);
+        feed_tone(&mut meter, -23.0, 60.0);
+        feed_tone(&mut meter, -36.0, 10.0);
+        assert_lufs(meter.integrated_lufs(), -23.0);
+    }
+
+    #[test]
+    fn ebu_3341_case_4_absolute_and_relative_gate() {
+        let mut meter = Lufsometer::new();
+        feed_tone(&mut meter, -72.0, 10.0);
+        feed_tone(&mut meter, -36.0, 10.0);
+        feed_tone(&mut meter, -23.0, 60.0);
+        feed_tone(&mut meter, -36.0, 10.0);
+        feed_tone(&mut meter, -72.0, 10.0);
+        assert_lufs(meter.integrated_lufs(), -23.0);
+    }
+
+    #[test]
+    fn ebu_3341_case_1_interleaved_stereo() {
+        let amplitude = 10f64.powf(-23.0 / 20.0);
+        let frames = 20 * SAMPLE_RATE as usize;
+        let stereo: Vec<f32> = (0..frames)
+            .map(|i| (amplitude * (2.0 * PI * 1000.0 * i as f64 / SAMPLE_RATE as f64).sin()) as f32)
+            .flat_map(|s| [s, s])
+            .collect();
+        let mut meter = Lufsometer::new();
+        for block in stereo.chunks(1024) {
+            meter.process_interleaved(block, 2, SAMPLE_RATE);
+        }
+        assert_lufs(meter.integrated_lufs(), -23.0);
+    }
+
+    #[test]
+    fn readouts_wait_for_enough_signal() {
+        let mut meter = Lufsometer::new();
+        feed_tone(&mut meter, -23.0, 0.3);
+        assert_eq!(meter.momentary_lufs(), f32::NEG_INFINITY);
+        assert_eq!(meter.short_term_lufs(), f32::NEG_INFINITY);
+        assert_eq!(meter.integrated_lufs(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn silence_is_gated_out() {
+        let mut meter = Lufsometer::new();
+        meter.process_block(&[0.0; 48_000], SAMPLE_RATE);
+        assert_eq!(meter.integrated_lufs(), f32::NEG_INFINITY);
+    }
+}
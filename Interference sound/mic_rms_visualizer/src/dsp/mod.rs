@@ -0,0 +1,38 @@
+pub mod agc;
+pub mod analyzer;
+pub mod aweighting;
+pub mod bands;
+pub mod biquad;
+pub mod cepstrum;
+pub mod convolver;
+pub mod drums;
+pub mod feedback;
+pub mod filter;
+pub mod flatness;
+pub mod gain_rider;
+pub mod goertzel;
+pub mod heatmap;
+pub mod leq;
+pub mod levels;
+pub mod lufs;
+pub mod onset;
+pub mod peq;
+pub mod pitch;
+pub mod replaygain;
+pub mod resample;
+pub mod resonance;
+pub mod rt60;
+pub mod rms;
+pub mod sel;
+pub mod silence;
+pub mod smoother;
+pub mod spectral_gate;
+pub mod spectral_subtraction;
+pub mod spectrum;
+pub mod stats;
+#[cfg(feature = "auralization")]
+pub mod stereo_width;
+pub mod test_tone;
+pub mod thd;
+pub mod wind;
+pub mod window;
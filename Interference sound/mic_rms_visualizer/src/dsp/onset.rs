@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+// Short-term energy block and the long-term history it is compared against
+const BLOCK_SECS: f32 = 0.043;
+const HISTORY_SECS: f32 = 1.0;
+
+// Blocks quieter than this (mean square, about -70 dBFS) never count as onsets
+const MIN_ENERGY: f32 = 1e-7;
+
+// The BPM estimate is the median of this many inter-onset intervals
+const INTERVALS: usize = 8;
+const MIN_INTERVALS: usize = 3;
+
+/// Energy onset detector: an onset fires when a 43 ms block carries more than `k`
+/// times the mean energy of the preceding second.
+pub struct OnsetDetector {
+    pub k: f32,
+    current: (f32, usize),
+    history: VecDeque<f32>,
+    block_len: usize,
+    sample_rate: u32,
+    elapsed_blocks: u64,
+    // Block index of the last onset, and the most recent intervals in seconds
+    last_onset: Option<u64>,
+    previous_block_onset: bool,
+    intervals: VecDeque<f32>,
+}
+
+impl OnsetDetector {
+    pub fn new(k: f32) -> Self {
+        Self {
+            k,
+            current: (0.0, 0),
+            history: VecDeque::new(),
+            block_len: 0,
+            sample_rate: 0,
+            elapsed_blocks: 0,
+            last_onset: None,
+            previous_block_onset: false,
+            intervals: VecDeque::with_capacity(INTERVALS),
+        }
+    }
+
+    /// Tempo from the median of the last `INTERVALS` inter-onset intervals, so a
+    /// missed or extra beat does not move it.
+    pub fn bpm(&self) -> Option<f32> {
+        if self.intervals.len() < MIN_INTERVALS {
+            return None;
+        }
+        let mut sorted: Vec<f32> = self.intervals.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        let median = sorted[sorted.len() / 2];
+        (median > 0.0).then(|| 60.0 / median)
+    }
+
+    /// Length of the energy blocks; an onset is reported up to this many samples
+    /// after it began.
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Returns true on the sample that completes an onset block.
+    pub fn process(&mut self, x: f32, sample_rate: u32) -> bool {
+        if sample_rate != self.sample_rate {
+            *self = Self {
+                block_len: ((sample_rate as f32 * BLOCK_SECS) as usize).max(1),
+                sample_rate,
+                ..Self::new(self.k)
+            };
+        }
+
+        self.current.0 += x * x;
+        self.current.1 += 1;
+        if self.current.1 < self.block_len {
+            return false;
+        }
+
+        let energy = self.current.0 / self.block_len as f32;
+        self.current = (0.0, 0);
+        self.elapsed_blocks += 1;
+
+        let history_len = (HISTORY_SECS / BLOCK_SECS) as usize;
+        let full = self.history.len() >= history_len;
+        let long_term = self.history.iter().sum::<f32>() / self.history.len().max(1) as f32;
+        // Consecutive loud blocks belong to the same onset
+        let onset = full && energy > MIN_ENERGY && energy > self.k * long_term && !self.previous_block_onset;
+        self.previous_block_onset = onset;
+
+        if full {
+            self.history.pop_front();
+        }
+        self.history.push_back(energy);
+
+        if onset {
+            if let Some(last) = self.last_onset {
+                if self.intervals.len() == INTERVALS {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back((self.elapsed_blocks - last) as f32 * self.block_len as f32 / sample_rate as f32);
+            }
+            self.last_onset = Some(self.elapsed_blocks);
+        }
+        onset
+    }
+}
+
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        Self::new(1.5)
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{anyhow, Result};
+
+use super::biquad::Biquad;
+
+// REW exports shelves without a Q; this is the usual Butterworth-like slope
+const DEFAULT_SHELF_Q: f32 = 0.707;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PeqKind {
+    Peak,
+    LowShelf,
+    HighShelf,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PeqFilter {
+    pub kind: PeqKind,
+    pub frequency_hz: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl PeqFilter {
+    pub fn to_biquad(&self, sample_rate: f32) -> Biquad {
+        match self.kind {
+            PeqKind::Peak => Biquad::peaking(sample_rate, self.frequency_hz, self.gain_db, self.q),
+            PeqKind::LowShelf => Biquad::low_shelf(sample_rate, self.frequency_hz, self.gain_db, self.q),
+            PeqKind::HighShelf => Biquad::high_shelf(sample_rate, self.frequency_hz, self.gain_db, self.q),
+        }
+    }
+}
+
+/// Parses a Room EQ Wizard filter settings export. Only enabled peak and shelf
+/// filters are returned, e.g.
+///
+/// ```text
+/// Filter  1: ON  PK       Fc   63.0 Hz  Gain  -5.0 dB  Q  4.00
+/// Filter  2: ON  LS       Fc    100 Hz  Gain   3.0 dB
+/// ```
+pub fn parse_rew_filters(text: &str) -> Result<Vec<PeqFilter>> {
+    let mut filters = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let Some(settings) = line.strip_prefix("Filter").and_then(|rest| rest.split_once(':')) else {
+            continue;
+        };
+        let tokens: Vec<&str> = settings.1.split_whitespace().collect();
+        if tokens.first() != Some(&"ON") {
+            continue;
+        }
+
+        // Types: PK / PEQ / Modal, and LS / LSC / HS / HSC with optional slope suffixes
+        let kind = match tokens.get(1).copied() {
+            Some("PK" | "PEQ" | "Modal") => PeqKind::Peak,
+            Some(t) if t.starts_with("LS") => PeqKind::LowShelf,
+            Some(t) if t.starts_with("HS") => PeqKind::HighShelf,
+            _ => continue,
+        };
+
+        let value_after = |key: &str| -> Option<f32> {
+            let i = tokens.iter().position(|&t| t == key)?;
+            tokens.get(i + 1)?.parse().ok()
+        };
+        let frequency_hz = value_after("Fc").ok_or_else(|| anyhow!("Missing Fc in \"{}\"", line))?;
+        let gain_db = value_after("Gain").ok_or_else(|| anyhow!("Missing Gain in \"{}\"", line))?;
+        let q = match kind {
+            PeqKind::Peak => value_after("Q").ok_or_else(|| anyhow!("Missing Q in \"{}\"", line))?,
+            PeqKind::LowShelf | PeqKind::HighShelf => value_after("Q").unwrap_or(DEFAULT_SHELF_Q),
+        };
+
+        filters.push(PeqFilter {
+            kind,
+            frequency_hz,
+            gain_db,
+            q,
+        });
+    }
+
+    if filters.is_empty() {
+        return Err(anyhow!("No enabled peak or shelf filters found"));
+    }
+    Ok(filters)
+}
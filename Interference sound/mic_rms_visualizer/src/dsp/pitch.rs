@@ -0,0 +1,96 @@
+// Search range of the fundamental
+const MIN_FREQ_HZ: f32 = 50.0;
+const MAX_FREQ_HZ: f32 = 2000.0;
+
+// YIN absolute threshold on the cumulative-mean-normalized difference; lower is stricter
+const YIN_THRESHOLD: f32 = 0.15;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Pitch {
+    pub frequency_hz: f32,
+    /// 1 − normalized difference at the chosen lag; 1 is perfectly periodic.
+    pub confidence: f32,
+}
+
+/// Fundamental frequency of `frame` by YIN: the first lag whose cumulative-mean-normalized
+/// difference dips below the threshold, refined by parabolic interpolation. Returns
+/// `None` for unpitched or silent frames.
+pub fn detect_pitch(frame: &[f32], sample_rate: u32) -> Option<Pitch> {
+    let sr = sample_rate as f32;
+    let min_lag = (sr / MAX_FREQ_HZ).floor().max(2.0) as usize;
+    let max_lag = ((sr / MIN_FREQ_HZ).ceil() as usize).min(frame.len() / 2);
+    if sample_rate == 0 || max_lag <= min_lag + 1 {
+        return None;
+    }
+
+    // Difference function over a window of half the frame
+    let window = frame.len() - max_lag;
+    let mut cmnd = vec![1.0f32; max_lag + 1];
+    let mut running_sum = 0.0;
+    for lag in 1..=max_lag {
+        let d: f32 = (0..window).map(|i| (frame[i] - frame[i + lag]).powi(2)).sum();
+        running_sum += d;
+        cmnd[lag] = if running_sum > 0.0 { d * lag as f32 / running_sum } else { 1.0 };
+    }
+
+    // First dip below the threshold, followed down to its local minimum
+    let mut lag = (min_lag..max_lag).find(|&lag| cmnd[lag] < YIN_THRESHOLD)?;
+    while lag + 1 < max_lag && cmnd[lag + 1] < cmnd[lag] {
+        lag += 1;
+    }
+
+    let (a, b, c) = (cmnd[lag - 1], cmnd[lag], cmnd[lag + 1]);
+    let denom = a - 2.0 * b + c;
+    let offset = if denom.abs() > f32::EPSILON { 0.5 * (a - c) / denom } else { 0.0 };
+
+    Some(Pitch {
+        frequency_hz: sr / (lag as f32 + offset.clamp(-0.5, 0.5)),
+        confidence: (1.0 - b).clamp(0.0, 1.0),
+    })
+}
+
+/// Nearest equal-tempered note (A4 = 440 Hz), e.g. "A4".
+pub fn note_name(frequency_hz: f32) -> String {
+    let midi = (69.0 + 12.0 * (frequency_hz / 440.0).log2()).round() as i32;
+    format!("{}{}", NOTE_NAMES[midi.rem_euclid(12) as usize], midi.div_euclid(12) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency_hz: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * frequency_hz * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_frequency_of_a_sine() {
+        for frequency in [110.0, 220.0, 440.0, 1000.0] {
+            let pitch = detect_pitch(&sine(frequency, 48_000, 2048), 48_000).unwrap();
+            assert!((pitch.frequency_hz - frequency).abs() < frequency * 0.005, "{}: {:?}", frequency, pitch);
+            assert!(pitch.confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn silence_has_no_pitch() {
+        assert!(detect_pitch(&[0.0; 2048], 48_000).is_none());
+    }
+
+    #[test]
+    fn short_frames_have_no_pitch() {
+        assert!(detect_pitch(&sine(440.0, 48_000, 64), 48_000).is_none());
+    }
+
+    #[test]
+    fn note_names_follow_a440() {
+        assert_eq!(note_name(440.0), "A4");
+        assert_eq!(note_name(261.63), "C4");
+        assert_eq!(note_name(27.5), "A0");
+        assert_eq!(note_name(466.16), "A#4");
+    }
+}
@@ -0,0 +1,66 @@
+use super::lufs::Lufsometer;
+
+// ReplayGain 2.0 plays tracks back at -18 LUFS
+const REFERENCE_LUFS: f32 = -18.0;
+
+/// Track gain and peak of a finished recording, as written to ReplayGain tags.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayGain {
+    /// Gain in dB that brings the track to the -18 LUFS reference.
+    pub gain_db: f32,
+    /// Largest |sample| of any channel, 1.0 at full scale.
+    pub peak: f32,
+}
+
+/// Measures interleaved `samples` with `channels` channels. `None` if the recording
+/// is too short or too quiet to pass the loudness gate.
+pub fn analyze(samples: &[f32], channels: usize, sample_rate: u32) -> Option<ReplayGain> {
+    let mut meter = Lufsometer::new();
+    meter.process_interleaved(samples, channels, sample_rate);
+    let integrated = meter.integrated_lufs();
+    if !integrated.is_finite() {
+        return None;
+    }
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    Some(ReplayGain {
+        gain_db: REFERENCE_LUFS - integrated,
+        peak,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn stereo_tone(dbfs: f32, secs: f32) -> Vec<f32> {
+        let amplitude = 10f32.powf(dbfs / 20.0);
+        (0..(secs * SAMPLE_RATE as f32) as usize)
+            .map(|i| amplitude * (2.0 * PI * 1000.0 * i as f32 / SAMPLE_RATE as f32).sin())
+            .flat_map(|s| [s, s])
+            .collect()
+    }
+
+    #[test]
+    fn a_tone_at_minus_23_lufs_gets_plus_5_db() {
+        // EBU Tech 3341 case 1: this tone reads -23 LUFS, 5 dB below the reference
+        let gain = analyze(&stereo_tone(-23.0, 10.0), 2, SAMPLE_RATE).unwrap();
+        assert!((gain.gain_db - 5.0).abs() < 0.1, "{} dB", gain.gain_db);
+        assert!((gain.peak - 10f32.powf(-23.0 / 20.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn louder_tracks_get_less_gain() {
+        let quiet = analyze(&stereo_tone(-30.0, 5.0), 2, SAMPLE_RATE).unwrap();
+        let loud = analyze(&stereo_tone(-10.0, 5.0), 2, SAMPLE_RATE).unwrap();
+        assert!((quiet.gain_db - loud.gain_db - 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn silence_and_short_clips_have_no_gain() {
+        assert_eq!(analyze(&vec![0.0; 96_000], 2, SAMPLE_RATE), None);
+        assert_eq!(analyze(&stereo_tone(-20.0, 0.2), 2, SAMPLE_RATE), None);
+    }
+}
@@ -0,0 +1,50 @@
+/// Resamples a whole buffer with linear interpolation.
+pub fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let step = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / step).round() as usize;
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let idx = pos as usize;
+            let frac = (pos - idx as f64) as f32;
+            let a = samples[idx.min(samples.len() - 1)];
+            let b = samples[(idx + 1).min(samples.len() - 1)];
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+/// Streaming linear-interpolation sample-rate converter. Band-limit the input
+/// below the output Nyquist frequency before downsampling.
+pub struct SampleRateConverter {
+    step: f64,
+    position: f64,
+    previous: f32,
+}
+
+impl SampleRateConverter {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            position: 0.0,
+            previous: 0.0,
+        }
+    }
+
+    /// Converts `input` and appends the output samples to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut impl Extend<f32>) {
+        for &x in input {
+            // Emit every output instant that falls between the previous and current input
+            while self.position < 1.0 {
+                let frac = self.position as f32;
+                output.extend(std::iter::once(self.previous + (x - self.previous) * frac));
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            self.previous = x;
+        }
+    }
+}
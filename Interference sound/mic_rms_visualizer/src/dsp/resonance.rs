@@ -0,0 +1,79 @@
+use std::f32::consts::{PI, SQRT_2};
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Resonance {
+    pub frequency_hz: f32,
+    pub q: f32,
+}
+
+impl Resonance {
+    /// Amplitude decay time constant of a resonator with this Q: τ = Q / (π·f).
+    pub fn decay_time_s(&self) -> f32 {
+        self.q / (PI * self.frequency_hz)
+    }
+
+    /// f×τ, which depends only on the damping of the material, not on its size.
+    pub fn material_signature(&self) -> f32 {
+        self.frequency_hz * self.decay_time_s()
+    }
+}
+
+/// Finds the dominant resonance of a ring-down recording using a single FFT over
+/// the whole capture. Q is taken from the –3 dB bandwidth around the peak.
+pub fn find_resonance(samples: &[f32], sample_rate: u32) -> Option<Resonance> {
+    let n = samples.len();
+    if n < 16 || sample_rate == 0 {
+        return None;
+    }
+
+    let mut spectrum: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
+    FftPlanner::new().plan_fft_forward(n).process(&mut spectrum);
+    let mags: Vec<f32> = spectrum[..n / 2].iter().map(|c| c.norm()).collect();
+
+    // Skip the DC bin
+    let (peak, &peak_mag) = mags
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if peak_mag <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation on the log magnitude for a sub-bin peak position
+    let mut peak_pos = peak as f32;
+    if peak + 1 < mags.len() {
+        let (a, b, c) = (
+            mags[peak - 1].max(f32::MIN_POSITIVE).ln(),
+            peak_mag.ln(),
+            mags[peak + 1].max(f32::MIN_POSITIVE).ln(),
+        );
+        let denom = a - 2.0 * b + c;
+        if denom.abs() > f32::EPSILON {
+            peak_pos += (0.5 * (a - c) / denom).clamp(-0.5, 0.5);
+        }
+    }
+
+    // Walk outwards until the magnitude falls below half power
+    let half_power = peak_mag / SQRT_2;
+    let lower = (0..peak)
+        .rev()
+        .find(|&i| mags[i] < half_power)
+        .map(|i| i as f32 + (half_power - mags[i]) / (mags[i + 1] - mags[i]))
+        .unwrap_or(0.0);
+    let upper = (peak + 1..mags.len())
+        .find(|&i| mags[i] < half_power)
+        .map(|i| i as f32 - (half_power - mags[i]) / (mags[i - 1] - mags[i]))
+        .unwrap_or((mags.len() - 1) as f32);
+
+    let bin_hz = sample_rate as f32 / n as f32;
+    let frequency_hz = peak_pos * bin_hz;
+    let bandwidth_hz = ((upper - lower) * bin_hz).max(bin_hz * 0.1);
+
+    Some(Resonance {
+        frequency_hz,
+        q: frequency_hz / bandwidth_hz,
+    })
+}
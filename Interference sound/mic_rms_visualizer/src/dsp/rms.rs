@@ -0,0 +1,44 @@
+/// Plain sum of squares; the reference for `sum_of_squares` and its fallback.
+pub fn sum_of_squares_scalar(samples: &[f32]) -> f32 {
+    samples.iter().map(|s| s * s).sum()
+}
+
+/// Sum of squares, eight samples at a time with AVX when the CPU has it. The
+/// result can differ from the scalar sum in the last bits since the additions
+/// happen in a different order.
+pub fn sum_of_squares(samples: &[f32]) -> f32 {
+    // The check compiles to a constant when the build already targets AVX
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx") {
+            // SAFETY: AVX support was checked just above
+            return unsafe { sum_of_squares_avx(samples) };
+        }
+    }
+    sum_of_squares_scalar(samples)
+}
+
+pub fn rms_scalar(samples: &[f32]) -> f32 {
+    (sum_of_squares_scalar(samples) / samples.len().max(1) as f32).sqrt()
+}
+
+pub fn rms_simd(samples: &[f32]) -> f32 {
+    (sum_of_squares(samples) / samples.len().max(1) as f32).sqrt()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn sum_of_squares_avx(samples: &[f32]) -> f32 {
+    use std::arch::x86_64::{_mm256_add_ps, _mm256_loadu_ps, _mm256_mul_ps, _mm256_setzero_ps, _mm256_storeu_ps};
+
+    let chunks = samples.chunks_exact(8);
+    let rest = chunks.remainder();
+    let mut acc = _mm256_setzero_ps();
+    for chunk in chunks {
+        let v = _mm256_loadu_ps(chunk.as_ptr());
+        acc = _mm256_add_ps(acc, _mm256_mul_ps(v, v));
+    }
+    let mut lanes = [0.0f32; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), acc);
+    lanes.iter().sum::<f32>() + sum_of_squares_scalar(rest)
+}
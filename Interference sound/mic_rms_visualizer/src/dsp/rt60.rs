@@ -0,0 +1,119 @@
+/// Levels are taken over windows of this length.
+pub const WINDOW_SECS: f32 = 0.005;
+
+// T20: the decay is fitted from 5 dB to 25 dB below the steady level and
+// extrapolated to 60 dB
+const FIT_START_DB: f32 = 5.0;
+const FIT_END_DB: f32 = 25.0;
+// Windows within this of the loudest one count as the steady level
+const STEADY_RANGE_DB: f32 = 3.0;
+
+/// Reverberation time by the interrupted noise method: the input level is recorded
+/// while noise plays and after it stops, and the decay at the end gives the RT60.
+#[derive(Clone, Debug)]
+pub struct DecayMeter {
+    window_len: usize,
+    // Sum of squares and samples of the window being filled
+    sum: f32,
+    count: usize,
+    levels_db: Vec<f32>,
+}
+
+impl DecayMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            window_len: ((WINDOW_SECS * sample_rate as f32) as usize).max(1),
+            sum: 0.0,
+            count: 0,
+            levels_db: Vec::new(),
+        }
+    }
+
+    pub fn process(&mut self, block: &[f32]) {
+        for &s in block {
+            self.sum += s * s;
+            self.count += 1;
+            if self.count == self.window_len {
+                self.levels_db.push(10.0 * (self.sum / self.window_len as f32).max(1e-20).log10());
+                self.sum = 0.0;
+                self.count = 0;
+            }
+        }
+    }
+
+    /// RT60 in seconds from the last decay in the recording, or `None` if the level
+    /// never fell 25 dB below the steady level (noise too quiet or recording too short).
+    /// Decays faster than the windows can resolve give an upper bound.
+    pub fn rt60_secs(&self) -> Option<f32> {
+        let peak = self.levels_db.iter().copied().fold(f32::MIN, f32::max);
+        let steady: Vec<f32> = self
+            .levels_db
+            .iter()
+            .copied()
+            .filter(|&l| l >= peak - STEADY_RANGE_DB)
+            .collect();
+        let steady = steady.iter().sum::<f32>() / steady.len().max(1) as f32;
+
+        // The last window before the decay, and the first one past its end
+        let start = self.levels_db.iter().rposition(|&l| l >= steady - FIT_START_DB)?;
+        let end = start + self.levels_db[start..].iter().position(|&l| l < steady - FIT_END_DB)?;
+        let fit = &self.levels_db[start + 1..end];
+        if fit.len() < 2 {
+            return Some((end - start) as f32 * WINDOW_SECS * 60.0 / (FIT_END_DB - FIT_START_DB));
+        }
+
+        // Least-squares slope in dB per window
+        let n = fit.len() as f32;
+        let mean_x = (n - 1.0) / 2.0;
+        let mean_y = fit.iter().sum::<f32>() / n;
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (i, &y) in fit.iter().enumerate() {
+            cov += (i as f32 - mean_x) * (y - mean_y);
+            var += (i as f32 - mean_x).powi(2);
+        }
+        let slope = cov / var;
+        (slope < 0.0).then(|| -60.0 / slope * WINDOW_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::test_tone::TestToneGenerator;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    // 1 s of noise, then the same noise decaying by 60 dB in `rt60` seconds over a -80 dB floor
+    fn interrupted_noise(rt60: f32) -> Vec<f32> {
+        let mut noise = TestToneGenerator::noise_burst(0.5, 4.0, SAMPLE_RATE);
+        std::iter::from_fn(|| noise.next_sample())
+            .enumerate()
+            .map(|(i, s)| {
+                let t = i as f32 / SAMPLE_RATE as f32 - 1.0;
+                let decay = if t < 0.0 { 1.0 } else { 10f32.powf(-3.0 * t / rt60) };
+                s * decay.max(1e-4)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_the_decay_time() {
+        for rt60 in [0.1, 0.5, 1.5] {
+            let mut meter = DecayMeter::new(SAMPLE_RATE);
+            for block in interrupted_noise(rt60).chunks(512) {
+                meter.process(block);
+            }
+            let measured = meter.rt60_secs().unwrap();
+            assert!((measured - rt60).abs() < 0.1 * rt60, "RT60 {} measured as {}", rt60, measured);
+        }
+    }
+
+    #[test]
+    fn steady_noise_has_no_decay() {
+        let mut noise = TestToneGenerator::noise_burst(0.5, 1.0, SAMPLE_RATE);
+        let block: Vec<f32> = std::iter::from_fn(|| noise.next_sample()).collect();
+        let mut meter = DecayMeter::new(SAMPLE_RATE);
+        meter.process(&block);
+        assert_eq!(meter.rt60_secs(), None);
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+
+// Envelope resolution and how far back an event can be measured
+const BLOCK_SECS: f32 = 0.01;
+const HISTORY_SECS: f32 = 10.0;
+
+// ISO 1996: integrate from 10 dB below the peak before it to 10 dB below after it
+const EVENT_RANGE: f32 = 0.1;
+
+// A triggered measurement stops waiting for the event to decay after this long
+const MAX_EVENT_SECS: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SoundExposure {
+    /// 10·log10(∫x²dt / 1 s), i.e. relative to a full-scale signal lasting one second.
+    pub sel_dbfs: f32,
+    pub duration_secs: f32,
+    pub peak_dbfs: f32,
+}
+
+/// Short-time energy envelope of the last `HISTORY_SECS`, from which the sound
+/// exposure level of the loudest recent event can be measured, either on request
+/// or once an event announced with `trigger` has decayed.
+#[derive(Default)]
+pub struct SelHistory {
+    current: (f32, f32, usize),
+    // (sum of squares, sample peak) per block
+    blocks: VecDeque<(f32, f32)>,
+    block_len: usize,
+    sample_rate: u32,
+    // Blocks completed so far, and the block a triggered event began in
+    blocks_done: u64,
+    armed: Option<u64>,
+    triggered: Option<SoundExposure>,
+}
+
+impl SelHistory {
+    pub fn push(&mut self, x: f32, sample_rate: u32) {
+        if sample_rate != self.sample_rate {
+            *self = Self {
+                block_len: ((sample_rate as f32 * BLOCK_SECS) as usize).max(1),
+                sample_rate,
+                ..Self::default()
+            };
+        }
+
+        self.current.0 += x * x;
+        self.current.1 = self.current.1.max(x.abs());
+        self.current.2 += 1;
+        if self.current.2 < self.block_len {
+            return;
+        }
+
+        let (sum_sq, peak, _) = std::mem::take(&mut self.current);
+        if self.blocks.len() as f32 >= HISTORY_SECS / BLOCK_SECS {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((sum_sq, peak));
+        self.blocks_done += 1;
+        if let Some(start) = self.armed {
+            self.finish_triggered(start);
+        }
+    }
+
+    /// Arms a measurement of the event whose onset began `onset_len` samples ago. It
+    /// is taken once the event has fallen 10 dB below its peak, and `take_triggered`
+    /// returns it. Onsets while armed belong to the same event and are ignored.
+    pub fn trigger(&mut self, onset_len: usize) {
+        if self.armed.is_some() || self.block_len == 0 {
+            return;
+        }
+        let samples = self.blocks_done * self.block_len as u64 + self.current.2 as u64;
+        self.armed = Some(samples.saturating_sub(onset_len as u64) / self.block_len as u64);
+    }
+
+    /// True while a triggered event is still ringing.
+    pub fn is_armed(&self) -> bool {
+        self.armed.is_some()
+    }
+
+    /// The measurement of the last triggered event, once it has decayed.
+    pub fn take_triggered(&mut self) -> Option<SoundExposure> {
+        self.triggered.take()
+    }
+
+    /// Measures the loudest event in the history, or `None` if it is silent.
+    pub fn measure(&self) -> Option<SoundExposure> {
+        self.measure_from(0)
+    }
+
+    fn finish_triggered(&mut self, start: u64) {
+        let oldest = self.blocks_done - self.blocks.len() as u64;
+        let first = (start.max(oldest) - oldest) as usize;
+        let Some((peak_block, peak_energy)) = (first..self.blocks.len())
+            .map(|i| (i, self.blocks[i].0))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            return;
+        };
+        let decayed = peak_block + 1 < self.blocks.len()
+            && self.blocks.back().is_some_and(|&(energy, _)| energy < peak_energy * EVENT_RANGE);
+        let timed_out = (self.blocks_done - start) as f32 * BLOCK_SECS >= MAX_EVENT_SECS;
+        if decayed || timed_out {
+            self.armed = None;
+            self.triggered = self.measure_from(first);
+        }
+    }
+
+    // Measures the event around the loudest block from `from` on; the event may
+    // begin before it
+    fn measure_from(&self, from: usize) -> Option<SoundExposure> {
+        let (peak_block, &(peak_energy, _)) = self
+            .blocks
+            .iter()
+            .enumerate()
+            .skip(from)
+            .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))?;
+        if peak_energy <= 0.0 {
+            return None;
+        }
+
+        let threshold = peak_energy * EVENT_RANGE;
+        let first = (0..peak_block)
+            .rev()
+            .find(|&i| self.blocks[i].0 < threshold)
+            .map_or(0, |i| i + 1);
+        let last = (peak_block + 1..self.blocks.len())
+            .find(|&i| self.blocks[i].0 < threshold)
+            .map_or(self.blocks.len() - 1, |i| i - 1);
+
+        let event = self.blocks.range(first..=last);
+        let (energy, peak) = event.fold((0.0f32, 0.0f32), |(e, p), &(sum_sq, peak)| (e + sum_sq, p.max(peak)));
+        let exposure = energy / self.sample_rate as f32;
+
+        Some(SoundExposure {
+            sel_dbfs: 10.0 * exposure.max(1e-20).log10(),
+            duration_secs: (last - first + 1) as f32 * self.block_len as f32 / self.sample_rate as f32,
+            peak_dbfs: 20.0 * peak.max(1e-10).log10(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn push(history: &mut SelHistory, amplitude: f32, secs: f32) {
+        for _ in 0..(secs * SAMPLE_RATE as f32) as usize {
+            history.push(amplitude, SAMPLE_RATE);
+        }
+    }
+
+    #[test]
+    fn a_one_second_full_scale_event_has_an_sel_of_zero() {
+        let mut history = SelHistory::default();
+        push(&mut history, 0.0, 1.0);
+        push(&mut history, 1.0, 1.0);
+        push(&mut history, 0.0, 1.0);
+
+        let exposure = history.measure().unwrap();
+        assert!(exposure.sel_dbfs.abs() < 0.1, "{}", exposure.sel_dbfs);
+        assert!((exposure.duration_secs - 1.0).abs() < 0.02);
+        assert!(exposure.peak_dbfs.abs() < 0.1);
+    }
+
+    #[test]
+    fn a_triggered_event_is_measured_once_it_decays() {
+        let mut history = SelHistory::default();
+        push(&mut history, 0.001, 1.0);
+        push(&mut history, 0.5, 0.05);
+        history.trigger((0.05 * SAMPLE_RATE as f32) as usize);
+        push(&mut history, 0.5, 0.2);
+        assert!(history.is_armed());
+        assert!(history.take_triggered().is_none());
+
+        push(&mut history, 0.001, 0.05);
+        assert!(!history.is_armed());
+        let exposure = history.take_triggered().unwrap();
+        assert!((exposure.duration_secs - 0.25).abs() < 0.02, "{}", exposure.duration_secs);
+        // 0.25 s at -6 dBFS
+        assert!((exposure.sel_dbfs - (-6.02 - 6.02)).abs() < 0.2, "{}", exposure.sel_dbfs);
+    }
+
+    #[test]
+    fn a_triggered_measurement_ignores_louder_events_before_it() {
+        let mut history = SelHistory::default();
+        push(&mut history, 1.0, 0.5);
+        push(&mut history, 0.001, 1.0);
+        history.trigger(0);
+        push(&mut history, 0.1, 0.1);
+        push(&mut history, 0.001, 0.05);
+
+        let exposure = history.take_triggered().unwrap();
+        assert!((exposure.peak_dbfs - -20.0).abs() < 0.1, "{}", exposure.peak_dbfs);
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::{Instant, SystemTime};
+
+/// A period during which the level stayed below the silence threshold.
+#[derive(Clone, Copy, Debug)]
+pub struct SilenceEvent {
+    pub start: Instant,
+    pub end: Instant,
+    /// Wall-clock times of `start` and `end`, for logs read after the session.
+    pub start_time: SystemTime,
+    pub end_time: SystemTime,
+    pub mean_rms: f32,
+}
+
+impl SilenceEvent {
+    pub fn duration_secs(&self) -> f32 {
+        self.end.duration_since(self.start).as_secs_f32()
+    }
+}
+
+/// Reports quiet periods: the RMS fed to `update` has to stay below
+/// `threshold_rms` without a break for at least `min_duration_s`.
+pub struct SilenceDetector {
+    pub threshold_rms: f32,
+    pub min_duration_s: f32,
+    // Start of the current quiet run and (sum, count) of its RMS readings
+    run: Option<(Instant, SystemTime, f64, u64)>,
+}
+
+impl Default for SilenceDetector {
+    fn default() -> Self {
+        Self::new(0.001, 5.0)
+    }
+}
+
+impl SilenceDetector {
+    pub fn new(threshold_rms: f32, min_duration_s: f32) -> Self {
+        Self {
+            threshold_rms,
+            min_duration_s,
+            run: None,
+        }
+    }
+
+    /// Feeds the rolling RMS at `now`. Returns the quiet period that has just
+    /// ended, if it lasted long enough.
+    pub fn update(&mut self, rms: f32, now: Instant) -> Option<SilenceEvent> {
+        if rms < self.threshold_rms {
+            let (_, _, sum, count) = self.run.get_or_insert((now, SystemTime::now(), 0.0, 0));
+            *sum += rms as f64;
+            *count += 1;
+            return None;
+        }
+
+        let (start, start_time, sum, count) = self.run.take()?;
+        let event = SilenceEvent {
+            start,
+            end: now,
+            start_time,
+            end_time: SystemTime::now(),
+            mean_rms: (sum / count.max(1) as f64) as f32,
+        };
+        (event.duration_secs() >= self.min_duration_s).then_some(event)
+    }
+
+    /// How long the level has been below the threshold so far, 0 when it is not.
+    pub fn quiet_secs(&self, now: Instant) -> f32 {
+        self.run.map_or(0.0, |(start, ..)| now.saturating_duration_since(start).as_secs_f32())
+    }
+
+    /// Drops an unfinished quiet period, e.g. after the threshold changes.
+    pub fn reset(&mut self) {
+        self.run = None;
+    }
+}
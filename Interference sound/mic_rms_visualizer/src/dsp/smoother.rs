@@ -0,0 +1,67 @@
+/// One-pole exponential moving average for per-block levels. The coefficient is
+/// derived from `tau_ms` and the block duration, so it holds for any block size.
+#[derive(Clone, Copy, Debug)]
+pub struct AudioSmoother {
+    pub tau_ms: f32,
+    value: f32,
+}
+
+impl AudioSmoother {
+    pub fn new(tau_ms: f32) -> Self {
+        Self { tau_ms, value: 0.0 }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Weight of a new block of `frames` samples.
+    pub fn alpha(&self, frames: usize, sample_rate: u32) -> f32 {
+        let block_ms = 1000.0 * frames as f32 / sample_rate.max(1) as f32;
+        1.0 - (-block_ms / self.tau_ms.max(f32::EPSILON)).exp()
+    }
+
+    pub fn update(&mut self, x: f32, frames: usize, sample_rate: u32) -> f32 {
+        let alpha = self.alpha(frames, sample_rate);
+        self.value = alpha * x + (1.0 - alpha) * self.value;
+        self.value
+    }
+}
+
+impl Default for AudioSmoother {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_reaches_63_percent_after_one_time_constant() {
+        let mut smoother = AudioSmoother::new(100.0);
+        // 100 ms at 48 kHz in 10 ms blocks
+        for _ in 0..10 {
+            smoother.update(1.0, 480, 48_000);
+        }
+        assert!((smoother.value() - (1.0 - (-1.0f32).exp())).abs() < 1e-4);
+    }
+
+    #[test]
+    fn result_does_not_depend_on_the_block_size() {
+        let mut large = AudioSmoother::new(50.0);
+        let mut small = AudioSmoother::new(50.0);
+        large.update(0.5, 4800, 48_000);
+        for _ in 0..100 {
+            small.update(0.5, 48, 48_000);
+        }
+        assert!((large.value() - small.value()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zero_time_constant_follows_the_input() {
+        let mut smoother = AudioSmoother::new(0.0);
+        assert_eq!(smoother.update(0.25, 256, 48_000), 0.25);
+    }
+}
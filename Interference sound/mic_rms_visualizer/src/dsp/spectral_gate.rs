@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+// STFT with 50 % overlap; a sqrt-Hann window on both analysis and synthesis sums to one
+const FRAME_LEN: usize = 1024;
+const HOP: usize = FRAME_LEN / 2;
+
+// The noise floor is the minimum of each bin over the last FLOOR_SECS, tracked in
+// FLOOR_SUBWINDOWS pieces so old minima can expire without storing every frame
+const FLOOR_SECS: f32 = 2.0;
+const FLOOR_SUBWINDOWS: usize = 8;
+
+// Bins within this factor of their floor (6 dB) count as noise
+const GATE_MARGIN: f32 = 2.0;
+
+/// Per-bin noise gate: STFT bins that do not rise above their own running-minimum
+/// noise floor are replaced by `residual` × floor, then the signal is rebuilt by
+/// overlap-add. Adds `FRAME_LEN` samples of latency.
+pub struct FrequencyDomainNoiseGate {
+    /// Fraction of the noise floor left in gated bins (0 = silence).
+    pub residual: f32,
+    stft: Option<Stft>,
+}
+
+struct Stft {
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    filled: usize,
+    scratch: Vec<Complex<f32>>,
+    overlap_add: Vec<f32>,
+    output: VecDeque<f32>,
+    frames_per_subwindow: usize,
+    frames_in_subwindow: usize,
+    subwindow_min: Vec<f32>,
+    past_minima: VecDeque<Vec<f32>>,
+    floor: Vec<f32>,
+}
+
+impl FrequencyDomainNoiseGate {
+    pub fn new(residual: f32) -> Self {
+        Self { residual, stft: None }
+    }
+
+    /// Current per-bin noise floor (linear magnitude), `FRAME_LEN / 2 + 1` bins.
+    pub fn floor(&self) -> &[f32] {
+        self.stft.as_ref().map(|stft| stft.floor.as_slice()).unwrap_or_default()
+    }
+
+    pub fn bin_hz(sample_rate: u32) -> f32 {
+        sample_rate as f32 / FRAME_LEN as f32
+    }
+
+    pub fn process(&mut self, x: f32, sample_rate: u32) -> f32 {
+        let residual = self.residual;
+        let stft = self.stft.get_or_insert_with(|| Stft::new(sample_rate));
+
+        stft.input[FRAME_LEN - HOP + stft.filled] = x;
+        stft.filled += 1;
+        if stft.filled == HOP {
+            stft.filled = 0;
+            stft.process_frame(residual);
+            stft.input.copy_within(HOP.., 0);
+        }
+        stft.output.pop_front().unwrap_or(0.0)
+    }
+}
+
+impl Default for FrequencyDomainNoiseGate {
+    fn default() -> Self {
+        Self::new(0.1)
+    }
+}
+
+impl Stft {
+    fn new(sample_rate: u32) -> Self {
+        let mut planner = FftPlanner::new();
+        let bins = FRAME_LEN / 2 + 1;
+        let floor_frames = (FLOOR_SECS * sample_rate as f32 / HOP as f32) as usize;
+
+        Self {
+            forward: planner.plan_fft_forward(FRAME_LEN),
+            inverse: planner.plan_fft_inverse(FRAME_LEN),
+            window: (0..FRAME_LEN)
+                .map(|i| (PI * i as f32 / FRAME_LEN as f32).sin())
+                .collect(),
+            input: vec![0.0; FRAME_LEN],
+            filled: 0,
+            scratch: vec![Complex::new(0.0, 0.0); FRAME_LEN],
+            overlap_add: vec![0.0; FRAME_LEN],
+            output: VecDeque::with_capacity(2 * HOP),
+            frames_per_subwindow: (floor_frames / FLOOR_SUBWINDOWS).max(1),
+            frames_in_subwindow: 0,
+            subwindow_min: vec![f32::INFINITY; bins],
+            past_minima: VecDeque::with_capacity(FLOOR_SUBWINDOWS),
+            floor: vec![0.0; bins],
+        }
+    }
+
+    fn process_frame(&mut self, residual: f32) {
+        for ((bin, &x), &w) in self.scratch.iter_mut().zip(&self.input).zip(&self.window) {
+            *bin = Complex::new(x * w, 0.0);
+        }
+        self.forward.process(&mut self.scratch);
+        self.update_floor();
+
+        for (k, bin) in self.scratch.iter_mut().enumerate() {
+            let floor = self.floor[k.min(FRAME_LEN - k)];
+            let mag = bin.norm();
+            if mag < floor * GATE_MARGIN {
+                *bin = if mag > 0.0 { *bin * (residual * floor / mag) } else { Complex::new(0.0, 0.0) };
+            }
+        }
+
+        self.inverse.process(&mut self.scratch);
+        let scale = 1.0 / FRAME_LEN as f32;
+        for ((acc, y), &w) in self.overlap_add.iter_mut().zip(&self.scratch).zip(&self.window) {
+            *acc += y.re * scale * w;
+        }
+        self.output.extend(&self.overlap_add[..HOP]);
+        self.overlap_add.copy_within(HOP.., 0);
+        self.overlap_add[FRAME_LEN - HOP..].fill(0.0);
+    }
+
+    fn update_floor(&mut self) {
+        for (min, bin) in self.subwindow_min.iter_mut().zip(&self.scratch) {
+            *min = min.min(bin.norm());
+        }
+
+        self.frames_in_subwindow += 1;
+        if self.frames_in_subwindow == self.frames_per_subwindow {
+            self.frames_in_subwindow = 0;
+            if self.past_minima.len() == FLOOR_SUBWINDOWS {
+                self.past_minima.pop_front();
+            }
+            let bins = self.subwindow_min.len();
+            self.past_minima
+                .push_back(std::mem::replace(&mut self.subwindow_min, vec![f32::INFINITY; bins]));
+        }
+
+        for (k, floor) in self.floor.iter_mut().enumerate() {
+            *floor = self
+                .past_minima
+                .iter()
+                .map(|m| m[k])
+                .fold(self.subwindow_min[k], f32::min);
+        }
+    }
+}
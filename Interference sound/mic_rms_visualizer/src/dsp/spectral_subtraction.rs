@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+// STFT with 50 % overlap; a sqrt-Hann window on analysis and synthesis makes an
+// overall Hann window, whose 50 % overlaps sum to one
+const FRAME_LEN: usize = 1024;
+const HOP: usize = FRAME_LEN / 2;
+
+// Part of each bin's own magnitude that is always kept, so over-subtracted bins
+// do not drop to zero and turn the residue into "musical noise"
+const SPECTRAL_FLOOR: f32 = 0.02;
+
+/// Allowed range of the oversubtraction factor.
+pub const ALPHA_RANGE: std::ops::RangeInclusive<f32> = 1.0..=2.5;
+
+/// Spectral subtraction of a stationary noise: the average magnitude spectrum
+/// learned during a quiet period, times `alpha`, is taken off every STFT bin
+/// and the signal is rebuilt by overlap-add. Adds `FRAME_LEN` samples of latency.
+pub struct SpectralSubtraction {
+    /// Oversubtraction factor, see `ALPHA_RANGE`.
+    pub alpha: f32,
+    /// Average noise magnitude per bin, `FRAME_LEN / 2 + 1` bins; empty until learned.
+    pub noise_spectrum: Vec<f32>,
+    // (sum of magnitudes per bin, frames) while the noise is being learned
+    learning: Option<(Vec<f32>, usize)>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    filled: usize,
+    scratch: Vec<Complex<f32>>,
+    overlap_add: Vec<f32>,
+    output: VecDeque<f32>,
+}
+
+impl SpectralSubtraction {
+    pub fn new(alpha: f32) -> Self {
+        let mut planner = FftPlanner::new();
+        Self {
+            alpha,
+            noise_spectrum: Vec::new(),
+            learning: None,
+            forward: planner.plan_fft_forward(FRAME_LEN),
+            inverse: planner.plan_fft_inverse(FRAME_LEN),
+            window: (0..FRAME_LEN)
+                .map(|i| (PI * i as f32 / FRAME_LEN as f32).sin())
+                .collect(),
+            input: vec![0.0; FRAME_LEN],
+            filled: 0,
+            scratch: vec![Complex::new(0.0, 0.0); FRAME_LEN],
+            overlap_add: vec![0.0; FRAME_LEN],
+            output: VecDeque::with_capacity(2 * HOP),
+        }
+    }
+
+    pub fn bin_hz(sample_rate: u32) -> f32 {
+        sample_rate as f32 / FRAME_LEN as f32
+    }
+
+    /// Starts averaging the spectrum of everything passed to `process`.
+    pub fn start_learning(&mut self) {
+        self.learning = Some((vec![0.0; FRAME_LEN / 2 + 1], 0));
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.learning.is_some()
+    }
+
+    /// Stores the average since `start_learning` as the noise spectrum.
+    pub fn finish_learning(&mut self) {
+        if let Some((sum, frames)) = self.learning.take() {
+            if frames > 0 {
+                self.noise_spectrum = sum.into_iter().map(|m| m / frames as f32).collect();
+            }
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        self.input[FRAME_LEN - HOP + self.filled] = x;
+        self.filled += 1;
+        if self.filled == HOP {
+            self.filled = 0;
+            self.process_frame();
+            self.input.copy_within(HOP.., 0);
+        }
+        self.output.pop_front().unwrap_or(0.0)
+    }
+
+    fn process_frame(&mut self) {
+        for ((bin, &x), &w) in self.scratch.iter_mut().zip(&self.input).zip(&self.window) {
+            *bin = Complex::new(x * w, 0.0);
+        }
+        self.forward.process(&mut self.scratch);
+
+        if let Some((sum, frames)) = &mut self.learning {
+            for (acc, bin) in sum.iter_mut().zip(&self.scratch) {
+                *acc += bin.norm();
+            }
+            *frames += 1;
+        }
+
+        if !self.noise_spectrum.is_empty() {
+            for (k, bin) in self.scratch.iter_mut().enumerate() {
+                let noise = self.noise_spectrum[k.min(FRAME_LEN - k)];
+                let mag = bin.norm();
+                if mag > 0.0 {
+                    // The phase is kept; only the magnitude is reduced
+                    let cleaned = (mag - self.alpha * noise).max(SPECTRAL_FLOOR * mag);
+                    *bin *= cleaned / mag;
+                }
+            }
+        }
+
+        self.inverse.process(&mut self.scratch);
+        let scale = 1.0 / FRAME_LEN as f32;
+        for ((acc, y), &w) in self.overlap_add.iter_mut().zip(&self.scratch).zip(&self.window) {
+            *acc += y.re * scale * w;
+        }
+        self.output.extend(&self.overlap_add[..HOP]);
+        self.overlap_add.copy_within(HOP.., 0);
+        self.overlap_add[FRAME_LEN - HOP..].fill(0.0);
+    }
+}
+
+impl Default for SpectralSubtraction {
+    fn default() -> Self {
+        Self::new(1.5)
+    }
+}
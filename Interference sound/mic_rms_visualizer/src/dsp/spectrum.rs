@@ -0,0 +1,82 @@
+use std::f32::consts::PI;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpectralPeak {
+    pub frequency_hz: f32,
+    pub level_dbfs: f32,
+}
+
+/// Hann-windowed magnitude spectrum in dBFS (a full-scale sine reads 0 dBFS).
+/// Returns `samples.len() / 2` bins.
+pub fn magnitude_spectrum_dbfs(samples: &[f32]) -> Vec<f32> {
+    let n = samples.len();
+    if n < 2 {
+        return Vec::new();
+    }
+
+    let mut buf: Vec<Complex<f32>> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let w = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+            Complex::new(s * w, 0.0)
+        })
+        .collect();
+    FftPlanner::new().plan_fft_forward(n).process(&mut buf);
+
+    // Hann coherent gain is 0.5, and a real sine splits its energy over ± frequencies
+    let scale = 4.0 / n as f32;
+    buf[..n / 2]
+        .iter()
+        .map(|c| 20.0 * (c.norm() * scale).max(1e-10).log10())
+        .collect()
+}
+
+/// The `count` strongest local maxima of the spectrum, loudest first.
+pub fn spectral_peaks(samples: &[f32], sample_rate: u32, count: usize) -> Vec<SpectralPeak> {
+    let spectrum = magnitude_spectrum_dbfs(samples);
+    let bin_hz = sample_rate as f32 / samples.len().max(1) as f32;
+
+    let mut peaks: Vec<SpectralPeak> = (1..spectrum.len().saturating_sub(1))
+        .filter(|&i| spectrum[i] > spectrum[i - 1] && spectrum[i] >= spectrum[i + 1])
+        .map(|i| SpectralPeak {
+            frequency_hz: i as f32 * bin_hz,
+            level_dbfs: spectrum[i],
+        })
+        .collect();
+    peaks.sort_by(|a, b| b.level_dbfs.total_cmp(&a.level_dbfs));
+    peaks.truncate(count);
+    peaks
+}
+
+/// Named bands used to summarise the frequency character of a signal, as
+/// `(name, low_hz, high_hz)`.
+pub const FREQUENCY_BANDS: [(&str, f32, f32); 5] = [
+    ("sub-bass", 20.0, 60.0),
+    ("bass", 60.0, 250.0),
+    ("midrange", 250.0, 2000.0),
+    ("upper-mid", 2000.0, 4000.0),
+    ("treble", 4000.0, 20000.0),
+];
+
+/// Index into `FREQUENCY_BANDS` of the band holding the most energy.
+pub fn dominant_band(samples: &[f32], sample_rate: u32) -> Option<usize> {
+    let spectrum = magnitude_spectrum_dbfs(samples);
+    let bin_hz = sample_rate as f32 / samples.len().max(1) as f32;
+
+    let mut energy = [0.0f32; FREQUENCY_BANDS.len()];
+    for (i, &db) in spectrum.iter().enumerate() {
+        let f = i as f32 * bin_hz;
+        if let Some(band) = FREQUENCY_BANDS.iter().position(|&(_, lo, hi)| f >= lo && f < hi) {
+            energy[band] += 10f32.powf(db / 10.0);
+        }
+    }
+    energy
+        .iter()
+        .enumerate()
+        .filter(|&(_, &e)| e > 0.0)
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(band, _)| band)
+}
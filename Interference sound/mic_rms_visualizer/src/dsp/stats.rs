@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub mean: f32,
+    pub std_dev: f32,
+    pub min: f32,
+    pub max: f32,
+    pub since_max_secs: f32,
+}
+
+/// Mean and variance of per-block levels over the last `window_secs`, kept with
+/// Welford's update as blocks enter and its inverse as they leave the window.
+pub struct RollingStats {
+    pub window_secs: f32,
+    // (time in seconds, value) of each block inside the window
+    values: VecDeque<(f64, f32)>,
+    mean: f64,
+    m2: f64,
+    elapsed_secs: f64,
+}
+
+impl RollingStats {
+    pub fn new(window_secs: f32) -> Self {
+        Self {
+            window_secs,
+            values: VecDeque::new(),
+            mean: 0.0,
+            m2: 0.0,
+            elapsed_secs: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new(self.window_secs);
+    }
+
+    pub fn push(&mut self, x: f32, block_secs: f32) {
+        self.elapsed_secs += block_secs as f64;
+        self.values.push_back((self.elapsed_secs, x));
+        let n = self.values.len() as f64;
+        let delta = x as f64 - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (x as f64 - self.mean);
+
+        let oldest = self.elapsed_secs - self.window_secs as f64;
+        while self.values.front().is_some_and(|&(t, _)| t <= oldest) {
+            let (_, old) = self.values.pop_front().unwrap();
+            let n = self.values.len() as f64;
+            if n == 0.0 {
+                self.mean = 0.0;
+                self.m2 = 0.0;
+                break;
+            }
+            let delta = old as f64 - self.mean;
+            self.mean -= delta / n;
+            self.m2 = (self.m2 - delta * (old as f64 - self.mean)).max(0.0);
+        }
+    }
+
+    pub fn stats(&self) -> Option<Stats> {
+        let n = self.values.len();
+        if n == 0 {
+            return None;
+        }
+        let (max_time, max) = self
+            .values
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        Some(Stats {
+            mean: self.mean as f32,
+            std_dev: (self.m2 / n as f64).sqrt() as f32,
+            min: self.values.iter().map(|&(_, v)| v).fold(f32::INFINITY, f32::min),
+            max,
+            since_max_secs: (self.elapsed_secs - max_time) as f32,
+        })
+    }
+}
+
+impl Default for RollingStats {
+    fn default() -> Self {
+        Self::new(10.0)
+    }
+}
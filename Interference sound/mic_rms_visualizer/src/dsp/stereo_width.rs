@@ -0,0 +1,74 @@
+// Schroeder allpass used to decorrelate the side signal from the mid signal
+const ALLPASS_DELAY_SECS: f32 = 0.0047;
+const ALLPASS_GAIN: f32 = 0.6;
+
+// Averaging time of the correlation meter
+const CORRELATION_TIME_SECS: f32 = 0.3;
+
+/// Mid/Side widener for a mono signal. The side channel is an allpass-filtered
+/// copy of the mid, so it has the same spectrum but is decorrelated from it.
+pub struct StereoWidth {
+    /// 0 = mono, 1 = normal, 2 = wide.
+    pub width: f32,
+    delay_line: Vec<f32>,
+    pos: usize,
+}
+
+impl StereoWidth {
+    pub fn new(sample_rate: u32, width: f32) -> Self {
+        let delay = ((sample_rate as f32 * ALLPASS_DELAY_SECS) as usize).max(1);
+        Self {
+            width,
+            delay_line: vec![0.0; delay],
+            pos: 0,
+        }
+    }
+
+    /// Returns `(L, R) = (M + S·width, M − S·width)`.
+    pub fn process(&mut self, mid: f32) -> (f32, f32) {
+        // v[n] = x[n] + g·v[n−D], y[n] = −g·v[n] + v[n−D]
+        let delayed = self.delay_line[self.pos];
+        let v = mid + ALLPASS_GAIN * delayed;
+        let side = delayed - ALLPASS_GAIN * v;
+        self.delay_line[self.pos] = v;
+        self.pos = (self.pos + 1) % self.delay_line.len();
+
+        let side = side * self.width;
+        (mid + side, mid - side)
+    }
+}
+
+/// Running L/R correlation: +1 is mono, 0 is fully decorrelated, −1 is out of phase.
+pub struct CorrelationMeter {
+    coeff: f32,
+    lr: f32,
+    ll: f32,
+    rr: f32,
+}
+
+impl CorrelationMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            coeff: (-1.0 / (CORRELATION_TIME_SECS * sample_rate.max(1) as f32)).exp(),
+            lr: 0.0,
+            ll: 0.0,
+            rr: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, l: f32, r: f32) {
+        let a = 1.0 - self.coeff;
+        self.lr += a * (l * r - self.lr);
+        self.ll += a * (l * l - self.ll);
+        self.rr += a * (r * r - self.rr);
+    }
+
+    pub fn correlation(&self) -> f32 {
+        let power = (self.ll * self.rr).sqrt();
+        if power > 1e-12 {
+            (self.lr / power).clamp(-1.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
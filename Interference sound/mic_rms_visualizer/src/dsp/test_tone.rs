@@ -0,0 +1,133 @@
+use std::f64::consts::{PI, TAU};
+
+// Raised-cosine fade at both ends so the sweep starts and stops without a click
+const FADE_SECS: f64 = 0.01;
+
+/// Settings of a logarithmic sine sweep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepParams {
+    pub f_start_hz: f32,
+    pub f_end_hz: f32,
+    pub duration_secs: f32,
+    /// Peak level, full scale = 1.0.
+    pub amplitude: f32,
+}
+
+impl Default for SweepParams {
+    fn default() -> Self {
+        Self {
+            f_start_hz: 20.0,
+            f_end_hz: 20_000.0,
+            duration_secs: 10.0,
+            amplitude: 0.5,
+        }
+    }
+}
+
+/// Known test signals for room measurements, generated sample by sample.
+#[derive(Clone, Debug)]
+pub enum TestToneGenerator {
+    /// Exponential sweep: the frequency doubles in equal time steps.
+    SineSweep {
+        params: SweepParams,
+        sample_rate: u32,
+        position: u64,
+        phase: f64,
+    },
+    /// White noise that stops abruptly, for measuring the decay after it.
+    NoiseBurst {
+        amplitude: f32,
+        len: u64,
+        position: u64,
+        // xorshift32 state
+        state: u32,
+    },
+}
+
+impl TestToneGenerator {
+    pub fn sine_sweep(params: SweepParams, sample_rate: u32) -> Self {
+        Self::SineSweep {
+            params,
+            sample_rate,
+            position: 0,
+            phase: 0.0,
+        }
+    }
+
+    /// Uniform white noise of peak level `amplitude` for `duration_secs`.
+    pub fn noise_burst(amplitude: f32, duration_secs: f32, sample_rate: u32) -> Self {
+        Self::NoiseBurst {
+            amplitude,
+            len: (duration_secs as f64 * sample_rate as f64) as u64,
+            position: 0,
+            state: 0x9E37_79B9,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self {
+            Self::SineSweep {
+                params,
+                sample_rate,
+                position,
+                ..
+            } => *position as f64 >= params.duration_secs as f64 * *sample_rate as f64,
+            Self::NoiseBurst { len, position, .. } => position >= len,
+        }
+    }
+
+    /// Next sample, or `None` once the signal has ended.
+    pub fn next_sample(&mut self) -> Option<f32> {
+        if self.is_finished() {
+            return None;
+        }
+        match self {
+            Self::SineSweep {
+                params,
+                sample_rate,
+                position,
+                phase,
+            } => {
+                let sr = *sample_rate as f64;
+                let duration = params.duration_secs as f64;
+                let t = *position as f64 / sr;
+                let f_start = params.f_start_hz.max(1.0) as f64;
+                let f_end = (params.f_end_hz as f64).clamp(1.0, sr / 2.0);
+                let freq = f_start * (f_end / f_start).powf(t / duration);
+
+                let edge = t.min(duration - t);
+                let fade = if edge < FADE_SECS {
+                    0.5 - 0.5 * (PI * edge / FADE_SECS).cos()
+                } else {
+                    1.0
+                };
+                let y = params.amplitude as f64 * fade * phase.sin();
+
+                *phase = (*phase + TAU * freq / sr) % TAU;
+                *position += 1;
+                Some(y as f32)
+            }
+            Self::NoiseBurst {
+                amplitude,
+                position,
+                state,
+                ..
+            } => {
+                *state ^= *state << 13;
+                *state ^= *state >> 17;
+                *state ^= *state << 5;
+                *position += 1;
+                Some(*amplitude * (*state as f32 / u32::MAX as f32 * 2.0 - 1.0))
+            }
+        }
+    }
+
+    /// Output data callback body: the same sample on every channel of each
+    /// frame, silence after the end. Returns false once the signal has ended.
+    pub fn fill(&mut self, data: &mut [f32], channels: usize) -> bool {
+        for frame in data.chunks_mut(channels.max(1)) {
+            frame.fill(self.next_sample().unwrap_or(0.0));
+        }
+        !self.is_finished()
+    }
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+// Bins on each side of a peak that belong to it; the Hann main lobe is ±2 bins wide
+const LOBE_BINS: usize = 3;
+// Highest harmonic counted separately from the noise
+const MAX_HARMONIC: usize = 10;
+
+/// Total harmonic distortion plus noise of a recorded test tone.
+pub struct ThdMeasurement;
+
+impl ThdMeasurement {
+    /// THD+N of `samples` as a ratio (0.01 = 1 %): the power of harmonics 2·f0 to
+    /// 10·f0 plus everything else that is not the fundamental or DC, relative to
+    /// the fundamental, as an amplitude ratio. Harmonics above Nyquist are skipped.
+    /// NaN when `f0` is not below Nyquist or there is no fundamental to compare with.
+    pub fn compute(samples: &[f32], f0: f32, sample_rate: u32) -> f32 {
+        let n = samples.len();
+        let nyquist = sample_rate as f32 / 2.0;
+        if n < 4 || f0 <= 0.0 || f0 >= nyquist {
+            return f32::NAN;
+        }
+
+        let mut buffer: Vec<Complex<f32>> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                let w = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+                Complex::new(s * w, 0.0)
+            })
+            .collect();
+        FftPlanner::new().plan_fft_forward(n).process(&mut buffer);
+        let power: Vec<f64> = buffer[..n / 2].iter().map(|c| c.norm_sqr() as f64).collect();
+
+        let bin_hz = sample_rate as f32 / n as f32;
+        let lobe = |f: f32| {
+            let center = (f / bin_hz).round() as usize;
+            center.saturating_sub(LOBE_BINS)..(center + LOBE_BINS + 1).min(power.len())
+        };
+
+        // Each bin is counted once: DC leakage first, then the fundamental, then harmonics
+        let mut counted = vec![false; power.len()];
+        let mut take = |range: std::ops::Range<usize>| {
+            let mut sum = 0.0;
+            for bin in range {
+                if !std::mem::replace(&mut counted[bin], true) {
+                    sum += power[bin];
+                }
+            }
+            sum
+        };
+        take(0..LOBE_BINS.min(power.len()));
+        let fundamental = take(lobe(f0));
+        let harmonics: f64 = (2..=MAX_HARMONIC)
+            .map(|k| k as f32 * f0)
+            .filter(|&f| f < nyquist)
+            .map(|f| take(lobe(f)))
+            .sum();
+        let noise = take(0..power.len());
+
+        if fundamental <= 0.0 {
+            return f32::NAN;
+        }
+        ((harmonics + noise) / fundamental).sqrt() as f32
+    }
+}
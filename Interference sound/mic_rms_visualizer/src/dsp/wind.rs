@@ -0,0 +1,81 @@
+use super::biquad::{Biquad, BUTTERWORTH_4TH_Q};
+use super::spectrum::magnitude_spectrum_dbfs;
+
+const FRAME_LEN: usize = 2048;
+
+// Wind: more than half as much energy below 100 Hz as in 100–2000 Hz, with real peaks
+const LOW_BAND_HZ: f32 = 100.0;
+const HIGH_BAND_HZ: f32 = 2000.0;
+const ENERGY_RATIO: f32 = 0.5;
+const MIN_PEAK: f32 = 0.1;
+
+const CUTOFF_HZ: f32 = 120.0;
+const CROSSFADE_SECS: f32 = 0.05;
+
+/// Detects wind buffeting and fades in a 4th-order Butterworth high-pass while it lasts.
+#[derive(Default)]
+pub struct WindNoiseFilter {
+    /// Keep the high-pass in regardless of the detector.
+    pub force_on: bool,
+    high_pass: Option<[Biquad; 2]>,
+    frame: Vec<f32>,
+    frame_peak: f32,
+    detected: bool,
+    mix: f32,
+}
+
+impl WindNoiseFilter {
+    pub fn is_detected(&self) -> bool {
+        self.detected
+    }
+
+    /// How much of the filtered signal is currently mixed in (0 = bypassed).
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    pub fn process(&mut self, x: f32, sample_rate: u32) -> f32 {
+        let high_pass = self
+            .high_pass
+            .get_or_insert_with(|| BUTTERWORTH_4TH_Q.map(|q| Biquad::high_pass(sample_rate as f32, CUTOFF_HZ, q)));
+        let wet = high_pass.iter_mut().fold(x, |s, section| section.process(s));
+
+        self.frame.push(x);
+        self.frame_peak = self.frame_peak.max(x.abs());
+        if self.frame.len() >= FRAME_LEN {
+            self.detected = self.frame_peak > MIN_PEAK && low_band_ratio(&self.frame, sample_rate) > ENERGY_RATIO;
+            self.frame.clear();
+            self.frame_peak = 0.0;
+        }
+
+        let target = if self.detected || self.force_on { 1.0 } else { 0.0 };
+        let step = 1.0 / (CROSSFADE_SECS * sample_rate as f32);
+        self.mix = if self.mix < target {
+            (self.mix + step).min(target)
+        } else {
+            (self.mix - step).max(target)
+        };
+
+        x + (wet - x) * self.mix
+    }
+}
+
+// Energy below LOW_BAND_HZ relative to the energy from there up to HIGH_BAND_HZ
+fn low_band_ratio(samples: &[f32], sample_rate: u32) -> f32 {
+    let bin_hz = sample_rate as f32 / samples.len() as f32;
+    let (mut low, mut high) = (0.0, 0.0);
+    for (i, db) in magnitude_spectrum_dbfs(samples).into_iter().enumerate().skip(1) {
+        let f = i as f32 * bin_hz;
+        let power = 10f32.powf(db / 10.0);
+        if f < LOW_BAND_HZ {
+            low += power;
+        } else if f < HIGH_BAND_HZ {
+            high += power;
+        }
+    }
+    if high > 0.0 {
+        low / high
+    } else {
+        0.0
+    }
+}
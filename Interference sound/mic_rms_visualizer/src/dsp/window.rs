@@ -0,0 +1,105 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum WindowFunction {
+    #[default]
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    pub const ALL: [WindowFunction; 4] = [Self::Rectangular, Self::Hann, Self::Hamming, Self::Blackman];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rectangular => "Rectangular",
+            Self::Hann => "Hann",
+            Self::Hamming => "Hamming",
+            Self::Blackman => "Blackman",
+        }
+    }
+
+    /// Coefficient `i` of a symmetric window of length `len`.
+    pub fn coefficient(self, i: usize, len: usize) -> f32 {
+        if len < 2 {
+            return 1.0;
+        }
+        let phase = 2.0 * PI * i as f32 / (len - 1) as f32;
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.5 - 0.5 * phase.cos(),
+            Self::Hamming => 0.54 - 0.46 * phase.cos(),
+            Self::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+        }
+    }
+
+    /// Mean of the squared coefficients for a long window. Dividing the mean square
+    /// of a windowed signal by this keeps its RMS calibrated.
+    pub fn power_gain(self) -> f32 {
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.375,
+            Self::Hamming => 0.3974,
+            Self::Blackman => 0.3046,
+        }
+    }
+}
+
+pub fn apply_window(samples: &[f32], window: WindowFunction) -> Vec<f32> {
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| s * window.coefficient(i, samples.len()))
+        .collect()
+}
+
+/// RMS of `samples` after windowing, corrected by the window's power gain.
+pub fn windowed_rms(samples: &[f32], window: WindowFunction) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = apply_window(samples, window).iter().map(|s| s * s).sum();
+    (sum_sq / samples.len() as f32 / window.power_gain()).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_gain_matches_the_coefficients() {
+        let len = 4096;
+        for window in WindowFunction::ALL {
+            let mean_square = (0..len).map(|i| window.coefficient(i, len).powi(2)).sum::<f32>() / len as f32;
+            assert!((mean_square - window.power_gain()).abs() < 1e-3, "{}", window.name());
+        }
+    }
+
+    #[test]
+    fn hann_is_zero_at_the_ends_and_one_in_the_middle() {
+        let len = 9;
+        assert!(WindowFunction::Hann.coefficient(0, len).abs() < 1e-6);
+        assert!(WindowFunction::Hann.coefficient(len - 1, len).abs() < 1e-6);
+        assert!((WindowFunction::Hann.coefficient(len / 2, len) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn windowed_rms_of_a_sine_stays_calibrated() {
+        let amplitude = 0.5;
+        let samples: Vec<f32> = (0..4800).map(|i| amplitude * (2.0 * PI * 1000.0 * i as f32 / 48_000.0).sin()).collect();
+        let expected = amplitude / 2f32.sqrt();
+        for window in WindowFunction::ALL {
+            let rms = windowed_rms(&samples, window);
+            assert!((rms - expected).abs() / expected < 0.01, "{}: {}", window.name(), rms);
+        }
+    }
+
+    #[test]
+    fn windowed_rms_of_nothing_is_zero() {
+        assert_eq!(windowed_rms(&[], WindowFunction::Hann), 0.0);
+    }
+}
@@ -0,0 +1,324 @@
+//! Free-field / diffuse-field frequency response calibration of a measurement mic.
+//!
+//! Step 1 measures the room's RT60 with interrupted noise. Up to 200 ms the room is
+//! treated as anechoic and the free-field correction applies, above it the
+//! diffuse-field one. Step 2 measures the mic's response with a sweep, and step 3
+//! adds the field's correction table (IEC 61094-8, from `corrections/`). The
+//! corrected response is saved as a `FlatResponseCalibration`.
+//!
+//! The tables are `corrections/free_field.csv` and `corrections/diffuse_field.csv`,
+//! each a `frequency_hz,correction_db` CSV copied from the mic's calibration chart.
+//! They differ from mic to mic, so none are bundled.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::bands::{band_center, THIRD_OCTAVE_BANDS};
+use crate::dsp::flatness::RoomResponseMeter;
+use crate::dsp::rt60::DecayMeter;
+
+/// Directory of the correction tables, relative to the working directory.
+pub const CORRECTIONS_DIR: &str = "corrections";
+/// Rooms with a longer RT60 are not anechoic.
+pub const ANECHOIC_RT60_SECS: f32 = 0.2;
+
+const CORRECTION_HEADER: &str = "frequency_hz,correction_db";
+
+/// Band levels in dB, `None` for bands that were not measured.
+pub type BandLevels = [Option<f32>; THIRD_OCTAVE_BANDS];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SoundField {
+    Free,
+    Diffuse,
+}
+
+impl SoundField {
+    /// The field a measurement in a room with this RT60 is made in.
+    pub fn for_rt60(rt60_secs: f32) -> Self {
+        if rt60_secs > ANECHOIC_RT60_SECS {
+            SoundField::Diffuse
+        } else {
+            SoundField::Free
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundField::Free => "free-field",
+            SoundField::Diffuse => "diffuse-field",
+        }
+    }
+
+    /// `corrections/free_field.csv` or `corrections/diffuse_field.csv`.
+    pub fn correction_path(self) -> PathBuf {
+        let file = match self {
+            SoundField::Free => "free_field.csv",
+            SoundField::Diffuse => "diffuse_field.csv",
+        };
+        Path::new(CORRECTIONS_DIR).join(file)
+    }
+}
+
+/// Correction in dB against frequency, added to a measured response.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorrectionTable {
+    /// `(frequency Hz, dB)`, sorted by frequency.
+    pub points: Vec<(f32, f32)>,
+}
+
+impl CorrectionTable {
+    /// Reads a `frequency_hz,correction_db` CSV file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid correction table {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(CORRECTION_HEADER) {
+            return Err(anyhow!("Expected header \"{}\"", CORRECTION_HEADER));
+        }
+        let mut points = lines
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let parse = || -> Option<(f32, f32)> {
+                    let (f, db) = line.trim().split_once(',')?;
+                    let f: f32 = f.trim().parse().ok()?;
+                    (f > 0.0).then_some((f, db.trim().parse().ok()?))
+                };
+                parse().with_context(|| format!("Invalid row {}: \"{}\"", i + 2, line))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if points.is_empty() {
+            return Err(anyhow!("No rows"));
+        }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { points })
+    }
+
+    /// Correction at `frequency_hz`, interpolated on a log frequency scale and held
+    /// at the end values outside the table. 0 for an empty table.
+    pub fn correction_db(&self, frequency_hz: f32) -> f32 {
+        let (Some(&first), Some(&last)) = (self.points.first(), self.points.last()) else {
+            return 0.0;
+        };
+        if frequency_hz <= first.0 {
+            return first.1;
+        }
+        if frequency_hz >= last.0 {
+            return last.1;
+        }
+        let i = self.points.partition_point(|&(f, _)| f < frequency_hz);
+        let ((f0, db0), (f1, db1)) = (self.points[i - 1], self.points[i]);
+        db0 + (db1 - db0) * (frequency_hz / f0).ln() / (f1 / f0).ln()
+    }
+}
+
+/// Frequency response correction of a microphone from the field calibrator, stored
+/// as JSON. Adding it to a level measured with the mic gives a flat mic's level.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FlatResponseCalibration {
+    pub field: SoundField,
+    pub rt60_secs: f32,
+    /// Correction per one-third-octave band centre.
+    pub correction: CorrectionTable,
+}
+
+impl FlatResponseCalibration {
+    /// The correction that flattens `corrected`, a field-corrected response.
+    pub fn from_response(corrected: &BandLevels, field: SoundField, rt60_secs: f32) -> Self {
+        let mean = mean_level(corrected);
+        Self {
+            field,
+            rt60_secs,
+            correction: CorrectionTable {
+                points: corrected
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, level)| level.map(|level| (band_center(i), mean - level)))
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Cannot create {}", path.display()))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{} is not a flat response calibration", path.display()))
+    }
+
+    /// `levels` as a flat mic would have measured them.
+    pub fn apply(&self, levels: &BandLevels) -> BandLevels {
+        std::array::from_fn(|i| levels[i].map(|level| level + self.correction.correction_db(band_center(i))))
+    }
+}
+
+/// What the audio callback feeds while the calibrator measures: the raw mic input.
+#[derive(Clone, Debug)]
+pub enum FieldMeasurement {
+    Decay(DecayMeter),
+    Response(RoomResponseMeter),
+}
+
+impl FieldMeasurement {
+    pub fn process(&mut self, block: &[f32]) {
+        match self {
+            FieldMeasurement::Decay(meter) => meter.process(block),
+            FieldMeasurement::Response(meter) => meter.process(block),
+        }
+    }
+}
+
+/// The steps of the field calibrator. The UI plays the test signals and moves it
+/// along; the measurements come from `FieldMeasurement`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FieldCalibrator {
+    #[default]
+    Idle,
+    /// Step 1: noise is playing, then its decay is recorded.
+    MeasuringRt60,
+    /// Step 2: waiting for the sweep, or playing it.
+    Rt60Measured { rt60_secs: f32, field: SoundField },
+    /// Step 3: the response with and without the field's correction, both with the
+    /// overall level taken out.
+    Done {
+        rt60_secs: f32,
+        field: SoundField,
+        uncorrected: Box<BandLevels>,
+        corrected: Box<BandLevels>,
+    },
+}
+
+impl FieldCalibrator {
+    pub fn start(&mut self) {
+        *self = Self::MeasuringRt60;
+    }
+
+    pub fn cancel(&mut self) {
+        *self = Self::Idle;
+    }
+
+    /// Ends step 1 with the recorded decay; without a usable decay the calibrator
+    /// goes back to `Idle`.
+    pub fn finish_rt60(&mut self, meter: &DecayMeter) -> Result<()> {
+        let Some(rt60_secs) = meter.rt60_secs() else {
+            *self = Self::Idle;
+            return Err(anyhow!("The level did not decay by 25 dB; play the noise louder"));
+        };
+        *self = Self::Rt60Measured {
+            rt60_secs,
+            field: SoundField::for_rt60(rt60_secs),
+        };
+        Ok(())
+    }
+
+    /// Ends steps 2 and 3 with the band levels of the sweep. A missing correction
+    /// table leaves the calibrator at step 2.
+    pub fn finish_response(&mut self, levels: &BandLevels) -> Result<()> {
+        let Self::Rt60Measured { rt60_secs, field } = *self else {
+            return Err(anyhow!("The RT60 has not been measured"));
+        };
+        if levels.iter().all(Option::is_none) {
+            return Err(anyhow!("No band was inside the sweep"));
+        }
+        let table = CorrectionTable::load(&field.correction_path())?;
+        let mean = mean_level(levels);
+        let uncorrected = levels.map(|level| level.map(|level| level - mean));
+        let corrected = std::array::from_fn(|i| uncorrected[i].map(|level| level + table.correction_db(band_center(i))));
+        *self = Self::Done {
+            rt60_secs,
+            field,
+            uncorrected: Box::new(uncorrected),
+            corrected: Box::new(corrected),
+        };
+        Ok(())
+    }
+
+    /// The calibration to save once done.
+    pub fn calibration(&self) -> Option<FlatResponseCalibration> {
+        match self {
+            Self::Done {
+                rt60_secs,
+                field,
+                corrected,
+                ..
+            } => Some(FlatResponseCalibration::from_response(corrected, *field, *rt60_secs)),
+            _ => None,
+        }
+    }
+}
+
+fn mean_level(levels: &BandLevels) -> f32 {
+    let measured: Vec<f32> = levels.iter().flatten().copied().collect();
+    measured.iter().sum::<f32>() / measured.len().max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_field_follows_the_rt60() {
+        assert_eq!(SoundField::for_rt60(0.15), SoundField::Free);
+        assert_eq!(SoundField::for_rt60(0.6), SoundField::Diffuse);
+        assert!(SoundField::Diffuse.correction_path().ends_with("corrections/diffuse_field.csv"));
+    }
+
+    #[test]
+    fn corrections_are_interpolated_on_log_frequency() {
+        let table = CorrectionTable::parse("frequency_hz,correction_db\n1000,0\n4000,2.0\n\n100,-1\n").unwrap();
+        assert_eq!(table.points[0], (100.0, -1.0));
+        assert_eq!(table.correction_db(50.0), -1.0);
+        assert!((table.correction_db(2000.0) - 1.0).abs() < 1e-5);
+        assert_eq!(table.correction_db(10_000.0), 2.0);
+        assert!(CorrectionTable::parse("x,y\n1,2\n").is_err());
+        assert!(CorrectionTable::parse("frequency_hz,correction_db\n1000\n").is_err());
+    }
+
+    #[test]
+    fn a_calibration_flattens_the_response_it_came_from() {
+        let response: BandLevels = std::array::from_fn(|i| (i % 3 == 0).then_some(i as f32 * 0.5));
+        let calibration = FlatResponseCalibration::from_response(&response, SoundField::Free, 0.1);
+        let flat = calibration.apply(&response);
+        let first = flat.iter().flatten().next().copied().unwrap();
+        assert!(flat.iter().flatten().all(|&level| (level - first).abs() < 1e-4), "{:?}", flat);
+        assert_eq!(flat.iter().flatten().count(), response.iter().flatten().count());
+    }
+
+    #[test]
+    fn calibrations_round_trip() {
+        let path = std::env::temp_dir().join(format!("flat-response-{}.json", std::process::id()));
+        let calibration = FlatResponseCalibration {
+            field: SoundField::Diffuse,
+            rt60_secs: 0.4,
+            correction: CorrectionTable {
+                points: vec![(100.0, 1.5), (1000.0, -0.5)],
+            },
+        };
+        calibration.save(&path).unwrap();
+        let loaded = FlatResponseCalibration::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, calibration);
+    }
+
+    #[test]
+    fn a_response_needs_the_rt60_first() {
+        let mut calibrator = FieldCalibrator::Idle;
+        assert!(calibrator.finish_response(&[Some(0.0); THIRD_OCTAVE_BANDS]).is_err());
+        calibrator.start();
+        assert!(calibrator.finish_rt60(&DecayMeter::new(48_000)).is_err());
+        assert_eq!(calibrator, FieldCalibrator::Idle);
+    }
+}
@@ -0,0 +1,52 @@
+use std::ops::RangeInclusive;
+
+const GAS_CONSTANT: f32 = 8.314;
+
+// Physically plausible inputs: lighter than H₂ or heavier than SF₆-class gases are
+// typos, and an ideal gas has 1 < γ ≤ 5/3
+pub const MOLAR_MASS_RANGE: RangeInclusive<f32> = 1.0..=400.0;
+pub const GAMMA_RANGE: RangeInclusive<f32> = 1.01..=1.67;
+pub const TEMPERATURE_RANGE_K: RangeInclusive<f32> = 50.0..=2000.0;
+
+/// Name, molar mass (g/mol) and heat capacity ratio γ of common process gases.
+pub const GAS_PRESETS: [(&str, f32, f32); 5] = [
+    ("Air", 28.97, 1.400),
+    ("N₂", 28.01, 1.400),
+    ("CO₂", 44.01, 1.289),
+    ("CH₄", 16.04, 1.304),
+    ("H₂", 2.016, 1.405),
+];
+
+/// Ideal gas used to derive the speed of sound in media other than ambient air.
+#[derive(Clone, Copy, Debug)]
+pub struct GasConfig {
+    pub molar_mass_g_mol: f32,
+    pub gamma: f32,
+    pub temperature_k: f32,
+}
+
+impl GasConfig {
+    pub fn from_preset(index: usize, temperature_k: f32) -> Self {
+        let (_, molar_mass_g_mol, gamma) = GAS_PRESETS[index];
+        Self {
+            molar_mass_g_mol,
+            gamma,
+            temperature_k,
+        }
+    }
+
+    pub fn is_plausible(&self) -> bool {
+        MOLAR_MASS_RANGE.contains(&self.molar_mass_g_mol)
+            && GAMMA_RANGE.contains(&self.gamma)
+            && TEMPERATURE_RANGE_K.contains(&self.temperature_k)
+    }
+
+    /// c = √(γ·R·T / M) in m/s, or `None` if the parameters are out of range.
+    pub fn speed_of_sound(&self) -> Option<f32> {
+        if !self.is_plausible() {
+            return None;
+        }
+        let molar_mass_kg = self.molar_mass_g_mol / 1000.0;
+        Some((self.gamma * GAS_CONSTANT * self.temperature_k / molar_mass_kg).sqrt())
+    }
+}
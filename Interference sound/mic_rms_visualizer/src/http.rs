@@ -0,0 +1,84 @@
+//! Polling access to the current levels over HTTP.
+//!
+//! ```text
+//! $ curl http://localhost:3000/metrics
+//! {"rms":0.032,"amplitude":0.11,"peak_hold":0.15,"timestamp_ms":1714000000000}
+//! $ curl "http://localhost:3000/history?n=200"
+//! [0.031,0.033,...]
+//! ```
+
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// Values returned by /history when `n` is missing
+const DEFAULT_HISTORY_LEN: usize = 100;
+
+/// Levels at the time of the request, all linear full scale.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct HttpMetrics {
+    pub rms: f32,
+    pub amplitude: f32,
+    pub peak_hold: f32,
+    /// Unix time in milliseconds; filled in by the server.
+    pub timestamp_ms: i64,
+}
+
+/// Serves `GET /metrics` and `GET /history?n=N` on port `port` of every interface.
+/// `metrics` returns the current levels and `history` the last N block RMS values,
+/// oldest first. Both are called on the server thread, never from the audio callback.
+pub fn start_http_server<M, H>(port: u16, mut metrics: M, mut history: H) -> Result<()>
+where
+    M: FnMut() -> HttpMetrics + Send + 'static,
+    H: FnMut(usize) -> Vec<f32> + Send + 'static,
+{
+    // Bound here so a busy port is reported to the caller
+    let server = Server::http(("0.0.0.0", port)).map_err(|e| anyhow!("Cannot listen on port {}: {}", port, e))?;
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match route(&request, &mut metrics, &mut history) {
+                Ok(json) => Response::from_string(json).with_header(json_header()),
+                Err((code, message)) => Response::from_string(message).with_status_code(code),
+            };
+            if let Err(e) = request.respond(response) {
+                eprintln!("HTTP response failed: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+// JSON body, or (status code, message) for anything that is not a known GET
+fn route(
+    request: &Request,
+    metrics: &mut impl FnMut() -> HttpMetrics,
+    history: &mut impl FnMut(usize) -> Vec<f32>,
+) -> Result<String, (u16, &'static str)> {
+    if *request.method() != Method::Get {
+        return Err((405, "Only GET is supported"));
+    }
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let json = match path {
+        "/metrics" => serde_json::to_string(&HttpMetrics {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            ..metrics()
+        }),
+        "/history" => {
+            let n = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("n="))
+                .map_or(Ok(DEFAULT_HISTORY_LEN), str::parse)
+                .map_err(|_| (400, "n must be a non-negative integer"))?;
+            serde_json::to_string(&history(n))
+        }
+        _ => return Err((404, "Not found")),
+    };
+    json.map_err(|_| (500, "Cannot encode the response"))
+}
+
+fn json_header() -> Header {
+    Header::from_bytes("Content-Type", "application/json").expect("static header is valid")
+}
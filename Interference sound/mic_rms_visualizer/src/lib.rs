@@ -0,0 +1,21 @@
+pub mod air;
+pub mod ascii;
+pub mod audio;
+pub mod calibration;
+pub mod capture;
+pub mod config;
+pub mod device;
+pub mod dsp;
+pub mod field_calibration;
+pub mod gas;
+pub mod http;
+pub mod osc;
+pub mod recording;
+pub mod report;
+pub mod ring;
+pub mod room;
+pub mod screenshot;
+pub mod session;
+pub mod stream_guard;
+pub mod widgets;
+pub mod ws;
@@ -1,112 +1,4296 @@
+use clap::Parser;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crossbeam::channel;
 use std::{
     collections::VecDeque,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 // Needed for plotting
-use egui_plot::{Line, Plot, PlotPoints, PlotBounds};
+use egui_plot::{
+    AxisHints, Bar, BarChart, HLine, HPlacement, Legend, Line, LineStyle, Plot, PlotBounds, PlotImage, PlotPoint,
+    PlotPoints, Points, Polygon, Text, VLine,
+};
 
-#[derive(Default)]
-struct AudioData {
-    samples: VecDeque<f32>,
-    rms: f32,
-    amplitude: f32,
+use mic_rms_visualizer::air::speed_of_sound;
+use mic_rms_visualizer::ascii::render_ascii_waveform;
+use mic_rms_visualizer::audio::{
+    build_input_stream_dynamic, latest_n_samples, AudioData, CallbackStats, SingleShot, TapState, ENVELOPE_BLOCKS,
+    OVERSIZED_CALLBACK_FRAMES,
+};
+use mic_rms_visualizer::calibration::{calibrated_level, CalibrationWizard, CALIBRATOR_DBSPL, CALIBRATOR_HZ};
+use mic_rms_visualizer::capture::{capture_to_file, CaptureHandle};
+use mic_rms_visualizer::config::{Config, DisplayMode};
+use mic_rms_visualizer::device::{find_input_device, input_capabilities, input_config, DeviceCapabilities};
+use mic_rms_visualizer::dsp::aweighting::AWeightingFilter;
+use mic_rms_visualizer::dsp::cepstrum::{find_echo_peaks, real_cepstrum};
+use mic_rms_visualizer::dsp::drums::{Drum, BEATS_PER_BAR};
+use mic_rms_visualizer::dsp::feedback::MAX_NOTCHES;
+use mic_rms_visualizer::dsp::bands::{band_label, THIRD_OCTAVE_BANDS};
+use mic_rms_visualizer::dsp::filter::FilterKind;
+use mic_rms_visualizer::dsp::flatness::{FrequencyResponseFlatness, RoomResponseMeter, TargetCurve};
+use mic_rms_visualizer::dsp::goertzel::{ToneDetector, ToneEvent, MAX_DETECTORS};
+use mic_rms_visualizer::dsp::heatmap::{
+    WaveformHeatmap, COLUMNS as HEATMAP_COLUMNS, HISTORY_SECS as HEATMAP_SECS, ROWS as HEATMAP_ROWS,
+};
+use mic_rms_visualizer::dsp::leq::combined_leq;
+use mic_rms_visualizer::dsp::levels::{
+    channel_rms, mix_down_unsilenced, silence_channels, zero_crossing_rate, ChannelSoloMute,
+};
+use mic_rms_visualizer::dsp::peq::{parse_rew_filters, PeqFilter, PeqKind};
+use mic_rms_visualizer::dsp::pitch::{detect_pitch, note_name};
+use mic_rms_visualizer::dsp::replaygain;
+use mic_rms_visualizer::dsp::resonance::{find_resonance, Resonance};
+use mic_rms_visualizer::dsp::rms::sum_of_squares;
+use mic_rms_visualizer::dsp::rt60::DecayMeter;
+use mic_rms_visualizer::dsp::sel::SoundExposure;
+use mic_rms_visualizer::dsp::silence::SilenceEvent;
+use mic_rms_visualizer::dsp::spectral_gate::FrequencyDomainNoiseGate;
+use mic_rms_visualizer::dsp::spectral_subtraction::{SpectralSubtraction, ALPHA_RANGE};
+use mic_rms_visualizer::dsp::test_tone::{SweepParams, TestToneGenerator};
+use mic_rms_visualizer::dsp::spectrum::magnitude_spectrum_dbfs;
+use mic_rms_visualizer::dsp::thd::ThdMeasurement;
+use mic_rms_visualizer::dsp::window::{windowed_rms, WindowFunction};
+use mic_rms_visualizer::field_calibration::{
+    BandLevels, FieldCalibrator, FieldMeasurement, FlatResponseCalibration, SoundField,
+};
+use mic_rms_visualizer::gas::{GasConfig, GAMMA_RANGE, GAS_PRESETS, MOLAR_MASS_RANGE, TEMPERATURE_RANGE_K};
+use mic_rms_visualizer::http::{start_http_server, HttpMetrics};
+use mic_rms_visualizer::osc::{start_osc_sender, OscMetrics};
+use mic_rms_visualizer::recording::{
+    backup_path, normalization_gain, read_wav, write_replay_gain_tags, write_wav_redundant, Redundancy,
+};
+use mic_rms_visualizer::ring::spawn_block_processor;
+use mic_rms_visualizer::screenshot::ScreenshotExporter;
+use mic_rms_visualizer::stream_guard::{AudioStreamGuard, StreamErrorFlag, StreamStatus, WATCH_INTERVAL};
+use mic_rms_visualizer::widgets::vu_meter::VuMeter;
+use mic_rms_visualizer::ws::{downsample, start_ws_server, NetworkSim, WsFrame, MAX_FRAME_SAMPLES};
+
+// Software gain on the input, after the ADC; past the warning level clipping turns the slider red
+const DIGITAL_GAIN_RANGE: std::ops::RangeInclusive<f32> = -20.0..=40.0;
+const DIGITAL_GAIN_WARN_DB: f32 = 20.0;
+
+// How long the CLIP badge stays lit after the last clipped block
+const CLIP_BADGE_SECS: f32 = 0.5;
+
+// How long the banner stays up after the callback length changed
+const CALLBACK_CHANGE_BANNER_SECS: f32 = 2.0;
+
+// Pitch detection frame, and the YIN confidence below which no pitch is shown
+const PITCH_FRAME_LEN: usize = 2048;
+const PITCH_MIN_CONFIDENCE: f32 = 0.8;
+
+// How long the beat panel flashes after an onset
+const ONSET_FLASH_SECS: f32 = 0.15;
+
+// Step of the beat grid phase nudge (left/right arrow keys)
+const BEAT_NUDGE_MS: f32 = 10.0;
+
+// Block RMS values kept for GET /history
+const RMS_HISTORY_LEN: usize = 1000;
+
+// Length of the quiet period measured by "Calibrate noise floor"
+const NOISE_CALIBRATION_SECS: f32 = 3.0;
+
+// Samples kept for the waveform plot, adjustable in the UI (shown in ms)
+const BUFFER_LEN_RANGE: std::ops::RangeInclusive<usize> = 100..=4000;
+
+// dBFS display: silence is clamped to the 16-bit floor, the plot shows the top 60 dB
+const DBFS_FLOOR: f32 = -96.0;
+const DBFS_PLOT_MIN: f64 = -60.0;
+const DBFS_REFERENCES: [f64; 2] = [-20.0, -6.0];
+
+// Linear waveform Y range: auto-scale follows the peak hold with 10 % headroom,
+// never narrower than ±AUTO_SCALE_MIN so silence does not blow up the noise
+const AUTO_SCALE_HEADROOM: f64 = 1.1;
+const AUTO_SCALE_MIN: f64 = 0.001;
+const Y_LIMIT: f64 = 2.0;
+
+// Terminal size used by --ascii mode
+const ASCII_WIDTH: usize = 100;
+const ASCII_HEIGHT: usize = 20;
+
+// Sample pairs shown on the goniometer / Lissajous plot, drawn in age groups that fade out
+const STEREO_PAIRS: usize = 512;
+const GONIOMETER_FADE_STEPS: usize = 8;
+const DEFAULT_LISSAJOUS_PAIRS: usize = 1000;
+const MAX_STEREO_PAIRS: usize = 4000;
+
+// Normalization preview: -3 dBFS peak, and the gain above which the input is
+// probably (near) silence
+const NORMALIZE_TARGET_PEAK: f32 = 0.708;
+const NORMALIZE_WARN_DB: f32 = 20.0;
+
+// Recordings are kept in memory; past this length the UI warns about memory use
+const RECORDING_WARN_SECS: f32 = 60.0;
+
+// Events shown in the SEL table
+const SEL_TABLE_LEN: usize = 10;
+
+// Input the callback can queue ahead of the processing thread
+const PROCESS_RING_SECS: usize = 1;
+
+// Length of the ring-down captured after a tap
+const TAP_CAPTURE_SECS: f32 = 0.5;
+
+// Samples before the trigger point kept by a single-shot capture or a triggered sweep
+const PRE_TRIGGER_RANGE: std::ops::RangeInclusive<usize> = 0..=200;
+const DEFAULT_TRIGGER_PRE: usize = 50;
+
+// The trigger level line can be grabbed this close to it, as a fraction of the plot height
+const TRIGGER_GRAB_FRACTION: f64 = 0.03;
+
+// Field calibrator noise burst, and how long its decay is recorded after it stops
+const FIELD_NOISE_SECS: f32 = 3.0;
+const FIELD_NOISE_AMPLITUDE: f32 = 0.5;
+const FIELD_DECAY_SECS: f32 = 2.0;
+
+// Trigger mode as shown by its LED
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum TriggerState {
+    Idle,
+    // Waiting for the first trigger
+    Armed,
+    // A new sweep was captured this frame
+    Triggered,
+    // No trigger this frame; the last sweep stays on screen
+    Holding,
+}
+
+impl TriggerState {
+    fn color(self) -> egui::Color32 {
+        match self {
+            TriggerState::Idle => egui::Color32::GRAY,
+            TriggerState::Armed => egui::Color32::YELLOW,
+            TriggerState::Triggered => egui::Color32::GREEN,
+            TriggerState::Holding => egui::Color32::RED,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TriggerState::Idle => "Idle",
+            TriggerState::Armed => "Armed",
+            TriggerState::Triggered => "Triggered",
+            TriggerState::Holding => "Holding",
+        }
+    }
+}
+
+// Propagation medium used for every delay <-> distance conversion
+#[derive(Clone, Copy, PartialEq)]
+enum Medium {
+    HumidAir,
+    Preset(usize),
+    CustomGas,
+}
+
+fn to_dbfs(x: f32) -> f32 {
+    (20.0 * x.log10()).max(DBFS_FLOOR)
+}
+
+#[derive(Parser)]
+#[command(about = "Live microphone waveform and level visualizer")]
+struct Args {
+    /// Input device, matched as a case-insensitive substring of its name
+    #[arg(long)]
+    device: Option<String>,
+    /// Sample rate in Hz; the device default is used if omitted
+    #[arg(long)]
+    sample_rate: Option<u32>,
+    /// Draw the waveform in the terminal instead of opening a window
+    #[arg(long)]
+    ascii: bool,
+    /// Stream levels and the waveform as JSON to WebSocket clients on this port
+    /// (see assets/index.html)
+    #[arg(long)]
+    ws_port: Option<u16>,
+    /// Debug: drop WebSocket frames with this probability (0.0-1.0)
+    #[arg(long, default_value_t = 0.0, value_parser = parse_drop_rate)]
+    ws_drop_rate: f32,
+    /// Debug: delay every WebSocket frame by this many milliseconds
+    #[arg(long, default_value_t = 0)]
+    ws_delay_ms: u64,
+    /// Serve GET /metrics and GET /history?n=N as JSON on this port
+    #[arg(long)]
+    http_port: Option<u16>,
+}
+
+fn parse_drop_rate(text: &str) -> Result<f32, String> {
+    let rate: f32 = text.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(format!("{} is not between 0.0 and 1.0", rate))
+    }
 }
 
 fn main() -> Result<(), eframe::Error> {
+    let args = Args::parse();
+    let settings = Config::load().unwrap_or_else(|e| {
+        eprintln!("Warning: {:#}; using default settings", e);
+        Config::default()
+    });
+
+    // Bad arguments are reported here rather than inside the audio thread. A saved
+    // device that has gone away falls back to the default one.
+    let host = cpal::default_host();
+    let device = match (&args.device, &settings.device) {
+        (Some(query), _) => find_input_device(&host, Some(query)),
+        (None, Some(saved)) => find_input_device(&host, Some(saved)).or_else(|e| {
+            eprintln!("Warning: saved device unavailable ({:#}); using the default input", e);
+            find_input_device(&host, None)
+        }),
+        (None, None) => find_input_device(&host, None),
+    };
+    let initial = device
+        .and_then(|device| {
+            let config = input_config(&device, args.sample_rate)?;
+            Ok((device, config))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        });
+
     let data = Arc::new(Mutex::new(AudioData::default()));
-    start_audio_thread(Arc::clone(&data));
+    // Device switch requests from the UI, by device name. Kept alive for the whole
+    // run; the audio thread exits when it is dropped.
+    let (device_sender, device_receiver) = channel::unbounded::<String>();
+    let buffer_len = Arc::new(AtomicUsize::new(
+        settings.buffer_size.clamp(*BUFFER_LEN_RANGE.start(), *BUFFER_LEN_RANGE.end()),
+    ));
+    {
+        let mut data = data.lock().unwrap();
+        data.rms_smoother.tau_ms = settings.tau_ms;
+        data.digital_gain_db = settings.gain_db.clamp(*DIGITAL_GAIN_RANGE.start(), *DIGITAL_GAIN_RANGE.end());
+        data.set_envelope_block(settings.envelope_block);
+    }
+    let stream_status = Arc::new(Mutex::new(StreamStatus::Running));
+    start_audio_thread(
+        Arc::clone(&data),
+        device_receiver,
+        Arc::clone(&buffer_len),
+        Arc::clone(&stream_status),
+        initial,
+    );
+
+    let ws_sim = NetworkSim {
+        drop_rate: args.ws_drop_rate,
+        delay: Duration::from_millis(args.ws_delay_ms),
+    };
+    if let Some(port) = args.ws_port {
+        let ws_data = Arc::clone(&data);
+        let started = start_ws_server(port, ws_sim, move || {
+            let data = ws_data.lock().unwrap();
+            WsFrame {
+                rms: data.rms,
+                amplitude: data.amplitude,
+                samples: downsample(data.samples.iter().copied(), MAX_FRAME_SAMPLES),
+            }
+        });
+        if let Err(e) = started {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(port) = args.http_port {
+        let metrics_data = Arc::clone(&data);
+        let history_data = Arc::clone(&data);
+        let started = start_http_server(
+            port,
+            move || {
+                let data = metrics_data.lock().unwrap();
+                HttpMetrics {
+                    rms: data.rms,
+                    amplitude: data.amplitude,
+                    peak_hold: data.peak_hold,
+                    ..HttpMetrics::default()
+                }
+            },
+            move |n| {
+                let data = history_data.lock().unwrap();
+                let skip = data.rms_history.len().saturating_sub(n);
+                data.rms_history.iter().skip(skip).copied().collect()
+            },
+        );
+        if let Err(e) = started {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    if args.ascii {
+        run_ascii(&data);
+    }
 
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "🎧 Mic Visualizer",
         native_options,
-        Box::new(|_cc| Box::new(AppState { data })),
+        Box::new(move |_cc| {
+            let mut app = AppState::new(data, device_sender, buffer_len, stream_status, &settings);
+            app.ws_sim = Some(ws_sim).filter(|sim| args.ws_port.is_some() && sim.is_active());
+            Box::new(app)
+        }),
     )
 }
 
+// Terminal-only mode: redraw the waveform in place at 10 Hz
+fn run_ascii(data: &Mutex<AudioData>) -> ! {
+    print!("\x1b[2J");
+    loop {
+        let frame = render_ascii_waveform(&data.lock().unwrap().samples, ASCII_WIDTH, ASCII_HEIGHT);
+        print!("\x1b[H{}", frame);
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Modal file dialogs requested by a panel. They run once the AudioData lock is
+// released, since the audio callback would wait for the lock while one is open.
+enum PendingDialog {
+    SaveRecording {
+        samples: Vec<f32>,
+        channels: u16,
+        sample_rate: u32,
+        // Normalize to NORMALIZE_TARGET_PEAK on export, as previewed
+        normalize: bool,
+    },
+    StartCapture(cpal::SupportedStreamConfig),
+    ExportToneEvents(Vec<ToneEvent>),
+    // The SEL events live in AppState
+    ExportSelEvents,
+    ExportSilenceLog,
+    SaveDrumPattern(String),
+    ImportPeq,
+    PickBackupFolder,
+    SaveFlatResponseCalibration(FlatResponseCalibration),
+    LoadMicCalibration,
+}
+
+// What the waveform plot draws, copied out of AudioData so the plot is built after the
+// lock is released and the processing thread can run meanwhile
+#[derive(Default)]
+struct WaveformSnapshot {
+    samples: Vec<f32>,
+    samples_written: u64,
+    envelope: Vec<f32>,
+    envelope_block: usize,
+    // Samples not yet in an envelope block
+    envelope_pending: usize,
+    zcr_history: Vec<(u64, f32)>,
+    clip_positions: Vec<u64>,
+    // Empty unless the channels are shown
+    channel_samples: Vec<Vec<f32>>,
+    peak_hold: f32,
+}
+
+impl WaveformSnapshot {
+    // Keeps the allocations of the previous frame
+    fn copy_from(&mut self, data: &AudioData, with_channels: bool) {
+        self.samples.clear();
+        self.samples.extend(&data.samples);
+        self.samples_written = data.samples_written;
+        self.envelope.clear();
+        self.envelope.extend(&data.envelope);
+        self.envelope_block = data.envelope_block;
+        self.envelope_pending = data.envelope_pending.1;
+        self.zcr_history.clear();
+        self.zcr_history.extend(&data.zcr_history);
+        self.clip_positions.clear();
+        self.clip_positions.extend(&data.clip_positions);
+        let channels = if with_channels { data.channel_samples.len() } else { 0 };
+        self.channel_samples.resize_with(channels, Vec::new);
+        for (copy, ring) in self.channel_samples.iter_mut().zip(&data.channel_samples) {
+            copy.clear();
+            copy.extend(ring);
+        }
+        self.peak_hold = data.peak_hold;
+    }
+}
+
+struct LeqPeriod {
+    start: chrono::DateTime<chrono::Local>,
+    end: chrono::DateTime<chrono::Local>,
+    duration_secs: f32,
+    leq_dbfs: f32,
+    // Calibration in effect when the period ended
+    sensitivity_correction_db: Option<f32>,
+}
+
+// Temperature and humidity from the status bar, logged next to measurements
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AirConditions {
+    temperature_c: f32,
+    humidity_pct: f32,
+}
+
+struct SelEvent {
+    timestamp: chrono::DateTime<chrono::Local>,
+    exposure: SoundExposure,
+    air: AirConditions,
+    // Calibration in effect when the event was measured
+    sensitivity_correction_db: Option<f32>,
+}
+
+impl SelEvent {
+    // SEL and peak as reported, and their unit
+    fn levels(&self) -> (f32, f32, &'static str) {
+        let (sel, unit) = calibrated_level(self.exposure.sel_dbfs, self.sensitivity_correction_db);
+        let (peak, _) = calibrated_level(self.exposure.peak_dbfs, self.sensitivity_correction_db);
+        (sel, peak, unit)
+    }
+}
+
 struct AppState {
     data: Arc<Mutex<AudioData>>,
+    device_sender: channel::Sender<String>,
+    // Length of `samples` and the channel rings, read by the audio callback
+    buffer_len: Arc<AtomicUsize>,
+    stream_status: Arc<Mutex<StreamStatus>>,
+    device_names: Vec<String>,
+    // Capabilities of the open device, queried again when the device changes
+    device_info: Option<(String, Result<DeviceCapabilities, String>)>,
+    tap_threshold: f32,
+    tap_key_held: bool,
+    taps: Vec<Resonance>,
+    show_heatmap: bool,
+    show_derivative: bool,
+    show_beat_grid: bool,
+    // Added to the onset anchor of the beat grid
+    beat_phase_ms: f32,
+    preview_normalized: bool,
+    show_dbfs: bool,
+    show_zcr: bool,
+    // Linear waveform Y bounds; `y_min` / `y_max` apply in fixed-scale mode
+    auto_scale: bool,
+    y_min: f64,
+    y_max: f64,
+    // Plot Ch1 against Ch2 instead of the M/S goniometer
+    show_lissajous: bool,
+    lissajous_pairs: usize,
+    trigger_enabled: bool,
+    trigger_level: f32,
+    // Last triggered sweep, held while no new trigger is found; the trigger point is
+    // `trigger_pre` samples into it
+    trigger_trace: Vec<f32>,
+    trigger_pre: usize,
+    trigger_state: TriggerState,
+    // The trigger level line is being dragged on the plot
+    trigger_level_dragged: bool,
+    // Single shot: the plot shows `single_shot_trace`, frozen while armed
+    single_shot_enabled: bool,
+    single_shot_level: f32,
+    pre_trigger: usize,
+    single_shot_trace: Vec<f32>,
+    show_channels: bool,
+    solo_mute: ChannelSoloMute,
+    peak_half_life_secs: f32,
+    rms_window: WindowFunction,
+    heatmap_texture: Option<egui::TextureHandle>,
+    // Reused between frames; see WaveformSnapshot
+    waveform: WaveformSnapshot,
+    lifter_ms: f32,
+    tone_status: Option<String>,
+    leq_duration_secs: u32,
+    leq_started: Option<chrono::DateTime<chrono::Local>>,
+    onset_flash: Option<Instant>,
+    drum_flash: Option<(Drum, Instant)>,
+    drum_status: Option<String>,
+    noise_floor_rms: f32,
+    subtract_noise_floor: bool,
+    noise_calibration_started: Option<Instant>,
+    // dBFS to dBSPL offset found with a calibrator; None shows plain dBFS
+    sensitivity_correction_db: Option<f32>,
+    calibration_status: Option<String>,
+    leq_periods: Vec<LeqPeriod>,
+    sel_events: Vec<SelEvent>,
+    sel_status: Option<String>,
+    silence_log: Vec<SilenceEvent>,
+    silence_status: Option<String>,
+    peq_filters: Vec<PeqFilter>,
+    peq_status: Option<String>,
+    recording_status: Option<String>,
+    // Write ReplayGain tags into finished recordings and captures
+    replay_gain_tags: bool,
+    pending_dialog: Option<PendingDialog>,
+    capture_handle: Option<CaptureHandle>,
+    capture_thread: Option<thread::JoinHandle<anyhow::Result<Redundancy>>>,
+    // Writer of a stopped capture, joined once the AudioData lock is released
+    stopped_capture: Option<thread::JoinHandle<anyhow::Result<Redundancy>>>,
+    capture_started: Option<Instant>,
+    capture_status: Option<String>,
+    capture_path: Option<std::path::PathBuf>,
+    // Written capture waiting to be measured once the AudioData lock is released
+    finished_capture: Option<(std::path::PathBuf, Redundancy)>,
+    // Second copy of recordings and captures, and how the last one went
+    redundant_path: Option<std::path::PathBuf>,
+    redundancy_status: Option<String>,
+    osc_enabled: bool,
+    osc_host: String,
+    osc_port: u16,
+    osc_status: Option<String>,
+    mic_spacing_cm: f32,
+    temperature_c: f32,
+    humidity_pct: f32,
+    medium: Medium,
+    gas: GasConfig,
+    sweep: SweepParams,
+    // Output stream of the sweep being played; dropping it stops playback
+    sweep_stream: Option<cpal::Stream>,
+    // Set by the output callback once the sweep has played to the end
+    sweep_finished: Arc<AtomicBool>,
+    sweep_status: Option<String>,
+    // THD+N at the end frequency, measured on the input while the sweep plays
+    sweep_thd: Option<f32>,
+    // Measure the band levels of the input during the next sweep
+    measure_room: bool,
+    room_target: TargetCurve,
+    // Band levels of the last room sweep, and of the one before the suggested EQ was applied
+    room_levels: Option<[Option<f32>; THIRD_OCTAVE_BANDS]>,
+    room_before: Option<[Option<f32>; THIRD_OCTAVE_BANDS]>,
+    room_status: Option<String>,
+    // Applied to the room levels before they are compared with the target
+    mic_calibration: Option<FlatResponseCalibration>,
+    field_calibrator: FieldCalibrator,
+    // Output stream of the calibrator's noise burst or sweep, and its end flag
+    field_stream: Option<cpal::Stream>,
+    field_finished: Arc<AtomicBool>,
+    // When the noise burst ended; its decay is recorded for FIELD_DECAY_SECS from here
+    field_decay_started: Option<Instant>,
+    field_status: Option<String>,
+    screenshots: ScreenshotExporter,
+    // Simulated WebSocket drops and delay, shown in the status bar while active
+    ws_sim: Option<NetworkSim>,
 }
 
-impl eframe::App for AppState {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("🎙 Live Microphone Input");
+impl AppState {
+    fn new(
+        data: Arc<Mutex<AudioData>>,
+        device_sender: channel::Sender<String>,
+        buffer_len: Arc<AtomicUsize>,
+        stream_status: Arc<Mutex<StreamStatus>>,
+        settings: &Config,
+    ) -> Self {
+        let mut state = Self {
+            data,
+            device_sender,
+            buffer_len,
+            stream_status,
+            device_names: input_device_names(),
+            device_info: None,
+            tap_threshold: 0.2,
+            tap_key_held: false,
+            taps: Vec::new(),
+            show_heatmap: false,
+            show_derivative: false,
+            show_beat_grid: false,
+            beat_phase_ms: 0.0,
+            preview_normalized: false,
+            show_dbfs: settings.display_mode == DisplayMode::Dbfs,
+            show_zcr: false,
+            auto_scale: false,
+            y_min: -0.1,
+            y_max: 0.1,
+            show_lissajous: false,
+            lissajous_pairs: DEFAULT_LISSAJOUS_PAIRS,
+            trigger_enabled: settings.trigger_enabled,
+            trigger_level: 0.0,
+            trigger_trace: Vec::new(),
+            trigger_pre: DEFAULT_TRIGGER_PRE,
+            trigger_state: TriggerState::Idle,
+            trigger_level_dragged: false,
+            single_shot_enabled: false,
+            single_shot_level: 0.1,
+            pre_trigger: 50,
+            single_shot_trace: Vec::new(),
+            show_channels: false,
+            solo_mute: ChannelSoloMute::default(),
+            peak_half_life_secs: 1.0,
+            rms_window: settings.window,
+            heatmap_texture: None,
+            waveform: WaveformSnapshot::default(),
+            lifter_ms: 0.5,
+            tone_status: None,
+            leq_duration_secs: 60,
+            leq_started: None,
+            onset_flash: None,
+            drum_flash: None,
+            drum_status: None,
+            noise_floor_rms: 0.0,
+            subtract_noise_floor: false,
+            noise_calibration_started: None,
+            sensitivity_correction_db: settings.sensitivity_correction_db,
+            calibration_status: None,
+            leq_periods: Vec::new(),
+            sel_events: Vec::new(),
+            sel_status: None,
+            silence_log: Vec::new(),
+            silence_status: None,
+            peq_filters: Vec::new(),
+            peq_status: None,
+            recording_status: None,
+            replay_gain_tags: false,
+            pending_dialog: None,
+            capture_handle: None,
+            capture_thread: None,
+            stopped_capture: None,
+            capture_started: None,
+            capture_status: None,
+            capture_path: None,
+            finished_capture: None,
+            redundant_path: settings.redundant_path.clone(),
+            redundancy_status: None,
+            osc_enabled: false,
+            osc_host: settings.osc_host.clone(),
+            osc_port: settings.osc_port,
+            osc_status: None,
+            mic_spacing_cm: 2.0,
+            temperature_c: 20.0,
+            humidity_pct: 50.0,
+            medium: Medium::HumidAir,
+            gas: GasConfig::from_preset(0, 293.15),
+            sweep: SweepParams::default(),
+            sweep_stream: None,
+            sweep_finished: Arc::new(AtomicBool::new(false)),
+            sweep_status: None,
+            sweep_thd: None,
+            measure_room: false,
+            room_target: TargetCurve::default(),
+            room_levels: None,
+            room_before: None,
+            room_status: None,
+            mic_calibration: None,
+            field_calibrator: FieldCalibrator::default(),
+            field_stream: None,
+            field_finished: Arc::new(AtomicBool::new(false)),
+            field_decay_started: None,
+            field_status: None,
+            screenshots: ScreenshotExporter::default(),
+            ws_sim: None,
+        };
+        let data = Arc::clone(&state.data);
+        state.set_osc_enabled(settings.osc_enabled, &mut data.lock().unwrap());
+        state
+    }
 
-            let data = self.data.lock().unwrap();
-            ui.label(format!(
-                "RMS: {:.4} | Amplitude: {:.4}",
-                data.rms, data.amplitude
-            ));
+    // Used for every delay <-> distance conversion
+    // Implausible gas parameters fall back to humid air
+    fn speed_of_sound(&self) -> f32 {
+        match self.medium {
+            Medium::HumidAir => None,
+            Medium::Preset(_) | Medium::CustomGas => self.gas.speed_of_sound(),
+        }
+        .unwrap_or_else(|| speed_of_sound(self.temperature_c, self.humidity_pct))
+    }
 
-            let plot = Plot::new("audio_plot")
-                .view_aspect(2.0)
-                .allow_scroll(false)
-                .allow_zoom(false);
+    fn air_conditions(&self) -> AirConditions {
+        AirConditions {
+            temperature_c: self.temperature_c,
+            humidity_pct: self.humidity_pct,
+        }
+    }
 
-            plot.show(ui, |plot_ui| {
-                // Set fixed plot bounds
-                plot_ui.set_plot_bounds(PlotBounds::from_min_max(
-                    [0.0, -0.1],   // X min, Y min
-                    [500.0, 0.1],  // X max, Y max
-                ));
+    fn medium_name(&self) -> &'static str {
+        match self.medium {
+            Medium::HumidAir => "humid air",
+            Medium::Preset(i) => GAS_PRESETS[i].0,
+            Medium::CustomGas => "custom gas",
+        }
+    }
 
-                let points: PlotPoints = data
-                    .samples
-                    .iter()
-                    .enumerate()
-                    .map(|(i, &s)| [i as f64, s as f64])
-                    .collect();
+    fn status_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let previous = self.medium;
+                egui::ComboBox::from_label("Medium")
+                    .selected_text(self.medium_name())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.medium, Medium::HumidAir, "humid air");
+                        for (i, (name, _, _)) in GAS_PRESETS.iter().enumerate() {
+                            ui.selectable_value(&mut self.medium, Medium::Preset(i), *name);
+                        }
+                        ui.selectable_value(&mut self.medium, Medium::CustomGas, "custom gas");
+                    });
+                if self.medium != previous {
+                    if let Medium::Preset(i) = self.medium {
+                        self.gas = GasConfig::from_preset(i, self.gas.temperature_k);
+                    }
+                }
+
+                match self.medium {
+                    Medium::HumidAir => {
+                        ui.label("Temperature:");
+                        ui.add(egui::DragValue::new(&mut self.temperature_c).clamp_range(-30.0..=50.0).speed(0.1).suffix(" °C"));
+                        ui.label("Humidity:");
+                        ui.add(egui::DragValue::new(&mut self.humidity_pct).clamp_range(0.0..=100.0).speed(0.5).suffix(" %"));
+                    }
+                    Medium::Preset(_) | Medium::CustomGas => {
+                        let custom = self.medium == Medium::CustomGas;
+                        ui.label("M:");
+                        ui.add_enabled(custom, egui::DragValue::new(&mut self.gas.molar_mass_g_mol).clamp_range(MOLAR_MASS_RANGE).speed(0.1).suffix(" g/mol"));
+                        ui.label("γ:");
+                        ui.add_enabled(custom, egui::DragValue::new(&mut self.gas.gamma).clamp_range(GAMMA_RANGE).speed(0.001));
+                        ui.label("T:");
+                        ui.add(egui::DragValue::new(&mut self.gas.temperature_k).clamp_range(TEMPERATURE_RANGE_K).speed(0.5).suffix(" K"));
+                        if !self.gas.is_plausible() {
+                            ui.colored_label(egui::Color32::RED, "Implausible gas parameters - using humid air");
+                        }
+                    }
+                }
 
-                plot_ui.line(Line::new(points));
+                ui.separator();
+                ui.label(format!("Sound speed ({}): {:.1} m/s", self.medium_name(), self.speed_of_sound()));
+
+                let (wind_detected, wind_mix) = {
+                    let data = self.data.lock().unwrap();
+                    (data.wind_enabled && data.wind.is_detected(), data.wind.mix())
+                };
+                if wind_detected {
+                    // Wave icon that drifts while the filter is engaged
+                    let phase = (ui.input(|i| i.time) * 4.0) as usize % 3;
+                    let waves = ["∿  ", " ∿ ", "  ∿"][phase];
+                    ui.separator();
+                    ui.colored_label(
+                        egui::Color32::LIGHT_BLUE,
+                        format!("🌬{} Wind noise detected (HP {:.0}%)", waves, wind_mix * 100.0),
+                    );
+                }
+
+                if let Some(status) = &self.redundancy_status {
+                    ui.separator();
+                    let color = if status.ends_with("FAILED") {
+                        egui::Color32::RED
+                    } else {
+                        egui::Color32::GREEN
+                    };
+                    ui.colored_label(color, status);
+                }
+
+                if let Some(sim) = self.ws_sim {
+                    ui.separator();
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("WS sim: {:.0}% drop, {} ms delay", sim.drop_rate * 100.0, sim.delay.as_millis()),
+                    );
+                }
             });
         });
+    }
 
-        ctx.request_repaint_after(Duration::from_millis(30));
+    fn settings(&self, data: &AudioData) -> Config {
+        Config {
+            device: Some(data.device_name.clone()).filter(|name| !name.is_empty()),
+            buffer_size: self.buffer_len.load(Ordering::Relaxed),
+            tau_ms: data.rms_smoother.tau_ms,
+            display_mode: if self.show_dbfs { DisplayMode::Dbfs } else { DisplayMode::Linear },
+            trigger_enabled: self.trigger_enabled,
+            window: self.rms_window,
+            gain_db: data.digital_gain_db,
+            envelope_block: data.envelope_block,
+            sensitivity_correction_db: self.sensitivity_correction_db,
+            osc_enabled: self.osc_enabled,
+            osc_host: self.osc_host.clone(),
+            osc_port: self.osc_port,
+            redundant_path: self.redundant_path.clone(),
+        }
     }
-}
 
-fn start_audio_thread(shared: Arc<Mutex<AudioData>>) {
-    thread::spawn(move || {
+    // (Re)starts the OSC sender for the current host and port, or stops it
+    fn set_osc_enabled(&mut self, enabled: bool, data: &mut AudioData) {
+        // Dropping the old sender ends its thread
+        data.osc = None;
+        self.osc_enabled = false;
+        self.osc_status = None;
+        if !enabled {
+            return;
+        }
+        match start_osc_sender(&self.osc_host, self.osc_port) {
+            Ok(sender) => {
+                data.osc = Some(sender);
+                self.osc_enabled = true;
+            }
+            Err(e) => self.osc_status = Some(format!("OSC disabled: {:#}", e)),
+        }
+    }
+
+    fn set_buffer_len(&self, buffer_len: usize, data: &mut AudioData) {
+        self.buffer_len.store(buffer_len, Ordering::Relaxed);
+        // Shrink now rather than waiting for the next callback
+        data.push_samples(&[], buffer_len);
+        for ring in data.channel_samples.iter_mut() {
+            let excess = ring.len().saturating_sub(buffer_len);
+            ring.drain(..excess);
+        }
+    }
+
+    // Everything except the device, which stays open until another one is picked
+    fn apply_settings(&mut self, settings: &Config, data: &mut AudioData) {
+        self.set_buffer_len(settings.buffer_size, data);
+        data.rms_smoother.tau_ms = settings.tau_ms;
+        self.show_dbfs = settings.display_mode == DisplayMode::Dbfs;
+        self.trigger_enabled = settings.trigger_enabled;
+        self.rms_window = settings.window;
+        data.digital_gain_db = settings.gain_db.clamp(*DIGITAL_GAIN_RANGE.start(), *DIGITAL_GAIN_RANGE.end());
+        self.sensitivity_correction_db = settings.sensitivity_correction_db;
+        data.set_envelope_block(settings.envelope_block);
+        self.osc_host = settings.osc_host.clone();
+        self.osc_port = settings.osc_port;
+        self.set_osc_enabled(settings.osc_enabled, data);
+        self.redundant_path = settings.redundant_path.clone();
+    }
+
+    fn settings_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Settings").show(ui, |ui| {
+            match Config::path() {
+                Some(path) => ui.label(format!("Saved on exit to {}", path.display())),
+                None => ui.label("No config directory; settings are not saved"),
+            };
+            if ui.button("Reset to defaults").clicked() {
+                self.apply_settings(&Config::default(), data);
+            }
+
+            let mut envelope_block = data.envelope_block;
+            egui::ComboBox::from_label("Envelope block (samples)")
+                .selected_text(envelope_block.to_string())
+                .show_ui(ui, |ui| {
+                    for block in ENVELOPE_BLOCKS {
+                        ui.selectable_value(&mut envelope_block, block, block.to_string());
+                    }
+                });
+            if envelope_block != data.envelope_block {
+                data.set_envelope_block(envelope_block);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                let mut enabled = self.osc_enabled;
+                let toggled = ui.checkbox(&mut enabled, "Enable OSC").changed();
+                ui.label("Host:");
+                let host_changed = ui.add(egui::TextEdit::singleline(&mut self.osc_host).desired_width(120.0)).lost_focus();
+                ui.label("Port:");
+                let port_changed = ui.add(egui::DragValue::new(&mut self.osc_port).clamp_range(1..=65535)).changed();
+                // A new destination takes effect immediately while OSC is on
+                if toggled || (self.osc_enabled && (host_changed || port_changed)) {
+                    self.set_osc_enabled(enabled, data);
+                }
+            });
+            ui.label("Sends /mic/rms, /mic/amplitude and /mic/peak_hold on every audio block");
+            if let Some(status) = &self.osc_status {
+                ui.colored_label(egui::Color32::RED, status);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                match &self.redundant_path {
+                    Some(dir) => ui.label(format!("Backup copies to {}", dir.display())),
+                    None => ui.label("No backup copies"),
+                };
+                if ui.button("Choose folder…").clicked() {
+                    self.pending_dialog = Some(PendingDialog::PickBackupFolder);
+                }
+                if self.redundant_path.is_some() && ui.button("Clear").clicked() {
+                    self.redundant_path = None;
+                }
+            });
+        });
+    }
+
+    fn device_panel(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::left("devices").show(ctx, |ui| {
+            ui.heading("Input device");
+            let current = self.data.lock().unwrap().device_name.clone();
+            let mut selected = current.clone();
+            egui::ComboBox::from_id_source("input_device")
+                .selected_text(selected.as_str())
+                .width(200.0)
+                .show_ui(ui, |ui| {
+                    for name in &self.device_names {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
+                });
+            if selected != current {
+                let _ = self.device_sender.send(selected);
+            }
+
+            // USB interfaces can be plugged in while the app runs
+            if ui.button("🔄 Refresh devices").clicked() {
+                self.device_names = input_device_names();
+            }
+        });
+    }
+
+    fn device_info_panel(&mut self, ctx: &egui::Context) {
+        let (name, stream_config, block_frames) = {
+            let data = self.data.lock().unwrap();
+            (data.device_name.clone(), data.stream_config.clone(), data.block_frames)
+        };
         let host = cpal::default_host();
-        let device = host.default_input_device().expect("No input device found");
-        let config = device.default_input_config().unwrap();
-        let channels = config.channels() as usize;
+        if self.device_info.as_ref().map(|(n, _)| n) != Some(&name) {
+            let capabilities = input_capabilities(&host, &name).map_err(|e| format!("{:#}", e));
+            self.device_info = Some((name.clone(), capabilities));
+        }
+
+        egui::SidePanel::right("device_info").show(ctx, |ui| {
+            ui.heading("Device info");
+            ui.label(format!("{} ({})", name, host.id().name()));
+
+            // cpal has no phantom power control, but a silent condenser mic is a common surprise
+            if name.to_lowercase().contains("phantom") || host.id().name() == "ASIO" {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Condenser mics need 48 V phantom power, switched on the interface or its control panel",
+                );
+            }
+
+            ui.separator();
+            ui.strong("Supported");
+            match self.device_info.as_ref().map(|(_, info)| info) {
+                Some(Ok(info)) => {
+                    for &(min, max) in &info.sample_rates {
+                        ui.label(if min == max {
+                            format!("{} Hz", min)
+                        } else {
+                            format!("{} – {} Hz", min, max)
+                        });
+                    }
+                    let channels: Vec<String> = info.channels.iter().map(|c| c.to_string()).collect();
+                    ui.label(format!("Channels: {}", channels.join(", ")));
+                    let formats: Vec<String> = info.sample_formats.iter().map(|f| f.to_string().to_uppercase()).collect();
+                    ui.label(format!("Formats: {}", formats.join(", ")));
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => {}
+            }
+
+            ui.separator();
+            ui.strong("Active stream");
+            match stream_config {
+                Some(config) => {
+                    let stream: cpal::StreamConfig = config.config();
+                    ui.label(format!("{} Hz, {} channels", stream.sample_rate.0, stream.channels));
+                    ui.label(format!("Format: {}", config.sample_format().to_string().to_uppercase()));
+                    ui.label(match stream.buffer_size {
+                        cpal::BufferSize::Fixed(frames) => format!("Buffer: {} frames", frames),
+                        cpal::BufferSize::Default => format!("Buffer: host default ({} frames per callback)", block_frames),
+                    });
+                }
+                None => {
+                    ui.label("No stream open");
+                }
+            }
+        });
+    }
+
+    fn digital_gain_controls(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        let clipping = data.digital_gain_db > DIGITAL_GAIN_WARN_DB
+            && data
+                .last_gain_clip
+                .is_some_and(|t| t.elapsed().as_secs_f32() < CLIP_BADGE_SECS);
+        ui.horizontal(|ui| {
+            ui.scope(|ui| {
+                if clipping {
+                    ui.visuals_mut().selection.bg_fill = egui::Color32::RED;
+                }
+                ui.add(
+                    egui::Slider::new(&mut data.digital_gain_db, DIGITAL_GAIN_RANGE)
+                        .suffix(" dB")
+                        .trailing_fill(clipping)
+                        .text("Digital gain"),
+                )
+                .on_hover_text("Applied in software after the ADC; this is not the interface's preamp gain");
+            });
+            ui.label(format!("RMS pre-gain: {:.4} | post-gain: {:.4}", data.pre_gain_rms, data.rms));
+            if clipping {
+                ui.colored_label(egui::Color32::RED, "Clipping after digital gain");
+            }
+        });
+    }
+
+    fn agc_controls(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        ui.horizontal(|ui| {
+            let label = if data.agc_enabled { "AGC on" } else { "AGC off" };
+            if ui
+                .toggle_value(&mut data.agc_enabled, label)
+                .on_hover_text("Scales the linear waveform plot towards the target level; levels and meters are unaffected")
+                .changed()
+            {
+                data.agc.reset();
+            }
+            if data.agc_enabled {
+                ui.label(format!("Display gain: {:+.1} dB", data.agc.gain_db()));
+            }
+        });
+    }
+
+    fn recording_controls(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some((samples, channels, sample_rate)) = data.interrupted_recording.take() {
+            self.pending_dialog = Some(PendingDialog::SaveRecording {
+                samples,
+                channels,
+                sample_rate,
+                normalize: self.preview_normalized,
+            });
+            self.recording_status = Some("Recording stopped: the input stream was reopened in another format".to_owned());
+        }
+
+        ui.horizontal(|ui| {
+            let mut recording = data.recording.is_some();
+            if ui.toggle_value(&mut recording, "⏺ Record").changed() {
+                if recording {
+                    data.recording = Some(Vec::new());
+                    self.recording_status = None;
+                } else if let Some(samples) = data.recording.take() {
+                    self.pending_dialog = Some(PendingDialog::SaveRecording {
+                        samples,
+                        channels: data.channels as u16,
+                        sample_rate: data.sample_rate,
+                        normalize: self.preview_normalized,
+                    });
+                }
+            }
+
+            if let Some(samples) = &data.recording {
+                let frames = samples.len() / data.channels.max(1);
+                let secs = frames as f32 / data.sample_rate.max(1) as f32;
+                ui.label(format!("Recording {:.1} s", secs));
+                if secs > RECORDING_WARN_SECS {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠ Long recording - all samples are kept in memory");
+                }
+            }
+            ui.checkbox(&mut self.replay_gain_tags, "Tag ReplayGain")
+                .on_hover_text("Writes REPLAYGAIN_TRACK_GAIN and REPLAYGAIN_TRACK_PEAK into saved recordings and captures");
+            if let Some(status) = &self.recording_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn capture_controls(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        // The stream was reopened, possibly in another format
+        if self.capture_handle.is_some() && data.capture.is_none() {
+            self.stop_capture(data);
+            self.capture_status = Some("Capture stopped: the input stream was reopened".to_owned());
+        }
+
+        ui.horizontal(|ui| {
+            if let Some(started) = self.capture_started {
+                ui.label(egui::RichText::new("● REC").strong().color(egui::Color32::RED));
+                let secs = started.elapsed().as_secs();
+                ui.label(format!("{:02}:{:02}", secs / 60, secs % 60));
+                if ui.button("⏹ Stop capture").clicked() {
+                    self.stop_capture(data);
+                }
+            } else if ui.button("⏺ Capture to file…").clicked() {
+                match data.stream_config.clone() {
+                    Some(config) => self.pending_dialog = Some(PendingDialog::StartCapture(config)),
+                    None => self.capture_status = Some("No input stream to capture".to_owned()),
+                }
+            }
+            if let Some(status) = &self.capture_status {
+                ui.label(status);
+            }
+            if self.capture_handle.as_ref().is_some_and(|handle| handle.backup_failed()) {
+                ui.colored_label(egui::Color32::YELLOW, "⚠ Backup copy failed - the primary file is still written");
+            }
+        });
+    }
+
+    // Picks the file without the AudioData lock, then takes it to hook up the callback
+    fn start_capture(&mut self, config: cpal::SupportedStreamConfig) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("WAV", &["wav"])
+            .set_file_name("capture.wav")
+            .save_file()
+        else {
+            return;
+        };
 
-        let sample_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut buffer = shared.lock().unwrap();
+        let data = Arc::clone(&self.data);
+        let mut data = data.lock().unwrap();
+        // The device may have been switched while the dialog was open
+        if data.stream_config.as_ref() != Some(&config) {
+            self.capture_status = Some("Capture not started: the input stream was reopened".to_owned());
+            return;
+        }
+        let (handle, writer) = capture_to_file(&path, self.redundant_path.as_deref(), &config);
+        data.capture = Some(handle.sender());
+        self.capture_handle = Some(handle);
+        self.capture_thread = Some(writer);
+        self.capture_started = Some(Instant::now());
+        self.capture_status = Some(format!("Capturing to {}", path.display()));
+        self.capture_path = Some(path);
+    }
 
-            let mut sum = 0.0;
-            let mut max: f32 = 0.0;
+    // Detaches the capture from the callback; the writer finishes the file in
+    // `finish_stopped_capture`, since joining it here would hold up the callback
+    fn stop_capture(&mut self, data: &mut AudioData) {
+        data.capture = None;
+        if let Some(handle) = self.capture_handle.take() {
+            handle.stop();
+        }
+        self.capture_started = None;
+        self.stopped_capture = self.capture_thread.take();
+    }
 
-            for frame in data.chunks(channels) {
-                let s = frame[0];
-                sum += s * s;
-                max = max.max(s.abs());
-                buffer.samples.push_back(s);
+    // Waits for the writer of a stopped capture so the file is complete when this
+    // returns. Called without the AudioData lock held.
+    fn finish_stopped_capture(&mut self) {
+        if let Some(writer) = self.stopped_capture.take() {
+            self.capture_status = Some(match writer.join() {
+                Ok(Ok(redundancy)) => {
+                    self.set_redundancy_status(&redundancy);
+                    self.finished_capture = self.capture_path.take().map(|path| (path, redundancy));
+                    "Capture saved".to_owned()
+                }
+                Ok(Err(e)) => format!("Capture failed: {:#}", e),
+                Err(_) => "Capture failed: the writer thread panicked".to_owned(),
+            });
+        }
+    }
 
-                if buffer.samples.len() > 500 {
-                    buffer.samples.pop_front();
+    // Called without the AudioData lock held
+    fn run_pending_dialog(&mut self) {
+        let Some(dialog) = self.pending_dialog.take() else {
+            return;
+        };
+        match dialog {
+            PendingDialog::SaveRecording {
+                samples,
+                channels,
+                sample_rate,
+                normalize,
+            } => self.save_recording(samples, channels, sample_rate, normalize),
+            PendingDialog::StartCapture(config) => self.start_capture(config),
+            PendingDialog::ExportToneEvents(events) => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("tone_events.csv")
+                    .save_file()
+                {
+                    self.tone_status = Some(match write_tone_events_csv(&path, &events, self.air_conditions()) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Failed to write CSV: {}", e),
+                    });
+                }
+            }
+            PendingDialog::ExportSelEvents => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("sel_events.csv")
+                    .save_file()
+                {
+                    self.sel_status = Some(match write_sel_events_csv(&path, &self.sel_events) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Failed to write CSV: {}", e),
+                    });
+                }
+            }
+            PendingDialog::ExportSilenceLog => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("silence_log.csv")
+                    .save_file()
+                {
+                    self.silence_status = Some(match write_silence_log_csv(&path, &self.silence_log, self.air_conditions()) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Failed to write CSV: {}", e),
+                    });
+                }
+            }
+            PendingDialog::SaveDrumPattern(text) => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Text", &["txt"])
+                    .set_file_name("drum_pattern.txt")
+                    .save_file()
+                {
+                    self.drum_status = Some(match std::fs::write(&path, text) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Failed to save pattern: {}", e),
+                    });
+                }
+            }
+            PendingDialog::ImportPeq => self.import_peq(),
+            PendingDialog::PickBackupFolder => {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    self.redundant_path = Some(dir);
+                }
+            }
+            PendingDialog::SaveFlatResponseCalibration(calibration) => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("flat_response_calibration.json")
+                    .save_file()
+                {
+                    self.field_status = Some(match calibration.save(&path) {
+                        Ok(()) => format!("Saved to {}", path.display()),
+                        Err(e) => format!("Failed to save calibration: {:#}", e),
+                    });
+                }
+            }
+            PendingDialog::LoadMicCalibration => {
+                if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                    match FlatResponseCalibration::load(&path) {
+                        Ok(calibration) => {
+                            self.room_status = Some(format!("Mic calibration loaded from {}", path.display()));
+                            self.mic_calibration = Some(calibration);
+                        }
+                        Err(e) => self.room_status = Some(format!("Failed to load mic calibration: {:#}", e)),
+                    }
                 }
             }
+        }
+    }
+
+    // ReplayGain of a capture stopped during this frame; reads the file back, so it
+    // runs without the AudioData lock
+    fn measure_finished_capture(&mut self) {
+        let Some((path, redundancy)) = self.finished_capture.take() else {
+            return;
+        };
+        let status = match read_wav(&path) {
+            Ok((samples, channels, sample_rate)) => {
+                self.replay_gain(&path, &redundancy, &samples, channels, sample_rate)
+            }
+            Err(e) => format!("ReplayGain not measured: {:#}", e),
+        };
+        self.capture_status = Some(format!("Capture saved; {}", status));
+    }
+
+    // Measures a finished recording and, if enabled, tags it and its backup copy
+    fn replay_gain(
+        &self,
+        path: &std::path::Path,
+        redundancy: &Redundancy,
+        samples: &[f32],
+        channels: u16,
+        sample_rate: u32,
+    ) -> String {
+        let Some(gain) = replaygain::analyze(samples, channels as usize, sample_rate) else {
+            return "too short or quiet for ReplayGain".to_owned();
+        };
+        eprintln!("ReplayGain of {}: {:+.2} dB, peak {:.6}", path.display(), gain.gain_db, gain.peak);
+        let status = format!("ReplayGain {:+.2} dB, peak {:.3}", gain.gain_db, gain.peak);
+        if !self.replay_gain_tags {
+            return status;
+        }
+        let backup = match (redundancy, &self.redundant_path) {
+            (Redundancy::Ok, Some(dir)) => Some(backup_path(path, dir)),
+            _ => None,
+        };
+        let tagged = std::iter::once(path.to_owned())
+            .chain(backup)
+            .try_for_each(|file| write_replay_gain_tags(&file, &gain));
+        match tagged {
+            Ok(()) => format!("{} (tagged)", status),
+            Err(e) => format!("{} (not tagged: {:#})", status, e),
+        }
+    }
+
+    // Summary for the status bar once a recording or capture is finished
+    fn set_redundancy_status(&mut self, redundancy: &Redundancy) {
+        if let Some(summary) = redundancy.summary() {
+            eprintln!("{}", summary);
+            self.redundancy_status = Some(summary);
+        }
+    }
+
+    // Reads the file without the AudioData lock; the lock is only taken to swap in the filters
+    fn import_peq(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("REW filters", &["txt"]).pick_file() else {
+            return;
+        };
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| parse_rew_filters(&text));
+        match parsed {
+            Ok(filters) => {
+                self.peq_status = Some(format!("Imported {} filters from REW", filters.len()));
+                let mut data = self.data.lock().unwrap();
+                let sample_rate = data.sample_rate.max(1) as f32;
+                data.peq = filters.iter().map(|f| f.to_biquad(sample_rate)).collect();
+                self.peq_filters = filters;
+            }
+            Err(e) => self.peq_status = Some(format!("Failed to import PEQ: {}", e)),
+        }
+    }
 
-            buffer.rms = (sum / data.len() as f32).sqrt();
-            buffer.amplitude = max;
+    fn save_recording(&mut self, mut samples: Vec<f32>, channels: u16, sample_rate: u32, normalize: bool) {
+        let file_name = if normalize { "recording_normalized.wav" } else { "recording.wav" };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("WAV", &["wav"])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            self.recording_status = Some("Recording discarded".to_owned());
+            return;
         };
 
-        let err_fn = |err| eprintln!("Stream error: {}", err);
-        let stream = device
-            .build_input_stream(&config.into(), sample_fn, err_fn, None)
-            .unwrap();
+        // The same gain on every sample of every channel, so the balance is kept
+        let mut normalized = String::new();
+        if normalize {
+            let gain = normalization_gain(&samples, NORMALIZE_TARGET_PEAK);
+            let gain_db = 20.0 * gain.log10();
+            samples.iter_mut().for_each(|s| *s *= gain);
+            normalized = format!(" (normalized {:+.1} dB)", gain_db);
+            if gain_db > NORMALIZE_WARN_DB {
+                normalized += " ⚠ the recording is (nearly) silent";
+            }
+        }
+
+        let written = write_wav_redundant(&path, self.redundant_path.as_deref(), &samples, channels, sample_rate);
+        self.recording_status = Some(match written {
+            Ok(redundancy) => {
+                self.set_redundancy_status(&redundancy);
+                let replay_gain = self.replay_gain(&path, &redundancy, &samples, channels, sample_rate);
+                format!("Saved {}{}; {}", path.display(), normalized, replay_gain)
+            }
+            Err(e) => format!("Failed to save recording: {:#}", e),
+        });
+    }
 
-        stream.play().unwrap();
+    // Hold T to arm, tap the object, then release T
+    fn update_tap_mode(&mut self, ctx: &egui::Context, data: &mut AudioData) {
+        let key_down = ctx.input(|i| i.key_down(egui::Key::T));
+        if key_down && !self.tap_key_held {
+            if let TapState::Idle = data.tap {
+                data.tap = TapState::Armed { threshold: self.tap_threshold };
+            }
+        } else if !key_down && self.tap_key_held {
+            if let TapState::Armed { .. } = data.tap {
+                data.tap = TapState::Idle;
+            }
+        }
+        self.tap_key_held = key_down;
 
-        loop {
-            std::thread::sleep(Duration::from_secs(1));
+        if let TapState::Done(_) = data.tap {
+            if let TapState::Done(capture) = std::mem::take(&mut data.tap) {
+                if let Some(resonance) = find_resonance(&capture, data.sample_rate) {
+                    self.taps.push(resonance);
+                }
+            }
         }
-    });
+    }
+
+    fn tap_panel(&mut self, ui: &mut egui::Ui, tap: &TapState) {
+        egui::CollapsingHeader::new("Tap Mode").show(ui, |ui| {
+            ui.add(egui::Slider::new(&mut self.tap_threshold, 0.01..=1.0).text("Onset threshold"));
+            ui.label(match tap {
+                TapState::Idle => "Hold T and tap the object",
+                TapState::Armed { .. } => "Armed - waiting for tap",
+                TapState::Capturing(_) | TapState::Done(_) => "Capturing ring-down...",
+            });
+
+            if let Some(last) = self.taps.last() {
+                ui.label(format!(
+                    "Resonant frequency: {:.0} Hz, Q = {:.0}, f×τ = {:.2}",
+                    last.frequency_hz,
+                    last.q,
+                    last.material_signature()
+                ));
+            }
+
+            if self.taps.len() > 1 {
+                egui::Grid::new("tap_measurements").striped(true).show(ui, |ui| {
+                    ui.label("#");
+                    ui.label("Frequency");
+                    ui.label("Q");
+                    ui.end_row();
+                    for (i, tap) in self.taps.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+                        ui.label(format!("{:.1} Hz", tap.frequency_hz));
+                        ui.label(format!("{:.1}", tap.q));
+                        ui.end_row();
+                    }
+                });
+
+                let mean = self.taps.iter().map(|t| t.frequency_hz).sum::<f32>() / self.taps.len() as f32;
+                ui.label(format!("Average fundamental over {} taps: {:.1} Hz", self.taps.len(), mean));
+            }
+
+            if ui.button("Clear taps").clicked() {
+                self.taps.clear();
+            }
+        });
+    }
+
+    fn cepstrum_panel(&mut self, ui: &mut egui::Ui, data: &AudioData) {
+        egui::CollapsingHeader::new("Cepstrum").show(ui, |ui| {
+            ui.add(egui::Slider::new(&mut self.lifter_ms, 0.0..=5.0).text("Lifter (ms)"));
+
+            let samples: Vec<f32> = data.samples.iter().copied().collect();
+            let sample_rate = data.sample_rate.max(1);
+            let mut cepstrum = real_cepstrum(&samples);
+
+            // Zero the low quefrencies that hold the direct path / spectral envelope
+            let lifter_bins = ((self.lifter_ms / 1000.0 * sample_rate as f32) as usize).min(cepstrum.len());
+            cepstrum[..lifter_bins].fill(0.0);
+
+            match find_echo_peaks(&cepstrum, sample_rate, lifter_bins).first() {
+                Some(peak) => ui.label(format!(
+                    "Echo delay: {:.1} ms (reflection ≈ {:.1} m away)",
+                    peak.delay_ms,
+                    self.speed_of_sound() * peak.delay_ms / 1000.0 / 2.0
+                )),
+                None => ui.label("Echo delay: —"),
+            };
+
+            let points: PlotPoints = cepstrum
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| [i as f64 / sample_rate as f64 * 1000.0, c as f64])
+                .collect();
+            Plot::new("cepstrum_plot")
+                .height(150.0)
+                .x_axis_label("Quefrency (ms)")
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(points).name("Real cepstrum"));
+                });
+        });
+    }
+
+    fn feedback_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Feedback Suppression").show(ui, |ui| {
+            ui.checkbox(&mut data.feedback_enabled, "Detect and notch feedback");
+
+            let notches = data.feedback.notches();
+            ui.label(format!("Active notches: {}/{} (click to remove)", notches.len(), MAX_NOTCHES));
+            let mut removed = None;
+            for (i, notch) in notches.iter().enumerate() {
+                if ui.button(format!("Notch: {:.0} Hz", notch.frequency_hz)).clicked() {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                data.feedback.remove_notch(i);
+            }
+
+            let (spectrum, bin_hz) = data.feedback.spectrum(data.sample_rate);
+            let points: PlotPoints = spectrum
+                .iter()
+                .enumerate()
+                .map(|(i, &db)| [i as f64 * bin_hz as f64, db as f64])
+                .collect();
+            Plot::new("feedback_spectrum")
+                .height(150.0)
+                .x_axis_label("Frequency (Hz)")
+                .include_y(-120.0)
+                .include_y(0.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(points).name("Spectrum (dBFS)"));
+                    for notch in data.feedback.notches() {
+                        plot_ui.vline(
+                            VLine::new(notch.frequency_hz as f64)
+                                .style(LineStyle::dotted_dense())
+                                .color(egui::Color32::RED),
+                        );
+                    }
+                });
+        });
+    }
+
+    fn pressure_gradient_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Pressure Gradient").show(ui, |ui| {
+            ui.add_enabled(
+                data.channels >= 2,
+                egui::Checkbox::new(&mut data.pressure_gradient, "Pressure Gradient Mode (Ch1 − Ch2)"),
+            );
+            if data.channels < 2 {
+                ui.label("Needs a stereo input: Ch1 front, Ch2 back");
+            }
+            ui.label("Gradient Axis: Ch2 (back) → Ch1 (front)");
+            ui.add(egui::Slider::new(&mut self.mic_spacing_cm, 0.5..=20.0).text("Mic spacing (cm)"));
+
+            // On-axis response of the difference of two omnis, |2·sin(π·f·d/c)|. It rises at
+            // +6 dB/octave up to c/(2d), the spatial sampling limit, then combs.
+            let spacing_m = self.mic_spacing_cm / 100.0;
+            let c = self.speed_of_sound();
+            let limit_hz = c / (2.0 * spacing_m);
+            ui.label(format!("Spatial sampling limit: {:.0} Hz", limit_hz));
+
+            let sample_rate = data.sample_rate.max(1) as f64;
+            let samples: Vec<f32> = data.samples.iter().copied().collect();
+            let bin_hz = sample_rate / samples.len().max(1) as f64;
+            let spectrum: PlotPoints = magnitude_spectrum_dbfs(&samples)
+                .iter()
+                .enumerate()
+                .map(|(i, &db)| [i as f64 * bin_hz, db as f64])
+                .collect();
+            let response: PlotPoints = (1..=400)
+                .map(|i| {
+                    let f = i as f64 / 400.0 * sample_rate / 2.0;
+                    let gain = 2.0 * (std::f64::consts::PI * f * spacing_m as f64 / c as f64).sin().abs();
+                    [f, 20.0 * gain.max(1e-6).log10()]
+                })
+                .collect();
+
+            Plot::new("gradient_spectrum")
+                .height(150.0)
+                .x_axis_label("Frequency (Hz)")
+                .include_y(-100.0)
+                .include_y(10.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(spectrum).name("Spectrum (dBFS)"));
+                    if data.pressure_gradient {
+                        plot_ui.line(
+                            Line::new(response)
+                                .style(LineStyle::dashed_dense())
+                                .name("Gradient sensitivity (dB)"),
+                        );
+                    }
+                });
+        });
+    }
+
+    fn noise_gate_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Spectral Noise Gate").show(ui, |ui| {
+            ui.checkbox(&mut data.noise_gate_enabled, "Gate noise per FFT bin");
+            ui.add(egui::Slider::new(&mut data.noise_gate.residual, 0.0..=1.0).text("Residual"));
+
+            let bin_hz = FrequencyDomainNoiseGate::bin_hz(data.sample_rate) as f64;
+            let points: PlotPoints = data
+                .bin_floor
+                .iter()
+                .enumerate()
+                .map(|(i, &m)| [i as f64 * bin_hz, 20.0 * (m as f64).max(1e-10).log10()])
+                .collect();
+            Plot::new("noise_floor")
+                .height(120.0)
+                .x_axis_label("Frequency (Hz)")
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(points).name("Noise floor (dB)"));
+                });
+        });
+    }
+
+    fn stereo_field_panel(&mut self, ui: &mut egui::Ui, data: &AudioData) {
+        let pair_count = if self.show_lissajous {
+            self.lissajous_pairs
+        } else {
+            STEREO_PAIRS
+        };
+        let recent = data.stereo.range(data.stereo.len().saturating_sub(pair_count)..);
+        let header = match correlation(recent.clone()) {
+            Some(r) => format!("Stereo Field (r = {:+.2})", r),
+            None => "Stereo Field".to_owned(),
+        };
+
+        egui::CollapsingHeader::new(header).id_source("stereo_field").show(ui, |ui| {
+            if data.stereo.is_empty() {
+                ui.label("Needs a stereo input");
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.show_lissajous, false, "Goniometer");
+                ui.radio_value(&mut self.show_lissajous, true, "Lissajous");
+                if self.show_lissajous {
+                    ui.add(egui::Slider::new(&mut self.lissajous_pairs, 100..=MAX_STEREO_PAIRS).text("Pairs"));
+                }
+            });
+
+            let (sum_l, sum_r) = recent
+                .clone()
+                .fold((0.0, 0.0), |(l, r), [sl, sr]| (l + sl * sl, r + sr * sr));
+            let n = recent.len().max(1) as f32;
+            let (rms_l, rms_r) = ((sum_l / n).sqrt(), (sum_r / n).sqrt());
+            let balance = if rms_l + rms_r > 0.0 {
+                (rms_r - rms_l) / (rms_r + rms_l)
+            } else {
+                0.0
+            };
+
+            balance_bar(ui, balance);
+            let side = if balance < 0.0 { "L" } else { "R" };
+            ui.label(format!("Balance: {:.1}% {}", balance.abs() * 100.0, side));
+
+            // Goniometer: S = L - R across, M = L + R up; mono sits on the vertical axis.
+            // Lissajous: Ch1 across, Ch2 up; mono is the rising diagonal.
+            let lissajous = self.show_lissajous;
+            let pairs: Vec<[f64; 2]> = recent
+                .map(|&[l, r]| {
+                    if lissajous {
+                        [l as f64, r as f64]
+                    } else {
+                        [(l - r) as f64, (l + r) as f64]
+                    }
+                })
+                .collect();
+            let group_len = pairs.len().div_ceil(GONIOMETER_FADE_STEPS).max(1);
+            let (x_label, y_label) = if lissajous {
+                ("Ch1", "Ch2")
+            } else {
+                ("S (L − R)", "M (L + R)")
+            };
+
+            Plot::new("goniometer")
+                .height(220.0)
+                .data_aspect(1.0)
+                .x_axis_label(x_label)
+                .y_axis_label(y_label)
+                .show(ui, |plot_ui| {
+                    // Oldest samples first, most transparent
+                    for (age, group) in pairs.chunks(group_len).enumerate() {
+                        let alpha = ((age + 1) as f32 / GONIOMETER_FADE_STEPS as f32).min(1.0);
+                        plot_ui.points(
+                            Points::new(group.to_vec())
+                                .radius(1.5)
+                                .color(egui::Color32::LIGHT_BLUE.gamma_multiply(alpha)),
+                        );
+                    }
+                    plot_ui.line(Line::new(rms_ellipse(&pairs)).color(egui::Color32::YELLOW).name("RMS locus"));
+                });
+        });
+    }
+
+    fn tone_detector_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Tone Detectors").show(ui, |ui| {
+            let tones = &mut data.tones;
+            let mut removed = None;
+            egui::Grid::new("tone_detectors").striped(true).show(ui, |ui| {
+                ui.label("");
+                ui.label("Frequency");
+                ui.label("Threshold");
+                ui.label("Level");
+                ui.end_row();
+                for (i, detector) in tones.detectors.iter_mut().enumerate() {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                    let color = if detector.is_present() {
+                        egui::Color32::GREEN
+                    } else {
+                        egui::Color32::RED
+                    };
+                    ui.painter().circle_filled(rect.center(), 6.0, color);
+                    ui.add(egui::DragValue::new(&mut detector.frequency_hz).clamp_range(20.0..=20_000.0).suffix(" Hz"));
+                    ui.add(egui::DragValue::new(&mut detector.threshold_dbfs).clamp_range(-120.0..=0.0).suffix(" dBFS"));
+                    ui.label(format!("{:.1} dBFS", detector.level_dbfs()));
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(i) = removed {
+                tones.detectors.remove(i);
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(tones.detectors.len() < MAX_DETECTORS, egui::Button::new("Add detector"))
+                    .clicked()
+                {
+                    tones.add(ToneDetector::new(1000.0, -40.0));
+                }
+                if ui.button("Load DTMF preset").clicked() {
+                    tones.load_dtmf_preset(-40.0);
+                }
+            });
+
+            ui.label(format!(
+                "DTMF key: {} | Decoded: {}",
+                tones.dtmf_key().map_or("—".to_owned(), String::from),
+                tones.decoded()
+            ));
+
+            ui.horizontal(|ui| {
+                ui.label(format!("{} events logged", tones.events().len()));
+                if ui.button("Export CSV…").clicked() {
+                    self.pending_dialog = Some(PendingDialog::ExportToneEvents(tones.events().to_vec()));
+                }
+                if ui.button("Clear log").clicked() {
+                    tones.clear_log();
+                }
+            });
+            if let Some(status) = &self.tone_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    // Level in dBSPL once calibrated, dBFS before
+    fn level_db(&self, x: f32) -> f32 {
+        calibrated_level(to_dbfs(x), self.sensitivity_correction_db).0
+    }
+
+    fn level_unit(&self) -> &'static str {
+        calibrated_level(0.0, self.sensitivity_correction_db).1
+    }
+
+    // Per-block RMS with the calibrated noise floor removed in power
+    fn noise_corrected(&self, rms: f32) -> f32 {
+        if self.subtract_noise_floor {
+            (rms * rms - self.noise_floor_rms * self.noise_floor_rms).max(0.0).sqrt()
+        } else {
+            rms
+        }
+    }
+
+    fn statistics_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Statistics").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut data.rms_stats.window_secs, 1.0..=300.0)
+                        .logarithmic(true)
+                        .suffix(" s")
+                        .text("Window"),
+                );
+                if ui.button("Clear statistics").clicked() {
+                    data.rms_stats.clear();
+                    data.zcr_stats.clear();
+                }
+            });
+
+            let Some(stats) = data.rms_stats.stats() else {
+                ui.label("No data yet");
+                return;
+            };
+            egui::Grid::new("rms_stats").striped(true).show(ui, |ui| {
+                for (label, value) in [
+                    ("Mean RMS", format!("{:.4}", stats.mean)),
+                    ("Std dev", format!("{:.4}", stats.std_dev)),
+                    ("Min RMS", format!("{:.4}", stats.min)),
+                    ("Max RMS", format!("{:.4}", stats.max)),
+                    ("Since max", format!("{:.1} s", stats.since_max_secs)),
+                ]
+                .into_iter()
+                .chain(data.zcr_stats.stats().into_iter().flat_map(|zcr| {
+                    [
+                        ("Mean ZCR", format!("{:.4}", zcr.mean)),
+                        ("ZCR std dev", format!("{:.4}", zcr.std_dev)),
+                    ]
+                })) {
+                    ui.label(label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn beat_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if std::mem::take(&mut data.onset_detected) {
+            self.onset_flash = Some(Instant::now());
+        }
+        let flash = self
+            .onset_flash
+            .map_or(0.0, |t| 1.0 - t.elapsed().as_secs_f32() / ONSET_FLASH_SECS)
+            .max(0.0);
+
+        egui::CollapsingHeader::new("Beat Detector").show(ui, |ui| {
+            egui::Frame::none()
+                .fill(egui::Color32::GREEN.gamma_multiply(flash * 0.6))
+                .inner_margin(6.0)
+                .show(ui, |ui| {
+                    ui.add(egui::Slider::new(&mut data.onset.k, 1.1..=4.0).text("Sensitivity k (short / long energy)"));
+                    ui.heading(match data.onset.bpm() {
+                        Some(bpm) => format!("♩ {:.0} BPM", bpm),
+                        None => "♩ — BPM".to_owned(),
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.show_beat_grid, "Beat grid on the waveform");
+                        ui.label(format!("Phase {:+.0} ms (← / →)", self.beat_phase_ms));
+                        if ui.small_button("Reset phase").clicked() {
+                            self.beat_phase_ms = 0.0;
+                        }
+                    });
+                });
+            self.drum_roll(ui, data);
+        });
+    }
+
+    // Kick/snare hits of the current bar on a 4-beat grid, with the beat under way lit
+    fn drum_roll(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some(drum) = data.drum_hit.take() {
+            self.drum_flash = Some((drum, Instant::now()));
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.strong("Drum pattern");
+            match data.drums.bpm() {
+                Some(bpm) => ui.label(format!("{:.0} BPM, 4/4", bpm)),
+                None => ui.label("waiting for a tempo and a kick"),
+            };
+            if ui.small_button("Reset").clicked() {
+                data.drums.reset();
+            }
+            if ui.button("Record Pattern…").clicked() {
+                self.pending_dialog = Some(PendingDialog::SaveDrumPattern(data.drums.pattern_text()));
+            }
+        });
+
+        let bar = data.drums.bars().back().copied().unwrap_or_default();
+        let current_beat = data.drums.current_beat();
+        let cell = 28.0;
+        let label_width = 50.0;
+        let size = egui::vec2(label_width + cell * BEATS_PER_BAR as f32, cell * Drum::ALL.len() as f32);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        if let Some(beat) = current_beat {
+            let column = egui::Rect::from_min_size(
+                rect.min + egui::vec2(label_width + beat as f32 * cell, 0.0),
+                egui::vec2(cell, size.y),
+            );
+            painter.rect_filled(column, 4.0, egui::Color32::from_gray(60));
+        }
+        for (row, drum) in Drum::ALL.into_iter().enumerate() {
+            let y = rect.top() + (row as f32 + 0.5) * cell;
+            let flash = self
+                .drum_flash
+                .filter(|&(hit, _)| hit == drum)
+                .map_or(0.0, |(_, t)| 1.0 - t.elapsed().as_secs_f32() / ONSET_FLASH_SECS)
+                .max(0.0);
+            let label_color = egui::Color32::from_gray((160.0 + 95.0 * flash) as u8);
+            painter.text(
+                egui::pos2(rect.left(), y),
+                egui::Align2::LEFT_CENTER,
+                drum.name(),
+                egui::FontId::proportional(14.0),
+                label_color,
+            );
+            for (beat, &hit) in bar[row].iter().enumerate() {
+                let center = egui::pos2(rect.left() + label_width + (beat as f32 + 0.5) * cell, y);
+                if hit {
+                    let color = match drum {
+                        Drum::Kick => egui::Color32::from_rgb(255, 140, 60),
+                        Drum::Snare => egui::Color32::from_rgb(100, 180, 255),
+                    };
+                    painter.circle_filled(center, cell * 0.3, color);
+                } else {
+                    painter.circle_stroke(center, cell * 0.3, egui::Stroke::new(1.0, egui::Color32::from_gray(90)));
+                }
+            }
+        }
+        if let Some(status) = &self.drum_status {
+            ui.label(status);
+        }
+    }
+
+    // Tempo and the indices into `samples` of the beats it predicts, projected both ways
+    // from the latest onset plus the phase nudge
+    fn beat_grid(&self, data: &AudioData) -> Option<(f32, Vec<usize>)> {
+        let bpm = data.onset.bpm()?;
+        let onset = data.last_onset_position?;
+        let period = data.sample_rate as f64 * 60.0 / bpm as f64;
+        let oldest = (data.samples_written - data.samples.len() as u64) as f64;
+        let anchor = onset as f64 + self.beat_phase_ms as f64 / 1000.0 * data.sample_rate as f64;
+
+        let first = ((oldest - anchor) / period).ceil();
+        let beats = (0..)
+            .map(|k| anchor + (first + k as f64) * period - oldest)
+            .take_while(|&i| i < data.samples.len() as f64)
+            .map(|i| i.round() as usize)
+            .collect();
+        Some((bpm, beats))
+    }
+
+    fn noise_floor_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some(started) = self.noise_calibration_started {
+            if started.elapsed().as_secs_f32() >= NOISE_CALIBRATION_SECS {
+                if let Some((sum, frames)) = data.noise_calibration.take() {
+                    self.noise_floor_rms = (sum / frames.max(1) as f32).sqrt();
+                }
+                data.spectral_subtraction.finish_learning();
+                self.noise_calibration_started = None;
+            }
+        }
+
+        egui::CollapsingHeader::new("Noise Floor").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                match self.noise_calibration_started {
+                    Some(started) => {
+                        let remaining = NOISE_CALIBRATION_SECS - started.elapsed().as_secs_f32();
+                        ui.label(format!("Stay quiet… {:.1} s", remaining.max(0.0)));
+                    }
+                    None => {
+                        if ui.button("Calibrate noise floor").clicked() {
+                            data.noise_calibration = Some((0.0, 0));
+                            data.spectral_subtraction.start_learning();
+                            self.noise_calibration_started = Some(Instant::now());
+                        }
+                    }
+                }
+                ui.checkbox(&mut self.subtract_noise_floor, "Subtract noise floor");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Noise floor RMS:");
+                ui.add(egui::DragValue::new(&mut self.noise_floor_rms).speed(0.0001).clamp_range(0.0..=1.0));
+                ui.label(format!("({:.1} dBFS)", to_dbfs(self.noise_floor_rms)));
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                let learned = !data.spectral_subtraction.noise_spectrum.is_empty();
+                ui.add_enabled(
+                    learned,
+                    egui::Checkbox::new(&mut data.spectral_subtraction_enabled, "Spectral subtraction"),
+                )
+                .on_disabled_hover_text("Calibrate the noise floor first to learn the noise spectrum");
+                ui.add(egui::Slider::new(&mut data.spectral_subtraction.alpha, ALPHA_RANGE).text("Oversubtraction α"));
+            });
+            ui.label(format!(
+                "Removes the calibrated noise spectrum from the waveform ({:.0} Hz bins, Hann STFT, 50 % overlap)",
+                SpectralSubtraction::bin_hz(data.sample_rate)
+            ));
+        });
+    }
+
+    fn calibration_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some(result) = data.calibration.finish() {
+            self.calibration_status = Some(match result {
+                Ok(correction) => {
+                    self.sensitivity_correction_db = Some(correction);
+                    format!("Calibrated: {:+.1} dB from dBFS to dBSPL", correction)
+                }
+                Err(e) => format!("Calibration failed: {:#}", e),
+            });
+        }
+
+        egui::CollapsingHeader::new("Mic Calibration").show(ui, |ui| {
+            match data.calibration {
+                CalibrationWizard::Idle => {
+                    ui.horizontal(|ui| {
+                        if ui.button("Calibrate…").clicked() {
+                            data.calibration.prompt();
+                            self.calibration_status = None;
+                        }
+                        if ui
+                            .add_enabled(self.sensitivity_correction_db.is_some(), egui::Button::new("Reset to uncalibrated"))
+                            .clicked()
+                        {
+                            self.sensitivity_correction_db = None;
+                            self.calibration_status = None;
+                        }
+                    });
+                }
+                CalibrationWizard::Prompt => {
+                    ui.label(format!(
+                        "Step 1: fit the {:.0} dBSPL calibrator at {:.0} Hz over the mic and switch it on.",
+                        CALIBRATOR_DBSPL, CALIBRATOR_HZ
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("Record").clicked() {
+                            data.calibration.start_recording();
+                        }
+                        if ui.button("Cancel").clicked() {
+                            data.calibration.cancel();
+                        }
+                    });
+                }
+                CalibrationWizard::Recording { .. } => {
+                    let remaining = data.calibration.remaining_secs().unwrap_or(0.0);
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Step 2: recording the calibrator… {:.1} s", remaining));
+                        if ui.button("Cancel").clicked() {
+                            data.calibration.cancel();
+                        }
+                    });
+                }
+            }
+
+            match self.sensitivity_correction_db {
+                Some(correction) => ui.label(format!("Levels, Leq and SEL shown in dBSPL ({:+.1} dB)", correction)),
+                None => ui.label("Levels, Leq and SEL shown in dBFS"),
+            }
+            .on_hover_text("Leq periods and SEL events keep the calibration they were measured with");
+            ui.label("The calibration only holds for the current device and gain.");
+            if let Some(status) = &self.calibration_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn leq_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some(leq_dbfs) = data.leq.take_result() {
+            let end = chrono::Local::now();
+            self.leq_periods.push(LeqPeriod {
+                start: self.leq_started.take().unwrap_or(end),
+                end,
+                duration_secs: self.leq_duration_secs as f32,
+                leq_dbfs,
+                sensitivity_correction_db: self.sensitivity_correction_db,
+            });
+        }
+
+        egui::CollapsingHeader::new("Leq Timer").show(ui, |ui| {
+            let running = data.leq.is_running();
+            ui.add_enabled_ui(!running, |ui| {
+                ui.horizontal(|ui| {
+                    let (mut h, mut m, mut s) = (
+                        self.leq_duration_secs / 3600,
+                        self.leq_duration_secs / 60 % 60,
+                        self.leq_duration_secs % 60,
+                    );
+                    ui.add(egui::DragValue::new(&mut h).clamp_range(0..=24).suffix(" h"));
+                    ui.add(egui::DragValue::new(&mut m).clamp_range(0..=59).suffix(" min"));
+                    ui.add(egui::DragValue::new(&mut s).clamp_range(0..=59).suffix(" s"));
+                    self.leq_duration_secs = (h * 3600 + m * 60 + s).max(1);
+                    for (label, secs) in [("1 min", 60), ("1 h", 3600), ("8 h", 8 * 3600)] {
+                        if ui.small_button(label).clicked() {
+                            self.leq_duration_secs = secs;
+                        }
+                    }
+                });
+            });
+
+            if running {
+                let remaining = data.leq.remaining_secs() as u32;
+                ui.label(format!(
+                    "Measuring… {:02}:{:02}:{:02} left",
+                    remaining / 3600,
+                    remaining / 60 % 60,
+                    remaining % 60
+                ));
+                if ui.button("Cancel").clicked() {
+                    data.leq.cancel();
+                    self.leq_started = None;
+                }
+            } else if ui.button("Start").clicked() {
+                data.leq.start(self.leq_duration_secs as f32);
+                self.leq_started = Some(chrono::Local::now());
+            }
+
+            if let Some(last) = self.leq_periods.last() {
+                if !running {
+                    ui.colored_label(egui::Color32::GREEN, "✔ Measurement complete");
+                }
+                let (leq, unit) = calibrated_level(last.leq_dbfs, last.sensitivity_correction_db);
+                ui.label(format!("Leq = {:.1} {}", leq, unit));
+            }
+
+            if !self.leq_periods.is_empty() {
+                egui::Grid::new("leq_periods").striped(true).show(ui, |ui| {
+                    ui.label("Start");
+                    ui.label("End");
+                    ui.label("Leq");
+                    ui.end_row();
+                    for period in &self.leq_periods {
+                        ui.label(period.start.format("%Y-%m-%d %H:%M:%S").to_string());
+                        ui.label(period.end.format("%H:%M:%S").to_string());
+                        let (leq, unit) = calibrated_level(period.leq_dbfs, period.sensitivity_correction_db);
+                        ui.label(format!("{:.1} {}", leq, unit));
+                        ui.end_row();
+                    }
+                });
+                // Periods measured under different calibrations are not on one scale
+                let correction = self.leq_periods[0].sensitivity_correction_db;
+                if self.leq_periods.iter().any(|p| p.sensitivity_correction_db != correction) {
+                    ui.label("No combined Leq: the periods were measured with different calibrations");
+                } else if let Some(total) =
+                    combined_leq(self.leq_periods.iter().map(|p| (p.leq_dbfs, p.duration_secs)))
+                {
+                    let (total, unit) = calibrated_level(total, correction);
+                    ui.label(format!("Combined Leq over {} periods: {:.1} {}", self.leq_periods.len(), total, unit));
+                }
+                if ui.button("Clear periods").clicked() {
+                    self.leq_periods.clear();
+                }
+            }
+        });
+    }
+
+    fn loudness_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Loudness (BS.1770)").show(ui, |ui| {
+            let lufs = &data.lufs;
+            let format_lufs = |value: f32| {
+                if value.is_finite() {
+                    format!("{:.1} LUFS", value)
+                } else {
+                    "—".to_owned()
+                }
+            };
+            egui::Grid::new("loudness").show(ui, |ui| {
+                ui.label("Momentary (400 ms)");
+                ui.label(format_lufs(lufs.momentary_lufs()));
+                ui.end_row();
+                ui.label("Short-term (3 s)");
+                ui.label(format_lufs(lufs.short_term_lufs()));
+                ui.end_row();
+                ui.label("Integrated");
+                ui.label(format_lufs(lufs.integrated_lufs()));
+                ui.end_row();
+            });
+
+            let secs = lufs.integrated_secs() as u32;
+            ui.horizontal(|ui| {
+                ui.label(format!("Integrating for {:02}:{:02}", secs / 60, secs % 60));
+                if ui.button("Reset").clicked() {
+                    data.lufs.reset();
+                }
+            });
+        });
+    }
+
+    fn push_sel_event(&mut self, exposure: SoundExposure) {
+        self.sel_events.push(SelEvent {
+            timestamp: chrono::Local::now(),
+            exposure,
+            air: self.air_conditions(),
+            sensitivity_correction_db: self.sensitivity_correction_db,
+        });
+    }
+
+    fn sel_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if let Some(exposure) = data.sel.take_triggered() {
+            self.push_sel_event(exposure);
+        }
+
+        egui::CollapsingHeader::new("Sound Exposure Level").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Measure last event").clicked() {
+                    match data.sel.measure() {
+                        Some(exposure) => self.push_sel_event(exposure),
+                        None => self.sel_status = Some("No event in the last 10 s".to_owned()),
+                    }
+                }
+                ui.checkbox(&mut data.sel_on_onset, "Measure on onsets")
+                    .on_hover_text("Measures each clap or other onset once it has decayed 10 dB below its peak");
+                if ui.button("Export CSV…").clicked() {
+                    self.pending_dialog = Some(PendingDialog::ExportSelEvents);
+                }
+            });
+            if data.sel_on_onset && data.sel.is_armed() {
+                ui.label("Onset detected, waiting for the event to decay…");
+            }
+
+            if let Some(last) = self.sel_events.last() {
+                let (sel, peak, unit) = last.levels();
+                ui.label(format!(
+                    "SEL: {:.1} {} | Duration: {:.2} s | Peak: {:.1} {}",
+                    sel, unit, last.exposure.duration_secs, peak, unit
+                ));
+            }
+
+            // Last SEL_TABLE_LEN events, loudest first
+            let mut recent: Vec<&SelEvent> = self.sel_events.iter().rev().take(SEL_TABLE_LEN).collect();
+            recent.sort_by(|a, b| b.levels().0.total_cmp(&a.levels().0));
+            if !recent.is_empty() {
+                egui::Grid::new("sel_events").striped(true).show(ui, |ui| {
+                    ui.label("Time");
+                    ui.label("SEL");
+                    ui.label("Duration");
+                    ui.label("Peak");
+                    ui.end_row();
+                    for event in recent {
+                        let (sel, peak, unit) = event.levels();
+                        ui.label(event.timestamp.format("%H:%M:%S").to_string());
+                        ui.label(format!("{:.1} {}", sel, unit));
+                        ui.label(format!("{:.2} s", event.exposure.duration_secs));
+                        ui.label(format!("{:.1} {}", peak, unit));
+                        ui.end_row();
+                    }
+                });
+            }
+
+            if let Some(status) = &self.sel_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn silence_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        self.silence_log.append(&mut data.silence_events);
+
+        egui::CollapsingHeader::new("Silence Detector").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut data.silence_enabled, "Log quiet periods").changed() {
+                    data.silence.reset();
+                }
+                let mut threshold_dbfs = to_dbfs(data.silence.threshold_rms);
+                if ui
+                    .add(egui::Slider::new(&mut threshold_dbfs, -90.0..=0.0).suffix(" dBFS").text("Threshold"))
+                    .changed()
+                {
+                    data.silence.threshold_rms = 10f32.powf(threshold_dbfs / 20.0);
+                    data.silence.reset();
+                }
+                ui.add(
+                    egui::Slider::new(&mut data.silence.min_duration_s, 1.0..=600.0)
+                        .logarithmic(true)
+                        .suffix(" s")
+                        .text("Min duration"),
+                );
+            });
+
+            if data.silence_enabled {
+                let quiet = data.silence.quiet_secs(Instant::now());
+                ui.label(if quiet > 0.0 {
+                    format!("Quiet for {:.1} s", quiet)
+                } else {
+                    "Above threshold".to_owned()
+                });
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Export silence log").clicked() {
+                    self.pending_dialog = Some(PendingDialog::ExportSilenceLog);
+                }
+                if ui.button("Clear").clicked() {
+                    self.silence_log.clear();
+                }
+            });
+
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                egui::Grid::new("silence_log").striped(true).show(ui, |ui| {
+                    ui.label("Start");
+                    ui.label("End");
+                    ui.label("Duration");
+                    ui.label("Mean RMS");
+                    ui.end_row();
+                    for event in self.silence_log.iter().rev() {
+                        let start: chrono::DateTime<chrono::Local> = event.start_time.into();
+                        let end: chrono::DateTime<chrono::Local> = event.end_time.into();
+                        ui.label(start.format("%H:%M:%S").to_string());
+                        ui.label(end.format("%H:%M:%S").to_string());
+                        ui.label(format!("{:.1} s", event.duration_secs()));
+                        ui.label(format!("{:.1} dBFS", to_dbfs(event.mean_rms)));
+                        ui.end_row();
+                    }
+                });
+            });
+
+            if let Some(status) = &self.silence_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn wind_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Wind Noise Filter").show(ui, |ui| {
+            ui.checkbox(&mut data.wind_enabled, "Detect wind and apply 120 Hz high-pass");
+            ui.checkbox(&mut data.wind.force_on, "Manual override: high-pass always on");
+            ui.label(if data.wind.is_detected() {
+                "Wind noise detected"
+            } else {
+                "No wind noise"
+            });
+            ui.add(egui::ProgressBar::new(data.wind.mix()).text("High-pass mix"));
+        });
+    }
+
+    fn filter_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("High/Low-pass Filter").show(ui, |ui| {
+            let label = |kind: FilterKind| match kind {
+                FilterKind::Off => "Off",
+                FilterKind::HighPass => "High-pass",
+                FilterKind::LowPass => "Low-pass",
+            };
+            let mut kind = data.filter.kind();
+            egui::ComboBox::from_label("Filter")
+                .selected_text(label(kind))
+                .show_ui(ui, |ui| {
+                    for option in [FilterKind::Off, FilterKind::HighPass, FilterKind::LowPass] {
+                        ui.selectable_value(&mut kind, option, label(option));
+                    }
+                });
+            data.filter.set_kind(kind);
+
+            let mut cutoff = data.filter.cutoff_hz();
+            let slider = egui::Slider::new(&mut cutoff, 20.0..=20_000.0)
+                .logarithmic(true)
+                .suffix(" Hz")
+                .text("Cutoff");
+            if ui.add_enabled(kind != FilterKind::Off, slider).changed() {
+                data.filter.set_cutoff(cutoff);
+            }
+        });
+    }
+
+    fn peq_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Parametric EQ").show(ui, |ui| {
+            let sample_rate = data.sample_rate.max(1) as f32;
+            ui.horizontal(|ui| {
+                if ui.button("Import PEQ from REW…").clicked() {
+                    self.pending_dialog = Some(PendingDialog::ImportPeq);
+                }
+                if ui.button("Clear").clicked() {
+                    data.peq.clear();
+                    self.peq_filters.clear();
+                    self.peq_status = None;
+                }
+            });
+            if let Some(status) = &self.peq_status {
+                ui.label(status);
+            }
+            if self.peq_filters.is_empty() {
+                return;
+            }
+
+            egui::Grid::new("peq_filters").striped(true).show(ui, |ui| {
+                ui.label("Type");
+                ui.label("Fc");
+                ui.label("Gain");
+                ui.label("Q");
+                ui.end_row();
+                for filter in &self.peq_filters {
+                    ui.label(match filter.kind {
+                        PeqKind::Peak => "Peak",
+                        PeqKind::LowShelf => "Low shelf",
+                        PeqKind::HighShelf => "High shelf",
+                    });
+                    ui.label(format!("{:.1} Hz", filter.frequency_hz));
+                    ui.label(format!("{:+.1} dB", filter.gain_db));
+                    ui.label(format!("{:.2}", filter.q));
+                    ui.end_row();
+                }
+            });
+
+            // Combined response on a log frequency axis, 20 Hz .. Nyquist
+            let (lo, hi) = (20f32.log10(), (sample_rate / 2.0).log10());
+            let response: PlotPoints = (0..=300)
+                .map(|i| {
+                    let log_f = lo + (hi - lo) * i as f32 / 300.0;
+                    let f = 10f32.powf(log_f);
+                    let db: f32 = data.peq.iter().map(|b| b.response_db(sample_rate, f)).sum();
+                    [log_f as f64, db as f64]
+                })
+                .collect();
+            Plot::new("peq_response")
+                .height(150.0)
+                .include_y(-12.0)
+                .include_y(12.0)
+                .x_axis_formatter(|mark, _, _| format!("{:.0} Hz", 10f64.powf(mark.value)))
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(response).name("EQ response (dB)"));
+                });
+        });
+    }
+
+    fn gain_rider_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Gain Rider").show(ui, |ui| {
+            ui.checkbox(&mut data.gain_rider_enabled, "Ride input gain");
+
+            let rider = &mut data.gain_rider;
+            ui.add(egui::Slider::new(&mut rider.target_rms, 0.001..=0.5).logarithmic(true).text("Target RMS"));
+            ui.add(egui::Slider::new(&mut rider.deadband, 0.0..=12.0).text("Deadband (dB)"));
+            ui.add(egui::Slider::new(&mut rider.max_gain_db, 0.0..=40.0).text("Max gain (dB)"));
+            ui.add(egui::Slider::new(&mut rider.speed, 0.1..=6.0).text("Speed (dB/s)"));
+
+            ui.horizontal(|ui| {
+                gain_fader(ui, rider.gain_db(), rider.max_gain_db);
+                ui.label(format!("Gain: {:+.1} dB", rider.gain_db()));
+            });
+
+            if ui.button("Reset gain").clicked() {
+                rider.reset();
+            }
+
+            Plot::new("gain_rider_history")
+                .height(120.0)
+                .include_y(-rider.max_gain_db as f64)
+                .include_y(rider.max_gain_db as f64)
+                .show(ui, |plot_ui| {
+                    let points = PlotPoints::from(rider.history().to_vec());
+                    plot_ui.line(Line::new(points).name("Gain (dB)"));
+                });
+        });
+    }
+
+    // Looks for a new triggered sweep in the waveform, or takes the newest one if `force`
+    fn update_trigger(&mut self, force: bool) {
+        if !self.trigger_enabled {
+            self.trigger_state = TriggerState::Idle;
+            self.trigger_trace.clear();
+            return;
+        }
+        // Scan a copy so the audio callback is not blocked during the search
+        let samples = latest_n_samples(&self.data, self.buffer_len.load(Ordering::Relaxed));
+        // Every sweep is half the window long, with the trigger point `pre` samples in
+        let sweep = samples.len() / 2;
+        let pre = self.trigger_pre.min(sweep);
+        let start = if force {
+            Some(samples.len() - sweep + pre)
+        } else {
+            find_trigger(&samples, sweep, pre, self.trigger_level)
+        };
+        match start {
+            Some(start) => {
+                self.trigger_trace = samples[start - pre..start - pre + sweep].to_vec();
+                self.trigger_state = TriggerState::Triggered;
+            }
+            None if self.trigger_trace.is_empty() => self.trigger_state = TriggerState::Armed,
+            None => self.trigger_state = TriggerState::Holding,
+        }
+    }
+
+    fn single_shot_controls(&mut self, ui: &mut egui::Ui) {
+        let mut data = self.data.lock().unwrap();
+        // A finished capture replaces the frozen view
+        if let SingleShot::Captured(capture) = &mut data.single_shot {
+            self.single_shot_trace = std::mem::take(capture);
+            data.single_shot = SingleShot::Off;
+        }
+
+        ui.horizontal(|ui| {
+            let mut rearm = false;
+            if ui.checkbox(&mut self.single_shot_enabled, "Single shot").changed() {
+                rearm = self.single_shot_enabled;
+                if !self.single_shot_enabled {
+                    data.single_shot = SingleShot::Off;
+                }
+            }
+            ui.add_enabled_ui(self.single_shot_enabled, |ui| {
+                ui.add(egui::Slider::new(&mut self.single_shot_level, -1.0..=1.0).text("Level"));
+                ui.add(egui::Slider::new(&mut self.pre_trigger, PRE_TRIGGER_RANGE).text("Pre-trigger (samples)"));
+                rearm |= ui.button("Re-arm").clicked();
+            });
+            if rearm {
+                // The plot holds the current waveform until the capture is complete
+                let buffer_len = self.buffer_len.load(Ordering::Relaxed);
+                let skip = data.samples.len().saturating_sub(buffer_len);
+                self.single_shot_trace = data.samples.iter().skip(skip).copied().collect();
+                data.single_shot = SingleShot::Armed {
+                    level: self.single_shot_level,
+                    pre_trigger: self.pre_trigger,
+                    history: VecDeque::with_capacity(self.pre_trigger + 1),
+                };
+            }
+        });
+    }
+
+    fn driver_diagnostics_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Driver diagnostics").show(ui, |ui| {
+            let channels = data.channels.max(1);
+            let stats = &data.callback_stats;
+            if stats.callbacks == 0 {
+                ui.label("No callbacks yet");
+                return;
+            }
+            egui::Grid::new("callback_stats").show(ui, |ui| {
+                ui.label("Callbacks");
+                ui.label(stats.callbacks.to_string());
+                ui.end_row();
+                for (name, len) in [("Shortest", stats.min_len), ("Longest", stats.max_len), ("Latest", stats.last_len)] {
+                    ui.label(name);
+                    ui.label(format!("{} samples ({} frames)", len, len / channels));
+                    ui.end_row();
+                }
+                ui.label(format!("Over {} frames", OVERSIZED_CALLBACK_FRAMES));
+                ui.label(stats.oversized.to_string());
+                ui.end_row();
+                ui.label("Length changes");
+                ui.label(stats.length_changes.to_string());
+                ui.end_row();
+                if let Some(p99) = stats.duration_percentile(99.0) {
+                    ui.label("Callback time (p99)");
+                    ui.label(format!("{:.1} µs", p99.as_secs_f64() * 1e6))
+                        .on_hover_text("Over the newest callbacks; the callback only queues its block");
+                    ui.end_row();
+                }
+                ui.label("Dropped samples");
+                ui.label(stats.dropped_samples.to_string())
+                    .on_hover_text("Input lost because the processing thread fell behind");
+                ui.end_row();
+            });
+            if ui.button("Reset").clicked() {
+                data.callback_stats = CallbackStats::default();
+            }
+        });
+    }
+
+    fn test_tone_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        if self.sweep_stream.is_some() {
+            if self.sweep_finished.load(Ordering::Relaxed) {
+                self.sweep_stream = None;
+                self.sweep_status = Some("Sweep finished".to_owned());
+                if let Some(meter) = data.room_response.take() {
+                    self.room_levels = Some(meter.band_levels_db(self.sweep.f_start_hz..=self.sweep.f_end_hz));
+                    self.room_status = Some("Room response measured".to_owned());
+                }
+            } else {
+                // Keeps the last reading before the end, when the sweep has reached f_end
+                let frame: Vec<f32> = data.pitch_frame.iter().copied().collect();
+                self.sweep_thd = Some(ThdMeasurement::compute(&frame, self.sweep.f_end_hz, data.sample_rate));
+            }
+        }
+
+        egui::CollapsingHeader::new("Test Tone").show(ui, |ui| {
+            let playing = self.sweep_stream.is_some();
+            // The field calibrator sweeps with these settings too
+            ui.add_enabled_ui(!playing && self.field_stream.is_none(), |ui| {
+                let sweep = &mut self.sweep;
+                ui.horizontal(|ui| {
+                    ui.label("From");
+                    ui.add(egui::DragValue::new(&mut sweep.f_start_hz).clamp_range(1.0..=20_000.0).suffix(" Hz"));
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut sweep.f_end_hz).clamp_range(1.0..=48_000.0).suffix(" Hz"));
+                });
+                ui.add(egui::Slider::new(&mut sweep.duration_secs, 1.0..=60.0).text("Duration (s)"));
+                ui.add(egui::Slider::new(&mut sweep.amplitude, 0.0..=1.0).text("Amplitude"));
+            });
+
+            ui.horizontal(|ui| {
+                if playing {
+                    if ui.button("⏹ Stop").clicked() {
+                        self.sweep_stream = None;
+                        self.sweep_status = Some("Sweep stopped".to_owned());
+                        self.sweep_thd = None;
+                        if data.room_response.take().is_some() {
+                            self.room_status = Some("Room measurement discarded: the sweep was stopped".to_owned());
+                        }
+                    }
+                } else if ui
+                    .add_enabled(self.field_stream.is_none(), egui::Button::new("▶ Play sweep"))
+                    .clicked()
+                {
+                    self.sweep_finished.store(false, Ordering::Relaxed);
+                    self.sweep_thd = None;
+                    let sweep = self.sweep;
+                    let generator = move |sample_rate| TestToneGenerator::sine_sweep(sweep, sample_rate);
+                    match play_test_tone(generator, Arc::clone(&self.sweep_finished)) {
+                        Ok(stream) => {
+                            self.sweep_stream = Some(stream);
+                            self.sweep_status = Some("Playing sweep…".to_owned());
+                            if self.measure_room {
+                                data.room_response = Some(RoomResponseMeter::new(data.sample_rate));
+                                self.room_status = Some("Measuring the room response…".to_owned());
+                            }
+                        }
+                        Err(e) => self.sweep_status = Some(format!("Cannot play sweep: {:#}", e)),
+                    }
+                }
+                if let Some(status) = &self.sweep_status {
+                    ui.label(status);
+                }
+            });
+
+            // Only meaningful once the tone has settled; a sweep with From = To plays a steady tone
+            if let Some(thd) = self.sweep_thd.filter(|_| !playing) {
+                ui.label(if thd.is_finite() {
+                    format!("THD+N at {:.0} Hz: {:.3} %", self.sweep.f_end_hz, thd * 100.0)
+                } else {
+                    format!("THD+N at {:.0} Hz: no fundamental found", self.sweep.f_end_hz)
+                });
+            }
+        });
+    }
+
+    // Deviation of the last room sweep from the target per band, and the PEQ that corrects it
+    fn room_eq_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        egui::CollapsingHeader::new("Room EQ").show(ui, |ui| {
+            ui.checkbox(&mut self.measure_room, "Measure the room response during the next sweep")
+                .on_hover_text("The sweep is played from the Test Tone panel; the input is measured after the parametric EQ");
+            egui::ComboBox::from_label("Target")
+                .selected_text(self.room_target.name())
+                .show_ui(ui, |ui| {
+                    for target in TargetCurve::ALL {
+                        ui.selectable_value(&mut self.room_target, target, target.name());
+                    }
+                });
+            ui.horizontal(|ui| {
+                match &self.mic_calibration {
+                    Some(calibration) => {
+                        ui.label(format!("Mic calibration: {}", calibration.field.name()));
+                        if ui.button("Clear").clicked() {
+                            self.mic_calibration = None;
+                        }
+                    }
+                    None => {
+                        ui.label("No mic calibration");
+                    }
+                }
+                if ui.button("Load mic calibration…").clicked() {
+                    self.pending_dialog = Some(PendingDialog::LoadMicCalibration);
+                }
+            });
+            if let Some(status) = &self.room_status {
+                ui.label(status);
+            }
+            let Some(levels) = self.room_levels else {
+                return;
+            };
+
+            // The mic's own response is taken out before comparing with the target
+            let mic_corrected = |levels: &BandLevels| match &self.mic_calibration {
+                Some(calibration) => calibration.apply(levels),
+                None => *levels,
+            };
+            let flatness = FrequencyResponseFlatness::new(&mic_corrected(&levels), self.room_target);
+            let before = self
+                .room_before
+                .as_ref()
+                .map(|levels| FrequencyResponseFlatness::new(&mic_corrected(levels), self.room_target));
+            ui.label(format!(
+                "Deviation from the {} target: {:.1} dB RMS",
+                self.room_target.name(),
+                flatness.rms_deviation_db()
+            ));
+            if let Some(before) = &before {
+                ui.label(format!("Before the suggested EQ: {:.1} dB RMS", before.rms_deviation_db()));
+            }
+
+            // Before and after side by side in each band
+            let bars = |flatness: &FrequencyResponseFlatness, offset: f64, color: egui::Color32| -> Vec<Bar> {
+                flatness
+                    .deviations
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, d)| d.map(|d| (i, d)))
+                    .map(|(i, d)| {
+                        Bar::new(i as f64 + offset, d as f64)
+                            .width(if before.is_some() { 0.4 } else { 0.8 })
+                            .fill(color)
+                            .name(format!("{} Hz", band_label(i)))
+                    })
+                    .collect()
+            };
+            let offset = if before.is_some() { 0.2 } else { 0.0 };
+            Plot::new("room_deviation")
+                .height(150.0)
+                .allow_scroll(false)
+                .include_y(-6.0)
+                .include_y(6.0)
+                .y_axis_label("dB")
+                .x_axis_formatter(|mark, _, _| {
+                    let i = mark.value.round();
+                    if (mark.value - i).abs() < 1e-6 && (0.0..THIRD_OCTAVE_BANDS as f64).contains(&i) {
+                        band_label(i as usize).to_owned()
+                    } else {
+                        String::new()
+                    }
+                })
+                .show(ui, |plot_ui| {
+                    if let Some(before) = &before {
+                        let gray = egui::Color32::from_gray(140);
+                        plot_ui.bar_chart(BarChart::new(bars(before, -offset, gray)).name("Before EQ"));
+                    }
+                    let color = egui::Color32::from_rgb(100, 150, 255);
+                    plot_ui.bar_chart(BarChart::new(bars(&flatness, offset, color)).name("Deviation"));
+                });
+
+            let eq = flatness.suggested_eq();
+            if eq.is_empty() {
+                ui.label("Every band is within 1 dB of the target");
+                return;
+            }
+            for suggestion in flatness.suggestions() {
+                ui.label(suggestion);
+            }
+            egui::Grid::new("room_eq_filters").striped(true).show(ui, |ui| {
+                ui.label("Fc");
+                ui.label("Gain");
+                ui.label("Q");
+                ui.end_row();
+                for filter in &eq {
+                    ui.label(format!("{:.1} Hz", filter.frequency_hz));
+                    ui.label(format!("{:+.1} dB", filter.gain_db));
+                    ui.label(format!("{:.2}", filter.q));
+                    ui.end_row();
+                }
+            });
+            // The sweep was measured through the current EQ, so the corrections add to it
+            if ui.button("Apply to parametric EQ").clicked() {
+                let sample_rate = data.sample_rate.max(1) as f32;
+                data.peq.extend(eq.iter().map(|f| f.to_biquad(sample_rate)));
+                self.peq_filters.extend(eq.iter().copied());
+                self.peq_status = Some(format!("Added {} filters from the room measurement", eq.len()));
+                self.room_before = self.room_levels.take();
+                self.room_status = Some("EQ applied; sweep again to compare".to_owned());
+            }
+        });
+    }
+
+    // Moves the field calibrator on once its noise burst or sweep has ended
+    fn update_field_calibrator(&mut self, data: &mut AudioData) {
+        if self.field_stream.is_some() && self.field_finished.load(Ordering::Relaxed) {
+            self.field_stream = None;
+            match self.field_calibrator {
+                FieldCalibrator::MeasuringRt60 => self.field_decay_started = Some(Instant::now()),
+                FieldCalibrator::Rt60Measured { .. } => {
+                    self.field_status = Some(match data.field_measurement.take() {
+                        Some(FieldMeasurement::Response(meter)) => {
+                            let levels = meter.band_levels_db(self.sweep.f_start_hz..=self.sweep.f_end_hz);
+                            match self.field_calibrator.finish_response(&levels) {
+                                Ok(()) => "Response measured".to_owned(),
+                                Err(e) => format!("Response not corrected: {:#}", e),
+                            }
+                        }
+                        _ => "Sweep discarded: the input stream was reopened".to_owned(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if self.field_decay_started.is_some_and(|t| t.elapsed().as_secs_f32() >= FIELD_DECAY_SECS) {
+            self.field_decay_started = None;
+            let result = match data.field_measurement.take() {
+                Some(FieldMeasurement::Decay(meter)) => self.field_calibrator.finish_rt60(&meter),
+                _ => {
+                    self.field_calibrator.cancel();
+                    Err(anyhow::anyhow!("the input stream was reopened"))
+                }
+            };
+            self.field_status = result.err().map(|e| format!("RT60 not measured: {:#}", e));
+        }
+    }
+
+    // Free-field / diffuse-field mic calibration: RT60, then a sweep, then the correction
+    fn field_calibrator_panel(&mut self, ui: &mut egui::Ui, data: &mut AudioData) {
+        self.update_field_calibrator(data);
+
+        egui::CollapsingHeader::new("Field Calibrator").show(ui, |ui| {
+            let measuring = self.field_stream.is_some() || self.field_decay_started.is_some();
+            match self.field_calibrator.clone() {
+                FieldCalibrator::Idle => {
+                    ui.label("Step 1: measure the room's RT60 with a burst of noise from the speaker");
+                    let enabled = self.sweep_stream.is_none();
+                    if ui.add_enabled(enabled, egui::Button::new("▶ Measure RT60")).clicked() {
+                        self.field_finished.store(false, Ordering::Relaxed);
+                        let generator = |sample_rate| {
+                            TestToneGenerator::noise_burst(FIELD_NOISE_AMPLITUDE, FIELD_NOISE_SECS, sample_rate)
+                        };
+                        match play_test_tone(generator, Arc::clone(&self.field_finished)) {
+                            Ok(stream) => {
+                                self.field_stream = Some(stream);
+                                data.field_measurement = Some(FieldMeasurement::Decay(DecayMeter::new(data.sample_rate)));
+                                self.field_calibrator.start();
+                                self.field_status = None;
+                            }
+                            Err(e) => self.field_status = Some(format!("Cannot play noise: {:#}", e)),
+                        }
+                    }
+                }
+                FieldCalibrator::MeasuringRt60 => {
+                    ui.horizontal(|ui| {
+                        ui.label(if self.field_decay_started.is_some() {
+                            "Recording the decay…"
+                        } else {
+                            "Playing noise…"
+                        });
+                        if ui.button("Cancel").clicked() {
+                            self.stop_field_measurement(data);
+                            self.field_calibrator.cancel();
+                        }
+                    });
+                }
+                FieldCalibrator::Rt60Measured { rt60_secs, field } => {
+                    field_summary(ui, rt60_secs, field);
+                    ui.label(format!(
+                        "Step 2: measure the response with a {:.0}–{:.0} Hz sweep (set in Test Tone)",
+                        self.sweep.f_start_hz, self.sweep.f_end_hz
+                    ));
+                    ui.horizontal(|ui| {
+                        if measuring {
+                            ui.label("Playing sweep…");
+                            if ui.button("⏹ Stop").clicked() {
+                                self.stop_field_measurement(data);
+                                self.field_status = Some("Sweep stopped".to_owned());
+                            }
+                        } else if ui
+                            .add_enabled(self.sweep_stream.is_none(), egui::Button::new("▶ Play sweep"))
+                            .clicked()
+                        {
+                            self.field_finished.store(false, Ordering::Relaxed);
+                            let sweep = self.sweep;
+                            let generator = move |sample_rate| TestToneGenerator::sine_sweep(sweep, sample_rate);
+                            match play_test_tone(generator, Arc::clone(&self.field_finished)) {
+                                Ok(stream) => {
+                                    self.field_stream = Some(stream);
+                                    data.field_measurement =
+                                        Some(FieldMeasurement::Response(RoomResponseMeter::new(data.sample_rate)));
+                                    self.field_status = None;
+                                }
+                                Err(e) => self.field_status = Some(format!("Cannot play sweep: {:#}", e)),
+                            }
+                        }
+                        if !measuring && ui.button("Start over").clicked() {
+                            self.field_calibrator.cancel();
+                        }
+                    });
+                }
+                FieldCalibrator::Done {
+                    rt60_secs,
+                    field,
+                    uncorrected,
+                    corrected,
+                } => {
+                    field_summary(ui, rt60_secs, field);
+                    ui.label(format!("Step 3: corrected with {}", field.correction_path().display()));
+                    field_response_plot(ui, &uncorrected, &corrected, field);
+                    ui.horizontal(|ui| {
+                        if let Some(calibration) = self.field_calibrator.calibration() {
+                            if ui.button("Save calibration…").clicked() {
+                                self.pending_dialog = Some(PendingDialog::SaveFlatResponseCalibration(calibration.clone()));
+                            }
+                            if ui.button("Use for Room EQ").clicked() {
+                                self.mic_calibration = Some(calibration);
+                                self.field_status = Some("Room EQ now corrects for this mic".to_owned());
+                            }
+                        }
+                        if ui.button("Start over").clicked() {
+                            self.field_calibrator.cancel();
+                        }
+                    });
+                }
+            }
+            if let Some(status) = &self.field_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn stop_field_measurement(&mut self, data: &mut AudioData) {
+        self.field_stream = None;
+        self.field_decay_started = None;
+        data.field_measurement = None;
+    }
+}
+
+// RT60 and the sound field it implies, with the warning for a reverberant room
+fn field_summary(ui: &mut egui::Ui, rt60_secs: f32, field: SoundField) {
+    ui.label(format!("RT60: {:.0} ms", rt60_secs * 1000.0));
+    match field {
+        SoundField::Diffuse => {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "⚠ This is not an anechoic measurement — diffuse-field correction will be applied",
+            );
+        }
+        SoundField::Free => {
+            ui.label("Anechoic conditions: free-field correction will be applied");
+        }
+    }
+}
+
+// Uncorrected and corrected response per band on one plot
+fn field_response_plot(ui: &mut egui::Ui, uncorrected: &BandLevels, corrected: &BandLevels, field: SoundField) {
+    let line = |levels: &BandLevels| -> PlotPoints {
+        levels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, level)| level.map(|level| [i as f64, level as f64]))
+            .collect()
+    };
+    Plot::new("field_response")
+        .height(150.0)
+        .allow_scroll(false)
+        .legend(Legend::default())
+        .include_y(-6.0)
+        .include_y(6.0)
+        .y_axis_label("dB")
+        .x_axis_formatter(|mark, _, _| {
+            let i = mark.value.round();
+            if (mark.value - i).abs() < 1e-6 && (0.0..THIRD_OCTAVE_BANDS as f64).contains(&i) {
+                band_label(i as usize).to_owned()
+            } else {
+                String::new()
+            }
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new(line(uncorrected)).color(egui::Color32::from_gray(140)).name("Uncorrected"));
+            plot_ui.line(Line::new(line(corrected)).name(format!("Corrected ({})", field.name())));
+        });
+}
+
+// Tone and silence events are not timestamped with the air, so they get the
+// conditions at export time
+fn write_tone_events_csv(path: &std::path::Path, events: &[ToneEvent], air: AirConditions) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "frequency_hz,start_s,duration_s,peak_dbfs,temperature_c,humidity_pct")?;
+    for event in events {
+        writeln!(
+            file,
+            "{:.1},{:.3},{:.3},{:.1},{:.1},{:.1}",
+            event.frequency_hz, event.start_secs, event.duration_secs, event.peak_dbfs, air.temperature_c, air.humidity_pct
+        )?;
+    }
+    file.flush()
+}
+
+fn write_sel_events_csv(path: &std::path::Path, events: &[SelEvent]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "timestamp,sel_db,duration_s,peak_db,unit,temperature_c,humidity_pct")?;
+    for event in events {
+        let (sel, peak, unit) = event.levels();
+        writeln!(
+            file,
+            "{},{:.1},{:.3},{:.1},{},{:.1},{:.1}",
+            event.timestamp.to_rfc3339(),
+            sel,
+            event.exposure.duration_secs,
+            peak,
+            unit,
+            event.air.temperature_c,
+            event.air.humidity_pct
+        )?;
+    }
+    file.flush()
+}
+
+fn write_silence_log_csv(path: &std::path::Path, events: &[SilenceEvent], air: AirConditions) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(file, "start,end,duration_s,mean_rms,mean_dbfs,temperature_c,humidity_pct")?;
+    for event in events {
+        writeln!(
+            file,
+            "{},{},{:.3},{:.6},{:.1},{:.1},{:.1}",
+            chrono::DateTime::<chrono::Local>::from(event.start_time).to_rfc3339(),
+            chrono::DateTime::<chrono::Local>::from(event.end_time).to_rfc3339(),
+            event.duration_secs(),
+            event.mean_rms,
+            to_dbfs(event.mean_rms),
+            air.temperature_c,
+            air.humidity_pct
+        )?;
+    }
+    file.flush()
+}
+
+// Horizontal L..R bar with a needle at `balance` (-1 = full left, +1 = full right)
+fn balance_bar(ui: &mut egui::Ui, balance: f32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 16.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, egui::Color32::DARK_GRAY);
+    painter.line_segment(
+        [rect.center_top(), rect.center_bottom()],
+        egui::Stroke::new(1.0, egui::Color32::GRAY),
+    );
+
+    let x = egui::lerp(rect.left()..=rect.right(), (balance.clamp(-1.0, 1.0) + 1.0) / 2.0);
+    painter.line_segment(
+        [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+        egui::Stroke::new(3.0, egui::Color32::LIGHT_GREEN),
+    );
+    painter.text(rect.left_center() + egui::vec2(4.0, 0.0), egui::Align2::LEFT_CENTER, "L", egui::FontId::monospace(11.0), egui::Color32::WHITE);
+    painter.text(rect.right_center() - egui::vec2(4.0, 0.0), egui::Align2::RIGHT_CENTER, "R", egui::FontId::monospace(11.0), egui::Color32::WHITE);
+}
+
+// One-sigma ellipse of the scatter, from the eigen-decomposition of its covariance
+// Pearson correlation of the two channels; None for silence or too few pairs
+fn correlation<'a>(pairs: impl Iterator<Item = &'a [f32; 2]>) -> Option<f32> {
+    let (mut n, mut sl, mut sr, mut sll, mut srr, mut slr) = (0.0f32, 0.0, 0.0, 0.0, 0.0, 0.0);
+    for &[l, r] in pairs {
+        n += 1.0;
+        sl += l;
+        sr += r;
+        sll += l * l;
+        srr += r * r;
+        slr += l * r;
+    }
+    if n < 2.0 {
+        return None;
+    }
+    let cov = slr - sl * sr / n;
+    let var = (sll - sl * sl / n) * (srr - sr * sr / n);
+    (var > f32::EPSILON).then(|| (cov / var.sqrt()).clamp(-1.0, 1.0))
+}
+
+fn rms_ellipse(points: &[[f64; 2]]) -> PlotPoints {
+    let n = points.len().max(1) as f64;
+    let (sxx, syy, sxy) = points
+        .iter()
+        .fold((0.0, 0.0, 0.0), |(xx, yy, xy), [x, y]| (xx + x * x, yy + y * y, xy + x * y));
+    let (sxx, syy, sxy) = (sxx / n, syy / n, sxy / n);
+
+    let mean = (sxx + syy) / 2.0;
+    let spread = (((sxx - syy) / 2.0).powi(2) + sxy * sxy).sqrt();
+    let (major, minor) = ((mean + spread).sqrt(), (mean - spread).max(0.0).sqrt());
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (sin, cos) = angle.sin_cos();
+
+    (0..=64)
+        .map(|i| {
+            let t = i as f64 / 64.0 * std::f64::consts::TAU;
+            let (x, y) = (major * t.cos(), minor * t.sin());
+            [x * cos - y * sin, x * sin + y * cos]
+        })
+        .collect()
+}
+
+// Vertical fader whose knob glides to the current gain
+fn gain_fader(ui: &mut egui::Ui, gain_db: f32, max_gain_db: f32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(24.0, 100.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let shown_db = ui.ctx().animate_value_with_time(egui::Id::new("gain_fader"), gain_db, 0.3);
+
+    let span = max_gain_db.max(1.0);
+    let t = ((shown_db / span + 1.0) / 2.0).clamp(0.0, 1.0);
+    let y = egui::lerp(rect.bottom()..=rect.top(), t);
+
+    painter.line_segment(
+        [rect.center_top(), rect.center_bottom()],
+        egui::Stroke::new(3.0, egui::Color32::DARK_GRAY),
+    );
+    painter.line_segment(
+        [egui::pos2(rect.left() + 4.0, rect.center().y), egui::pos2(rect.right() - 4.0, rect.center().y)],
+        egui::Stroke::new(1.0, egui::Color32::GRAY),
+    );
+    painter.rect_filled(
+        egui::Rect::from_center_size(egui::pos2(rect.center().x, y), egui::vec2(22.0, 8.0)),
+        2.0,
+        egui::Color32::LIGHT_BLUE,
+    );
+}
+
+impl eframe::App for AppState {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let data = Arc::clone(&self.data);
+        if self.capture_handle.is_some() {
+            self.stop_capture(&mut data.lock().unwrap());
+            self.finish_stopped_capture();
+            self.measure_finished_capture();
+            if let Some(status) = &self.capture_status {
+                eprintln!("{}", status);
+            }
+        }
+        let data = data.lock().unwrap();
+        if let Err(e) = self.settings(&data).save() {
+            eprintln!("Failed to save settings: {:#}", e);
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.screenshots.update(ctx);
+        self.status_bar(ctx);
+        self.device_panel(ctx);
+        self.device_info_panel(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            stream_banner(ui, &self.stream_status.lock().unwrap());
+            callback_change_banner(ui, &self.data.lock().unwrap().callback_stats);
+            ui.horizontal(|ui| {
+                ui.heading("🎙 Live Microphone Input");
+                ui.label(if self.sensitivity_correction_db.is_some() {
+                    "dBSPL (calibrated)"
+                } else {
+                    "dBFS (uncalibrated)"
+                });
+                let mut data = self.data.lock().unwrap();
+                let recent_clip = data
+                    .last_clip
+                    .is_some_and(|t| t.elapsed().as_secs_f32() < CLIP_BADGE_SECS);
+                if recent_clip {
+                    ui.label(
+                        egui::RichText::new(" CLIP ")
+                            .strong()
+                            .color(egui::Color32::WHITE)
+                            .background_color(egui::Color32::RED),
+                    );
+                }
+                ui.label(format!("Clipped blocks: {}", data.clip_count));
+                if ui.small_button("Reset clip counter").clicked() {
+                    data.clip_count = 0;
+                    data.last_clip = None;
+                    data.clip_positions.clear();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.trigger_enabled, "Trigger");
+                let mut force = false;
+                ui.add_enabled_ui(self.trigger_enabled, |ui| {
+                    ui.add(egui::Slider::new(&mut self.trigger_level, -1.0..=1.0).text("Trigger level"));
+                    ui.add(egui::Slider::new(&mut self.trigger_pre, PRE_TRIGGER_RANGE).text("Pre-trigger (samples)"));
+                    force = ui.button("Force trigger").clicked();
+                });
+                self.update_trigger(force);
+
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                ui.painter().circle_filled(rect.center(), 6.0, self.trigger_state.color());
+                response.on_hover_text("Trigger state");
+                ui.label(self.trigger_state.name());
+            });
+            self.single_shot_controls(ui);
+
+            // Pitch is estimated on a copy so the lock is not held during the search
+            let (pitch_frame, sample_rate) = {
+                let data = self.data.lock().unwrap();
+                (data.pitch_frame.iter().copied().collect::<Vec<f32>>(), data.sample_rate)
+            };
+            let pitch = (pitch_frame.len() == PITCH_FRAME_LEN)
+                .then(|| detect_pitch(&pitch_frame, sample_rate))
+                .flatten()
+                .filter(|p| p.confidence >= PITCH_MIN_CONFIDENCE);
+            ui.label(match pitch {
+                Some(p) => format!("Pitch: ~{:.0} Hz ({})", p.frequency_hz, note_name(p.frequency_hz)),
+                None => "Pitch: —".to_owned(),
+            });
+
+            let data_arc = Arc::clone(&self.data);
+            let mut data = data_arc.lock().unwrap();
+            let now = Instant::now();
+            if let Some(last) = data.peak_hold_age.replace(now) {
+                let elapsed = now.duration_since(last).as_secs_f32();
+                data.peak_hold *= 0.5f32.powf(elapsed / self.peak_half_life_secs);
+            }
+            // Rectangular shows the last block's RMS; other windows weight the display buffer
+            let rms = match self.rms_window {
+                WindowFunction::Rectangular => self.noise_corrected(data.rms),
+                window => self.noise_corrected(windowed_rms(data.samples.make_contiguous(), window)),
+            };
+            let [rms_db, smoothed_db, amplitude_db, peak_db] =
+                [rms, data.rms_smoother.value(), data.amplitude, data.peak_hold].map(|x| self.level_db(x));
+            let unit = self.level_unit();
+            ui.horizontal(|ui| {
+                let label = if self.show_dbfs { unit } else { "Linear" };
+                ui.toggle_value(&mut self.show_dbfs, label);
+                ui.selectable_value(&mut data.a_weighted, false, "Flat");
+                ui.selectable_value(&mut data.a_weighted, true, "A-weighted")
+                    .on_hover_text("Weights the block RMS (and its smoothed value) by the A curve");
+                if self.show_dbfs {
+                    ui.label(format!(
+                        "RMS: {:.1} {unit} | Smoothed: {:.1} {unit} | Amplitude: {:.1} {unit} | Peak: {:.1} {unit} | ZCR: {:.3}",
+                        rms_db, smoothed_db, amplitude_db, peak_db, data.zcr
+                    ));
+                } else {
+                    ui.label(format!(
+                        "RMS: {:.4} | Smoothed: {:.4} | Amplitude: {:.4} | Peak: {:.4} | ZCR: {:.3}",
+                        rms,
+                        data.rms_smoother.value(),
+                        data.amplitude,
+                        data.peak_hold,
+                        data.zcr
+                    ));
+                }
+                ui.checkbox(&mut self.show_zcr, "Plot ZCR")
+                    .on_hover_text("Zero crossings per sample, on the right axis of the waveform plot");
+                egui::ComboBox::from_label("RMS window")
+                    .selected_text(self.rms_window.name())
+                    .show_ui(ui, |ui| {
+                        for window in WindowFunction::ALL {
+                            ui.selectable_value(&mut self.rms_window, window, window.name());
+                        }
+                    });
+                ui.add(
+                    egui::Slider::new(&mut self.peak_half_life_secs, 0.1..=5.0)
+                        .logarithmic(true)
+                        .suffix(" s")
+                        .text("Peak hold half-life"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut data.rms_smoother.tau_ms, 1.0..=500.0)
+                        .logarithmic(true)
+                        .suffix(" ms")
+                        .text("RMS smoothing τ"),
+                );
+            });
+            if data.channel_rms.len() > 1 {
+                self.solo_mute.set_channels(data.channel_rms.len());
+                ui.horizontal(|ui| {
+                    // Ctrl adds to the solo instead of replacing it, as in a DAW
+                    let additive = ui.input(|i| i.modifiers.ctrl);
+                    for (i, rms) in data.channel_rms.iter().enumerate() {
+                        ui.label(format!("Ch{} RMS: {:.4}", i + 1, rms));
+                        if ui.selectable_label(self.solo_mute.muted[i], "M").on_hover_text("Mute").clicked() {
+                            self.solo_mute.toggle_mute(i);
+                        }
+                        let solo = ui.selectable_label(self.solo_mute.soloed[i], "S");
+                        if solo.on_hover_text("Solo (Ctrl: add to the solo)").clicked() {
+                            self.solo_mute.toggle_solo(i, additive);
+                        }
+                        ui.add_space(8.0);
+                    }
+                    ui.checkbox(&mut self.show_channels, "Show channels");
+                });
+                self.solo_mute.silenced(&mut data.channel_silenced);
+            }
+
+            // The waveform X axis is in ms so the shown window does not depend on the rate
+            let sample_rate = data.sample_rate;
+            let ms_per_sample = 1000.0 / sample_rate.max(1) as f64;
+            let mut buffer_len = self.buffer_len.load(Ordering::Relaxed);
+            ui.horizontal(|ui| {
+                let mut window_ms = buffer_len as f64 * ms_per_sample;
+                let range_ms = *BUFFER_LEN_RANGE.start() as f64 * ms_per_sample
+                    ..=*BUFFER_LEN_RANGE.end() as f64 * ms_per_sample;
+                let slider = egui::Slider::new(&mut window_ms, range_ms)
+                    .suffix(" ms")
+                    .text("Window");
+                if ui.add(slider).changed() {
+                    buffer_len = ((window_ms / ms_per_sample).round() as usize)
+                        .clamp(*BUFFER_LEN_RANGE.start(), *BUFFER_LEN_RANGE.end());
+                    self.set_buffer_len(buffer_len, &mut data);
+                }
+                ui.label(format!(
+                    "Last {:.1} ms at {} Hz ({} samples)",
+                    buffer_len as f64 * ms_per_sample,
+                    sample_rate,
+                    buffer_len
+                ));
+            });
+
+            self.digital_gain_controls(ui, &mut data);
+            self.agc_controls(ui, &mut data);
+            self.recording_controls(ui, &mut data);
+            self.capture_controls(ui, &mut data);
+            self.update_tap_mode(ctx, &mut data);
+            self.tap_panel(ui, &data.tap);
+            self.gain_rider_panel(ui, &mut data);
+            self.cepstrum_panel(ui, &data);
+            self.feedback_panel(ui, &mut data);
+            self.pressure_gradient_panel(ui, &mut data);
+            self.noise_gate_panel(ui, &mut data);
+            self.stereo_field_panel(ui, &data);
+            self.tone_detector_panel(ui, &mut data);
+            self.settings_panel(ui, &mut data);
+            self.statistics_panel(ui, &mut data);
+            self.beat_panel(ui, &mut data);
+            self.noise_floor_panel(ui, &mut data);
+            self.calibration_panel(ui, &mut data);
+            self.leq_panel(ui, &mut data);
+            self.loudness_panel(ui, &mut data);
+            self.sel_panel(ui, &mut data);
+            self.silence_panel(ui, &mut data);
+            self.wind_panel(ui, &mut data);
+            self.filter_panel(ui, &mut data);
+            self.peq_panel(ui, &mut data);
+            self.test_tone_panel(ui, &mut data);
+            self.room_eq_panel(ui, &mut data);
+            self.field_calibrator_panel(ui, &mut data);
+            self.driver_diagnostics_panel(ui, &mut data);
+
+            if ctx.input(|i| i.key_pressed(egui::Key::H)) {
+                self.show_heatmap = !self.show_heatmap;
+            }
+            ui.label(if self.show_heatmap {
+                format!("Heatmap view, last {:.0} s (press H for waveform)", HEATMAP_SECS)
+            } else {
+                "Waveform view (press H for heatmap)".to_owned()
+            });
+
+            if ctx.input(|i| i.key_pressed(egui::Key::D)) {
+                self.show_derivative = !self.show_derivative;
+            }
+            if self.show_beat_grid {
+                let nudge = ctx.input(|i| {
+                    i.key_pressed(egui::Key::ArrowRight) as i32 - i.key_pressed(egui::Key::ArrowLeft) as i32
+                });
+                self.beat_phase_ms += nudge as f32 * BEAT_NUDGE_MS;
+            }
+            let beat_grid = self.show_beat_grid.then(|| self.beat_grid(&data)).flatten();
+            let mut waveform = std::mem::take(&mut self.waveform);
+            waveform.copy_from(&data, self.show_channels);
+            let derivative = self.show_derivative.then(|| waveform_derivative(&waveform.samples));
+            if let Some(derivative) = &derivative {
+                let max_slew = derivative.iter().fold(0.0f32, |m, d| m.max(d.abs()));
+                ui.label(format!(
+                    "Max slew: {:.3}/ms (press D to hide dy/dt)",
+                    max_slew * data.sample_rate as f32 / 1000.0
+                ));
+            }
+
+            ui.checkbox(&mut self.preview_normalized, "Preview Normalized (-3 dBFS peak)")
+                .on_hover_text("Recordings saved while this is on are normalized the same way");
+            let display_gain = if self.preview_normalized {
+                let gain = normalization_gain(&waveform.samples, NORMALIZE_TARGET_PEAK);
+                let gain_db = 20.0 * gain.log10();
+                ui.label(format!("Normalization gain: {:+.1} dB", gain_db));
+                if gain_db > NORMALIZE_WARN_DB {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠ Gain above +20 dB - the input is (nearly) silent");
+                }
+                Some(gain)
+            } else {
+                None
+            };
+
+            ui.add_enabled_ui(!self.show_dbfs && display_gain.is_none(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.auto_scale, true, "Auto-scale");
+                    ui.selectable_value(&mut self.auto_scale, false, "Fixed scale");
+                    if !self.auto_scale {
+                        ui.label("Y min");
+                        ui.add(egui::DragValue::new(&mut self.y_min).speed(0.001).clamp_range(-Y_LIMIT..=self.y_max));
+                        ui.label("Y max");
+                        ui.add(egui::DragValue::new(&mut self.y_max).speed(0.001).clamp_range(self.y_min..=Y_LIMIT));
+                    }
+                    if ui.button("Zoom to current signal").clicked() {
+                        (self.y_min, self.y_max) = auto_scale_bounds(data.peak_hold);
+                        self.auto_scale = false;
+                    }
+                });
+            });
+
+            let heatmap = self.show_heatmap.then(|| {
+                let (image, means) = waveform_heatmap(&data.heatmap);
+                let texture = match &mut self.heatmap_texture {
+                    Some(texture) => {
+                        texture.set(image, egui::TextureOptions::NEAREST);
+                        texture.clone()
+                    }
+                    None => {
+                        let texture = ctx.load_texture("waveform_heatmap", image, egui::TextureOptions::NEAREST);
+                        self.heatmap_texture.insert(texture).clone()
+                    }
+                };
+                (texture, means)
+            });
+
+            let window_ms = buffer_len as f64 * ms_per_sample;
+            let single_shot_armed = matches!(data.single_shot, SingleShot::Armed { .. } | SingleShot::Capturing(_));
+            // Fixed plot bounds; a normalized preview needs the full scale. In dBFS mode the
+            // samples stay linear and only their magnitude is plotted in dB.
+            // The AGC only scales the linear view, and a normalized preview replaces it;
+            // dB readings keep the true level
+            let agc_gain = if data.agc_enabled && !self.show_dbfs {
+                data.agc.gain_factor()
+            } else {
+                1.0
+            };
+            let gain = display_gain.unwrap_or(agc_gain);
+            // Everything below draws from the snapshot
+            drop(data);
+            let show_dbfs = self.show_dbfs;
+            // Shifts the dB view to dBSPL once the mic is calibrated
+            let level_offset = self.sensitivity_correction_db.unwrap_or(0.0) as f64;
+            let (y_min, y_max) = if heatmap.is_some() {
+                (-1.0, 1.0)
+            } else if show_dbfs {
+                (DBFS_PLOT_MIN + level_offset, level_offset)
+            } else if display_gain.is_some() {
+                (-1.0, 1.0)
+            } else if self.auto_scale {
+                auto_scale_bounds(waveform.peak_hold * agc_gain)
+            } else if self.y_max > self.y_min {
+                (self.y_min, self.y_max)
+            } else {
+                (self.y_min - AUTO_SCALE_MIN, self.y_min + AUTO_SCALE_MIN)
+            };
+            // ZCR (0..1) is drawn over the full height and read off a right-hand axis
+            let zcr_to_y = move |zcr: f32| y_min + zcr as f64 * (y_max - y_min);
+
+            // Dragging on the plot moves the trigger level line instead of the view
+            let mut plot = Plot::new("audio_plot")
+                .view_aspect(2.0)
+                .x_axis_label("Time (ms)")
+                .allow_scroll(false)
+                .allow_zoom(false)
+                .allow_drag(!self.trigger_enabled);
+            if self.show_zcr {
+                plot = plot.custom_y_axes(vec![
+                    AxisHints::new_y(),
+                    AxisHints::new_y()
+                        .label("ZCR")
+                        .placement(HPlacement::Right)
+                        .formatter(move |mark, _, _| format!("{:.2}", (mark.value - y_min) / (y_max - y_min))),
+                ]);
+            }
+
+            // Level meter beside the waveform
+            ui.horizontal_top(|ui| {
+                ui.add(VuMeter::new(rms, waveform.peak_hold));
+                plot.show(ui, |plot_ui| {
+                    // Oscilloscope-style cursor: a dashed line and its readout while hovered
+                    if let Some(pointer) = plot_ui.pointer_coordinate() {
+                        plot_ui.vline(
+                            VLine::new(pointer.x)
+                                .color(egui::Color32::from_gray(160))
+                                .style(LineStyle::dashed_dense()),
+                        );
+                        let readout = if show_dbfs {
+                            format!("t = {:.2} ms, level = {:.1} {}", pointer.x, pointer.y, unit)
+                        } else {
+                            format!("t = {:.2} ms, amplitude = {:.4}", pointer.x, pointer.y)
+                        };
+                        plot_ui.text(
+                            Text::new(PlotPoint::new(0.0, y_max), readout)
+                                .anchor(egui::Align2::LEFT_TOP)
+                                .color(egui::Color32::from_gray(200)),
+                        );
+                    }
+
+                    // The heatmap spans its own, longer history
+                    if let Some((texture, means)) = heatmap {
+                        let width = HEATMAP_SECS as f64 * 1000.0;
+                        plot_ui.set_plot_bounds(PlotBounds::from_min_max([0.0, y_min], [width, y_max]));
+                        plot_ui.image(PlotImage::new(
+                            &texture,
+                            PlotPoint::new(width / 2.0, 0.0),
+                            [width as f32, 2.0],
+                        ));
+                        plot_ui.line(Line::new(PlotPoints::new(means)).color(egui::Color32::WHITE).name("Mean"));
+                        return;
+                    }
+
+                    plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                        [0.0, y_min],   // X min, Y min
+                        [window_ms, y_max],  // X max, Y max
+                    ));
+                    let x_ms = |i: usize| i as f64 * ms_per_sample;
+                    let display = |s: f32| -> f64 {
+                        if show_dbfs {
+                            to_dbfs((s * gain).abs()) as f64 + level_offset
+                        } else {
+                            (s * gain) as f64
+                        }
+                    };
+
+                    if self.single_shot_enabled {
+                        let points: PlotPoints = self
+                            .single_shot_trace
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &s)| [x_ms(i), display(s)])
+                            .collect();
+                        plot_ui.line(Line::new(points).name("Single shot"));
+                        if !single_shot_armed {
+                            plot_ui.vline(
+                                VLine::new(x_ms(self.pre_trigger))
+                                    .color(egui::Color32::from_gray(120))
+                                    .style(LineStyle::dashed_loose())
+                                    .name("Trigger point"),
+                            );
+                        }
+                        if !show_dbfs {
+                            plot_ui.hline(
+                                HLine::new(self.single_shot_level as f64 * gain as f64)
+                                    .color(egui::Color32::from_gray(120))
+                                    .style(LineStyle::dashed_loose())
+                                    .name("Trigger level"),
+                            );
+                        }
+                        if single_shot_armed {
+                            plot_ui.text(
+                                Text::new(PlotPoint::new(window_ms, y_max), "⏳ Armed - waiting for trigger")
+                                    .anchor(egui::Align2::RIGHT_TOP)
+                                    .color(egui::Color32::LIGHT_BLUE),
+                            );
+                        }
+                        return;
+                    }
+
+                    if self.trigger_enabled {
+                        let points: PlotPoints = self
+                            .trigger_trace
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &s)| [x_ms(i), display(s)])
+                            .collect();
+                        plot_ui.line(Line::new(points).name("Triggered sweep"));
+                        if !self.trigger_trace.is_empty() {
+                            let trigger_x = x_ms(self.trigger_pre.min(self.trigger_trace.len()));
+                            plot_ui.vline(VLine::new(trigger_x).color(egui::Color32::RED).name("Trigger point"));
+                            plot_ui.text(
+                                Text::new(PlotPoint::new(trigger_x, y_max), "T")
+                                    .anchor(egui::Align2::LEFT_TOP)
+                                    .color(egui::Color32::RED),
+                            );
+                        }
+                        if !self.show_dbfs {
+                            let level_y = self.trigger_level as f64 * gain as f64;
+                            let (drag_started, dragged) = {
+                                let response = plot_ui.response();
+                                (response.drag_started(), response.dragged())
+                            };
+                            let pointer = plot_ui.pointer_coordinate();
+                            if drag_started {
+                                let grab = (y_max - y_min) * TRIGGER_GRAB_FRACTION;
+                                self.trigger_level_dragged = pointer.is_some_and(|p| (p.y - level_y).abs() <= grab);
+                            }
+                            self.trigger_level_dragged &= dragged;
+                            if let Some(pointer) = pointer.filter(|_| self.trigger_level_dragged) {
+                                self.trigger_level = (pointer.y / gain as f64).clamp(-1.0, 1.0) as f32;
+                            }
+                            plot_ui.hline(
+                                HLine::new(self.trigger_level as f64 * gain as f64)
+                                    .color(egui::Color32::from_gray(120))
+                                    .style(LineStyle::dashed_loose())
+                                    .width(if self.trigger_level_dragged { 2.0 } else { 1.0 })
+                                    .name("Trigger level (drag to move)"),
+                            );
+                        }
+                        if self.trigger_state == TriggerState::Holding {
+                            plot_ui.text(
+                                Text::new(PlotPoint::new(window_ms, y_max), "❄ No trigger - holding")
+                                    .anchor(egui::Align2::RIGHT_TOP)
+                                    .color(egui::Color32::LIGHT_BLUE),
+                            );
+                        }
+                        return;
+                    }
+
+                    let points: PlotPoints = waveform
+                        .samples
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &s)| [x_ms(i), display(s)])
+                        .collect();
+
+                    let oldest = waveform.samples_written - waveform.samples.len() as u64;
+                    // Envelope behind the waveform: one trapezoid between neighbouring block
+                    // centres, each convex so the fill renders correctly
+                    let block = waveform.envelope_block.max(1);
+                    let envelope_end = waveform.samples_written - waveform.envelope_pending as u64;
+                    let envelope_start = envelope_end - (waveform.envelope.len() * block) as u64;
+                    let centre = |j: usize| x_ms((envelope_start - oldest) as usize + j * block + block / 2);
+                    let lower = |e: f32| if show_dbfs { y_min } else { display(-e) };
+                    let envelope_color = egui::Color32::from_rgb(100, 150, 255).gamma_multiply(0.25);
+                    for (j, (&e0, &e1)) in waveform.envelope.iter().zip(waveform.envelope.iter().skip(1)).enumerate() {
+                        let (x0, x1) = (centre(j), centre(j + 1));
+                        let corners = vec![[x0, lower(e0)], [x0, display(e0)], [x1, display(e1)], [x1, lower(e1)]];
+                        plot_ui.polygon(
+                            Polygon::new(PlotPoints::from(corners))
+                                .fill_color(envelope_color)
+                                .stroke(egui::Stroke::NONE),
+                        );
+                    }
+
+                    plot_ui.line(Line::new(points).name("Mean of channels"));
+                    if self.show_zcr {
+                        let zcr: PlotPoints = waveform
+                            .zcr_history
+                            .iter()
+                            .map(|&(end, zcr)| [x_ms(end.saturating_sub(oldest) as usize), zcr_to_y(zcr)])
+                            .collect();
+                        plot_ui.line(Line::new(zcr).color(egui::Color32::from_rgb(0, 160, 160)).name("ZCR (right axis)"));
+                    }
+                    if let Some((bpm, beats)) = &beat_grid {
+                        let name = format!("Beat grid ({:.0} BPM)", bpm);
+                        for &i in beats {
+                            plot_ui.vline(
+                                VLine::new(x_ms(i))
+                                    .color(egui::Color32::from_rgb(255, 140, 0).gamma_multiply(0.4))
+                                    .name(&name),
+                            );
+                        }
+                    }
+                    for &position in &waveform.clip_positions {
+                        plot_ui.vline(
+                            VLine::new(x_ms(position.saturating_sub(oldest) as usize))
+                                .color(egui::Color32::RED.gamma_multiply(0.5))
+                                .name("Clipped"),
+                        );
+                    }
+                    let peak_color = egui::Color32::from_rgb(255, 140, 0);
+                    if show_dbfs {
+                        plot_ui.hline(HLine::new(display(waveform.peak_hold)).color(peak_color).name("Peak hold"));
+                        for db in DBFS_REFERENCES {
+                            plot_ui.hline(
+                                HLine::new(db + level_offset)
+                                    .color(egui::Color32::from_gray(120))
+                                    .style(LineStyle::dashed_dense())
+                                    .name(format!("{} dBFS", db)),
+                            );
+                        }
+                    } else {
+                        let peak = display(waveform.peak_hold);
+                        for y in [peak, -peak] {
+                            plot_ui.hline(HLine::new(y).color(peak_color).name("Peak hold"));
+                        }
+                    }
+
+                    if self.show_channels {
+                        for (ch, ring) in waveform.channel_samples.iter().enumerate() {
+                            let points: PlotPoints = ring
+                                .iter()
+                                .enumerate()
+                                .map(|(i, &s)| [x_ms(i), display(s)])
+                                .collect();
+                            plot_ui.line(Line::new(points).name(format!("Ch{}", ch + 1)));
+                        }
+                    }
+
+                    if let Some(derivative) = derivative.filter(|_| !show_dbfs) {
+                        let points: PlotPoints = derivative
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &d)| [x_ms(i + 1), d as f64])
+                            .collect();
+                        plot_ui.line(Line::new(points).color(egui::Color32::LIGHT_RED).name("dy/dt (per sample)"));
+                    }
+                });
+            });
+            self.waveform = waveform;
+        });
+        // The panels above held the AudioData lock; it is released now
+        self.run_pending_dialog();
+        self.finish_stopped_capture();
+        self.measure_finished_capture();
+
+        ctx.request_repaint_after(Duration::from_millis(30));
+    }
+}
+
+fn auto_scale_bounds(peak_hold: f32) -> (f64, f64) {
+    let half = (peak_hold as f64 * AUTO_SCALE_HEADROOM).max(AUTO_SCALE_MIN);
+    (-half, half)
+}
+
+// First index where the signal crosses `level` going up
+fn find_rising_edge(samples: &[f32], level: f32) -> Option<usize> {
+    samples
+        .windows(2)
+        .position(|pair| pair[0] < level && pair[1] >= level)
+        .map(|i| i + 1)
+}
+
+// First rising edge with `pre` samples before it and `sweep - pre` after it
+fn find_trigger(samples: &[f32], sweep: usize, pre: usize, level: f32) -> Option<usize> {
+    // Latest trigger point that still leaves a whole sweep
+    let last = (samples.len() + pre).checked_sub(sweep)?;
+    let search = pre.saturating_sub(1);
+    let end = (last + 1).min(samples.len());
+    if end <= search {
+        return None;
+    }
+    find_rising_edge(&samples[search..end], level).map(|i| search + i)
+}
+
+// Central difference (s[i+1] - s[i-1]) / 2 for the inner samples, per sample; multiply
+// by the sample rate for 1/s
+fn waveform_derivative(samples: &[f32]) -> Vec<f32> {
+    samples
+        .iter()
+        .zip(samples.iter().skip(2))
+        .map(|(prev, next)| (next - prev) / 2.0)
+        .collect()
+}
+
+// The heatmap's (time, amplitude) histogram as an image, newest column on the right,
+// plus the mean of each column against time in ms
+fn waveform_heatmap(heatmap: &WaveformHeatmap) -> (egui::ColorImage, Vec<[f64; 2]>) {
+    let columns = heatmap.columns();
+    // Columns the history has not filled yet stay black on the left
+    let offset = HEATMAP_COLUMNS - columns.len();
+    let column_ms = heatmap.column_secs() as f64 * 1000.0;
+
+    let max = columns.iter().flat_map(|c| c.counts.iter().copied()).max().unwrap_or(0).max(1) as f32;
+    let mut pixels = vec![heat_color(0.0); HEATMAP_COLUMNS * HEATMAP_ROWS];
+    for (col, column) in columns.iter().enumerate() {
+        for (row, &count) in column.counts.iter().enumerate() {
+            pixels[row * HEATMAP_COLUMNS + offset + col] = heat_color(count as f32 / max);
+        }
+    }
+    let means = columns
+        .iter()
+        .enumerate()
+        .map(|(col, column)| [(offset + col) as f64 * column_ms + column_ms / 2.0, column.mean() as f64])
+        .collect();
+
+    let image = egui::ColorImage {
+        size: [HEATMAP_COLUMNS, HEATMAP_ROWS],
+        pixels,
+    };
+    (image, means)
+}
+
+// Black -> red -> yellow -> white
+fn heat_color(t: f32) -> egui::Color32 {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    let channel = |x: f32| (x.clamp(0.0, 1.0) * 255.0) as u8;
+    egui::Color32::from_rgb(channel(t), channel(t - 1.0), channel(t - 2.0))
+}
+
+// Yellow while the stream is being rebuilt, red once recovery has given up
+fn stream_banner(ui: &mut egui::Ui, status: &StreamStatus) {
+    if let Some(message) = status.message() {
+        let color = match status {
+            StreamStatus::Failed(_) => egui::Color32::RED,
+            _ => egui::Color32::YELLOW,
+        };
+        ui.label(egui::RichText::new(message).strong().color(egui::Color32::BLACK).background_color(color));
+    }
+}
+
+// Plays the signal `generator` makes for the output sample rate on the default output
+// device next to the running input stream, so the microphone captures it as the room
+// returns it
+fn play_test_tone(
+    generator: impl FnOnce(u32) -> TestToneGenerator,
+    finished: Arc<AtomicBool>,
+) -> anyhow::Result<cpal::Stream> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
+    let config = device.default_output_config()?;
+    let channels = config.channels() as usize;
+    let mut generator = generator(config.sample_rate().0);
+
+    let stream = device.build_output_stream(
+        &config.into(),
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            if !generator.fill(data, channels) {
+                finished.store(true, Ordering::Relaxed);
+            }
+        },
+        |err| eprintln!("Output stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    Ok(stream)
+}
+
+// Some drivers change the callback length during underruns
+fn callback_change_banner(ui: &mut egui::Ui, stats: &CallbackStats) {
+    let Some((at, from, to)) = stats.last_change else {
+        return;
+    };
+    if at.elapsed().as_secs_f32() < CALLBACK_CHANGE_BANNER_SECS {
+        ui.label(
+            egui::RichText::new(format!(
+                "⚠ Callback buffer changed from {} to {} samples - possible driver underrun",
+                from, to
+            ))
+            .strong()
+            .color(egui::Color32::BLACK)
+            .background_color(egui::Color32::YELLOW),
+        );
+    }
+}
+
+fn input_device_names() -> Vec<String> {
+    match cpal::default_host().input_devices() {
+        Ok(devices) => devices.filter_map(|d| d.name().ok()).collect(),
+        Err(e) => {
+            eprintln!("Failed to list input devices: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+// Runs one input stream at a time; a device name from `devices` replaces it
+// Starts on `initial`; devices picked in the UI later open at their default config
+// Stream errors are recovered by the guard, which reports through `status`
+fn start_audio_thread(
+    shared: Arc<Mutex<AudioData>>,
+    devices: channel::Receiver<String>,
+    buffer_len: Arc<AtomicUsize>,
+    status: Arc<Mutex<StreamStatus>>,
+    initial: (cpal::Device, cpal::SupportedStreamConfig),
+) {
+    thread::spawn(move || {
+        let host = cpal::default_host();
+        let (initial_device, initial_config) = initial;
+        let mut device = Some(initial_device);
+        let mut config = Some(initial_config);
+
+        loop {
+            let mut guard = match device.take() {
+                Some(device) => {
+                    let config = config.take();
+                    let shared = Arc::clone(&shared);
+                    let buffer_len = Arc::clone(&buffer_len);
+                    let build = move |errors: &StreamErrorFlag| {
+                        build_input_stream(&device, config.clone(), Arc::clone(&shared), Arc::clone(&buffer_len), errors)
+                    };
+                    match AudioStreamGuard::new(Arc::clone(&status), build) {
+                        Ok(guard) => Some(guard),
+                        Err(e) => {
+                            eprintln!("Failed to open input device: {}", e);
+                            None
+                        }
+                    }
+                }
+                None => {
+                    eprintln!("No input device found");
+                    None
+                }
+            };
+
+            // Keep the stream alive until another device is picked
+            let name = loop {
+                match devices.recv_timeout(WATCH_INTERVAL) {
+                    Ok(name) => break name,
+                    Err(channel::RecvTimeoutError::Timeout) => {
+                        if let Some(guard) = &mut guard {
+                            guard.watch();
+                        }
+                    }
+                    Err(channel::RecvTimeoutError::Disconnected) => return,
+                }
+            };
+            // Stop and drop the old stream before opening the new device
+            drop(guard);
+            device = host
+                .input_devices()
+                .ok()
+                .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        }
+    });
+}
+
+fn build_input_stream(
+    device: &cpal::Device,
+    config: Option<cpal::SupportedStreamConfig>,
+    shared: Arc<Mutex<AudioData>>,
+    buffer_len: Arc<AtomicUsize>,
+    errors: &StreamErrorFlag,
+) -> anyhow::Result<cpal::Stream> {
+    let config = match config {
+        Some(config) => config,
+        None => input_config(device, None)?,
+    };
+    let channels = config.channels() as usize;
+    let sample_rate = config.sample_rate().0;
+    reset_for_stream(&mut shared.lock().unwrap(), device.name().unwrap_or_default(), &config);
+
+    // The callback only queues its block. The DSP chain runs on a processing thread,
+    // which shares the lock with the UI, so the audio thread never waits for either.
+    let capacity = sample_rate as usize * channels * PROCESS_RING_SECS;
+    let mut producer = spawn_block_processor(capacity, channels, move |info, block| {
+        let mut buffer = shared.lock().unwrap();
+        buffer.callback_stats.record(info.callback_len, channels);
+        buffer.callback_stats.record_duration(info.duration);
+        buffer.callback_stats.dropped_samples += info.dropped as u64;
+        process_block(&mut buffer, block, channels, sample_rate, buffer_len.load(Ordering::Relaxed));
+    });
+    let sample_fn = move |data: &[f32], _: &cpal::InputCallbackInfo, started: Instant| {
+        producer.push(data, started);
+    };
+
+    let stream = build_input_stream_dynamic(device, &config, sample_fn, errors.callback())?;
+    stream.play()?;
+    Ok(stream)
+}
+
+// Prepares `data` for a stream from device `name` in `config`. Reopening the same
+// device in the same format, as error recovery does, keeps everything gathered so far,
+// the recording included; any other change starts over.
+fn reset_for_stream(data: &mut AudioData, name: String, config: &cpal::SupportedStreamConfig) {
+    if data.device_name == name && data.stream_config.as_ref() == Some(config) {
+        return;
+    }
+
+    // A recording cannot change format midway
+    if let Some(samples) = data.recording.take() {
+        data.interrupted_recording = Some((samples, data.channels as u16, data.sample_rate));
+    }
+    let channels = config.channels() as usize;
+    data.device_name = name;
+    data.sample_rate = config.sample_rate().0;
+    data.channels = channels;
+    data.samples.clear();
+    data.envelope.clear();
+    data.envelope_pending = (0.0, 0);
+    data.heatmap = WaveformHeatmap::default();
+    data.stereo.clear();
+    data.pitch_frame.clear();
+    data.channel_samples = vec![VecDeque::new(); channels];
+    data.channel_rms = Vec::with_capacity(channels);
+    data.channel_silenced = vec![false; channels];
+    data.drums.reset();
+    data.capture = None;
+    data.stream_config = Some(config.clone());
+    data.callback_stats = CallbackStats::default();
+    data.a_weighting = None;
+    data.room_response = None;
+    data.field_measurement = None;
+}
+
+// Runs one callback block of interleaved input through the DSP chain into `buffer`
+fn process_block(buffer: &mut AudioData, data: &[f32], channels: usize, sample_rate: u32, max_len: usize) {
+    let tap_capture_len = (sample_rate as f32 * TAP_CAPTURE_SECS) as usize;
+    // Muted and non-soloed channels are silent from here on, the recording included
+    let mut silenced_block = std::mem::take(&mut buffer.silenced_block);
+    let data = if buffer.channel_silenced.contains(&true) {
+        silence_channels(data, channels, &buffer.channel_silenced, &mut silenced_block);
+        silenced_block.as_slice()
+    } else {
+        data
+    };
+    if let Some(recording) = &mut buffer.recording {
+        recording.extend_from_slice(data);
+    }
+    if let Some(capture) = &buffer.capture {
+        // A full queue drops the block rather than stalling the processing
+        let _ = capture.try_send(data);
+    }
+
+    let mut max: f32 = 0.0;
+    let mut pre_gain_sum = 0.0;
+    let digital_gain = 10f32.powf(buffer.digital_gain_db / 20.0);
+    let rider_gain = if buffer.gain_rider_enabled {
+        buffer.gain_rider.gain()
+    } else {
+        1.0
+    };
+    let gain = digital_gain * rider_gain;
+    let mut gain_clipped = false;
+
+    let mut block_clipped = false;
+    buffer.block.clear();
+    buffer.unweighted_block.clear();
+    buffer.eq_block.clear();
+    buffer.field_block.clear();
+    for frame in data.chunks(channels) {
+        if let [l, r, ..] = *frame {
+            buffer.stereo.push_back([l, r]);
+            if buffer.stereo.len() > MAX_STEREO_PAIRS {
+                buffer.stereo.pop_front();
+            }
+        }
+
+        for (ring, &s) in buffer.channel_samples.iter_mut().zip(frame) {
+            ring.push_back(s);
+            if ring.len() > max_len {
+                ring.pop_front();
+            }
+        }
+
+        let input = if buffer.pressure_gradient && frame.len() >= 2 {
+            frame[0] - frame[1]
+        } else {
+            mix_down_unsilenced(frame, &buffer.channel_silenced)
+        };
+        pre_gain_sum += input * input;
+        buffer.field_block.push(input);
+        let gained = input * gain;
+        gain_clipped |= gained.abs() > 1.0;
+        let mut s = buffer.filter.process(gained, sample_rate);
+        for section in buffer.peq.iter_mut() {
+            s = section.process(s);
+        }
+        buffer.eq_block.push(s);
+        if buffer.wind_enabled {
+            s = buffer.wind.process(s, sample_rate);
+        }
+        // Also runs while disabled if the noise spectrum is being learned
+        if buffer.spectral_subtraction_enabled || buffer.spectral_subtraction.is_learning() {
+            let cleaned = buffer.spectral_subtraction.process(s);
+            if buffer.spectral_subtraction_enabled {
+                s = cleaned;
+            }
+        }
+        if buffer.noise_gate_enabled {
+            s = buffer.noise_gate.process(s, sample_rate);
+        }
+        if buffer.feedback_enabled {
+            s = buffer.feedback.process(s, sample_rate);
+        }
+        let weighted = if buffer.a_weighted {
+            buffer
+                .a_weighting
+                .get_or_insert_with(|| AWeightingFilter::new(sample_rate as f32))
+                .process_sample(s)
+        } else {
+            s
+        };
+        buffer.block.push(weighted);
+        buffer.unweighted_block.push(s);
+        max = max.max(s.abs());
+        if frame.iter().any(|x| x.abs() >= 1.0) {
+            block_clipped = true;
+            let position = buffer.samples_written;
+            buffer.clip_positions.push_back(position);
+        }
+        buffer.samples_written += 1;
+        buffer.tones.process(s, sample_rate);
+        buffer.leq.push(s, sample_rate);
+        buffer.sel.push(s, sample_rate);
+        buffer.heatmap.push(s, sample_rate);
+        buffer.pitch_frame.push_back(s);
+        if buffer.pitch_frame.len() > PITCH_FRAME_LEN {
+            buffer.pitch_frame.pop_front();
+        }
+        buffer.drums.push(s);
+        if buffer.onset.process(s, sample_rate) {
+            buffer.onset_detected = true;
+            let onset_start = buffer.samples_written.saturating_sub(buffer.onset.block_len() as u64);
+            buffer.last_onset_position = Some(onset_start);
+            let bpm = buffer.onset.bpm();
+            if let Some(drum) = buffer.drums.onset(buffer.onset.block_len(), bpm, sample_rate) {
+                buffer.drum_hit = Some(drum);
+            }
+            if buffer.sel_on_onset {
+                buffer.sel.trigger(buffer.onset.block_len());
+            }
+        }
+        buffer.tap.push(s, tap_capture_len);
+        buffer.single_shot.push(s, max_len);
+    }
+
+    let sum = sum_of_squares(&buffer.block);
+    if let Some(meter) = &mut buffer.room_response {
+        meter.process(&buffer.eq_block);
+    }
+    if let Some(measurement) = &mut buffer.field_measurement {
+        measurement.process(&buffer.field_block);
+    }
+    // The AGC gain is applied when the waveform is plotted, so everything reading
+    // `samples` sees the level as it is
+    let unweighted = std::mem::take(&mut buffer.unweighted_block);
+    buffer.lufs.process_block(&unweighted, sample_rate);
+    buffer.push_samples(&unweighted, max_len);
+    buffer.unweighted_block = unweighted;
+
+    if block_clipped {
+        buffer.clip_count += 1;
+        buffer.last_clip = Some(Instant::now());
+    }
+    // Forget clips that have scrolled out of the plot
+    let oldest = buffer.samples_written - buffer.samples.len() as u64;
+    while buffer.clip_positions.front().is_some_and(|&p| p < oldest) {
+        buffer.clip_positions.pop_front();
+    }
+
+    if buffer.noise_gate_enabled {
+        let buffer = &mut *buffer;
+        buffer.bin_floor.clear();
+        buffer.bin_floor.extend_from_slice(buffer.noise_gate.floor());
+    }
+
+    buffer.rms = (sum / (data.len() / channels).max(1) as f32).sqrt();
+    buffer.pre_gain_rms = (pre_gain_sum / (data.len() / channels).max(1) as f32).sqrt();
+    if gain_clipped {
+        buffer.last_gain_clip = Some(Instant::now());
+    }
+    if let Some((cal_sum, cal_frames)) = &mut buffer.noise_calibration {
+        *cal_sum += sum;
+        *cal_frames += data.len() / channels;
+    }
+    buffer.calibration.push(sum, data.len() / channels);
+    let rms = buffer.rms;
+    buffer.rms_history.push_back(rms);
+    if buffer.rms_history.len() > RMS_HISTORY_LEN {
+        buffer.rms_history.pop_front();
+    }
+    buffer.rms_smoother.update(rms, data.len() / channels, sample_rate);
+    buffer.rms_stats.push(rms, (data.len() / channels) as f32 / sample_rate as f32);
+    if buffer.agc_enabled {
+        buffer.agc.update(rms, (data.len() / channels) as f32 / sample_rate as f32);
+    }
+    if buffer.silence_enabled {
+        let smoothed = buffer.rms_smoother.value();
+        if let Some(event) = buffer.silence.update(smoothed, Instant::now()) {
+            buffer.silence_events.push(event);
+        }
+    }
+    buffer.zcr = zero_crossing_rate(&buffer.unweighted_block);
+    let zcr = buffer.zcr;
+    buffer.zcr_stats.window_secs = buffer.rms_stats.window_secs;
+    buffer.zcr_stats.push(zcr, (data.len() / channels) as f32 / sample_rate as f32);
+    let block_end = buffer.samples_written;
+    buffer.zcr_history.push_back((block_end, zcr));
+    let oldest = block_end - buffer.samples.len() as u64;
+    while buffer.zcr_history.front().is_some_and(|&(end, _)| end < oldest) {
+        buffer.zcr_history.pop_front();
+    }
+    channel_rms(data, channels, &mut buffer.channel_rms);
+    buffer.block_frames = data.len() / channels;
+    buffer.amplitude = max;
+    if max > buffer.peak_hold {
+        buffer.peak_hold = max;
+        buffer.peak_hold_age = Some(Instant::now());
+    }
+    if let Some(osc) = &buffer.osc {
+        // A full queue drops this block rather than stalling the processing
+        let _ = osc.try_send(OscMetrics {
+            rms,
+            amplitude: max,
+            peak_hold: buffer.peak_hold,
+        });
+    }
+
+    if buffer.gain_rider_enabled {
+        // The rider works on top of the digital gain
+        let rider_input = pre_gain_sum * digital_gain * digital_gain;
+        buffer.gain_rider.update(rider_input, data.len() / channels, sample_rate);
+    }
+    buffer.silenced_block = silenced_block;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn stream_config(channels: u16) -> cpal::SupportedStreamConfig {
+        cpal::SupportedStreamConfig::new(
+            channels,
+            cpal::SampleRate(48_000),
+            cpal::SupportedBufferSize::Unknown,
+            cpal::SampleFormat::F32,
+        )
+    }
+
+    #[test]
+    fn reopening_in_the_same_format_keeps_the_recording() {
+        let mut data = AudioData::default();
+        reset_for_stream(&mut data, "Mic".to_owned(), &stream_config(2));
+        data.recording = Some(vec![0.25; 8]);
+        data.samples.push_back(0.5);
+
+        reset_for_stream(&mut data, "Mic".to_owned(), &stream_config(2));
+        assert_eq!(data.recording.as_deref(), Some(&[0.25; 8][..]));
+        assert_eq!(data.samples.len(), 1);
+        assert!(data.interrupted_recording.is_none());
+    }
+
+    #[test]
+    fn reopening_in_another_format_hands_over_the_recording() {
+        let mut data = AudioData::default();
+        reset_for_stream(&mut data, "Mic".to_owned(), &stream_config(2));
+        data.recording = Some(vec![0.25; 8]);
+
+        reset_for_stream(&mut data, "Mic".to_owned(), &stream_config(1));
+        assert!(data.recording.is_none());
+        assert_eq!(data.interrupted_recording, Some((vec![0.25; 8], 2, 48_000)));
+        assert_eq!(data.channels, 1);
+    }
+
+    #[test]
+    fn waveform_snapshot_copies_the_channels_only_when_shown() {
+        let data = AudioData {
+            samples: VecDeque::from(vec![0.1, 0.2]),
+            channel_samples: vec![VecDeque::from(vec![0.3]); 2],
+            peak_hold: 0.5,
+            ..AudioData::default()
+        };
+        let mut waveform = WaveformSnapshot::default();
+        waveform.copy_from(&data, true);
+        assert_eq!(waveform.samples, [0.1, 0.2]);
+        assert_eq!(waveform.channel_samples, [[0.3], [0.3]]);
+        assert_eq!(waveform.peak_hold, 0.5);
+        waveform.copy_from(&data, false);
+        assert!(waveform.channel_samples.is_empty());
+    }
+
+    #[test]
+    fn a_muted_channel_does_not_reach_the_level() {
+        let mut buffer = AudioData {
+            channel_samples: vec![VecDeque::new(); 2],
+            channel_silenced: vec![true, false],
+            ..AudioData::default()
+        };
+        // Loud left channel, silent right one
+        let block: Vec<f32> = (0..4800).flat_map(|i| [0.5 * (i as f32 * 0.1).sin(), 0.0]).collect();
+        process_block(&mut buffer, &block, 2, 48_000, 4800);
+        assert_eq!(buffer.rms, 0.0);
+        assert_eq!(buffer.channel_rms, [0.0, 0.0]);
+        assert!(buffer.channel_samples[0].iter().all(|&s| s == 0.0));
+
+        buffer.channel_silenced = vec![false, true];
+        process_block(&mut buffer, &block, 2, 48_000, 4800);
+        assert!(buffer.rms > 0.3, "{}", buffer.rms);
+    }
+
+    #[test]
+    fn args_are_well_formed() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn args_parse_device_and_sample_rate() {
+        let args = Args::try_parse_from(["mic_2d", "--device", "USB Mic", "--sample-rate", "48000"]).unwrap();
+        assert_eq!(args.device.as_deref(), Some("USB Mic"));
+        assert_eq!(args.sample_rate, Some(48_000));
+        assert!(!args.ascii);
+    }
+
+    #[test]
+    fn args_default_to_the_default_device() {
+        let args = Args::try_parse_from(["mic_2d"]).unwrap();
+        assert_eq!(args.device, None);
+        assert_eq!(args.sample_rate, None);
+    }
+
+    #[test]
+    fn args_reject_a_non_numeric_sample_rate() {
+        assert!(Args::try_parse_from(["mic_2d", "--sample-rate", "fast"]).is_err());
+    }
+
+    #[test]
+    fn trigger_leaves_room_for_the_pre_trigger_samples() {
+        // Rising edges at 1 and 9
+        let samples = [0.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        assert_eq!(find_trigger(&samples, 6, 0, 0.5), Some(1));
+        // The first edge has too little before it
+        assert_eq!(find_trigger(&samples, 6, 3, 0.5), Some(9));
+        // ... and the second too little after it
+        assert_eq!(find_trigger(&samples, 6, 2, 0.5), None);
+    }
+
+    #[test]
+    fn trigger_needs_a_rising_edge() {
+        assert_eq!(find_trigger(&[1.0, 0.5, 0.0, -0.5], 2, 0, 0.2), None);
+        assert_eq!(find_trigger(&[], 0, 0, 0.0), None);
+    }
+
+    #[test]
+    fn event_csvs_log_the_air_conditions() {
+        let air = AirConditions {
+            temperature_c: 21.5,
+            humidity_pct: 40.0,
+        };
+        let path = std::env::temp_dir().join(format!("tone-events-air-{}.csv", std::process::id()));
+        let events = [ToneEvent {
+            frequency_hz: 1000.0,
+            start_secs: 1.5,
+            duration_secs: 0.25,
+            peak_dbfs: -12.0,
+        }];
+        write_tone_events_csv(&path, &events, air).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                "frequency_hz,start_s,duration_s,peak_dbfs,temperature_c,humidity_pct",
+                "1000.0,1.500,0.250,-12.0,21.5,40.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn sel_csv_reports_calibrated_events_in_dbspl() {
+        let air = AirConditions {
+            temperature_c: 20.0,
+            humidity_pct: 50.0,
+        };
+        let exposure = SoundExposure {
+            sel_dbfs: -30.0,
+            duration_secs: 0.25,
+            peak_dbfs: -20.0,
+        };
+        let timestamp = chrono::Local::now();
+        let events = [
+            SelEvent {
+                timestamp,
+                exposure,
+                air,
+                sensitivity_correction_db: Some(120.0),
+            },
+            SelEvent {
+                timestamp,
+                exposure,
+                air,
+                sensitivity_correction_db: None,
+            },
+        ];
+        let path = std::env::temp_dir().join(format!("sel-events-{}.csv", std::process::id()));
+        write_sel_events_csv(&path, &events).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let stamp = timestamp.to_rfc3339();
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                "timestamp,sel_db,duration_s,peak_db,unit,temperature_c,humidity_pct".to_owned(),
+                format!("{},90.0,0.250,100.0,dBSPL,20.0,50.0", stamp),
+                format!("{},-30.0,0.250,-20.0,dBFS,20.0,50.0", stamp),
+            ]
+        );
+    }
+
+    #[test]
+    fn args_parse_ws_simulation() {
+        let args = Args::try_parse_from(["mic_2d", "--ws-drop-rate", "0.25", "--ws-delay-ms", "80"]).unwrap();
+        assert_eq!(args.ws_drop_rate, 0.25);
+        assert_eq!(args.ws_delay_ms, 80);
+
+        let args = Args::try_parse_from(["mic_2d"]).unwrap();
+        assert_eq!(args.ws_drop_rate, 0.0);
+        assert_eq!(args.ws_delay_ms, 0);
+    }
+
+    #[test]
+    fn args_reject_a_drop_rate_outside_the_unit_interval() {
+        assert!(Args::try_parse_from(["mic_2d", "--ws-drop-rate", "1.5"]).is_err());
+        assert!(Args::try_parse_from(["mic_2d", "--ws-drop-rate", "-0.1"]).is_err());
+        assert!(Args::try_parse_from(["mic_2d", "--ws-drop-rate", "often"]).is_err());
+    }
 }
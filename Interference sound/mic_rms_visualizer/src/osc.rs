@@ -0,0 +1,113 @@
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use crossbeam::channel;
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+
+// Metrics waiting for the sender thread; when the network falls behind, new ones are dropped
+const QUEUE_LEN: usize = 64;
+
+/// Levels sent once per audio callback, all linear full scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OscMetrics {
+    pub rms: f32,
+    pub amplitude: f32,
+    pub peak_hold: f32,
+}
+
+/// One bundle with `/mic/rms`, `/mic/amplitude` and `/mic/peak_hold`, each a single float.
+pub fn encode_metrics(metrics: &OscMetrics) -> Result<Vec<u8>> {
+    let message = |addr: &str, value: f32| {
+        OscPacket::Message(OscMessage {
+            addr: addr.to_owned(),
+            args: vec![OscType::Float(value)],
+        })
+    };
+    let bundle = OscPacket::Bundle(OscBundle {
+        // The special "immediately" time tag
+        timetag: OscTime { seconds: 0, fractional: 1 },
+        content: vec![
+            message("/mic/rms", metrics.rms),
+            message("/mic/amplitude", metrics.amplitude),
+            message("/mic/peak_hold", metrics.peak_hold),
+        ],
+    });
+    rosc::encoder::encode(&bundle).map_err(|e| anyhow!("Cannot encode OSC bundle: {:?}", e))
+}
+
+/// Starts a thread sending every queued `OscMetrics` to `host:port` over UDP. The
+/// thread exits once the returned sender is dropped.
+pub fn start_osc_sender(host: &str, port: u16) -> Result<channel::Sender<OscMetrics>> {
+    let target = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("Cannot resolve {}:{}", host, port))?
+        .next()
+        .ok_or_else(|| anyhow!("No address for {}:{}", host, port))?;
+    let bind = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind).context("Cannot open a UDP socket")?;
+
+    let (sender, receiver) = channel::bounded::<OscMetrics>(QUEUE_LEN);
+    thread::spawn(move || {
+        for metrics in receiver {
+            let sent = encode_metrics(&metrics).and_then(|packet| Ok(socket.send_to(&packet, target)?));
+            if let Err(e) = sent {
+                eprintln!("OSC send failed: {:#}", e);
+            }
+        }
+    });
+    Ok(sender)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const METRICS: OscMetrics = OscMetrics {
+        rms: 0.25,
+        amplitude: 0.5,
+        peak_hold: 0.75,
+    };
+
+    fn decode(packet: &[u8]) -> Vec<(String, Vec<OscType>)> {
+        let (_, packet) = rosc::decoder::decode_udp(packet).unwrap();
+        let OscPacket::Bundle(bundle) = packet else {
+            panic!("Expected a bundle");
+        };
+        bundle
+            .content
+            .into_iter()
+            .map(|packet| match packet {
+                OscPacket::Message(message) => (message.addr, message.args),
+                OscPacket::Bundle(_) => panic!("Unexpected nested bundle"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bundle_carries_one_float_per_address() {
+        let messages = decode(&encode_metrics(&METRICS).unwrap());
+        assert_eq!(
+            messages,
+            [
+                ("/mic/rms".to_owned(), vec![OscType::Float(0.25)]),
+                ("/mic/amplitude".to_owned(), vec![OscType::Float(0.5)]),
+                ("/mic/peak_hold".to_owned(), vec![OscType::Float(0.75)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn sender_thread_delivers_over_udp() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let port = receiver.local_addr().unwrap().port();
+
+        let sender = start_osc_sender("127.0.0.1", port).unwrap();
+        sender.send(METRICS).unwrap();
+        let mut buffer = [0u8; 1024];
+        let len = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(buffer[..len], encode_metrics(&METRICS).unwrap()[..]);
+    }
+}
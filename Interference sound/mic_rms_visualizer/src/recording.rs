@@ -0,0 +1,264 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use id3::frame::ExtendedText;
+use id3::{TagLike, Version};
+
+use crate::dsp::replaygain::ReplayGain;
+
+/// How the backup copy of a recording fared.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Redundancy {
+    /// No backup directory is set.
+    Off,
+    Ok,
+    Failed(String),
+}
+
+impl Redundancy {
+    /// "Primary: OK, Redundant: OK" style summary, or `None` without a backup.
+    pub fn summary(&self) -> Option<String> {
+        match self {
+            Redundancy::Off => None,
+            Redundancy::Ok => Some("Primary: OK, Redundant: OK".to_owned()),
+            Redundancy::Failed(_) => Some("Primary: OK, Redundant: FAILED".to_owned()),
+        }
+    }
+}
+
+/// The backup of `path` in `dir`, under the same file name.
+pub fn backup_path(path: &Path, dir: &Path) -> PathBuf {
+    dir.join(path.file_name().unwrap_or_else(|| "recording.wav".as_ref()))
+}
+
+/// Errors unless the files at `primary` and `backup` have the same size.
+pub fn check_same_size(primary: &Path, backup: &Path) -> Result<()> {
+    let size = |path: &Path| {
+        std::fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .with_context(|| format!("Cannot read {}", path.display()))
+    };
+    let (primary_size, backup_size) = (size(primary)?, size(backup)?);
+    anyhow::ensure!(
+        primary_size == backup_size,
+        "{} is {} bytes but {} is {} bytes",
+        backup.display(),
+        backup_size,
+        primary.display(),
+        primary_size
+    );
+    Ok(())
+}
+
+fn wav_spec(channels: u16, sample_rate: u32) -> hound::WavSpec {
+    hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    }
+}
+
+/// Gain that brings the largest |sample| to `target_peak`. Silence gets a huge gain
+/// rather than an infinite one, so callers can warn about it.
+pub fn normalization_gain<'a>(samples: impl IntoIterator<Item = &'a f32>, target_peak: f32) -> f32 {
+    let peak = samples.into_iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    target_peak / peak.max(f32::MIN_POSITIVE)
+}
+
+/// Writes interleaved `f32` samples as a 32-bit float WAV file.
+pub fn write_wav(path: &Path, samples: &[f32], channels: u16, sample_rate: u32) -> Result<()> {
+    let mut writer = hound::WavWriter::create(path, wav_spec(channels, sample_rate))
+        .with_context(|| format!("Cannot create {}", path.display()))?;
+    for &s in samples {
+        writer.write_sample(s)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// `write_wav` to `path` and, if `backup_dir` is set, to the same file name in it,
+/// both in one pass over the samples. Only the primary file can fail the call; a
+/// failed backup is logged and reported in the returned `Redundancy`.
+pub fn write_wav_redundant(
+    path: &Path,
+    backup_dir: Option<&Path>,
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+) -> Result<Redundancy> {
+    let spec = wav_spec(channels, sample_rate);
+    let mut writer =
+        hound::WavWriter::create(path, spec).with_context(|| format!("Cannot create {}", path.display()))?;
+    let backup = backup_dir.map(|dir| backup_path(path, dir));
+    // Err once the backup has failed; the primary is written on regardless
+    let mut backup_writer = backup.as_ref().map(|backup| {
+        hound::WavWriter::create(backup, spec).with_context(|| format!("Cannot create {}", backup.display()))
+    });
+    for &s in samples {
+        writer.write_sample(s)?;
+        if let Some(Ok(backup)) = &mut backup_writer {
+            if let Err(e) = backup.write_sample(s) {
+                backup_writer = Some(Err(e.into()));
+            }
+        }
+    }
+    writer.finalize()?;
+
+    let (Some(backup), Some(backup_writer)) = (backup, backup_writer) else {
+        return Ok(Redundancy::Off);
+    };
+    let written = backup_writer
+        .and_then(|backup_writer| Ok(backup_writer.finalize()?))
+        .and_then(|()| check_same_size(path, &backup));
+    Ok(match written {
+        Ok(()) => {
+            eprintln!("Redundant copy written to {}", backup.display());
+            Redundancy::Ok
+        }
+        Err(e) => {
+            eprintln!("Redundant copy failed: {:#}", e);
+            Redundancy::Failed(format!("{:#}", e))
+        }
+    })
+}
+
+/// Reads a WAV file as interleaved `f32` samples, with its channel count and rate.
+pub fn read_wav(path: &Path) -> Result<(Vec<f32>, u16, u32)> {
+    let mut reader = hound::WavReader::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u32 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok((samples, spec.channels, spec.sample_rate))
+}
+
+/// Stores `gain` as REPLAYGAIN_TRACK_GAIN and REPLAYGAIN_TRACK_PEAK in an ID3 chunk
+/// of the WAV file at `path`.
+pub fn write_replay_gain_tags(path: &Path, gain: &ReplayGain) -> Result<()> {
+    let mut tag = id3::Tag::new();
+    tag.add_frame(ExtendedText {
+        description: "REPLAYGAIN_TRACK_GAIN".to_owned(),
+        value: format!("{:+.2} dB", gain.gain_db),
+    });
+    tag.add_frame(ExtendedText {
+        description: "REPLAYGAIN_TRACK_PEAK".to_owned(),
+        value: format!("{:.6}", gain.peak),
+    });
+    tag.write_to_path(path, Version::Id3v24)
+        .with_context(|| format!("Cannot tag {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn redundant_write_creates_two_identical_files() {
+        let primary_dir = temp_dir("recording-primary");
+        let backup_dir = temp_dir("recording-backup");
+        let path = primary_dir.join("take.wav");
+        let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.01).sin()).collect();
+
+        let redundancy = write_wav_redundant(&path, Some(&backup_dir), &samples, 2, 48_000).unwrap();
+        let primary = std::fs::read(&path).unwrap();
+        let backup = std::fs::read(backup_dir.join("take.wav")).unwrap();
+        let _ = std::fs::remove_dir_all(&primary_dir);
+        let _ = std::fs::remove_dir_all(&backup_dir);
+        assert_eq!(redundancy, Redundancy::Ok);
+        assert_eq!(primary, backup);
+    }
+
+    #[test]
+    fn failed_backup_keeps_the_primary() {
+        let primary_dir = temp_dir("recording-lone-primary");
+        let path = primary_dir.join("take.wav");
+        let missing = primary_dir.join("no-such-dir");
+
+        let redundancy = write_wav_redundant(&path, Some(&missing), &[0.5; 64], 1, 48_000).unwrap();
+        let primary_exists = path.exists();
+        let _ = std::fs::remove_dir_all(&primary_dir);
+        assert!(matches!(redundancy, Redundancy::Failed(_)));
+        assert!(primary_exists);
+        assert_eq!(redundancy.summary().as_deref(), Some("Primary: OK, Redundant: FAILED"));
+    }
+
+    #[test]
+    fn replay_gain_tags_are_readable_and_keep_the_audio() {
+        let dir = temp_dir("recording-tags");
+        let path = dir.join("take.wav");
+        let samples: Vec<f32> = (0..4800).map(|i| 0.25 * (i as f32 * 0.13).sin()).collect();
+        write_wav(&path, &samples, 1, 48_000).unwrap();
+
+        let gain = ReplayGain { gain_db: -4.5, peak: 0.25 };
+        write_replay_gain_tags(&path, &gain).unwrap();
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        let read_back = read_wav(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let values: Vec<(String, String)> =
+            tag.extended_texts().map(|t| (t.description.clone(), t.value.clone())).collect();
+        assert!(values.contains(&("REPLAYGAIN_TRACK_GAIN".to_owned(), "-4.50 dB".to_owned())));
+        assert!(values.contains(&("REPLAYGAIN_TRACK_PEAK".to_owned(), "0.250000".to_owned())));
+        assert_eq!(read_back, (samples, 1, 48_000));
+    }
+
+    #[test]
+    fn read_wav_scales_integer_samples() {
+        let dir = temp_dir("recording-int");
+        let path = dir.join("int.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for s in [0i16, 16384, -32768] {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+        let (samples, channels, sample_rate) = read_wav(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(samples, [0.0, 0.5, -1.0]);
+        assert_eq!((channels, sample_rate), (1, 8_000));
+    }
+
+    #[test]
+    fn normalization_brings_the_peak_to_the_target() {
+        let samples = [0.1, -0.25, 0.2];
+        let gain = normalization_gain(&samples, 0.708);
+        assert!((gain - 2.832).abs() < 1e-4);
+        let peak = samples.iter().map(|s| (s * gain).abs()).fold(0.0f32, f32::max);
+        assert!((peak - 0.708).abs() < 1e-6);
+    }
+
+    #[test]
+    fn normalizing_silence_gives_a_finite_gain() {
+        let gain = normalization_gain(&[0.0; 8], 0.708);
+        assert!(gain.is_finite() && gain > 1e30);
+    }
+
+    #[test]
+    fn no_backup_dir_means_no_redundancy() {
+        let dir = temp_dir("recording-single");
+        let path = dir.join("take.wav");
+        let redundancy = write_wav_redundant(&path, None, &[0.1; 16], 1, 8_000).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(redundancy, Redundancy::Off);
+        assert_eq!(redundancy.summary(), None);
+    }
+}
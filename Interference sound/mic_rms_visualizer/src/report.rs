@@ -0,0 +1,274 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use printpdf::{Color, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point, Rgb};
+
+use crate::dsp::spectrum::SpectralPeak;
+use crate::session::{Session, SessionComparison};
+
+// A4 portrait, in millimetres
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+
+// Plot area
+const PLOT_LEFT: f32 = MARGIN + 10.0;
+const PLOT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const PLOT_BOTTOM: f32 = 150.0;
+const PLOT_TOP: f32 = 230.0;
+
+// Comparison report: both sessions above, their difference below
+const COMPARISON_TOP: f32 = 245.0;
+const COMPARISON_BOTTOM: f32 = 185.0;
+const DIFFERENCE_TOP: f32 = 165.0;
+const DIFFERENCE_BOTTOM: f32 = 115.0;
+
+// Curve colours as RGB
+type CurveColor = (f32, f32, f32);
+const BLUE: CurveColor = (0.1, 0.3, 0.8);
+const ORANGE: CurveColor = (0.9, 0.5, 0.1);
+const RED: CurveColor = (0.8, 0.1, 0.1);
+
+pub struct SessionReport<'a> {
+    pub organization: &'a str,
+    pub device_name: &'a str,
+    pub sample_rate: u32,
+    pub date: String,
+    pub duration: Duration,
+    /// `(x_position, rms_amplitude)` pairs, sorted by X.
+    pub values: &'a [(f32, f32)],
+    pub peaks: &'a [SpectralPeak],
+}
+
+/// Two sessions for `write_comparison_pdf`, B compared against A.
+pub struct ComparisonReport<'a> {
+    pub a_name: &'a str,
+    pub b_name: &'a str,
+    pub a: &'a Session,
+    pub b: &'a Session,
+    pub comparison: &'a SessionComparison,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AmplitudeStats {
+    pub mean: f32,
+    pub max: f32,
+    pub min: f32,
+    pub std_dev: f32,
+}
+
+impl AmplitudeStats {
+    pub fn from_values(values: &[(f32, f32)]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let n = values.len() as f32;
+        let mean = values.iter().map(|&(_, a)| a).sum::<f32>() / n;
+        let variance = values.iter().map(|&(_, a)| (a - mean).powi(2)).sum::<f32>() / n;
+        Some(Self {
+            mean,
+            max: values.iter().map(|&(_, a)| a).fold(f32::MIN, f32::max),
+            min: values.iter().map(|&(_, a)| a).fold(f32::MAX, f32::min),
+            std_dev: variance.sqrt(),
+        })
+    }
+}
+
+/// Writes a one-page PDF summary of the session. Text uses egui's bundled
+/// Ubuntu font, embedded in the file, so non-ASCII annotations render anywhere.
+pub fn write_pdf(path: &Path, report: &SessionReport) -> Result<()> {
+    let (doc, page, layer) = PdfDocument::new(
+        "Measurement report",
+        Mm(PAGE_WIDTH),
+        Mm(PAGE_HEIGHT),
+        "Report",
+    );
+    let fonts = egui::FontDefinitions::default();
+    let font_data = fonts
+        .font_data
+        .get("Ubuntu-Light")
+        .ok_or_else(|| anyhow!("Bundled font is not available"))?;
+    let font = doc.add_external_font(font_data.font.as_ref())?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    let text = |s: &str, size: f32, x: f32, y: f32| layer.use_text(s, size, Mm(x), Mm(y), &font);
+
+    text("Amplitude vs X Position - Measurement Report", 16.0, MARGIN, 280.0);
+    if !report.organization.is_empty() {
+        text(report.organization, 12.0, MARGIN, 272.0);
+    }
+    text(&format!("Device: {}", report.device_name), 10.0, MARGIN, 262.0);
+    text(&format!("Sample rate: {} Hz", report.sample_rate), 10.0, MARGIN, 256.0);
+    text(&format!("Date: {}", report.date), 10.0, MARGIN, 250.0);
+    let secs = report.duration.as_secs();
+    text(
+        &format!("Duration: {:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+        10.0,
+        MARGIN,
+        244.0,
+    );
+
+    draw_plot(&layer, &font, (PLOT_BOTTOM, PLOT_TOP), "RMS", &[(report.values, BLUE)]);
+
+    text("Summary statistics (RMS amplitude)", 12.0, MARGIN, 132.0);
+    let mut y = 124.0;
+    match AmplitudeStats::from_values(report.values) {
+        Some(stats) => {
+            for (label, value) in [
+                ("Mean", stats.mean),
+                ("Max", stats.max),
+                ("Min", stats.min),
+                ("Std dev", stats.std_dev),
+            ] {
+                text(label, 10.0, MARGIN, y);
+                text(&format!("{:.6}", value), 10.0, MARGIN + 40.0, y);
+                y -= 6.0;
+            }
+            text("Points", 10.0, MARGIN, y);
+            text(&report.values.len().to_string(), 10.0, MARGIN + 40.0, y);
+        }
+        None => text("No measurements recorded", 10.0, MARGIN, y),
+    }
+
+    text("Top spectral peaks", 12.0, 110.0, 132.0);
+    let mut y = 124.0;
+    if report.peaks.is_empty() {
+        text("No spectrum captured", 10.0, 110.0, y);
+    }
+    for (i, peak) in report.peaks.iter().enumerate() {
+        text(
+            &format!("{}. {:.1} Hz  {:.1} dBFS", i + 1, peak.frequency_hz, peak.level_dbfs),
+            10.0,
+            110.0,
+            y,
+        );
+        y -= 6.0;
+    }
+
+    doc.save(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+/// Writes a one-page PDF comparing two sessions: both amplitude curves, their
+/// difference and the difference statistics.
+pub fn write_comparison_pdf(path: &Path, report: &ComparisonReport) -> Result<()> {
+    let (doc, page, layer) = PdfDocument::new("Session comparison", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+    let fonts = egui::FontDefinitions::default();
+    let font_data = fonts
+        .font_data
+        .get("Ubuntu-Light")
+        .ok_or_else(|| anyhow!("Bundled font is not available"))?;
+    let font = doc.add_external_font(font_data.font.as_ref())?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    let text = |s: &str, size: f32, x: f32, y: f32| layer.use_text(s, size, Mm(x), Mm(y), &font);
+
+    text("Amplitude vs X Position - Session Comparison", 16.0, MARGIN, 280.0);
+    for (y, label, name, session) in [(270.0, "A", report.a_name, report.a), (264.0, "B", report.b_name, report.b)] {
+        text(
+            &format!("{} (blue/orange): {} - {}, {} Hz, {}", label, name, session.device_name, session.sample_rate, session.date),
+            10.0,
+            MARGIN,
+            y,
+        );
+    }
+
+    draw_plot(
+        &layer,
+        &font,
+        (COMPARISON_BOTTOM, COMPARISON_TOP),
+        "RMS",
+        &[(&report.a.values, BLUE), (&report.b.values, ORANGE)],
+    );
+    draw_plot(
+        &layer,
+        &font,
+        (DIFFERENCE_BOTTOM, DIFFERENCE_TOP),
+        "B - A",
+        &[(&report.comparison.amplitude_difference, RED)],
+    );
+
+    text("Difference statistics", 12.0, MARGIN, 97.0);
+    let similarity = match report.comparison.similarity {
+        Some(r) => format!("{:.1} %", r * 100.0),
+        None => "n/a (no common X range)".to_owned(),
+    };
+    let stats = |stats: Option<(f32, f32)>, digits: usize| match stats {
+        Some((mean, max)) => format!("mean {:+.*}, largest {:.*}", digits, mean, digits, max),
+        None => "n/a".to_owned(),
+    };
+    let rows = [
+        ("Similarity (Pearson)", similarity),
+        ("Amplitude B - A", stats(report.comparison.amplitude_difference_stats(), 6)),
+        ("Spectrum B - A (dB)", stats(report.comparison.spectrum_difference_stats(), 1)),
+        ("Points A / B", format!("{} / {}", report.a.values.len(), report.b.values.len())),
+    ];
+    let mut y = 89.0;
+    for (label, value) in rows {
+        text(label, 10.0, MARGIN, y);
+        text(&value, 10.0, MARGIN + 50.0, y);
+        y -= 6.0;
+    }
+
+    doc.save(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+// Axes, range labels and `curves` (values and colour) as PDF path segments between
+// `bottom` and `top`. The Y axis includes zero.
+fn draw_plot(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    (bottom, top): (f32, f32),
+    y_label: &str,
+    curves: &[(&[(f32, f32)], CurveColor)],
+) {
+    let point = |x: f32, y: f32| (Point::new(Mm(x), Mm(y)), false);
+
+    layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    layer.set_outline_thickness(0.8);
+    layer.add_line(Line {
+        points: vec![
+            point(PLOT_LEFT, bottom),
+            point(PLOT_RIGHT, bottom),
+            point(PLOT_RIGHT, top),
+            point(PLOT_LEFT, top),
+        ],
+        is_closed: true,
+    });
+
+    let all = || curves.iter().flat_map(|(values, _)| values.iter());
+    let (x_min, x_max) = all().fold((f32::MAX, f32::MIN), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+    let (y_min, y_max) = all().fold((0.0f32, 0.0f32), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+    let (x_min, x_max) = if all().next().is_none() { (0.0, 100.0) } else { (x_min, x_max) };
+    let x_span = (x_max - x_min).max(f32::EPSILON);
+    let y_span = (y_max - y_min).max(f32::EPSILON);
+
+    let label = |s: String, x: f32, y: f32| layer.use_text(s, 8.0, Mm(x), Mm(y), font);
+    label(format!("{:.2}", x_min), PLOT_LEFT, bottom - 5.0);
+    label(format!("{:.2}", x_max), PLOT_RIGHT - 10.0, bottom - 5.0);
+    label("X position".to_owned(), (PLOT_LEFT + PLOT_RIGHT) / 2.0 - 8.0, bottom - 5.0);
+    label(if y_min == 0.0 { "0".to_owned() } else { format!("{:.3}", y_min) }, PLOT_LEFT - 10.0, bottom);
+    label(format!("{:.3}", y_max), PLOT_LEFT - 10.0, top - 3.0);
+    label(y_label.to_owned(), PLOT_LEFT - 10.0, (bottom + top) / 2.0);
+
+    layer.set_outline_thickness(1.0);
+    for &(values, (r, g, b)) in curves.iter().filter(|(values, _)| values.len() >= 2) {
+        layer.set_outline_color(Color::Rgb(Rgb::new(r, g, b, None)));
+        layer.add_line(Line {
+            points: values
+                .iter()
+                .map(|&(x, y)| {
+                    point(
+                        PLOT_LEFT + (x - x_min) / x_span * (PLOT_RIGHT - PLOT_LEFT),
+                        bottom + (y - y_min) / y_span * (top - bottom),
+                    )
+                })
+                .collect(),
+            is_closed: false,
+        });
+    }
+}
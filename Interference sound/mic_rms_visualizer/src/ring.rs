@@ -0,0 +1,210 @@
+//! Lock-free hand-off of input blocks from the audio callback to a processing thread.
+//!
+//! The callback writes interleaved samples to one single-producer/single-consumer
+//! ring and a `BlockInfo` per callback to a second one, so the processing thread
+//! sees the same block boundaries as the driver. Neither side blocks, and after
+//! the first blocks neither side allocates.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rtrb::{Consumer, Producer, RingBuffer};
+
+// Callback blocks that can wait for the processing thread
+const BLOCK_ENTRIES: usize = 4096;
+// How often `spawn_block_processor` looks for new blocks
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// One callback as the processing thread sees it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BlockInfo {
+    /// Samples (all channels) the driver delivered.
+    pub callback_len: usize,
+    /// Samples of this block that made it into the ring.
+    pub len: usize,
+    /// Samples lost since the previous block because a ring was full.
+    pub dropped: usize,
+    /// Time from the start of the callback until the block was queued.
+    pub duration: Duration,
+}
+
+/// Audio-thread end of the ring; see `block_ring`.
+pub struct BlockProducer {
+    samples: Producer<f32>,
+    blocks: Producer<BlockInfo>,
+    channels: usize,
+    // Samples lost since the last queued BlockInfo
+    dropped: usize,
+}
+
+/// Processing-thread end of the ring; see `block_ring`.
+pub struct BlockConsumer {
+    samples: Consumer<f32>,
+    blocks: Consumer<BlockInfo>,
+    // The popped block as one slice, reused across calls
+    block: Vec<f32>,
+}
+
+/// Creates a ring holding up to `capacity` interleaved samples of `channels` channels.
+pub fn block_ring(capacity: usize, channels: usize) -> (BlockProducer, BlockConsumer) {
+    let (sample_producer, sample_consumer) = RingBuffer::new(capacity);
+    let (block_producer, block_consumer) = RingBuffer::new(BLOCK_ENTRIES);
+    let producer = BlockProducer {
+        samples: sample_producer,
+        blocks: block_producer,
+        channels: channels.max(1),
+        dropped: 0,
+    };
+    let consumer = BlockConsumer {
+        samples: sample_consumer,
+        blocks: block_consumer,
+        block: Vec::with_capacity(capacity),
+    };
+    (producer, consumer)
+}
+
+/// Creates a ring like `block_ring` and a thread that calls `process` with each
+/// queued block, in order. `process` may lock and allocate, so the callback only has
+/// to push. The thread ends with the producer; blocks it had not processed yet are
+/// dropped.
+pub fn spawn_block_processor<P>(capacity: usize, channels: usize, mut process: P) -> BlockProducer
+where
+    P: FnMut(BlockInfo, &[f32]) + Send + 'static,
+{
+    let (producer, mut consumer) = block_ring(capacity, channels);
+    thread::spawn(move || {
+        while !consumer.is_abandoned() {
+            while let Some((info, block)) = consumer.pop() {
+                process(info, block);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    producer
+}
+
+impl BlockProducer {
+    /// Queues one callback block without blocking. `started` is when the callback began,
+    /// for the duration in its `BlockInfo`. Whole frames that do not fit are dropped and
+    /// counted in the next `BlockInfo`.
+    pub fn push(&mut self, data: &[f32], started: Instant) {
+        // Samples without an entry could not be matched to a block later
+        if self.blocks.is_full() {
+            self.dropped += data.len();
+            return;
+        }
+        let room = self.samples.slots() / self.channels * self.channels;
+        let len = data.len().min(room);
+        let written = match self.samples.write_chunk_uninit(len) {
+            Ok(chunk) => chunk.fill_from_iter(data[..len].iter().copied()),
+            Err(_) => 0,
+        };
+        let info = BlockInfo {
+            callback_len: data.len(),
+            len: written,
+            dropped: self.dropped + data.len() - written,
+            duration: started.elapsed(),
+        };
+        if self.blocks.push(info).is_ok() {
+            self.dropped = 0;
+        }
+    }
+}
+
+impl BlockConsumer {
+    /// The oldest queued block and its samples, or `None` if the callback has not
+    /// queued anything since the last call.
+    pub fn pop(&mut self) -> Option<(BlockInfo, &[f32])> {
+        let info = self.blocks.pop().ok()?;
+        self.block.clear();
+        // The samples were committed before the entry, so they are all there
+        if let Ok(chunk) = self.samples.read_chunk(info.len) {
+            let (first, second) = chunk.as_slices();
+            self.block.extend_from_slice(first);
+            self.block.extend_from_slice(second);
+            chunk.commit_all();
+        }
+        Some((info, &self.block))
+    }
+
+    /// True once the producer, and with it the stream callback, has been dropped.
+    pub fn is_abandoned(&self) -> bool {
+        self.blocks.is_abandoned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_come_out_whole_and_in_order() {
+        let (mut producer, mut consumer) = block_ring(16, 2);
+        producer.push(&[1.0, 2.0, 3.0, 4.0], Instant::now());
+        producer.push(&[5.0, 6.0], Instant::now());
+
+        let (info, block) = consumer.pop().unwrap();
+        assert_eq!((info.callback_len, info.len, info.dropped), (4, 4, 0));
+        assert_eq!(block, [1.0, 2.0, 3.0, 4.0]);
+        let (info, block) = consumer.pop().unwrap();
+        assert_eq!(info.len, 2);
+        assert_eq!(block, [5.0, 6.0]);
+        assert!(consumer.pop().is_none());
+    }
+
+    #[test]
+    fn blocks_survive_the_wrap_around() {
+        let (mut producer, mut consumer) = block_ring(6, 1);
+        for round in 0..5 {
+            let data: Vec<f32> = (0..4).map(|i| (round * 4 + i) as f32).collect();
+            producer.push(&data, Instant::now());
+            let (_, block) = consumer.pop().unwrap();
+            assert_eq!(block, data.as_slice());
+        }
+    }
+
+    #[test]
+    fn a_full_ring_drops_whole_frames_and_reports_them() {
+        let (mut producer, mut consumer) = block_ring(5, 2);
+        producer.push(&[0.1; 6], Instant::now());
+
+        // Only two stereo frames fit in five slots
+        let (info, block) = consumer.pop().unwrap();
+        assert_eq!((info.callback_len, info.len, info.dropped), (6, 4, 2));
+        assert_eq!(block.len(), 4);
+    }
+
+    #[test]
+    fn duration_counts_from_the_callback_entry() {
+        let (mut producer, mut consumer) = block_ring(8, 1);
+        let entered = Instant::now() - Duration::from_millis(5);
+        producer.push(&[0.0; 4], entered);
+        let (info, _) = consumer.pop().unwrap();
+        assert!(info.duration >= Duration::from_millis(5), "{:?}", info.duration);
+    }
+
+    #[test]
+    fn the_processor_thread_gets_every_block() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut producer = spawn_block_processor(16, 1, move |info, block| {
+            let _ = sender.send((info.len, block.to_vec()));
+        });
+        producer.push(&[1.0, 2.0], Instant::now());
+        producer.push(&[3.0], Instant::now());
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(receiver.recv_timeout(timeout).unwrap(), (2, vec![1.0, 2.0]));
+        assert_eq!(receiver.recv_timeout(timeout).unwrap(), (1, vec![3.0]));
+        // The thread, and the sender it owns, go away with the producer
+        drop(producer);
+        assert!(receiver.recv_timeout(timeout).is_err());
+    }
+
+    #[test]
+    fn consumer_notices_the_callback_going_away() {
+        let (producer, consumer) = block_ring(8, 1);
+        assert!(!consumer.is_abandoned());
+        drop(producer);
+        assert!(consumer.is_abandoned());
+    }
+}
@@ -0,0 +1,34 @@
+// Distances below this are clamped so a grid point on top of a source stays finite
+const MIN_DISTANCE: f32 = 0.01;
+
+/// Axis-aligned shoebox room, in the same units as the measurement grid.
+#[derive(Clone, Copy, Debug)]
+pub struct RoomBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl RoomBox {
+    /// Mirrors `source` in each of the six walls (first-order image sources).
+    pub fn first_order_images(&self, source: [f32; 3]) -> [[f32; 3]; 6] {
+        let mut images = [source; 6];
+        for axis in 0..3 {
+            images[axis * 2][axis] = 2.0 * self.min[axis] - source[axis];
+            images[axis * 2 + 1][axis] = 2.0 * self.max[axis] - source[axis];
+        }
+        images
+    }
+
+    /// Simulated pressure amplitude at `point`: the 1/r contributions of the direct
+    /// source and its first-order images, with perfectly reflecting walls.
+    pub fn amplitude_at(&self, source: [f32; 3], point: [f32; 3]) -> f32 {
+        std::iter::once(source)
+            .chain(self.first_order_images(source))
+            .map(|s| 1.0 / distance(s, point).max(MIN_DISTANCE))
+            .sum()
+    }
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt()
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+// How long the "Saved" / error toast stays up
+const TOAST_SECS: f32 = 2.0;
+// A backend that cannot take screenshots never answers; give up after this long
+const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `mic_visualizer_YYYYMMDD_HHMMSS.png` in the current directory.
+pub fn screenshot_path() -> PathBuf {
+    PathBuf::from(chrono::Local::now().format("mic_visualizer_%Y%m%d_%H%M%S.png").to_string())
+}
+
+/// Writes an egui screenshot as an RGBA PNG.
+pub fn save_png(path: &Path, screenshot: &egui::ColorImage) -> Result<()> {
+    let [width, height] = screenshot.size;
+    let rgba: Vec<u8> = screenshot.pixels.iter().flat_map(|c| c.to_array()).collect();
+    image::save_buffer(path, &rgba, width as u32, height as u32, image::ColorType::Rgba8)
+        .with_context(|| format!("Cannot write {}", path.display()))
+}
+
+struct Toast {
+    text: String,
+    is_error: bool,
+    shown: Instant,
+}
+
+/// Ctrl+P screenshots for the eframe windows. Call `update` at the start of every frame.
+#[derive(Default)]
+pub struct ScreenshotExporter {
+    requested: Option<Instant>,
+    toast: Option<Toast>,
+}
+
+impl ScreenshotExporter {
+    pub fn update(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::P)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+            self.requested = Some(Instant::now());
+        }
+
+        let screenshot = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(Arc::clone(image)),
+                _ => None,
+            })
+        });
+        if let Some(screenshot) = screenshot {
+            self.requested = None;
+            let path = screenshot_path();
+            self.show_toast(match save_png(&path, &screenshot) {
+                Ok(()) => Ok(format!("Saved: {}", path.display())),
+                Err(e) => Err(format!("Screenshot failed: {:#}", e)),
+            });
+        } else if self.requested.is_some_and(|t| t.elapsed() >= SCREENSHOT_TIMEOUT) {
+            self.requested = None;
+            self.show_toast(Err("Screenshots are not available on this display".to_owned()));
+        }
+        if self.requested.is_some() {
+            ctx.request_repaint();
+        }
+
+        if self.toast.as_ref().is_some_and(|t| t.shown.elapsed().as_secs_f32() >= TOAST_SECS) {
+            self.toast = None;
+        }
+        if let Some(toast) = &self.toast {
+            egui::Area::new(egui::Id::new("screenshot_toast"))
+                .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        let color = if toast.is_error { egui::Color32::RED } else { egui::Color32::LIGHT_GREEN };
+                        ui.colored_label(color, &toast.text);
+                    });
+                });
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    fn show_toast(&mut self, message: Result<String, String>) {
+        let is_error = message.is_err();
+        self.toast = Some(Toast {
+            text: message.unwrap_or_else(|e| e),
+            is_error,
+            shown: Instant::now(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screenshot_names_carry_the_timestamp() {
+        let name = screenshot_path().to_string_lossy().into_owned();
+        assert!(name.starts_with("mic_visualizer_") && name.ends_with(".png"), "{}", name);
+        // mic_visualizer_YYYYMMDD_HHMMSS.png
+        assert_eq!(name.len(), "mic_visualizer_".len() + 15 + ".png".len());
+    }
+
+    #[test]
+    fn png_keeps_every_pixel() {
+        let pixels = vec![
+            egui::Color32::RED,
+            egui::Color32::GREEN,
+            egui::Color32::BLUE,
+            egui::Color32::from_rgba_premultiplied(10, 20, 30, 255),
+            egui::Color32::WHITE,
+            egui::Color32::BLACK,
+        ];
+        let screenshot = egui::ColorImage {
+            size: [3, 2],
+            pixels: pixels.clone(),
+        };
+        let path = std::env::temp_dir().join(format!("screenshot-test-{}.png", std::process::id()));
+        save_png(&path, &screenshot).unwrap();
+        let read = image::open(&path).unwrap().to_rgba8();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(read.dimensions(), (3, 2));
+        let expected: Vec<u8> = pixels.iter().flat_map(|c| c.to_array()).collect();
+        assert_eq!(read.into_raw(), expected);
+    }
+
+    #[test]
+    fn unanswered_request_turns_into_an_error_toast() {
+        let ctx = egui::Context::default();
+        let mut exporter = ScreenshotExporter {
+            requested: Some(Instant::now() - SCREENSHOT_TIMEOUT),
+            toast: None,
+        };
+        let _ = ctx.run(egui::RawInput::default(), |ctx| exporter.update(ctx));
+        assert!(exporter.requested.is_none());
+        assert!(exporter.toast.as_ref().is_some_and(|t| t.is_error));
+    }
+}
@@ -0,0 +1,241 @@
+//! `.mrviz` session files: one amplitude-vs-X measurement with the spectrum taken at
+//! the end of it, saved by `mic_2d_A_vs_x` and compared by `mic_session_compare`.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::spectrum::magnitude_spectrum_dbfs;
+
+pub const SESSION_EXTENSION: &str = "mrviz";
+// Written into every file; files of another version are refused
+const SESSION_VERSION: u32 = 1;
+
+// The two amplitude curves are compared at this many X positions
+const COMPARISON_POINTS: usize = 200;
+
+/// One saved measurement, stored as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub device_name: String,
+    pub sample_rate: u32,
+    pub date: String,
+    pub temperature_c: f32,
+    pub humidity_pct: f32,
+    /// Mean RMS amplitude per X position, sorted by X; the rows of the CSV export.
+    pub values: Vec<(f32, f32)>,
+    /// `(frequency Hz, dBFS)` per FFT bin, DC left out.
+    pub spectrum: Vec<(f32, f32)>,
+}
+
+impl Session {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Cannot create {}", path.display()))?;
+        serde_json::to_writer(BufWriter::new(file), &Session { version: SESSION_VERSION, ..self.clone() })?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let mut session: Session = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{} is not a session file", path.display()))?;
+        if session.version != SESSION_VERSION {
+            return Err(anyhow!("Unsupported session file version {}", session.version));
+        }
+        session.values.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(session)
+    }
+}
+
+/// Spectrum of `samples` for a session, one point per bin above DC.
+pub fn spectrum_points(samples: &[f32], sample_rate: u32) -> Vec<(f32, f32)> {
+    let bin_hz = sample_rate as f32 / samples.len().max(1) as f32;
+    magnitude_spectrum_dbfs(samples)
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, db)| (i as f32 * bin_hz, db))
+        .collect()
+}
+
+/// Linear interpolation in `points` (sorted by X) at `x`; `None` outside their range.
+pub fn interpolate_at(points: &[(f32, f32)], x: f32) -> Option<f32> {
+    // First point at or after `x`
+    let i = points.partition_point(|&(px, _)| px < x);
+    let &(x1, y1) = points.get(i)?;
+    if x1 == x {
+        return Some(y1);
+    }
+    let &(x0, y0) = points.get(i.checked_sub(1)?)?;
+    Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+}
+
+/// Pearson correlation of two equally long series; `None` for fewer than two
+/// points or a series that does not vary.
+pub fn pearson(a: &[f32], b: &[f32]) -> Option<f32> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let mean = |s: &[f32]| s[..n].iter().sum::<f32>() / n as f32;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a[..n].iter().zip(&b[..n]) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    (var_a > 0.0 && var_b > 0.0).then(|| cov / (var_a * var_b).sqrt())
+}
+
+/// Session B against session A, over the X range and frequencies both cover.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionComparison {
+    /// `(x, B - A)` on an even grid.
+    pub amplitude_difference: Vec<(f32, f32)>,
+    /// `(frequency Hz, B - A in dB)` at A's bins.
+    pub spectrum_difference: Vec<(f32, f32)>,
+    /// Pearson correlation of the two amplitude curves on the grid.
+    pub similarity: Option<f32>,
+}
+
+impl SessionComparison {
+    pub fn new(a: &Session, b: &Session) -> Self {
+        let range = |values: &[(f32, f32)]| Some((values.first()?.0, values.last()?.0));
+        let mut amplitude_difference = Vec::new();
+        let (mut grid_a, mut grid_b) = (Vec::new(), Vec::new());
+        if let (Some((a_min, a_max)), Some((b_min, b_max))) = (range(&a.values), range(&b.values)) {
+            let (lo, hi) = (a_min.max(b_min), a_max.min(b_max));
+            if hi > lo {
+                for i in 0..COMPARISON_POINTS {
+                    let x = lo + (hi - lo) * i as f32 / (COMPARISON_POINTS - 1) as f32;
+                    if let (Some(ya), Some(yb)) = (interpolate_at(&a.values, x), interpolate_at(&b.values, x)) {
+                        grid_a.push(ya);
+                        grid_b.push(yb);
+                        amplitude_difference.push((x, yb - ya));
+                    }
+                }
+            }
+        }
+
+        let spectrum_difference = a
+            .spectrum
+            .iter()
+            .filter_map(|&(f, db_a)| interpolate_at(&b.spectrum, f).map(|db_b| (f, db_b - db_a)))
+            .collect();
+
+        Self {
+            amplitude_difference,
+            spectrum_difference,
+            similarity: pearson(&grid_a, &grid_b),
+        }
+    }
+
+    /// Mean and largest absolute amplitude difference.
+    pub fn amplitude_difference_stats(&self) -> Option<(f32, f32)> {
+        difference_stats(&self.amplitude_difference)
+    }
+
+    /// Mean and largest absolute spectrum difference in dB.
+    pub fn spectrum_difference_stats(&self) -> Option<(f32, f32)> {
+        difference_stats(&self.spectrum_difference)
+    }
+}
+
+fn difference_stats(difference: &[(f32, f32)]) -> Option<(f32, f32)> {
+    if difference.is_empty() {
+        return None;
+    }
+    let mean = difference.iter().map(|&(_, d)| d).sum::<f32>() / difference.len() as f32;
+    let max = difference.iter().map(|&(_, d)| d.abs()).fold(0.0, f32::max);
+    Some((mean, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(values: Vec<(f32, f32)>, spectrum: Vec<(f32, f32)>) -> Session {
+        Session {
+            values,
+            spectrum,
+            ..Session::default()
+        }
+    }
+
+    #[test]
+    fn sessions_round_trip() {
+        let path = std::env::temp_dir().join(format!("session-{}.{}", std::process::id(), SESSION_EXTENSION));
+        let saved = Session {
+            device_name: "USB Mic".to_owned(),
+            sample_rate: 48_000,
+            date: "2026-10-15 10:00:00".to_owned(),
+            ..session(vec![(0.0, 0.1), (1.5, 0.2)], vec![(100.0, -40.0)])
+        };
+        saved.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Session { version: SESSION_VERSION, ..saved });
+    }
+
+    #[test]
+    fn loading_refuses_other_files() {
+        let path = std::env::temp_dir().join(format!("not-a-session-{}.{}", std::process::id(), SESSION_EXTENSION));
+        std::fs::write(&path, "x_position,rms_amplitude\n0.5,0.25\n").unwrap();
+        let error = Session::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(format!("{:#}", error).contains("not a session file"), "{:#}", error);
+    }
+
+    #[test]
+    fn interpolation_stays_inside_the_points() {
+        let points = [(0.0, 0.0), (1.0, 1.0), (3.0, 0.0)];
+        assert_eq!(interpolate_at(&points, 0.0), Some(0.0));
+        assert_eq!(interpolate_at(&points, 0.5), Some(0.5));
+        assert_eq!(interpolate_at(&points, 2.0), Some(0.5));
+        assert_eq!(interpolate_at(&points, 3.0), Some(0.0));
+        assert_eq!(interpolate_at(&points, -0.1), None);
+        assert_eq!(interpolate_at(&points, 3.1), None);
+        assert_eq!(interpolate_at(&[], 0.0), None);
+    }
+
+    #[test]
+    fn pearson_of_scaled_and_inverted_series() {
+        let a = [1.0, 2.0, 3.0, 4.0];
+        assert!((pearson(&a, &[2.0, 4.0, 6.0, 8.0]).unwrap() - 1.0).abs() < 1e-6);
+        assert!((pearson(&a, &[4.0, 3.0, 2.0, 1.0]).unwrap() + 1.0).abs() < 1e-6);
+        assert_eq!(pearson(&a, &[1.0; 4]), None);
+        assert_eq!(pearson(&a[..1], &a[..1]), None);
+    }
+
+    #[test]
+    fn comparison_covers_the_common_range() {
+        let a = session(vec![(0.0, 0.1), (10.0, 0.2)], vec![(100.0, -40.0), (200.0, -50.0)]);
+        let b = session(vec![(5.0, 0.3), (20.0, 0.6)], vec![(100.0, -37.0), (300.0, -37.0)]);
+        let comparison = SessionComparison::new(&a, &b);
+
+        let difference = &comparison.amplitude_difference;
+        assert_eq!(difference.len(), COMPARISON_POINTS);
+        assert_eq!((difference[0].0, difference.last().unwrap().0), (5.0, 10.0));
+        // At x = 5: A = 0.15, B = 0.3
+        assert!((difference[0].1 - 0.15).abs() < 1e-6);
+        // Both rise linearly over the overlap
+        assert!((comparison.similarity.unwrap() - 1.0).abs() < 1e-4);
+        assert_eq!(comparison.spectrum_difference, [(100.0, 3.0), (200.0, 13.0)]);
+        assert_eq!(comparison.spectrum_difference_stats(), Some((8.0, 13.0)));
+    }
+
+    #[test]
+    fn sessions_without_overlap_are_not_compared() {
+        let a = session(vec![(0.0, 0.1), (1.0, 0.2)], Vec::new());
+        let b = session(vec![(2.0, 0.1), (3.0, 0.2)], Vec::new());
+        let comparison = SessionComparison::new(&a, &b);
+        assert!(comparison.amplitude_difference.is_empty());
+        assert_eq!(comparison.similarity, None);
+        assert_eq!(comparison.amplitude_difference_stats(), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+// Rebuilds wait 500 ms, then twice as long after each failure
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(500);
+pub const MAX_RETRIES: u32 = 5;
+// A rebuilt stream that runs this long without an error gets a fresh set of retries
+const STABLE_AFTER: Duration = Duration::from_secs(5);
+/// How often `run` checks the error flag; also a sensible period for other `watch` loops.
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum StreamStatus {
+    #[default]
+    Running,
+    /// The stream failed; rebuild `attempt` of MAX_RETRIES is pending.
+    Recovering { attempt: u32, error: String },
+    /// Every retry failed and the stream stays closed.
+    Failed(String),
+}
+
+impl StreamStatus {
+    /// Text for the UI banner, `None` while the stream runs.
+    pub fn message(&self) -> Option<String> {
+        match self {
+            StreamStatus::Running => None,
+            StreamStatus::Recovering { attempt, error } => {
+                Some(format!("Audio stream error: {} (retry {}/{})", error, attempt, MAX_RETRIES))
+            }
+            StreamStatus::Failed(error) => {
+                Some(format!("Audio stream lost: {} (gave up after {} retries)", error, MAX_RETRIES))
+            }
+        }
+    }
+}
+
+/// Set by the stream's error callback, polled by `AudioStreamGuard::watch`.
+#[derive(Clone, Default)]
+pub struct StreamErrorFlag {
+    failed: Arc<AtomicBool>,
+    error: Arc<Mutex<String>>,
+}
+
+impl StreamErrorFlag {
+    /// Error callback for `build_input_stream`; it only records the error.
+    pub fn callback(&self) -> impl FnMut(cpal::StreamError) + Send + 'static {
+        let flag = self.clone();
+        move |err| {
+            eprintln!("Stream error: {}", err);
+            *flag.error.lock().unwrap() = err.to_string();
+            flag.failed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Owns an input stream and rebuilds it with `build` after a stream error, backing
+/// off exponentially and giving up after MAX_RETRIES failed attempts. `build` must
+/// return a started stream whose error callback comes from the given flag.
+pub struct AudioStreamGuard<F> {
+    build: F,
+    stream: Option<cpal::Stream>,
+    flag: StreamErrorFlag,
+    status: Arc<Mutex<StreamStatus>>,
+    attempts: u32,
+    error: String,
+    next_retry: Option<Instant>,
+    running_since: Option<Instant>,
+}
+
+impl<F> AudioStreamGuard<F>
+where
+    F: FnMut(&StreamErrorFlag) -> Result<cpal::Stream>,
+{
+    /// Opens the first stream; failing here is reported rather than retried.
+    pub fn new(status: Arc<Mutex<StreamStatus>>, mut build: F) -> Result<Self> {
+        let flag = StreamErrorFlag::default();
+        let stream = build(&flag)?;
+        *status.lock().unwrap() = StreamStatus::Running;
+        Ok(Self {
+            build,
+            stream: Some(stream),
+            flag,
+            status,
+            attempts: 0,
+            error: String::new(),
+            next_retry: None,
+            running_since: None,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Handles a reported error and runs a due rebuild. Call it regularly from the
+    /// thread that owns the guard.
+    pub fn watch(&mut self) {
+        if self.flag.failed.swap(false, Ordering::Relaxed) {
+            // Close the broken stream before trying to open a new one
+            self.stream = None;
+            self.running_since = None;
+            self.error = self.flag.error.lock().unwrap().clone();
+            self.schedule_retry();
+        }
+
+        if self.next_retry.is_some_and(|t| Instant::now() >= t) {
+            self.next_retry = None;
+            match (self.build)(&self.flag) {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    self.running_since = Some(Instant::now());
+                    *self.status.lock().unwrap() = StreamStatus::Running;
+                }
+                Err(e) => {
+                    self.error = format!("{:#}", e);
+                    self.schedule_retry();
+                }
+            }
+        }
+
+        if self.running_since.is_some_and(|t| t.elapsed() >= STABLE_AFTER) {
+            self.running_since = None;
+            self.attempts = 0;
+        }
+    }
+
+    /// Watches the stream for as long as the thread lives.
+    pub fn run(mut self) -> ! {
+        loop {
+            self.watch();
+            thread::sleep(WATCH_INTERVAL);
+        }
+    }
+
+    fn schedule_retry(&mut self) {
+        let status = if self.attempts >= MAX_RETRIES {
+            self.next_retry = None;
+            StreamStatus::Failed(self.error.clone())
+        } else {
+            self.attempts += 1;
+            self.next_retry = Some(Instant::now() + FIRST_RETRY_DELAY * 2u32.pow(self.attempts - 1));
+            StreamStatus::Recovering {
+                attempt: self.attempts,
+                error: self.error.clone(),
+            }
+        };
+        *self.status.lock().unwrap() = status;
+    }
+}
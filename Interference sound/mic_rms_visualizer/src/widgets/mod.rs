@@ -0,0 +1 @@
+pub mod vu_meter;
@@ -0,0 +1,84 @@
+use egui::{pos2, vec2, Color32, Rect, Response, Sense, Stroke, Ui, Widget};
+
+// Colour zones of the bar, in dBFS
+pub const YELLOW_FROM_DBFS: f32 = -20.0;
+pub const RED_ABOVE_DBFS: f32 = -6.0;
+
+const WIDTH: f32 = 24.0;
+const HEIGHT: f32 = 200.0;
+
+/// Vertical level meter: a bar for `level` and a tick at `peak`, both linear full
+/// scale (0..=1). Add it with `ui.add(VuMeter::new(rms, peak_hold))`.
+pub struct VuMeter {
+    level: f32,
+    peak: f32,
+}
+
+impl VuMeter {
+    pub fn new(level: f32, peak: f32) -> Self {
+        Self {
+            level: level.clamp(0.0, 1.0),
+            peak: peak.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Green below -20 dBFS, yellow from -20 to -6 dBFS, red above -6 dBFS.
+pub fn level_color(level: f32) -> Color32 {
+    let dbfs = 20.0 * level.max(1e-10).log10();
+    if dbfs > RED_ABOVE_DBFS {
+        Color32::RED
+    } else if dbfs >= YELLOW_FROM_DBFS {
+        Color32::YELLOW
+    } else {
+        Color32::GREEN
+    }
+}
+
+impl Widget for VuMeter {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(WIDTH, HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, Color32::DARK_GRAY);
+
+        let y = |value: f32| egui::lerp(rect.bottom()..=rect.top(), value);
+        let bar = Rect::from_min_max(pos2(rect.left(), y(self.level)), rect.right_bottom());
+        painter.rect_filled(bar, 2.0, level_color(self.level));
+        if self.peak > 0.0 {
+            let peak_y = y(self.peak);
+            painter.line_segment(
+                [pos2(rect.left(), peak_y), pos2(rect.right(), peak_y)],
+                Stroke::new(2.0, Color32::WHITE),
+            );
+        }
+
+        let dbfs = |value: f32| 20.0 * value.max(1e-10).log10();
+        response.on_hover_text(format!("{:.1} dBFS, peak {:.1} dBFS", dbfs(self.level), dbfs(self.peak)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_dbfs(dbfs: f32) -> f32 {
+        10f32.powf(dbfs / 20.0)
+    }
+
+    #[test]
+    fn colour_zones_follow_the_dbfs_thresholds() {
+        assert_eq!(level_color(0.0), Color32::GREEN);
+        assert_eq!(level_color(from_dbfs(-30.0)), Color32::GREEN);
+        assert_eq!(level_color(from_dbfs(-19.9)), Color32::YELLOW);
+        assert_eq!(level_color(from_dbfs(-10.0)), Color32::YELLOW);
+        assert_eq!(level_color(from_dbfs(-6.1)), Color32::YELLOW);
+        assert_eq!(level_color(from_dbfs(-5.9)), Color32::RED);
+        assert_eq!(level_color(1.0), Color32::RED);
+    }
+
+    #[test]
+    fn levels_are_clamped_to_full_scale() {
+        let meter = VuMeter::new(1.5, -0.5);
+        assert_eq!((meter.level, meter.peak), (1.0, 0.0));
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use futures_util::SinkExt;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+pub const MAX_FRAME_SAMPLES: usize = 500;
+
+// Frames queued per client; a slow client skips ahead instead of holding up the others
+const CLIENT_BACKLOG: usize = 4;
+
+/// One message to the browser, sent as JSON.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct WsFrame {
+    pub rms: f32,
+    pub amplitude: f32,
+    pub samples: Vec<f32>,
+}
+
+/// Simulated network trouble applied to every client, for testing the browser view.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NetworkSim {
+    /// Probability (0.0-1.0) that a frame is dropped instead of sent.
+    pub drop_rate: f32,
+    /// Extra delay before each frame is sent.
+    pub delay: Duration,
+}
+
+impl NetworkSim {
+    /// True if either drops or delay are switched on.
+    pub fn is_active(&self) -> bool {
+        self.drop_rate > 0.0 || !self.delay.is_zero()
+    }
+}
+
+// xorshift32; good enough to decide which frames to drop
+struct DropDice(u32);
+
+impl DropDice {
+    fn new(seed: u32) -> Self {
+        // xorshift gets stuck at zero
+        Self(seed.max(1))
+    }
+
+    // Uniform in [0, 1)
+    fn roll(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn drops(&mut self, drop_rate: f32) -> bool {
+        drop_rate > 0.0 && self.roll() < drop_rate
+    }
+}
+
+// Frames held back by the simulated delay. Each is due `delay` after it arrived, so the
+// delay adds latency without slowing the frame rate.
+struct DelayQueue {
+    delay: Duration,
+    frames: VecDeque<(Instant, String)>,
+}
+
+impl DelayQueue {
+    fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, frame: String, now: Instant) {
+        self.frames.push_back((now + self.delay, frame));
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.frames.front().map(|&(deadline, _)| deadline)
+    }
+
+    // The oldest frame, once it is due
+    fn pop_due(&mut self, now: Instant) -> Option<String> {
+        if self.next_deadline()? > now {
+            return None;
+        }
+        self.frames.pop_front().map(|(_, frame)| frame)
+    }
+}
+
+/// Every n-th sample, with n chosen so at most `max_points` remain.
+pub fn downsample(samples: impl ExactSizeIterator<Item = f32>, max_points: usize) -> Vec<f32> {
+    let step = samples.len().div_ceil(max_points.max(1)).max(1);
+    samples.step_by(step).collect()
+}
+
+/// Serves a frame from `next_frame` to every WebSocket client on `127.0.0.1:port`
+/// every 50 ms. The server runs on its own thread and tokio runtime; frames are only
+/// built while at least one client is connected. `sim` drops and delays frames per client.
+pub fn start_ws_server<F>(port: u16, sim: NetworkSim, mut next_frame: F) -> Result<()>
+where
+    F: FnMut() -> WsFrame + Send + 'static,
+{
+    // Bound here so a busy port is reported to the caller
+    let listener = TcpListener::bind(("127.0.0.1", port)).with_context(|| format!("Cannot listen on port {}", port))?;
+    listener.set_nonblocking(true)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Cannot start the WebSocket runtime")?;
+
+    thread::spawn(move || {
+        runtime.block_on(async move {
+            let listener = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    eprintln!("WebSocket server failed: {}", e);
+                    return;
+                }
+            };
+            let (frames, _) = broadcast::channel::<String>(CLIENT_BACKLOG);
+
+            let ticker = frames.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(FRAME_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if ticker.receiver_count() == 0 {
+                        continue;
+                    }
+                    match serde_json::to_string(&next_frame()) {
+                        Ok(json) => {
+                            let _ = ticker.send(json);
+                        }
+                        Err(e) => eprintln!("Cannot encode WebSocket frame: {}", e),
+                    }
+                }
+            });
+
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        eprintln!("WebSocket accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let mut receiver = frames.subscribe();
+                let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |t| t.subsec_nanos());
+                let mut dice = DropDice::new(nanos ^ u32::from(peer.port()));
+                tokio::spawn(async move {
+                    let mut socket = match tokio_tungstenite::accept_async(stream).await {
+                        Ok(socket) => socket,
+                        Err(e) => {
+                            eprintln!("WebSocket handshake with {} failed: {}", peer, e);
+                            return;
+                        }
+                    };
+                    let mut delayed = DelayQueue::new(sim.delay);
+                    loop {
+                        while let Some(json) = delayed.pop_due(Instant::now()) {
+                            // The client went away
+                            if socket.send(Message::Text(json)).await.is_err() {
+                                return;
+                            }
+                        }
+                        // Wait for the next frame, or until the oldest delayed one is due
+                        let received = match delayed.next_deadline() {
+                            Some(deadline) => {
+                                match tokio::time::timeout_at(deadline.into(), receiver.recv()).await {
+                                    Ok(received) => received,
+                                    Err(_) => continue,
+                                }
+                            }
+                            None => receiver.recv().await,
+                        };
+                        match received {
+                            Ok(json) => {
+                                if !dice.drops(sim.drop_rate) {
+                                    delayed.push(json, Instant::now());
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => return,
+                        }
+                    }
+                });
+            }
+        });
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_keeps_at_most_max_points() {
+        let samples: Vec<f32> = (0..1001).map(|i| i as f32).collect();
+        let points = downsample(samples.iter().copied(), 500);
+        assert!(points.len() <= 500);
+        assert_eq!(&points[..3], [0.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn dice_rolls_stay_in_the_unit_interval() {
+        let mut dice = DropDice::new(0);
+        for _ in 0..10_000 {
+            let roll = dice.roll();
+            assert!((0.0..1.0).contains(&roll));
+        }
+    }
+
+    #[test]
+    fn drop_rate_sets_the_fraction_of_dropped_frames() {
+        let mut dice = DropDice::new(12345);
+        assert!((0..1000).all(|_| !dice.drops(0.0)));
+        assert!((0..1000).all(|_| dice.drops(1.0)));
+        let dropped = (0..10_000).filter(|_| dice.drops(0.3)).count();
+        assert!((2700..3300).contains(&dropped), "{} of 10000 dropped", dropped);
+    }
+
+    #[test]
+    fn delayed_frames_come_out_in_order_once_due() {
+        let start = Instant::now();
+        let mut delayed = DelayQueue::new(Duration::from_millis(200));
+        for i in 0..10 {
+            delayed.push(i.to_string(), start + FRAME_INTERVAL * i);
+        }
+        assert_eq!(delayed.pop_due(start), None);
+        assert_eq!(delayed.next_deadline(), Some(start + Duration::from_millis(200)));
+        let due: Vec<String> = std::iter::from_fn(|| delayed.pop_due(start + Duration::from_millis(300))).collect();
+        assert_eq!(due, ["0", "1", "2"]);
+    }
+
+    #[test]
+    fn delay_alone_drops_no_frames() {
+        // Longer than the client backlog lasts, so sleeping per frame would lag
+        let delay = FRAME_INTERVAL * (2 * CLIENT_BACKLOG as u32);
+        let port = {
+            let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().port()
+        };
+        let mut count = 0.0;
+        let sim = NetworkSim { drop_rate: 0.0, delay };
+        start_ws_server(port, sim, move || {
+            count += 1.0;
+            WsFrame {
+                rms: count,
+                ..WsFrame::default()
+            }
+        })
+        .unwrap();
+
+        let (mut socket, _) = tokio_tungstenite::tungstenite::connect(format!("ws://127.0.0.1:{}", port)).unwrap();
+        let mut previous = None;
+        for _ in 0..20 {
+            let message = socket.read().unwrap();
+            let frame: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+            let rms = frame["rms"].as_f64().unwrap();
+            if let Some(previous) = previous {
+                assert_eq!(rms, previous + 1.0, "frames skipped");
+            }
+            previous = Some(rms);
+        }
+    }
+
+    #[test]
+    fn sim_is_inactive_by_default() {
+        assert!(!NetworkSim::default().is_active());
+        assert!(NetworkSim { drop_rate: 0.1, ..NetworkSim::default() }.is_active());
+        assert!(NetworkSim { delay: Duration::from_millis(20), ..NetworkSim::default() }.is_active());
+    }
+}
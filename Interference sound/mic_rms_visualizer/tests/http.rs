@@ -0,0 +1,62 @@
+use std::net::TcpListener;
+
+use mic_rms_visualizer::http::{start_http_server, HttpMetrics};
+
+// A port that was free a moment ago; the server binds it again right away
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+// Serves fixed metrics and a history of 0.0, 1.0, ... 9.0
+fn start_server() -> String {
+    let port = free_port();
+    let metrics = || HttpMetrics {
+        rms: 0.25,
+        amplitude: 0.5,
+        peak_hold: 0.75,
+        timestamp_ms: 0,
+    };
+    let history = |n: usize| {
+        let all: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        all[all.len().saturating_sub(n)..].to_vec()
+    };
+    start_http_server(port, metrics, history).unwrap();
+    format!("http://127.0.0.1:{}", port)
+}
+
+#[test]
+fn metrics_returns_the_levels_as_json() {
+    let base = start_server();
+    let response = reqwest::blocking::get(format!("{}/metrics", base)).unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let json: serde_json::Value = response.json().unwrap();
+    assert_eq!(json["rms"], 0.25);
+    assert_eq!(json["amplitude"], 0.5);
+    assert_eq!(json["peak_hold"], 0.75);
+    // Filled in by the server, so it is a recent Unix time rather than 0
+    assert!(json["timestamp_ms"].as_i64().unwrap() > 1_600_000_000_000);
+}
+
+#[test]
+fn history_returns_the_newest_n_values() {
+    let base = start_server();
+    let values: Vec<f32> = reqwest::blocking::get(format!("{}/history?n=3", base)).unwrap().json().unwrap();
+    assert_eq!(values, [7.0, 8.0, 9.0]);
+
+    // More than there is gives everything
+    let values: Vec<f32> = reqwest::blocking::get(format!("{}/history?n=50", base)).unwrap().json().unwrap();
+    assert_eq!(values.len(), 10);
+}
+
+#[test]
+fn bad_requests_get_error_codes() {
+    let base = start_server();
+    let status = |url: String| reqwest::blocking::get(url).unwrap().status();
+    assert_eq!(status(format!("{}/history?n=-1", base)), 400);
+    assert_eq!(status(format!("{}/nope", base)), 404);
+
+    let post = reqwest::blocking::Client::new().post(format!("{}/metrics", base)).send().unwrap();
+    assert_eq!(post.status(), 405);
+}